@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::{download::ArtifactsDownloadCommand, list::ArtifactsListCommand};
+use crate::command::BldCommand;
+
+#[derive(Subcommand)]
+pub enum ArtifactsCommands {
+    Ls(ArtifactsListCommand),
+    Download(ArtifactsDownloadCommand),
+}
+
+#[derive(Parser)]
+#[command(about = "Manage the stored artifacts of pipeline runs in a bld server")]
+pub struct ArtifactsCommand {
+    #[command(subcommand)]
+    command: ArtifactsCommands,
+}
+
+impl ArtifactsCommand {
+    pub fn invoke(self) -> Result<()> {
+        match self.command {
+            ArtifactsCommands::Ls(list) => list.invoke(),
+            ArtifactsCommands::Download(download) => download.invoke(),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::command::BldCommand;
+use actix::System;
+use anyhow::{bail, Result};
+use bld_config::{path, BldConfig};
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use std::path::PathBuf;
+use tokio::fs::create_dir_all;
+
+#[derive(Args)]
+#[command(about = "Downloads the stored artifacts of a pipeline run")]
+pub struct ArtifactsDownloadCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to download the artifacts from"
+    )]
+    server: String,
+
+    #[arg(short = 'i', long = "id", help = "The id of the pipeline run")]
+    run_id: String,
+
+    #[arg(
+        short = 'n',
+        long = "name",
+        help = "The name of the artifact to download, if not provided all artifacts of the run will be downloaded"
+    )]
+    name: Option<String>,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        default_value = ".",
+        help = "The directory that the artifacts will be downloaded to"
+    )]
+    output: String,
+}
+
+/// Returns the path that an artifact is downloaded to, the names of the artifacts are
+/// provided by the server so any name that could be written outside of the output
+/// directory is rejected.
+fn artifact_path(output: &str, name: &str) -> Result<PathBuf> {
+    let is_valid = !name.trim().is_empty()
+        && name != "."
+        && !name.contains("..")
+        && !name.contains(['/', '\\']);
+
+    if !is_valid {
+        bail!("invalid artifact name {name}");
+    }
+
+    Ok(path![output, name])
+}
+
+impl BldCommand for ArtifactsDownloadCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let artifacts: Vec<_> = client
+                .artifacts_list(&self.run_id)
+                .await?
+                .into_iter()
+                .filter(|a| self.name.as_ref().map(|x| x == &a.name).unwrap_or(true))
+                .collect();
+
+            if artifacts.is_empty() {
+                bail!("no artifacts found for pipeline run {}", self.run_id);
+            }
+
+            create_dir_all(&self.output).await?;
+
+            for artifact in artifacts {
+                let path = artifact_path(&self.output, &artifact.name)?;
+                client
+                    .artifacts_download(&self.run_id, &artifact.id, &path)
+                    .await?;
+                println!("{}", path.display());
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifacts_are_downloaded_in_the_output_directory() {
+        let path = artifact_path("out", "report.tar.gz").unwrap();
+        assert_eq!(path, PathBuf::from("out/report.tar.gz"));
+
+        let path = artifact_path("out", ".report").unwrap();
+        assert_eq!(path, PathBuf::from("out/.report"));
+    }
+
+    #[test]
+    fn artifact_names_with_path_separators_are_rejected() {
+        for name in [
+            "../report",
+            "dir/report",
+            "/etc/passwd",
+            "dir\\report",
+            "..report",
+            "..",
+            ".",
+            "",
+        ] {
+            let error = artifact_path("out", name).unwrap_err();
+            assert_eq!(error.to_string(), format!("invalid artifact name {name}"));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::command::BldCommand;
+use actix::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use tabled::{Style, Table, Tabled};
+
+#[derive(Tabled)]
+struct ArtifactInfoRow<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub size: i64,
+    pub date_created: &'a str,
+}
+
+#[derive(Args)]
+#[command(about = "Lists the stored artifacts of a pipeline run")]
+pub struct ArtifactsListCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to list the artifacts from"
+    )]
+    server: String,
+
+    #[arg(short = 'i', long = "id", help = "The id of the pipeline run")]
+    run_id: String,
+}
+
+impl BldCommand for ArtifactsListCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let response = client.artifacts_list(&self.run_id).await?;
+
+            if !response.is_empty() {
+                let data: Vec<ArtifactInfoRow> = response
+                    .iter()
+                    .map(|a| ArtifactInfoRow {
+                        id: &a.id,
+                        name: &a.name,
+                        size: a.size,
+                        date_created: &a.date_created,
+                    })
+                    .collect();
+                let table = Table::new(data).with(Style::modern()).to_string();
+                println!("{table}");
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,3 @@
+pub mod command;
+pub mod download;
+pub mod list;
@@ -1,3 +1,4 @@
+use crate::artifacts::command::ArtifactsCommand;
 use crate::auth::AuthCommand;
 use crate::cat::CatCommand;
 use crate::check::CheckCommand;
@@ -14,6 +15,7 @@ use crate::push::PushCommand;
 use crate::r#move::MoveCommand;
 use crate::remove::RemoveCommand;
 use crate::run::RunCommand;
+use crate::secrets::command::SecretsCommand;
 use crate::server::ServerCommand;
 use crate::stop::StopCommand;
 use crate::supervisor::SupervisorCommand;
@@ -26,6 +28,7 @@ use clap::{Parser, Subcommand};
 #[derive(Subcommand)]
 enum Commands {
     Login(AuthCommand),
+    Artifacts(ArtifactsCommand),
     Cat(CatCommand),
     Check(CheckCommand),
     Config(ConfigCommand),
@@ -42,6 +45,7 @@ enum Commands {
     Push(PushCommand),
     Rm(RemoveCommand),
     Run(RunCommand),
+    Secrets(SecretsCommand),
     Server(ServerCommand),
     Stop(StopCommand),
     Supervisor(SupervisorCommand),
@@ -60,6 +64,7 @@ impl Cli {
     pub fn invoke(self) -> Result<()> {
         match self.command {
             Commands::Login(auth) => auth.invoke(),
+            Commands::Artifacts(artifacts) => artifacts.invoke(),
             Commands::Cat(cat) => cat.invoke(),
             Commands::Check(check) => check.invoke(),
             Commands::Config(config) => config.invoke(),
@@ -76,6 +81,7 @@ impl Cli {
             Commands::Push(push) => push.invoke(),
             Commands::Rm(remove) => remove.invoke(),
             Commands::Run(run) => run.invoke(),
+            Commands::Secrets(secrets) => secrets.invoke(),
             Commands::Server(server) => server.invoke(),
             Commands::Stop(stop) => stop.invoke(),
             Commands::Supervisor(supervisor) => supervisor.invoke(),
@@ -58,8 +58,8 @@ impl BldCommand for CronAddCommand {
         System::new().block_on(async move {
             let config = BldConfig::load().await?.into_arc();
             let client = HttpClient::new(config, &self.server)?;
-            let variables = Some(parse_variables(&self.variables));
-            let environment = Some(parse_variables(&self.environment));
+            let variables = Some(parse_variables(&self.variables)?);
+            let environment = Some(parse_variables(&self.environment)?);
             let request =
                 AddJobRequest::new(self.schedule, self.pipeline, variables, environment, false);
             client.cron_add(&request).await
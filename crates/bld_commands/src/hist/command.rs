@@ -3,7 +3,9 @@ use actix_web::rt::System;
 use anyhow::Result;
 use bld_config::BldConfig;
 use bld_http::HttpClient;
-use bld_models::dtos::HistoryEntry;
+use bld_models::dtos::{
+    HistoryEntry, HistoryImageEntry, HistoryJobEntry, HistoryNotificationEntry,
+};
 use bld_utils::sync::IntoArc;
 use clap::Args;
 use tabled::{Style, Table, Tabled};
@@ -40,6 +42,95 @@ impl From<HistoryEntry> for HistoryEntryRow {
     }
 }
 
+#[derive(Tabled)]
+struct HistoryStepRow {
+    pub job: String,
+    #[tabled(display_with = "HistoryEntryRow::display_option")]
+    pub step: Option<String>,
+    pub state: String,
+    #[tabled(display_with = "HistoryStepRow::display_exit_code")]
+    pub exit_code: Option<i64>,
+    #[tabled(display_with = "HistoryEntryRow::display_option")]
+    pub start_date_time: Option<String>,
+    #[tabled(display_with = "HistoryEntryRow::display_option")]
+    pub end_date_time: Option<String>,
+}
+
+impl HistoryStepRow {
+    pub fn display_exit_code(value: &Option<i64>) -> String {
+        value.map(|x| x.to_string()).unwrap_or_default()
+    }
+
+    pub fn rows(job: HistoryJobEntry) -> Vec<Self> {
+        let mut rows = vec![Self {
+            job: job.name.clone(),
+            step: None,
+            state: job.state,
+            exit_code: None,
+            start_date_time: job.start_date_time,
+            end_date_time: job.end_date_time,
+        }];
+        rows.extend(job.steps.into_iter().map(|step| {
+            Self {
+                job: job.name.clone(),
+                step: Some(
+                    step.name
+                        .unwrap_or_else(|| format!("#{}", step.position + 1)),
+                ),
+                state: step.state,
+                exit_code: step.exit_code,
+                start_date_time: step.start_date_time,
+                end_date_time: step.end_date_time,
+            }
+        }));
+        rows
+    }
+}
+
+#[derive(Tabled)]
+struct HistoryImageRow {
+    pub container_id: String,
+    pub image: String,
+    #[tabled(display_with = "HistoryEntryRow::display_option")]
+    pub digest: Option<String>,
+    pub date_time: String,
+}
+
+impl From<HistoryImageEntry> for HistoryImageRow {
+    fn from(value: HistoryImageEntry) -> Self {
+        Self {
+            container_id: value.container_id.chars().take(12).collect(),
+            image: value.image,
+            digest: value.digest,
+            date_time: value.date_time,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct HistoryNotificationRow {
+    pub target: String,
+    pub event: String,
+    pub attempt: i32,
+    pub state: String,
+    #[tabled(display_with = "HistoryEntryRow::display_option")]
+    pub error: Option<String>,
+    pub date_time: String,
+}
+
+impl From<HistoryNotificationEntry> for HistoryNotificationRow {
+    fn from(value: HistoryNotificationEntry) -> Self {
+        Self {
+            target: value.target,
+            event: value.event,
+            attempt: value.attempt,
+            state: value.state,
+            error: value.error,
+            date_time: value.date_time,
+        }
+    }
+}
+
 #[derive(Args)]
 #[command(about = "Fetches execution history of pipelines on a bld server")]
 pub struct HistCommand {
@@ -75,6 +166,13 @@ pub struct HistCommand {
         help = "Limit the results"
     )]
     limit: u64,
+
+    #[arg(
+        short = 'd',
+        long = "details",
+        help = "Show the job and step history, the images used and the notifications sent by the pipeline run with the given id"
+    )]
+    details: Option<String>,
 }
 
 impl BldCommand for HistCommand {
@@ -97,7 +195,49 @@ impl BldCommand for HistCommand {
                 self.server, self.limit,
             );
 
-            let history: Vec<HistoryEntryRow> = HttpClient::new(config, &self.server)?
+            let client = HttpClient::new(config, &self.server)?;
+
+            if let Some(run_id) = self.details {
+                let steps: Vec<HistoryStepRow> = client
+                    .hist_steps(&run_id)
+                    .await?
+                    .into_iter()
+                    .flat_map(HistoryStepRow::rows)
+                    .collect();
+
+                if !steps.is_empty() {
+                    let table = Table::new(steps).with(Style::modern()).to_string();
+                    println!("{table}");
+                }
+
+                let images: Vec<HistoryImageRow> = client
+                    .hist_images(&run_id)
+                    .await?
+                    .into_iter()
+                    .map(From::from)
+                    .collect();
+
+                if !images.is_empty() {
+                    let table = Table::new(images).with(Style::modern()).to_string();
+                    println!("{table}");
+                }
+
+                let notifications: Vec<HistoryNotificationRow> = client
+                    .hist_notifications(&run_id)
+                    .await?
+                    .into_iter()
+                    .map(From::from)
+                    .collect();
+
+                if !notifications.is_empty() {
+                    let table = Table::new(notifications).with(Style::modern()).to_string();
+                    println!("{table}");
+                }
+
+                return Ok(());
+            }
+
+            let history: Vec<HistoryEntryRow> = client
                 .hist(state, self.pipeline, self.limit)
                 .await?
                 .into_iter()
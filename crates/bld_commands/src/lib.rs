@@ -1,4 +1,5 @@
 mod add;
+mod artifacts;
 mod auth;
 mod cat;
 mod check;
@@ -17,6 +18,7 @@ mod pull;
 mod push;
 mod remove;
 mod run;
+mod secrets;
 mod server;
 mod signals;
 mod stop;
@@ -42,6 +42,24 @@ pub struct MonitCommand {
         help = "Monitor the execution of the last invoked pipeline. Takes precedence over pipeline-id and pipeline"
     )]
     last: bool,
+
+    #[arg(
+        long = "job",
+        help = "Show only the output of the job with the given name"
+    )]
+    job: Option<String>,
+
+    #[arg(
+        long = "step",
+        help = "Show only the output of the step with the given name"
+    )]
+    step: Option<String>,
+
+    #[arg(
+        long = "json",
+        help = "Print the structured log entries as json lines instead of plain text"
+    )]
+    json: bool,
 }
 
 impl MonitCommand {
@@ -72,9 +90,12 @@ impl MonitCommand {
             self.pipeline_id, self.pipeline, self.last
         );
 
-        addr.send(MonitInfo::new(self.pipeline_id, self.pipeline, self.last))
-            .await
-            .map_err(|e| anyhow!(e))
+        let info = MonitInfo::new(self.pipeline_id, self.pipeline, self.last)
+            .job(self.job)
+            .step(self.step)
+            .structured(self.json);
+
+        addr.send(info).await.map_err(|e| anyhow!(e))
     }
 }
 
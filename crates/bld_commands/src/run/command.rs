@@ -53,8 +53,8 @@ impl BldCommand for RunCommand {
     fn exec(self) -> Result<()> {
         System::new().block_on(async move {
             let config = BldConfig::load().await?.into_arc();
-            let variables = parse_variables(&self.variables);
-            let environment = parse_variables(&self.environment);
+            let variables = parse_variables(&self.variables)?;
+            let environment = parse_variables(&self.environment)?;
             let adapter = RunBuilder::new(config, self.pipeline, variables, environment)
                 .server(self.server.as_ref())
                 .detach(self.detach)
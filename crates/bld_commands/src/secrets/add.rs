@@ -0,0 +1,57 @@
+use crate::command::BldCommand;
+use actix::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_models::dtos::AddSecretRequest;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use std::io::stdin;
+
+#[derive(Args)]
+#[command(about = "Adds or updates a secret on a server")]
+pub struct SecretsAddCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to add the secret to"
+    )]
+    server: String,
+
+    #[arg(short = 'n', long = "name", help = "The name of the secret")]
+    name: String,
+
+    #[arg(
+        short = 'v',
+        long = "value",
+        help = "The value of the secret, if not provided it will be read from stdin"
+    )]
+    value: Option<String>,
+}
+
+impl BldCommand for SecretsAddCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        let value = match self.value {
+            Some(value) => value,
+            None => {
+                let mut value = String::new();
+                stdin().read_line(&mut value)?;
+                value.trim_end_matches(['\r', '\n']).to_owned()
+            }
+        };
+
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let body = AddSecretRequest::new(self.name, value);
+            client.secrets_add(&body).await
+        })
+    }
+}
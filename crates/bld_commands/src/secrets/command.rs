@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::{add::SecretsAddCommand, list::SecretsListCommand, remove::SecretsRemoveCommand};
+use crate::command::BldCommand;
+
+#[derive(Subcommand)]
+pub enum SecretsCommands {
+    Add(SecretsAddCommand),
+    Ls(SecretsListCommand),
+    Rm(SecretsRemoveCommand),
+}
+
+#[derive(Parser)]
+#[command(about = "Manage the secrets of a bld server")]
+pub struct SecretsCommand {
+    #[command(subcommand)]
+    command: SecretsCommands,
+}
+
+impl SecretsCommand {
+    pub fn invoke(self) -> Result<()> {
+        match self.command {
+            SecretsCommands::Add(add) => add.invoke(),
+            SecretsCommands::Ls(list) => list.invoke(),
+            SecretsCommands::Rm(remove) => remove.invoke(),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::command::BldCommand;
+use actix::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use tabled::{Style, Table, Tabled};
+
+#[derive(Tabled)]
+struct SecretInfoRow<'a> {
+    pub name: &'a str,
+    pub date_created: &'a str,
+    pub date_updated: &'a str,
+}
+
+#[derive(Args)]
+#[command(about = "Lists the names of all secrets in a server")]
+pub struct SecretsListCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to list the secrets from"
+    )]
+    server: String,
+}
+
+impl BldCommand for SecretsListCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let response = client.secrets_list().await?;
+
+            if !response.is_empty() {
+                let data: Vec<SecretInfoRow> = response
+                    .iter()
+                    .map(|s| SecretInfoRow {
+                        name: &s.name,
+                        date_created: &s.date_created,
+                        date_updated: s.date_updated.as_deref().unwrap_or(""),
+                    })
+                    .collect();
+                let table = Table::new(data).with(Style::modern()).to_string();
+                println!("{table}");
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod add;
+pub mod command;
+pub mod list;
+pub mod remove;
@@ -0,0 +1,38 @@
+use crate::command::BldCommand;
+use actix::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+
+#[derive(Args)]
+#[command(about = "Removes a secret from a server")]
+pub struct SecretsRemoveCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to remove the secret from"
+    )]
+    server: String,
+
+    #[arg(short = 'n', long = "name", help = "The name of the secret to remove")]
+    name: String,
+}
+
+impl BldCommand for SecretsRemoveCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            client.secrets_remove(&self.name).await
+        })
+    }
+}
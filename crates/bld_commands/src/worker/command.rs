@@ -67,12 +67,13 @@ impl BldCommand for WorkerCommand {
 
             let pipeline = self.pipeline.into_arc();
             let run_id = self.run_id.into_arc();
-            let variables = parse_variables(&self.variables).into_arc();
-            let environment = parse_variables(&self.environment).into_arc();
+            let variables = parse_variables(&self.variables)?.into_arc();
+            let environment = parse_variables(&self.environment)?.into_arc();
 
             let conn = new_connection_pool(config.clone()).await?.into_arc();
             let start_date = Utc::now().naive_utc();
             pipeline_runs::update_start_date(conn.as_ref(), &run_id, &start_date).await?;
+            let run = pipeline_runs::select_by_id(conn.as_ref(), &run_id).await?;
             let start_date = start_date.format("%F %X").to_string();
             let fs = FileSystem::Server {
                 config: config.clone(),
@@ -83,7 +84,7 @@ impl BldCommand for WorkerCommand {
             let (worker_tx, worker_rx) = channel(4096);
             let worker_tx = Some(worker_tx).into_arc();
             let logger = Logger::file(config.clone(), &run_id).await?.into_arc();
-            let context = Context::server(config.clone(), conn, &run_id).into_arc();
+            let context = Context::server(config.clone(), conn, &run).into_arc();
             let (cmd_signals, signals_rx) = CommandSignals::new()?;
 
             let socket_handle = spawn(async move {
@@ -92,6 +93,7 @@ impl BldCommand for WorkerCommand {
                 }
             });
 
+            let runner_context = context.clone();
             let runner_handle = spawn(async move {
                 match RunnerBuilder::default()
                     .run_id(&run_id)
@@ -102,7 +104,7 @@ impl BldCommand for WorkerCommand {
                     .logger(logger)
                     .environment(environment)
                     .variables(variables)
-                    .context(context)
+                    .context(runner_context)
                     .ipc(worker_tx)
                     .signals(signals_rx)
                     .build()
@@ -116,6 +118,10 @@ impl BldCommand for WorkerCommand {
                     Err(e) => error!("failed on building the runner, {e}"),
                 }
 
+                if let Err(e) = context.flush_notifications().await {
+                    error!("{e}");
+                }
+
                 let _ = cmd_signals.stop().await;
             });
 
@@ -0,0 +1,30 @@
+use crate::definitions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BldArtifactsConfig {
+    #[serde(default = "BldArtifactsConfig::default_path")]
+    pub path: String,
+
+    /// The number of days that the artifacts of a run are kept.
+    pub max_age: Option<u64>,
+
+    /// The number of the most recent runs whose artifacts are kept.
+    pub max_runs: Option<u64>,
+}
+
+impl BldArtifactsConfig {
+    fn default_path() -> String {
+        definitions::LOCAL_ARTIFACTS_DIR.to_owned()
+    }
+}
+
+impl Default for BldArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            path: Self::default_path(),
+            max_age: None,
+            max_runs: None,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::definitions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BldLocalCacheConfig {
+    #[serde(default = "BldLocalCacheConfig::default_path")]
+    pub path: String,
+
+    /// The maximum size of the cache directory in megabytes, when exceeded the least
+    /// recently used entries are removed.
+    #[serde(default = "BldLocalCacheConfig::default_max_size")]
+    pub max_size: u64,
+}
+
+impl BldLocalCacheConfig {
+    fn default_path() -> String {
+        definitions::LOCAL_CACHE_DIR.to_owned()
+    }
+
+    fn default_max_size() -> u64 {
+        definitions::LOCAL_CACHE_MAX_SIZE
+    }
+
+    pub fn max_size_in_bytes(&self) -> u64 {
+        self.max_size.saturating_mul(1024 * 1024)
+    }
+}
+
+impl Default for BldLocalCacheConfig {
+    fn default() -> Self {
+        Self {
+            path: Self::default_path(),
+            max_size: Self::default_max_size(),
+        }
+    }
+}
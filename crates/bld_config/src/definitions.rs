@@ -13,6 +13,9 @@ pub const KEYWORD_BLD_DIR_V2: &str = "bld_root_dir";
 pub const KEYWORD_PROJECT_DIR_V2: &str = "bld_project_dir";
 pub const KEYWORD_RUN_PROPS_ID_V2: &str = "bld_run_id";
 pub const KEYWORD_RUN_PROPS_START_TIME_V2: &str = "bld_start_time";
+pub const KEYWORD_CHECKOUT_COMMIT_V2: &str = "bld_checkout_commit";
+pub const KEYWORD_MATRIX_V2: &str = "matrix";
+pub const KEYWORD_SECRETS_V2: &str = "secrets";
 
 pub const TOOL_DEFAULT_PIPELINE: &str = "default";
 pub const TOOL_DEFAULT_PIPELINE_FILE: &str = "default.yaml";
@@ -26,11 +29,26 @@ pub const LOCAL_SUPERVISOR_HOST: &str = "127.0.0.1";
 pub const LOCAL_SUPERVISOR_PORT: i64 = 7080;
 pub const LOCAL_SUPERVISOR_WORKERS: i64 = 5;
 pub const LOCAL_HA_MODE: bool = false;
+pub const LOCAL_HA_HEARTBEAT_INTERVAL: u64 = 150;
+pub const LOCAL_HA_ELECTION_TIMEOUT: u64 = 1000;
+pub const LOCAL_HA_SNAPSHOT_THRESHOLD: i32 = 1000;
 pub const LOCAL_LOGS: &str = "logs";
+pub const LOCAL_ARTIFACTS_DIR: &str = "artifacts";
 pub const LOCAL_DEFAULT_DB_DIR: &str = "db";
 pub const LOCAL_DEFAULT_DB_NAME: &str = "bld-server.db";
 pub const LOCAL_DOCKER_URL: &str = "tcp://127.0.0.1:2376";
+pub const LOCAL_PODMAN_SOCKET: &str = "unix:///run/podman/podman.sock";
+pub const LOCAL_PODMAN_ROOTLESS_SOCKET: &str = "podman/podman.sock";
 pub const LOCAL_MACHINE_TMP_DIR: &str = "tmp";
+pub const LOCAL_CACHE_DIR: &str = "cache";
+pub const LOCAL_CACHE_MAX_SIZE: u64 = 1024;
+pub const DEFAULT_COMMIT_STATUS_CONTEXT: &str = "bld";
+pub const DEFAULT_COMMIT_STATUS_RETRIES: u64 = 3;
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+pub const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+pub const DEFAULT_NOTIFICATION_RETRIES: u64 = 3;
+pub const KUBERNETES_DEFAULT_NAMESPACE: &str = "default";
+pub const KUBERNETES_DEFAULT_POD_TIMEOUT: u64 = 300;
 
 pub const REMOTE_SERVER_NAME: &str = "demo_server";
 pub const REMOTE_SERVER_HOST: &str = "127.0.0.1";
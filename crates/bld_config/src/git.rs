@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitConfig {
+    #[serde(default = "GitConfig::default_username")]
+    pub username: String,
+
+    /// The password or access token used for repositories over https.
+    pub password: String,
+}
+
+impl GitConfig {
+    fn default_username() -> String {
+        "git".to_owned()
+    }
+}
@@ -0,0 +1,72 @@
+use crate::definitions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldHaMember {
+    pub id: i32,
+    pub host: String,
+    pub port: i64,
+
+    #[serde(default)]
+    pub tls: bool,
+}
+
+impl BldHaMember {
+    fn http_protocol(&self) -> String {
+        if self.tls {
+            "https".to_string()
+        } else {
+            "http".to_string()
+        }
+    }
+
+    pub fn base_url_http(&self) -> String {
+        format!("{}://{}:{}", self.http_protocol(), self.host, self.port)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldHaConfig {
+    /// The id of this server, it should match the id of one of the members.
+    pub node_id: i32,
+
+    /// All the servers of the cluster including this one.
+    pub members: Vec<BldHaMember>,
+
+    /// A shared token sent by the members of the cluster to each other
+    /// in order to authorize the internal high availability requests.
+    pub token: String,
+
+    #[serde(default = "BldHaConfig::default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+
+    #[serde(default = "BldHaConfig::default_election_timeout")]
+    pub election_timeout: u64,
+
+    /// The number of applied log entries after which a snapshot of the state
+    /// is taken and the log entries that it contains are removed.
+    #[serde(default = "BldHaConfig::default_snapshot_threshold")]
+    pub snapshot_threshold: i32,
+}
+
+impl BldHaConfig {
+    fn default_heartbeat_interval() -> u64 {
+        definitions::LOCAL_HA_HEARTBEAT_INTERVAL
+    }
+
+    fn default_election_timeout() -> u64 {
+        definitions::LOCAL_HA_ELECTION_TIMEOUT
+    }
+
+    fn default_snapshot_threshold() -> i32 {
+        definitions::LOCAL_HA_SNAPSHOT_THRESHOLD
+    }
+
+    pub fn member(&self, id: i32) -> Option<&BldHaMember> {
+        self.members.iter().find(|m| m.id == id)
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &BldHaMember> {
+        self.members.iter().filter(|m| m.id != self.node_id)
+    }
+}
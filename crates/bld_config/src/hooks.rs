@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::definitions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BldHookProvider {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+impl Display for BldHookProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Github => write!(f, "github"),
+            Self::Gitlab => write!(f, "gitlab"),
+            Self::Gitea => write!(f, "gitea"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BldHookEvent {
+    Push,
+    PullRequest,
+}
+
+impl Display for BldHookEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Push => write!(f, "push"),
+            Self::PullRequest => write!(f, "pull_request"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldHookConfig {
+    pub provider: BldHookProvider,
+
+    /// The secret used to verify the signature of a delivery, or the token
+    /// sent by gitlab in the X-Gitlab-Token header.
+    pub secret: String,
+
+    /// The pipeline that is run when a delivery matches the hook.
+    pub pipeline: String,
+
+    /// The full name of the repository, for example owner/name.
+    pub repository: Option<String>,
+
+    /// Glob patterns for the pushed branch or the target branch of a pull request.
+    #[serde(default)]
+    pub branches: Vec<String>,
+
+    /// The events that trigger the hook, all events when empty.
+    #[serde(default)]
+    pub events: Vec<BldHookEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldCommitStatusConfig {
+    pub provider: BldHookProvider,
+
+    /// The base url of the provider's api, required for gitea servers.
+    pub api_url: Option<String>,
+
+    /// The access token used to create the commit statuses.
+    pub token: String,
+
+    /// The full name of the repository, statuses are reported for all repositories when empty.
+    pub repository: Option<String>,
+
+    /// The name that the provider shows for the statuses.
+    #[serde(default = "BldCommitStatusConfig::default_context")]
+    pub context: String,
+
+    /// The number of additional attempts when a status can't be reported.
+    #[serde(default = "BldCommitStatusConfig::default_retries")]
+    pub retries: u64,
+}
+
+impl BldCommitStatusConfig {
+    fn default_context() -> String {
+        definitions::DEFAULT_COMMIT_STATUS_CONTEXT.to_owned()
+    }
+
+    fn default_retries() -> u64 {
+        definitions::DEFAULT_COMMIT_STATUS_RETRIES
+    }
+
+    pub fn api_url(&self) -> Option<&str> {
+        match (self.api_url.as_deref(), self.provider) {
+            (Some(url), _) => Some(url.trim_end_matches('/')),
+            (None, BldHookProvider::Github) => Some(definitions::GITHUB_API_URL),
+            (None, BldHookProvider::Gitlab) => Some(definitions::GITLAB_API_URL),
+            (None, BldHookProvider::Gitea) => None,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::definitions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubernetesConfig {
+    pub url: String,
+
+    #[serde(default = "KubernetesConfig::default_namespace")]
+    pub namespace: String,
+
+    pub token: Option<String>,
+    pub token_file: Option<String>,
+
+    /// The certificate of the authority that signed the certificate of the api server,
+    /// the native certificates of the host are used when not set.
+    pub ca_cert: Option<String>,
+
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+
+    /// The number of seconds to wait for the pod of a run to start.
+    #[serde(default = "KubernetesConfig::default_pod_timeout")]
+    pub pod_timeout: u64,
+}
+
+impl KubernetesConfig {
+    fn default_namespace() -> String {
+        definitions::KUBERNETES_DEFAULT_NAMESPACE.to_owned()
+    }
+
+    fn default_pod_timeout() -> u64 {
+        definitions::KUBERNETES_DEFAULT_POD_TIMEOUT
+    }
+}
@@ -1,17 +1,33 @@
+mod artifacts;
 mod auth;
+mod cache;
 pub mod definitions;
 mod docker;
+mod git;
+mod ha;
+mod hooks;
+mod kubernetes;
 mod local;
+mod notifications;
 mod path;
+mod podman;
 mod server;
 mod ssh;
 mod supervisor;
 mod tls;
 
+pub use artifacts::*;
 pub use auth::*;
+pub use cache::*;
 pub use docker::*;
+pub use git::*;
+pub use ha::*;
+pub use hooks::*;
+pub use kubernetes::*;
 pub use local::*;
+pub use notifications::*;
 pub use path::*;
+pub use podman::*;
 pub use server::*;
 pub use ssh::*;
 pub use supervisor::*;
@@ -22,7 +38,7 @@ use crate::definitions::{
     LOCAL_SERVER_PORT, REMOTE_SERVER_AUTH, TOOL_DEFAULT_CONFIG_FILE, TOOL_DIR,
     WEB_CLIENT_DEBUG_ORIGIN,
 };
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, bail, Error, Result};
 use openidconnect::core::CoreClient;
 use serde::{Deserialize, Serialize};
 use std::env::current_dir;
@@ -96,6 +112,14 @@ impl BldConfig {
         let content = read_to_string(&path).await?;
         let mut instance: Self = serde_yaml::from_str(&content).map_err(|e| anyhow!(e))?;
 
+        for (i, hook) in instance.local.server.hooks.iter().enumerate() {
+            if hook.secret.trim().is_empty() {
+                bail!(
+                    "a non empty secret is required for the hook {i} of the server configuration"
+                );
+            }
+        }
+
         instance.root_dir = root_dir
             .to_str()
             .map(ToOwned::to_owned)
@@ -155,6 +179,20 @@ impl BldConfig {
             .ok_or_else(|| anyhow!("ssh configuration with name '{name}' wasn't found"))
     }
 
+    pub fn kubernetes(&self, name: &str) -> Result<&KubernetesConfig> {
+        self.local
+            .kubernetes
+            .get(name)
+            .ok_or_else(|| anyhow!("kubernetes configuration with name '{name}' wasn't found"))
+    }
+
+    pub fn git(&self, name: &str) -> Result<&GitConfig> {
+        self.local
+            .git
+            .get(name)
+            .ok_or_else(|| anyhow!("git configuration with name '{name}' wasn't found"))
+    }
+
     pub fn registry(&self, name: &str) -> Option<&RegistryConfig> {
         self.local.registries.get(name)
     }
@@ -212,4 +250,12 @@ impl BldConfig {
     pub fn tmp_full_path(&self, name: &str) -> PathBuf {
         path![&self.root_dir, LOCAL_MACHINE_TMP_DIR, name]
     }
+
+    pub fn artifacts_full_path(&self, run_id: &str) -> PathBuf {
+        path![&self.root_dir, &self.local.server.artifacts.path, run_id]
+    }
+
+    pub fn cache_full_path(&self) -> PathBuf {
+        path![&self.root_dir, &self.local.cache.path]
+    }
 }
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use crate::{
-    definitions, ssh::SshConfig, BldLocalServerConfig, BldLocalSupervisorConfig, DockerUrl,
+    definitions, ssh::SshConfig, BldLocalCacheConfig, BldLocalPodmanConfig, BldLocalServerConfig,
+    BldLocalSupervisorConfig, ContainerEngine, DockerUrl, GitConfig, KubernetesConfig,
     RegistryConfig,
 };
 use serde::{Deserialize, Serialize};
@@ -17,9 +18,15 @@ pub struct BldLocalConfig {
     #[serde(default)]
     pub supervisor: BldLocalSupervisorConfig,
 
+    #[serde(default)]
+    pub container_engine: ContainerEngine,
+
     #[serde(default)]
     pub docker_url: DockerUrl,
 
+    #[serde(default)]
+    pub podman: BldLocalPodmanConfig,
+
     #[serde(default = "BldLocalConfig::default_editor")]
     pub editor: String,
 
@@ -28,6 +35,18 @@ pub struct BldLocalConfig {
 
     #[serde(default)]
     pub registries: HashMap<String, RegistryConfig>,
+
+    #[serde(default)]
+    pub kubernetes: HashMap<String, KubernetesConfig>,
+
+    #[serde(default)]
+    pub git: HashMap<String, GitConfig>,
+
+    #[serde(default)]
+    pub tag_stderr: bool,
+
+    #[serde(default)]
+    pub cache: BldLocalCacheConfig,
 }
 
 impl BldLocalConfig {
@@ -45,6 +64,23 @@ impl BldLocalConfig {
         debug!("server > pipelines: {}", self.server.pipelines);
         debug!("logs: {}", self.server.logs);
         debug!("db: {:?}", self.server.db);
+        debug!("server > artifacts > path: {}", self.server.artifacts.path);
+        debug!(
+            "server > artifacts > max_age: {:?}",
+            self.server.artifacts.max_age
+        );
+        debug!(
+            "server > artifacts > max_runs: {:?}",
+            self.server.artifacts.max_runs
+        );
+        debug!(
+            "secrets_key: {}",
+            if self.server.secrets_key.is_some() {
+                "set"
+            } else {
+                "not set"
+            }
+        );
         if let Some(Auth::OpenId(openid)) = &self.server.auth {
             debug!("auth > method: openid");
             debug!("auth > issuer_url: {:?}", openid.issuer_url);
@@ -64,6 +100,74 @@ impl BldLocalConfig {
             debug!("server > tls > cert-chain: {}", tls.cert_chain);
             debug!("server > tls > private-key: {}", tls.private_key);
         }
+        if let Some(ha) = &self.server.ha {
+            debug!("server > ha > node_id: {}", ha.node_id);
+            debug!(
+                "server > ha > heartbeat_interval: {}",
+                ha.heartbeat_interval
+            );
+            debug!("server > ha > election_timeout: {}", ha.election_timeout);
+            for member in &ha.members {
+                debug!(
+                    "server > ha > members > {}: {}",
+                    member.id,
+                    member.base_url_http()
+                );
+            }
+        }
+        for (i, hook) in self.server.hooks.iter().enumerate() {
+            debug!("server > hooks > {i} > provider: {}", hook.provider);
+            debug!("server > hooks > {i} > secret: ********");
+            debug!("server > hooks > {i} > pipeline: {}", hook.pipeline);
+            debug!("server > hooks > {i} > repository: {:?}", hook.repository);
+            debug!("server > hooks > {i} > branches: {:?}", hook.branches);
+            debug!("server > hooks > {i} > events: {:?}", hook.events);
+        }
+        for (i, status) in self.server.commit_statuses.iter().enumerate() {
+            debug!(
+                "server > commit_statuses > {i} > provider: {}",
+                status.provider
+            );
+            debug!(
+                "server > commit_statuses > {i} > api_url: {:?}",
+                status.api_url()
+            );
+            debug!("server > commit_statuses > {i} > token: ********");
+            debug!(
+                "server > commit_statuses > {i} > repository: {:?}",
+                status.repository
+            );
+            debug!(
+                "server > commit_statuses > {i} > context: {}",
+                status.context
+            );
+            debug!(
+                "server > commit_statuses > {i} > retries: {}",
+                status.retries
+            );
+        }
+        for (i, notification) in self.server.notifications.iter().enumerate() {
+            debug!(
+                "server > notifications > {i} > type: {}",
+                notification.target
+            );
+            debug!(
+                "server > notifications > {i} > events: {:?}",
+                notification.events
+            );
+            debug!(
+                "server > notifications > {i} > retries: {}",
+                notification.retries
+            );
+        }
+        if let Some(smtp) = &self.server.smtp {
+            debug!("server > smtp > host: {}", smtp.host);
+            debug!("server > smtp > port: {:?}", smtp.port);
+            debug!("server > smtp > username: {:?}", smtp.username);
+            debug!("server > smtp > password: ********");
+            debug!("server > smtp > from: {}", smtp.from);
+            debug!("server > smtp > tls: {:?}", smtp.tls);
+        }
         debug!("supervisor > host {}", self.supervisor.host);
         debug!("supervisor > port {}", self.supervisor.port);
         debug!("supervisor > workers {}", self.supervisor.workers);
@@ -92,7 +196,26 @@ impl BldLocalConfig {
                     debug!("ssh > {key} > userauth > type: agent");
                 }
             }
+            debug!("ssh > {key} > known_hosts: {:?}", config.known_hosts);
+            debug!("ssh > {key} > fingerprint: {:?}", config.fingerprint);
+            debug!(
+                "ssh > {key} > trust_on_first_use: {}",
+                config.trust_on_first_use
+            );
+        }
+        for (key, config) in &self.kubernetes {
+            debug!("kubernetes > {key} > url: {}", config.url);
+            debug!("kubernetes > {key} > namespace: {}", config.namespace);
+            debug!("kubernetes > {key} > token_file: {:?}", config.token_file);
+            debug!("kubernetes > {key} > ca_cert: {:?}", config.ca_cert);
+            debug!("kubernetes > {key} > client_cert: {:?}", config.client_cert);
+            debug!("kubernetes > {key} > pod_timeout: {}", config.pod_timeout);
+        }
+        for (key, config) in &self.git {
+            debug!("git > {key} > username: {}", config.username);
+            debug!("git > {key} > password: ********");
         }
+        debug!("container_engine: {:?}", self.container_engine);
         match &self.docker_url {
             DockerUrl::Single(url) => debug!("docker_url: {url}"),
             DockerUrl::Multiple(urls) => {
@@ -106,6 +229,11 @@ impl BldLocalConfig {
                 }
             }
         }
+        debug!("podman > socket: {:?}", self.podman.socket);
+        debug!("podman > rootless: {}", self.podman.rootless);
+        debug!("tag_stderr: {}", self.tag_stderr);
+        debug!("cache > path: {}", self.cache.path);
+        debug!("cache > max_size: {}", self.cache.max_size);
     }
 }
 
@@ -114,10 +242,16 @@ impl Default for BldLocalConfig {
         Self {
             server: Default::default(),
             supervisor: Default::default(),
+            container_engine: Default::default(),
             docker_url: Default::default(),
+            podman: Default::default(),
             editor: Self::default_editor(),
             ssh: Default::default(),
             registries: Default::default(),
+            kubernetes: Default::default(),
+            git: Default::default(),
+            tag_stderr: false,
+            cache: Default::default(),
         }
     }
 }
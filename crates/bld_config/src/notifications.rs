@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::definitions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BldNotificationEvent {
+    Success,
+    Failure,
+}
+
+impl Display for BldNotificationEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Success => write!(f, "success"),
+            Self::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BldNotificationTarget {
+    /// Sends the details of the run as json to any url.
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+
+    /// Sends an email using the smtp server of the server configuration.
+    Email { to: Vec<String> },
+
+    /// Sends a message to a slack compatible incoming webhook.
+    Slack { url: String },
+
+    /// Sends a message card to a microsoft teams incoming webhook.
+    Teams { url: String },
+
+    /// Sends a message to a matrix room using the client server api.
+    Matrix {
+        homeserver: String,
+        room_id: String,
+        access_token: String,
+    },
+}
+
+impl Display for BldNotificationTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Webhook { .. } => write!(f, "webhook"),
+            Self::Email { .. } => write!(f, "email"),
+            Self::Slack { .. } => write!(f, "slack"),
+            Self::Teams { .. } => write!(f, "teams"),
+            Self::Matrix { .. } => write!(f, "matrix"),
+        }
+    }
+}
+
+impl BldNotificationTarget {
+    /// The values of the target that can contain expressions of a pipeline.
+    pub fn values_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Self::Webhook { url, headers } => {
+                let mut values = vec![url];
+                values.extend(headers.values_mut());
+                values
+            }
+            Self::Email { to } => to.iter_mut().collect(),
+            Self::Slack { url } | Self::Teams { url } => vec![url],
+            Self::Matrix {
+                homeserver,
+                room_id,
+                access_token,
+            } => vec![homeserver, room_id, access_token],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldNotificationConfig {
+    #[serde(flatten)]
+    pub target: BldNotificationTarget,
+
+    /// The events that send the notification, all events when empty.
+    #[serde(default)]
+    pub events: Vec<BldNotificationEvent>,
+
+    /// The number of additional attempts when a notification can't be delivered.
+    #[serde(default = "BldNotificationConfig::default_retries")]
+    pub retries: u64,
+}
+
+impl BldNotificationConfig {
+    fn default_retries() -> u64 {
+        definitions::DEFAULT_NOTIFICATION_RETRIES
+    }
+
+    pub fn is_for(&self, event: BldNotificationEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BldSmtpTls {
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldSmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// The address that notifications are sent from.
+    pub from: String,
+
+    #[serde(default)]
+    pub tls: BldSmtpTls,
+}
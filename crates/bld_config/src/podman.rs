@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::definitions;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    #[default]
+    Docker,
+    Podman,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BldLocalPodmanConfig {
+    pub socket: Option<String>,
+
+    /// When enabled the socket of the podman service of the current user is used
+    /// and operations that require root privileges on the host are rejected.
+    #[serde(default)]
+    pub rootless: bool,
+}
+
+impl BldLocalPodmanConfig {
+    /// Returns the configured socket or the default socket of either the rootful
+    /// or the rootless podman service.
+    pub fn socket_url(&self) -> Result<String> {
+        if let Some(socket) = &self.socket {
+            return Ok(socket.to_owned());
+        }
+
+        if !self.rootless {
+            return Ok(definitions::LOCAL_PODMAN_SOCKET.to_owned());
+        }
+
+        let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|_| {
+            anyhow!("unable to find the rootless podman socket since XDG_RUNTIME_DIR isn't set, define the socket in the podman config")
+        })?;
+
+        Ok(format!(
+            "unix://{}/{}",
+            runtime_dir.trim_end_matches('/'),
+            definitions::LOCAL_PODMAN_ROOTLESS_SOCKET
+        ))
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{definitions, Auth, BldTlsConfig};
+use crate::{
+    definitions, Auth, BldArtifactsConfig, BldCommitStatusConfig, BldHaConfig, BldHookConfig,
+    BldNotificationConfig, BldSmtpConfig, BldTlsConfig,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +23,30 @@ pub struct BldLocalServerConfig {
     pub logs: String,
 
     pub db: Option<String>,
+
+    /// The key used to encrypt the secrets stored in the database.
+    pub secrets_key: Option<String>,
+
+    /// The configuration of the cluster when the server runs in high availability mode.
+    pub ha: Option<BldHaConfig>,
+
+    #[serde(default)]
+    pub artifacts: BldArtifactsConfig,
+
+    /// The webhooks that start pipeline runs on deliveries from git providers.
+    #[serde(default)]
+    pub hooks: Vec<BldHookConfig>,
+
+    /// The providers that the results of runs started by webhooks are reported to.
+    #[serde(default)]
+    pub commit_statuses: Vec<BldCommitStatusConfig>,
+
+    /// The notifications sent for runs of pipelines that don't declare their own.
+    #[serde(default)]
+    pub notifications: Vec<BldNotificationConfig>,
+
+    /// The smtp server used for email notifications.
+    pub smtp: Option<BldSmtpConfig>,
 }
 
 impl BldLocalServerConfig {
@@ -82,6 +109,13 @@ impl Default for BldLocalServerConfig {
             pipelines: Self::default_pipelines(),
             logs: Self::default_logs(),
             db: None,
+            secrets_key: None,
+            ha: None,
+            artifacts: Default::default(),
+            hooks: vec![],
+            commit_statuses: vec![],
+            notifications: vec![],
+            smtp: None,
         }
     }
 }
@@ -21,6 +21,10 @@ pub struct SshConfig {
     pub port: String,
     pub user: String,
     pub userauth: SshUserAuth,
+    pub known_hosts: Option<String>,
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub trust_on_first_use: bool,
 }
 
 impl SshConfig {
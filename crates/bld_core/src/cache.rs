@@ -0,0 +1,127 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Result};
+use bld_config::{path, BldConfig};
+use tokio::fs::{create_dir_all, metadata, read_dir, remove_file, rename};
+use tracing::debug;
+use uuid::Uuid;
+
+const CACHE_ARCHIVE_EXTENSION: &str = "tar.gz";
+const CACHE_STAGING_PREFIX: &str = ".staging-";
+
+/// Local store of the cache archives that are shared between pipeline runs.
+/// Each key maps to a single tarball and the least recently used archives are
+/// removed when the size of the store exceeds the configured limit.
+pub struct CacheStore {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl CacheStore {
+    pub fn new(config: &BldConfig) -> Self {
+        Self {
+            dir: config.cache_full_path(),
+            max_size: config.local.cache.max_size_in_bytes(),
+        }
+    }
+
+    fn sanitize_key(key: &str) -> Result<String> {
+        let key: String = key
+            .trim()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        if key.is_empty() || key.starts_with('.') {
+            bail!("invalid cache key {key}");
+        }
+
+        Ok(key)
+    }
+
+    fn archive_path(&self, key: &str) -> Result<PathBuf> {
+        let key = Self::sanitize_key(key)?;
+        Ok(path![&self.dir, format!("{key}.{CACHE_ARCHIVE_EXTENSION}")])
+    }
+
+    /// Returns the path of the archive for the provided key if one exists and marks it
+    /// as recently used.
+    pub async fn find(&self, key: &str) -> Result<Option<PathBuf>> {
+        let path = self.archive_path(key)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let file = std::fs::File::options().write(true).open(&path)?;
+        file.set_modified(SystemTime::now())?;
+
+        Ok(Some(path))
+    }
+
+    /// Creates a unique path in the store that a new archive can be written to before
+    /// it is committed with a key.
+    pub async fn staging_path(&self) -> Result<PathBuf> {
+        if !self.dir.is_dir() {
+            create_dir_all(&self.dir).await?;
+        }
+        let name = format!(
+            "{CACHE_STAGING_PREFIX}{}.{CACHE_ARCHIVE_EXTENSION}",
+            Uuid::new_v4()
+        );
+        Ok(path![&self.dir, name])
+    }
+
+    /// Moves a staged archive to the path of the provided key, replacing any previous
+    /// archive, and evicts entries if the store has grown over its limit.
+    pub async fn commit(&self, staging: &Path, key: &str) -> Result<()> {
+        let path = self.archive_path(key)?;
+        rename(staging, &path).await?;
+        self.evict().await
+    }
+
+    async fn evict(&self) -> Result<()> {
+        let mut entries = vec![];
+        let mut total_size = 0;
+
+        let mut dir = read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let name = entry.file_name();
+            let is_archive = name
+                .to_str()
+                .map(|x| {
+                    !x.starts_with(CACHE_STAGING_PREFIX) && x.ends_with(CACHE_ARCHIVE_EXTENSION)
+                })
+                .unwrap_or_default();
+
+            if !is_archive {
+                continue;
+            }
+
+            let metadata = metadata(entry.path()).await?;
+            total_size += metadata.len();
+            entries.push((entry.path(), metadata.modified()?, metadata.len()));
+        }
+
+        entries.sort_by_key(|x| x.1);
+
+        for (path, _, size) in entries {
+            if total_size <= self.max_size {
+                break;
+            }
+            debug!("evicting cache archive {}", path.display());
+            remove_file(&path).await?;
+            total_size -= size;
+        }
+
+        Ok(())
+    }
+}
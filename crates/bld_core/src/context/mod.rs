@@ -4,11 +4,11 @@ pub mod server;
 
 use crate::platform::Platform;
 use anyhow::{anyhow, Result};
-use bld_config::BldConfig;
-use bld_models::pipeline_run_containers::PipelineRunContainers;
+use bld_config::{BldConfig, BldNotificationConfig};
+use bld_models::{pipeline_run_containers::PipelineRunContainers, pipeline_runs::PipelineRuns};
 use run::RemoteRun;
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::mpsc::{channel, Sender};
 use tokio::sync::oneshot;
 
@@ -24,9 +24,13 @@ pub enum Context {
 }
 
 impl Context {
-    pub fn server(config: Arc<BldConfig>, pool: Arc<DatabaseConnection>, run_id: &str) -> Self {
+    pub fn server(
+        config: Arc<BldConfig>,
+        pool: Arc<DatabaseConnection>,
+        run: &PipelineRuns,
+    ) -> Self {
         let (tx, rx) = channel(4096);
-        ServerContextBackend::new(config, pool.clone(), run_id, rx).receive();
+        ServerContextBackend::new(config, pool.clone(), run, rx).receive();
         Self::Server {
             tx,
             conn: pool.clone(),
@@ -101,6 +105,31 @@ impl Context {
         }
     }
 
+    /// Replaces the notifications of the server configuration with the ones of the pipeline.
+    pub async fn set_notifications(&self, notifications: Vec<BldNotificationConfig>) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetNotifications(notifications))
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Waits for the commit statuses and notifications of the run to be delivered.
+    pub async fn flush_notifications(&self) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        tx.send(ServerContextMessage::FlushNotifications(resp_tx))
+            .await
+            .map_err(|e| anyhow!("{e}"))?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
     pub async fn set_pipeline_as_running(&self, run_id: String) -> Result<()> {
         let Self::Server { tx, .. } = self else {
             return Ok(());
@@ -116,9 +145,12 @@ impl Context {
             return Ok(());
         };
 
-        tx.send(ServerContextMessage::SetPipelineAsFinished(run_id))
+        let (resp_tx, resp_rx) = oneshot::channel();
+        tx.send(ServerContextMessage::SetPipelineAsFinished { run_id, resp_tx })
             .await
-            .map_err(|e| anyhow!("{e}"))
+            .map_err(|e| anyhow!("{e}"))?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
     }
 
     pub async fn set_pipeline_as_faulted(&self, run_id: String) -> Result<()> {
@@ -126,9 +158,12 @@ impl Context {
             return Ok(());
         };
 
-        tx.send(ServerContextMessage::SetPipelineAsFaulted(run_id))
+        let (resp_tx, resp_rx) = oneshot::channel();
+        tx.send(ServerContextMessage::SetPipelineAsFaulted { run_id, resp_tx })
             .await
-            .map_err(|e| anyhow!("{e}"))
+            .map_err(|e| anyhow!("{e}"))?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
     }
 
     pub async fn add_container(
@@ -181,6 +216,110 @@ impl Context {
             .map_err(|e| anyhow!("{e}"))
     }
 
+    /// Retrieves the decrypted secrets of the server, a local context has no secrets
+    /// so a run that references one fails when its tokens are applied.
+    pub async fn secrets(&self) -> Result<HashMap<String, String>> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(HashMap::new());
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(ServerContextMessage::GetSecrets(resp_tx))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        resp_rx.await.map_err(|e| anyhow!("{e}"))?
+    }
+
+    pub async fn add_job(&self, name: String, state: &str) -> Result<Option<String>> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(None);
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(ServerContextMessage::AddJob {
+            name,
+            state: state.to_owned(),
+            resp_tx,
+        })
+        .await
+        .map_err(|e| anyhow!(e.to_string()))?;
+
+        resp_rx.await.map_err(|e| anyhow!("{e}"))
+    }
+
+    pub async fn set_job_state(&self, id: String, state: &str) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetJobState {
+            id,
+            state: state.to_owned(),
+        })
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    pub async fn add_step(
+        &self,
+        job_id: String,
+        name: Option<String>,
+        position: i32,
+        state: &str,
+    ) -> Result<Option<String>> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(None);
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(ServerContextMessage::AddStep {
+            job_id,
+            name,
+            position,
+            state: state.to_owned(),
+            resp_tx,
+        })
+        .await
+        .map_err(|e| anyhow!(e.to_string()))?;
+
+        resp_rx.await.map_err(|e| anyhow!("{e}"))
+    }
+
+    pub async fn set_step_state(
+        &self,
+        id: String,
+        state: &str,
+        exit_code: Option<i64>,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetStepState {
+            id,
+            state: state.to_owned(),
+            exit_code,
+        })
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Records an artifact of the run that has been stored in the artifacts directory
+    /// of the server, a local context doesn't keep track of artifacts.
+    pub async fn add_artifact(&self, name: String, size: i64) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::AddArtifact { name, size })
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+
     pub async fn run_faulted(&self) -> Result<()> {
         let (resp_tx, resp_rx) = oneshot::channel();
 
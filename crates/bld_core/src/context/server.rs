@@ -1,18 +1,25 @@
 use super::run::RemoteRun;
-use crate::platform::Platform;
+use crate::{
+    notifier::{CommitState, CommitStatusNotifier, DeliveryQueue, RunNotifier},
+    platform::Platform,
+};
 use actix::spawn;
 use anyhow::{anyhow, Result};
-use bld_config::BldConfig;
+use bld_config::{BldConfig, BldNotificationConfig, BldNotificationEvent};
 use bld_http::Request;
 use bld_models::{
+    pipeline_run_artifacts::{self, InsertPipelineRunArtifact},
     pipeline_run_containers::{
         self, InsertPipelineRunContainer, PipelineRunContainers, PRC_STATE_FAULTED,
         PRC_STATE_KEEP_ALIVE, PRC_STATE_REMOVED,
     },
-    pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_RUNNING},
+    pipeline_run_jobs::{self, InsertPipelineRunJob},
+    pipeline_run_steps::{self, InsertPipelineRunStep},
+    pipeline_runs::{self, PipelineRuns, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_RUNNING},
+    secrets,
 };
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{mpsc::Receiver, oneshot};
 use tracing::{debug, error};
 use uuid::Uuid;
@@ -22,9 +29,17 @@ pub enum ServerContextMessage {
     RemoveRemoteRun(String),
     AddPlatform(Arc<Platform>),
     RemovePlatform(String),
+    SetNotifications(Vec<BldNotificationConfig>),
+    FlushNotifications(oneshot::Sender<()>),
     SetPipelineAsRunning(String),
-    SetPipelineAsFinished(String),
-    SetPipelineAsFaulted(String),
+    SetPipelineAsFinished {
+        run_id: String,
+        resp_tx: oneshot::Sender<()>,
+    },
+    SetPipelineAsFaulted {
+        run_id: String,
+        resp_tx: oneshot::Sender<()>,
+    },
     AddContainer {
         container_id: String,
         resp_tx: oneshot::Sender<Option<PipelineRunContainers>>,
@@ -32,6 +47,32 @@ pub enum ServerContextMessage {
     SetContainerAsRemoved(String),
     SetContainerAsFaulted(String),
     KeepAliveContainer(String),
+    AddJob {
+        name: String,
+        state: String,
+        resp_tx: oneshot::Sender<Option<String>>,
+    },
+    SetJobState {
+        id: String,
+        state: String,
+    },
+    AddStep {
+        job_id: String,
+        name: Option<String>,
+        position: i32,
+        state: String,
+        resp_tx: oneshot::Sender<Option<String>>,
+    },
+    SetStepState {
+        id: String,
+        state: String,
+        exit_code: Option<i64>,
+    },
+    AddArtifact {
+        name: String,
+        size: i64,
+    },
+    GetSecrets(oneshot::Sender<Result<HashMap<String, String>>>),
     RunFaulted(oneshot::Sender<()>),
 }
 
@@ -41,6 +82,7 @@ pub struct ServerContextBackend {
     remote_runs: Vec<RemoteRun>,
     conn: Arc<DatabaseConnection>,
     platforms: Vec<Arc<Platform>>,
+    deliveries: DeliveryQueue,
     rx: Receiver<ServerContextMessage>,
 }
 
@@ -48,23 +90,27 @@ impl ServerContextBackend {
     pub fn new(
         config: Arc<BldConfig>,
         conn: Arc<DatabaseConnection>,
-        run_id: &str,
+        run: &PipelineRuns,
         rx: Receiver<ServerContextMessage>,
     ) -> Self {
+        let commit_status = run
+            .hook()
+            .and_then(|hook| CommitStatusNotifier::new(config.clone(), &run.id, hook));
+        let notifier = RunNotifier::new(config.clone(), conn.clone());
+        let deliveries = DeliveryQueue::new(commit_status, notifier);
         Self {
             config,
-            run_id: run_id.to_owned(),
+            run_id: run.id.to_owned(),
             remote_runs: vec![],
             conn,
             platforms: vec![],
+            deliveries,
             rx,
         }
     }
 
-    pub async fn update_pipeline_state(&self, run_id: &str, state: &str) -> Result<()> {
-        pipeline_runs::update_state(self.conn.as_ref(), run_id, state)
-            .await
-            .map(|_| ())
+    pub async fn update_pipeline_state(&self, run_id: &str, state: &str) -> Result<PipelineRuns> {
+        pipeline_runs::update_state(self.conn.as_ref(), run_id, state).await
     }
 
     pub fn receive(self) {
@@ -95,19 +141,44 @@ impl ServerContextBackend {
                     self.platforms.retain(|p| !p.is(&platform_id));
                 }
 
+                ServerContextMessage::SetNotifications(notifications) => {
+                    self.deliveries.set_notifications(notifications);
+                }
+
+                ServerContextMessage::FlushNotifications(resp_tx) => {
+                    let deliveries = self.deliveries.clone();
+                    spawn(async move {
+                        deliveries.flush().await;
+                        let _ = resp_tx.send(());
+                    });
+                }
+
                 ServerContextMessage::SetPipelineAsRunning(run_id) => {
                     self.update_pipeline_state(&run_id, PR_STATE_RUNNING)
                         .await?;
+                    self.deliveries.commit_status(CommitState::Running);
                 }
 
-                ServerContextMessage::SetPipelineAsFinished(run_id) => {
-                    self.update_pipeline_state(&run_id, PR_STATE_FINISHED)
+                ServerContextMessage::SetPipelineAsFinished { run_id, resp_tx } => {
+                    let run = self
+                        .update_pipeline_state(&run_id, PR_STATE_FINISHED)
                         .await?;
+                    self.deliveries.commit_status(CommitState::Success);
+                    self.deliveries.notify(BldNotificationEvent::Success, run);
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
                 }
 
-                ServerContextMessage::SetPipelineAsFaulted(run_id) => {
-                    self.update_pipeline_state(&run_id, PR_STATE_FAULTED)
+                ServerContextMessage::SetPipelineAsFaulted { run_id, resp_tx } => {
+                    let run = self
+                        .update_pipeline_state(&run_id, PR_STATE_FAULTED)
                         .await?;
+                    self.deliveries.commit_status(CommitState::Failure);
+                    self.deliveries.notify(BldNotificationEvent::Failure, run);
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
                 }
 
                 ServerContextMessage::AddContainer {
@@ -142,6 +213,56 @@ impl ServerContextBackend {
                     .await?;
                 }
 
+                ServerContextMessage::AddJob {
+                    name,
+                    state,
+                    resp_tx,
+                } => self.add_job(name, state, resp_tx).await?,
+
+                ServerContextMessage::SetJobState { id, state } => {
+                    pipeline_run_jobs::update_state(self.conn.as_ref(), &id, &state).await?;
+                }
+
+                ServerContextMessage::AddStep {
+                    job_id,
+                    name,
+                    position,
+                    state,
+                    resp_tx,
+                } => {
+                    let model = InsertPipelineRunStep {
+                        id: Uuid::new_v4().to_string(),
+                        job_id,
+                        name,
+                        position,
+                        state,
+                    };
+                    self.add_step(model, resp_tx).await?
+                }
+
+                ServerContextMessage::SetStepState {
+                    id,
+                    state,
+                    exit_code,
+                } => {
+                    pipeline_run_steps::update_state(self.conn.as_ref(), &id, &state, exit_code)
+                        .await?;
+                }
+
+                ServerContextMessage::AddArtifact { name, size } => {
+                    let model = InsertPipelineRunArtifact {
+                        id: Uuid::new_v4().to_string(),
+                        run_id: self.run_id.to_owned(),
+                        name,
+                        size,
+                    };
+                    let _ = pipeline_run_artifacts::upsert(self.conn.as_ref(), model)
+                        .await
+                        .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::GetSecrets(resp_tx) => self.get_secrets(resp_tx).await?,
+
                 ServerContextMessage::RunFaulted(resp_tx) => self.run_faulted(resp_tx).await?,
             }
         }
@@ -160,6 +281,8 @@ impl ServerContextBackend {
                 run_id: self.run_id.to_owned(),
                 container_id: container_id.to_owned(),
                 state: "active".to_owned(),
+                image: None,
+                image_digest: None,
             },
         )
         .await
@@ -173,9 +296,69 @@ impl ServerContextBackend {
         Ok(())
     }
 
+    async fn get_secrets(
+        &self,
+        resp_tx: oneshot::Sender<Result<HashMap<String, String>>>,
+    ) -> Result<()> {
+        let secrets = match &self.config.local.server.secrets_key {
+            Some(key) => secrets::select_all_decrypted(self.conn.as_ref(), key).await,
+            None => Ok(HashMap::new()),
+        };
+
+        resp_tx
+            .send(secrets)
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
+
+    async fn add_job(
+        &mut self,
+        name: String,
+        state: String,
+        resp_tx: oneshot::Sender<Option<String>>,
+    ) -> Result<()> {
+        let entity = pipeline_run_jobs::insert(
+            self.conn.as_ref(),
+            InsertPipelineRunJob {
+                id: Uuid::new_v4().to_string(),
+                run_id: self.run_id.to_owned(),
+                name,
+                state,
+            },
+        )
+        .await
+        .map_err(|e| error!("{e}"))
+        .ok();
+
+        resp_tx
+            .send(entity.map(|x| x.id))
+            .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+
+        Ok(())
+    }
+
+    async fn add_step(
+        &mut self,
+        model: InsertPipelineRunStep,
+        resp_tx: oneshot::Sender<Option<String>>,
+    ) -> Result<()> {
+        let entity = pipeline_run_steps::insert(self.conn.as_ref(), model)
+            .await
+            .map_err(|e| error!("{e}"))
+            .ok();
+
+        resp_tx
+            .send(entity.map(|x| x.id))
+            .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+
+        Ok(())
+    }
+
     async fn run_faulted(&mut self, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        self.update_pipeline_state(&self.run_id, PR_STATE_FAULTED)
+        let run = self
+            .update_pipeline_state(&self.run_id, PR_STATE_FAULTED)
             .await?;
+        self.deliveries.commit_status(CommitState::Failure);
+        self.deliveries.notify(BldNotificationEvent::Failure, run);
 
         for run in self.remote_runs.iter() {
             let _ = self
@@ -38,6 +38,13 @@ impl FileSystem {
         Self::Server { config, conn }
     }
 
+    pub fn get_conn(&self) -> Option<Arc<DatabaseConnection>> {
+        match self {
+            Self::Local { .. } => None,
+            Self::Server { conn, .. } => Some(conn.clone()),
+        }
+    }
+
     fn config(&self) -> &BldConfig {
         match self {
             Self::Server { config, .. } | Self::Local { config } => config,
@@ -113,6 +120,20 @@ impl FileSystem {
     }
 
     pub async fn create(&self, name: &str, content: &str, overwrite: bool) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        self.create_with_id(&id, name, content, overwrite).await
+    }
+
+    /// Creates the pipeline using the provided id for its database entry if the
+    /// pipeline doesn't already exist, so that the same pipeline can be created
+    /// with the same id on multiple servers.
+    pub async fn create_with_id(
+        &self,
+        id: &str,
+        name: &str,
+        content: &str,
+        overwrite: bool,
+    ) -> Result<()> {
         let local_path = self.config().full_path(name);
 
         if !local_path.valid_path() {
@@ -122,9 +143,8 @@ impl FileSystem {
         if let Self::Server { conn, .. } = self {
             let response = pipeline::select_by_name(conn.as_ref(), name).await;
             if response.is_err() {
-                let id = Uuid::new_v4().to_string();
                 let model = InsertPipeline {
-                    id,
+                    id: id.to_owned(),
                     name: name.to_owned(),
                 };
                 pipeline::insert(conn.as_ref(), model).await?;
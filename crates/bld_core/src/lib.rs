@@ -1,6 +1,8 @@
+pub mod cache;
 pub mod context;
 pub mod fs;
 pub mod logger;
+pub mod notifier;
 pub mod platform;
 pub mod regex;
 pub mod scanner;
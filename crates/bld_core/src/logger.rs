@@ -1,6 +1,8 @@
 use actix_web::rt::spawn;
 use anyhow::{anyhow, Result};
 use bld_config::BldConfig;
+use bld_models::dtos::{LogEntry, LogLevel, LogStream};
+use chrono::{SecondsFormat, Utc};
 use std::{fmt::Write as FmtWrite, io::Write, sync::Arc};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use tokio::{
@@ -13,7 +15,7 @@ use tokio::{
 };
 use tracing::error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum LogType {
     Write,
     WriteLine,
@@ -21,6 +23,43 @@ enum LogType {
     InfoLine,
     Error,
     ErrorLine,
+    Stderr,
+    StderrLine,
+}
+
+impl LogType {
+    fn is_line(&self) -> bool {
+        matches!(
+            self,
+            Self::WriteLine | Self::InfoLine | Self::ErrorLine | Self::StderrLine
+        )
+    }
+
+    /// The same type without the trailing new line, used when the new line
+    /// is already part of the text.
+    fn without_line(&self) -> Self {
+        match self {
+            Self::WriteLine => Self::Write,
+            Self::InfoLine => Self::Info,
+            Self::ErrorLine => Self::Error,
+            Self::StderrLine => Self::Stderr,
+            _ => *self,
+        }
+    }
+
+    fn level(&self) -> LogLevel {
+        match self {
+            Self::Error | Self::ErrorLine => LogLevel::Error,
+            _ => LogLevel::Info,
+        }
+    }
+
+    fn stream(&self) -> LogStream {
+        match self {
+            Self::Stderr | Self::StderrLine => LogStream::Stderr,
+            _ => LogStream::Stdout,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -30,19 +69,40 @@ enum LoggerMessage {
         log_type: LogType,
         resp_tx: oneshot::Sender<()>,
     },
+    WriteEntries {
+        entries: Vec<LogEntry>,
+        resp_tx: oneshot::Sender<()>,
+    },
+    SetScope {
+        job: Option<String>,
+        step: Option<String>,
+        resp_tx: oneshot::Sender<()>,
+    },
+    AddSecrets {
+        secrets: Vec<String>,
+        resp_tx: oneshot::Sender<()>,
+    },
     TryRetrieveOutput {
         resp_tx: oneshot::Sender<String>,
     },
+    TryRetrieveEntries {
+        resp_tx: oneshot::Sender<Vec<LogEntry>>,
+    },
 }
 
 enum LoggerType {
     Shell,
     File(File),
-    InMemory(String),
+    InMemory(Vec<LogEntry>),
 }
 
 struct LoggerBackend {
     logger_type: LoggerType,
+    job: Option<String>,
+    step: Option<String>,
+    secrets: Vec<String>,
+    masked_tails: Vec<LogEntry>,
+    partial_lines: Vec<LogEntry>,
     rx: Receiver<LoggerMessage>,
 }
 
@@ -50,6 +110,11 @@ impl LoggerBackend {
     pub fn shell(rx: Receiver<LoggerMessage>) -> Self {
         Self {
             logger_type: LoggerType::Shell,
+            job: None,
+            step: None,
+            secrets: vec![],
+            masked_tails: vec![],
+            partial_lines: vec![],
             rx,
         }
     }
@@ -66,13 +131,23 @@ impl LoggerBackend {
             } else {
                 File::create(&path).await?
             }),
+            job: None,
+            step: None,
+            secrets: vec![],
+            masked_tails: vec![],
+            partial_lines: vec![],
             rx,
         })
     }
 
     pub fn in_memory(rx: Receiver<LoggerMessage>) -> Self {
         Self {
-            logger_type: LoggerType::InMemory(String::new()),
+            logger_type: LoggerType::InMemory(vec![]),
+            job: None,
+            step: None,
+            secrets: vec![],
+            masked_tails: vec![],
+            partial_lines: vec![],
             rx,
         }
     }
@@ -82,46 +157,53 @@ impl LoggerBackend {
             match msg {
                 LoggerMessage::Write {
                     text,
-                    log_type: LogType::Write,
-                    resp_tx,
-                } => self.write(&text, resp_tx).await?,
-
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::WriteLine,
+                    log_type,
                     resp_tx,
-                } => self.write_line(&text, resp_tx).await?,
-
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::Info,
-                    resp_tx,
-                } => self.info(&text, resp_tx).await?,
+                } => {
+                    self.write(&text, log_type).await?;
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::InfoLine,
-                    resp_tx,
-                } => self.info_line(&text, resp_tx).await?,
+                LoggerMessage::WriteEntries { entries, resp_tx } => {
+                    for entry in entries {
+                        self.write_entry(entry).await?;
+                    }
+                    self.flush_pending().await?;
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::Error,
-                    resp_tx,
-                } => self.error(&text, resp_tx).await?,
+                LoggerMessage::SetScope { job, step, resp_tx } => {
+                    self.flush_pending().await?;
+                    self.job = job;
+                    self.step = step;
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::ErrorLine,
-                    resp_tx,
-                } => self.error_line(&text, resp_tx).await?,
+                LoggerMessage::AddSecrets { secrets, resp_tx } => {
+                    self.add_secrets(secrets);
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
 
                 LoggerMessage::TryRetrieveOutput { resp_tx } => {
+                    self.flush_pending().await?;
                     self.try_retrieve_output(resp_tx).await?
                 }
+
+                LoggerMessage::TryRetrieveEntries { resp_tx } => {
+                    self.flush_pending().await?;
+                    self.try_retrieve_entries(resp_tx)?
+                }
             }
         }
-        Ok(())
+        self.flush_pending().await
     }
 
     pub fn receive(self) {
@@ -132,150 +214,250 @@ impl LoggerBackend {
         });
     }
 
-    pub async fn write(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        match &mut self.logger_type {
-            LoggerType::Shell => {
-                print!("{}", text);
-            }
-            LoggerType::File(handle) => {
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
-            }
-            LoggerType::InMemory(output) => {
-                write!(output, "{text}")?;
+    fn add_secrets(&mut self, secrets: Vec<String>) {
+        for secret in secrets {
+            // multi line secrets are also masked line by line since the output
+            // of the platforms is streamed one line at a time.
+            let lines: Vec<String> = secret.lines().map(|x| x.to_owned()).collect();
+            self.secrets.push(secret);
+            if lines.len() > 1 {
+                self.secrets.extend(lines);
             }
         }
-
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        self.secrets.retain(|x| !x.trim().is_empty());
+        self.secrets
+            .sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        self.secrets.dedup();
     }
 
-    pub async fn write_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        match &mut self.logger_type {
-            LoggerType::Shell => {
-                println!("{text}");
-            }
-            LoggerType::File(handle) => {
-                let text = format!("{text}\n");
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
-            }
-            LoggerType::InMemory(output) => {
-                writeln!(output, "{text}")?;
+    fn mask(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for secret in self.secrets.iter() {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), "***");
             }
         }
-
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        text
     }
 
-    pub async fn info(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        match &mut self.logger_type {
-            LoggerType::Shell => {
-                let mut stdout = StandardStream::stdout(ColorChoice::Always);
-                let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)));
-                let _ = write!(&mut stdout, "{text}");
-                let _ = stdout.set_color(ColorSpec::new().set_fg(None));
-            }
-            LoggerType::File(handle) => {
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
-            }
-            LoggerType::InMemory(output) => {
-                write!(output, "{text}")?;
-            }
+    /// Masks the text of an entry, holding back its end when it could be the start of
+    /// a secret so that secrets split between the chunks of the output are masked too.
+    fn mask_entry(&mut self, mut entry: LogEntry) -> LogEntry {
+        if let Some(position) = self
+            .masked_tails
+            .iter()
+            .position(|x| Self::same_scope(x, &entry))
+        {
+            let mut tail = self.masked_tails.remove(position);
+            tail.message.push_str(&entry.message);
+            entry = tail;
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        let text = &entry.message;
+        let max_len = self.secrets.first().map(|x| x.len()).unwrap_or(0);
+        let mut split = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .filter(|i| text.len() - i < max_len)
+            .find(|i| {
+                let suffix = &text[*i..];
+                self.secrets
+                    .iter()
+                    .any(|x| x.len() > suffix.len() && x.starts_with(suffix))
+            })
+            .unwrap_or(text.len());
+
+        // the text is never split inside of a secret.
+        while let Some(start) = self.secrets.iter().find_map(|secret| {
+            text.match_indices(secret.as_str())
+                .map(|(start, _)| start)
+                .find(|start| *start < split && split < start + secret.len())
+        }) {
+            split = start;
+        }
+
+        let tail = entry.message.split_off(split);
+        if !tail.is_empty() {
+            self.masked_tails.push(LogEntry {
+                message: tail,
+                ..entry.clone()
+            });
+        }
+        entry.message = self.mask(&entry.message);
+        entry
     }
 
-    pub async fn info_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        match &mut self.logger_type {
-            LoggerType::Shell => {
-                let mut stdout = StandardStream::stdout(ColorChoice::Always);
-                let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)));
-                let _ = writeln!(&mut stdout, "{text}");
-                let _ = stdout.set_color(ColorSpec::new().set_fg(None));
-            }
-            LoggerType::File(handle) => {
-                let text = format!("{text}\n");
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
-            }
-            LoggerType::InMemory(output) => {
-                writeln!(output, "{text}")?;
-            }
+    fn same_scope(left: &LogEntry, right: &LogEntry) -> bool {
+        left.job == right.job
+            && left.step == right.step
+            && left.stream == right.stream
+            && left.level == right.level
+    }
+
+    async fn write(&mut self, text: &str, log_type: LogType) -> Result<()> {
+        let message = if log_type.is_line() {
+            format!("{text}\n")
+        } else {
+            text.to_owned()
+        };
+
+        let entry = LogEntry {
+            timestamp: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+            job: self.job.clone(),
+            step: self.step.clone(),
+            stream: log_type.stream(),
+            level: log_type.level(),
+            message,
+        };
+
+        if let LoggerType::Shell = self.logger_type {
+            let entry = self.mask_entry(entry);
+            Self::shell_write(&entry.message, log_type.without_line());
+            return Ok(());
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        self.write_entry(entry).await
+    }
+
+    async fn write_entry(&mut self, entry: LogEntry) -> Result<()> {
+        let entry = self.mask_entry(entry);
+        if entry.message.is_empty() {
+            return Ok(());
+        }
+        self.write_masked_entry(entry).await
     }
 
-    pub async fn error(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn write_masked_entry(&mut self, mut entry: LogEntry) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
-                let mut stderr = StandardStream::stderr(ColorChoice::Always);
-                let _ = stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)));
-                let _ = write!(&mut stderr, "{text}");
-                let _ = stderr.set_color(ColorSpec::new().set_fg(None));
+                let log_type = match entry.level {
+                    LogLevel::Error => LogType::Error,
+                    LogLevel::Info => LogType::Write,
+                };
+                Self::shell_write(&entry.message, log_type);
             }
-            LoggerType::File(handle) => {
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
+            LoggerType::File(_) => {
+                // the file logger keeps one json entry per line so that the
+                // log file can be tailed and filtered by job or step, so the
+                // trailing partial line of an entry is kept until the rest of
+                // it is written in the same scope.
+                let position = self
+                    .partial_lines
+                    .iter()
+                    .position(|x| Self::same_scope(x, &entry));
+                if let Some(position) = position {
+                    let mut partial = self.partial_lines.remove(position);
+                    partial.message.push_str(&entry.message);
+                    entry = partial;
+                }
+
+                let end = entry.message.rfind('\n').map(|x| x + 1).unwrap_or(0);
+                let remainder = entry.message.split_off(end);
+                if !remainder.is_empty() {
+                    self.partial_lines.push(LogEntry {
+                        message: remainder,
+                        ..entry.clone()
+                    });
+                }
+                self.write_file_lines(&entry).await?;
             }
-            LoggerType::InMemory(output) => {
-                write!(output, "{text}")?;
+            LoggerType::InMemory(entries) => {
+                entries.push(entry);
             }
         }
+        Ok(())
+    }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot rsponse sender dropped"))
+    async fn write_file_lines(&mut self, entry: &LogEntry) -> Result<()> {
+        let LoggerType::File(handle) = &mut self.logger_type else {
+            return Ok(());
+        };
+
+        for line in entry.message.lines() {
+            let line_entry = LogEntry {
+                message: line.to_owned(),
+                ..entry.clone()
+            };
+            let mut text = serde_json::to_string(&line_entry)?;
+            text.push('\n');
+            handle.write_all(text.as_bytes()).await?;
+        }
+        handle.flush().await?;
+        Ok(())
     }
 
-    pub async fn error_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
-        match &mut self.logger_type {
-            LoggerType::Shell => {
-                let mut stderr = StandardStream::stderr(ColorChoice::Always);
-                let _ = stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)));
-                let _ = writeln!(&mut stderr, "{text}");
-                let _ = stderr.set_color(ColorSpec::new().set_fg(None));
-            }
-            LoggerType::File(handle) => {
-                let text = format!("{text}\n");
-                let bytes = text.as_bytes();
-                handle.write_all(bytes).await?;
-            }
-            LoggerType::InMemory(output) => {
-                writeln!(output, "{text}")?;
-            }
+    /// Writes the masked tails and partial lines that are still pending, used when the
+    /// scope of the logger changes or no more entries are expected for them.
+    async fn flush_pending(&mut self) -> Result<()> {
+        for mut entry in std::mem::take(&mut self.masked_tails) {
+            entry.message = self.mask(&entry.message);
+            self.write_masked_entry(entry).await?;
         }
+        for entry in std::mem::take(&mut self.partial_lines) {
+            self.write_file_lines(&entry).await?;
+        }
+        Ok(())
+    }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    fn shell_write(text: &str, log_type: LogType) {
+        let color = match log_type {
+            LogType::Info | LogType::InfoLine => Some(Color::Green),
+            LogType::Error | LogType::ErrorLine => Some(Color::Red),
+            _ => None,
+        };
+
+        let Some(color) = color else {
+            if log_type.is_line() {
+                println!("{text}");
+            } else {
+                print!("{text}");
+            }
+            return;
+        };
+
+        let mut stream = match log_type.level() {
+            LogLevel::Error => StandardStream::stderr(ColorChoice::Always),
+            LogLevel::Info => StandardStream::stdout(ColorChoice::Always),
+        };
+        let _ = stream.set_color(ColorSpec::new().set_fg(Some(color)));
+        if log_type.is_line() {
+            let _ = writeln!(&mut stream, "{text}");
+        } else {
+            let _ = write!(&mut stream, "{text}");
+        }
+        let _ = stream.set_color(ColorSpec::new().set_fg(None));
     }
 
     async fn try_retrieve_output(&mut self, resp_tx: oneshot::Sender<String>) -> Result<()> {
         let output = match &mut self.logger_type {
             LoggerType::Shell => String::new(),
             LoggerType::File(handle) => {
+                let mut content = String::new();
+                handle.read_to_string(&mut content).await?;
                 let mut output = String::new();
-                handle.read_to_string(&mut output).await?;
+                for line in content.lines() {
+                    writeln!(output, "{}", LogEntry::parse(line))?;
+                }
                 output
             }
-            LoggerType::InMemory(output) => output.clone(),
+            LoggerType::InMemory(entries) => entries.iter().map(|x| x.message.as_str()).collect(),
         };
 
         resp_tx
             .send(output)
             .map_err(|_| anyhow!("oneshot response sender dropped"))
     }
+
+    fn try_retrieve_entries(&mut self, resp_tx: oneshot::Sender<Vec<LogEntry>>) -> Result<()> {
+        let entries = match &mut self.logger_type {
+            LoggerType::InMemory(entries) => entries.clone(),
+            LoggerType::Shell | LoggerType::File(_) => vec![],
+        };
+
+        resp_tx
+            .send(entries)
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
 }
 
 pub struct Logger {
@@ -405,6 +587,66 @@ impl Logger {
         resp_rx.await.map_err(|e| anyhow!(e))
     }
 
+    pub async fn stderr(&self, text: String) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::Write {
+                text,
+                log_type: LogType::Stderr,
+                resp_tx,
+            })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    pub async fn stderr_line(&self, text: String) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::Write {
+                text,
+                log_type: LogType::StderrLine,
+                resp_tx,
+            })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    pub async fn write_entries(&self, entries: Vec<LogEntry>) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::WriteEntries { entries, resp_tx })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Sets the job and step that the next log entries will be tagged with.
+    pub async fn set_scope(&self, job: Option<String>, step: Option<String>) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::SetScope { job, step, resp_tx })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Adds values that will be masked in every entry written by the logger.
+    pub async fn add_secrets(&self, secrets: Vec<String>) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::AddSecrets { secrets, resp_tx })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
     pub async fn try_retrieve_output(&self) -> Result<String> {
         let (resp_tx, resp_rx) = oneshot::channel();
 
@@ -414,4 +656,120 @@ impl Logger {
 
         resp_rx.await.map_err(|e| anyhow!(e))
     }
+
+    pub async fn try_retrieve_entries(&self) -> Result<Vec<LogEntry>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        self.tx
+            .send(LoggerMessage::TryRetrieveEntries { resp_tx })
+            .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, path::PathBuf};
+
+    use bld_config::path;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn entries_are_tagged_with_the_scope() {
+        let logger = Logger::in_memory();
+
+        logger.write_line("setup".to_owned()).await.unwrap();
+        logger
+            .set_scope(Some("build".to_owned()), Some("compile".to_owned()))
+            .await
+            .unwrap();
+        logger.stderr_line("warning".to_owned()).await.unwrap();
+        logger.error_line("failed".to_owned()).await.unwrap();
+
+        let entries = logger.try_retrieve_entries().await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].job, None);
+        assert_eq!(entries[0].step, None);
+        assert_eq!(entries[0].message, "setup\n");
+        assert_eq!(entries[1].job.as_deref(), Some("build"));
+        assert_eq!(entries[1].step.as_deref(), Some("compile"));
+        assert_eq!(entries[1].stream, LogStream::Stderr);
+        assert_eq!(entries[1].level, LogLevel::Info);
+        assert_eq!(entries[2].stream, LogStream::Stdout);
+        assert_eq!(entries[2].level, LogLevel::Error);
+        assert!(entries.iter().all(|x| x.timestamp.is_some()));
+    }
+
+    #[actix_web::test]
+    async fn secrets_split_between_writes_are_masked() {
+        let logger = Logger::in_memory();
+        logger
+            .add_secrets(vec!["hunter2".to_owned(), "first\nsecond".to_owned()])
+            .await
+            .unwrap();
+
+        logger.write("password: hun".to_owned()).await.unwrap();
+        logger.write("ter2, key: ".to_owned()).await.unwrap();
+        logger.write_line("first".to_owned()).await.unwrap();
+        logger.write_line("second".to_owned()).await.unwrap();
+        logger.write_line("hunt".to_owned()).await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "password: ***, key: ***\nhunt\n");
+    }
+
+    #[actix_web::test]
+    async fn file_entries_are_written_one_line_at_a_time() {
+        let root_dir = path![temp_dir(), format!("bld-logger-{}", Uuid::new_v4())];
+        let config = Arc::new(BldConfig {
+            root_dir: root_dir.display().to_string(),
+            ..Default::default()
+        });
+        std::fs::create_dir_all(path![&root_dir, &config.local.server.logs]).unwrap();
+
+        let logger = Logger::file(config.clone(), "run").await.unwrap();
+        logger
+            .set_scope(Some("build".to_owned()), None)
+            .await
+            .unwrap();
+        logger.write("first ".to_owned()).await.unwrap();
+        logger.stderr_line("warning".to_owned()).await.unwrap();
+        logger.write("line\nsecond".to_owned()).await.unwrap();
+        logger
+            .set_scope(Some("test".to_owned()), None)
+            .await
+            .unwrap();
+        logger.write_line("third".to_owned()).await.unwrap();
+
+        let content = std::fs::read_to_string(config.log_full_path("run")).unwrap();
+        let entries: Vec<LogEntry> = content.lines().map(LogEntry::parse).collect();
+        let lines: Vec<(&str, Option<&str>, LogStream)> = entries
+            .iter()
+            .map(|x| (x.message.as_str(), x.job.as_deref(), x.stream))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("warning", Some("build"), LogStream::Stderr),
+                ("first line", Some("build"), LogStream::Stdout),
+                ("second", Some("build"), LogStream::Stdout),
+                ("third", Some("test"), LogStream::Stdout),
+            ]
+        );
+        assert!(entries[1].is_match(Some("build"), None));
+        assert!(!entries[1].is_match(Some("test"), None));
+
+        std::fs::remove_dir_all(root_dir).unwrap();
+    }
+
+    #[test]
+    fn plain_text_lines_are_parsed_as_entries() {
+        let entry = LogEntry::parse("plain text");
+        assert_eq!(entry.message, "plain text");
+        assert_eq!(entry.job, None);
+        assert_eq!(entry.level, LogLevel::Info);
+    }
 }
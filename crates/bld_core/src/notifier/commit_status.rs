@@ -0,0 +1,363 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use bld_config::{BldCommitStatusConfig, BldConfig, BldHookProvider};
+use bld_http::Request;
+use bld_models::pipeline_runs::PipelineRunHook;
+use serde_json::json;
+use tokio::time::sleep;
+use tracing::{debug, error};
+use url::form_urlencoded::byte_serialize;
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy)]
+pub enum CommitState {
+    Running,
+    Success,
+    Failure,
+}
+
+impl CommitState {
+    fn as_provider_state(&self, provider: BldHookProvider) -> &'static str {
+        match (self, provider) {
+            (Self::Running, BldHookProvider::Gitlab) => "running",
+            (Self::Running, _) => "pending",
+            (Self::Success, _) => "success",
+            (Self::Failure, BldHookProvider::Gitlab) => "failed",
+            (Self::Failure, _) => "failure",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Running => "The run is in progress",
+            Self::Success => "The run has finished successfully",
+            Self::Failure => "The run has failed",
+        }
+    }
+}
+
+/// Reports the state of a run as a commit status to the providers of the server
+/// configuration, for runs that were started by a webhook of the same provider.
+pub struct CommitStatusNotifier {
+    config: Arc<BldConfig>,
+    run_id: String,
+    provider: BldHookProvider,
+    repository: String,
+    commit_sha: String,
+}
+
+impl CommitStatusNotifier {
+    pub fn new(config: Arc<BldConfig>, run_id: &str, hook: PipelineRunHook) -> Option<Self> {
+        let provider = match hook.provider.as_str() {
+            "github" => BldHookProvider::Github,
+            "gitlab" => BldHookProvider::Gitlab,
+            "gitea" => BldHookProvider::Gitea,
+            _ => return None,
+        };
+        let PipelineRunHook {
+            repository,
+            commit_sha,
+            ..
+        } = hook;
+
+        let has_status = config
+            .local
+            .server
+            .commit_statuses
+            .iter()
+            .any(|x| Self::is_for(x, provider, &repository));
+        if !has_status {
+            return None;
+        }
+
+        Some(Self {
+            config,
+            run_id: run_id.to_owned(),
+            provider,
+            repository,
+            commit_sha,
+        })
+    }
+
+    fn is_for(status: &BldCommitStatusConfig, provider: BldHookProvider, repository: &str) -> bool {
+        status.provider == provider
+            && status
+                .repository
+                .as_ref()
+                .map(|x| x.eq_ignore_ascii_case(repository))
+                .unwrap_or(true)
+    }
+
+    /// Sends the state to every matching provider, a status that can't be reported
+    /// is logged without affecting the run.
+    pub async fn notify(&self, state: CommitState) {
+        for status in self
+            .config
+            .local
+            .server
+            .commit_statuses
+            .iter()
+            .filter(|x| Self::is_for(x, self.provider, &self.repository))
+        {
+            if let Err(e) = self.send_with_retries(status, state).await {
+                error!(
+                    "unable to report commit status for {} at {} due to: {e}",
+                    self.repository, self.commit_sha
+                );
+            }
+        }
+    }
+
+    async fn send_with_retries(
+        &self,
+        status: &BldCommitStatusConfig,
+        state: CommitState,
+    ) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.send(status, state).await {
+                Ok(_) => return Ok(()),
+                Err(e) if attempt <= status.retries => {
+                    error!("commit status attempt {attempt} failed due to: {e}");
+                    sleep(RETRY_DELAY * attempt as u32).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn send(&self, status: &BldCommitStatusConfig, state: CommitState) -> Result<()> {
+        let api_url = status.api_url().ok_or_else(|| {
+            anyhow!(
+                "no api_url configured for {} commit statuses",
+                self.provider
+            )
+        })?;
+        let target_url = format!(
+            "{}/monit?id={}",
+            self.config.local.server.base_url_http(),
+            self.run_id
+        );
+        let provider_state = state.as_provider_state(self.provider);
+
+        let (request, body) = match self.provider {
+            BldHookProvider::Github | BldHookProvider::Gitea => {
+                let url = format!(
+                    "{api_url}/repos/{}/statuses/{}",
+                    self.repository, self.commit_sha
+                );
+                let auth = match self.provider {
+                    BldHookProvider::Github => format!("Bearer {}", status.token),
+                    _ => format!("token {}", status.token),
+                };
+                let request = Request::post(&url)
+                    .header("Authorization", &auth)
+                    .header("Accept", "application/json");
+                let body = json!({
+                    "state": provider_state,
+                    "target_url": target_url,
+                    "description": state.description(),
+                    "context": status.context,
+                });
+                (request, body)
+            }
+            BldHookProvider::Gitlab => {
+                let project: String = byte_serialize(self.repository.as_bytes()).collect();
+                let url = format!("{api_url}/projects/{project}/statuses/{}", self.commit_sha);
+                let request = Request::post(&url).header("PRIVATE-TOKEN", &status.token);
+                let body = json!({
+                    "state": provider_state,
+                    "target_url": target_url,
+                    "description": state.description(),
+                    "name": status.context,
+                });
+                (request, body)
+            }
+        };
+
+        debug!(
+            "reporting commit status {provider_state} for {} at {}",
+            self.repository, self.commit_sha
+        );
+        request.text_with_data(&body).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use bld_utils::sync::IntoArc;
+    use serde_json::Value;
+
+    use crate::notifier::stub::{StubRequest, StubServer};
+
+    use super::*;
+
+    const SHA: &str = "8f2a1c0";
+
+    fn config(statuses: Vec<BldCommitStatusConfig>) -> Arc<BldConfig> {
+        let mut config = BldConfig::default();
+        config.local.server.commit_statuses = statuses;
+        config.into_arc()
+    }
+
+    fn status(provider: BldHookProvider, api_url: &str) -> BldCommitStatusConfig {
+        BldCommitStatusConfig {
+            provider,
+            api_url: Some(format!("{api_url}/")),
+            token: "status-token".to_owned(),
+            repository: None,
+            context: "bld".to_owned(),
+            retries: 0,
+        }
+    }
+
+    fn hook(provider: &str, repository: &str) -> PipelineRunHook {
+        PipelineRunHook {
+            provider: provider.to_owned(),
+            repository: repository.to_owned(),
+            commit_sha: SHA.to_owned(),
+        }
+    }
+
+    /// Reports every state of a run and returns the requests the provider received.
+    async fn report(
+        provider: BldHookProvider,
+        hook_provider: &str,
+    ) -> (Arc<BldConfig>, Vec<StubRequest>) {
+        let server = StubServer::start(&[StatusCode::CREATED]);
+        let config = config(vec![status(provider, &server.url)]);
+        let notifier =
+            CommitStatusNotifier::new(config.clone(), "run-1", hook(hook_provider, "owner/repo"))
+                .unwrap();
+
+        for state in [
+            CommitState::Running,
+            CommitState::Success,
+            CommitState::Failure,
+        ] {
+            notifier.notify(state).await;
+        }
+        (config, server.requests())
+    }
+
+    fn states(requests: &[StubRequest]) -> Vec<&str> {
+        requests
+            .iter()
+            .map(|x| x.body["state"].as_str().unwrap())
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn github_statuses_are_reported() {
+        let (config, requests) = report(BldHookProvider::Github, "github").await;
+
+        assert_eq!(states(&requests), vec!["pending", "success", "failure"]);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, format!("/repos/owner/repo/statuses/{SHA}"));
+        assert_eq!(request.headers["authorization"], "Bearer status-token");
+        assert_eq!(request.headers["accept"], "application/json");
+        assert_eq!(
+            request.body,
+            json!({
+                "state": "pending",
+                "target_url": format!("{}/monit?id=run-1", config.local.server.base_url_http()),
+                "description": "The run is in progress",
+                "context": "bld",
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn gitea_statuses_are_reported() {
+        let (_, requests) = report(BldHookProvider::Gitea, "gitea").await;
+
+        assert_eq!(states(&requests), vec!["pending", "success", "failure"]);
+        let request = &requests[1];
+        assert_eq!(request.path, format!("/repos/owner/repo/statuses/{SHA}"));
+        assert_eq!(request.headers["authorization"], "token status-token");
+        assert_eq!(
+            request.body["description"],
+            "The run has finished successfully"
+        );
+        assert_eq!(request.body["context"], "bld");
+    }
+
+    #[actix_web::test]
+    async fn gitlab_statuses_are_reported() {
+        let (config, requests) = report(BldHookProvider::Gitlab, "gitlab").await;
+
+        assert_eq!(states(&requests), vec!["running", "success", "failed"]);
+        let request = &requests[2];
+        assert_eq!(
+            request.path,
+            format!("/projects/owner%2Frepo/statuses/{SHA}")
+        );
+        assert_eq!(request.headers["private-token"], "status-token");
+        assert!(!request.headers.contains_key("authorization"));
+        assert_eq!(
+            request.body,
+            json!({
+                "state": "failed",
+                "target_url": format!("{}/monit?id=run-1", config.local.server.base_url_http()),
+                "description": "The run has failed",
+                "name": "bld",
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn statuses_are_only_reported_to_the_provider_of_the_hook() {
+        let server = StubServer::start(&[StatusCode::CREATED]);
+        let mut other_repository = status(BldHookProvider::Github, &server.url);
+        other_repository.repository = Some("owner/other".to_owned());
+        let mut same_repository = status(BldHookProvider::Github, &server.url);
+        same_repository.repository = Some("Owner/Repo".to_owned());
+        same_repository.context = "bld/repo".to_owned();
+        let config = config(vec![
+            status(BldHookProvider::Gitlab, &server.url),
+            other_repository,
+            same_repository,
+        ]);
+
+        assert!(
+            CommitStatusNotifier::new(config.clone(), "run-1", hook("gitea", "owner/repo"))
+                .is_none()
+        );
+        assert!(CommitStatusNotifier::new(
+            config.clone(),
+            "run-1",
+            hook("bitbucket", "owner/repo")
+        )
+        .is_none());
+
+        let notifier =
+            CommitStatusNotifier::new(config, "run-1", hook("github", "owner/repo")).unwrap();
+        notifier.notify(CommitState::Success).await;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body["context"], "bld/repo");
+    }
+
+    #[actix_web::test]
+    async fn statuses_are_retried_until_reported() {
+        let server = StubServer::start(&[StatusCode::INTERNAL_SERVER_ERROR, StatusCode::CREATED]);
+        let mut status = status(BldHookProvider::Github, &server.url);
+        status.retries = 1;
+        let config = config(vec![status]);
+
+        let notifier =
+            CommitStatusNotifier::new(config, "run-1", hook("github", "owner/repo")).unwrap();
+        notifier.notify(CommitState::Running).await;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, requests[1].body);
+        assert_eq!(requests[1].body["state"], Value::from("pending"));
+    }
+}
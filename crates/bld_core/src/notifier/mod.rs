@@ -0,0 +1,90 @@
+mod commit_status;
+mod queue;
+mod run_notification;
+
+pub use commit_status::*;
+pub use queue::*;
+pub use run_notification::*;
+
+/// A local http server that records the requests of the notifiers and responds
+/// with a configurable status.
+#[cfg(test)]
+pub(crate) mod stub {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use actix_web::{
+        http::StatusCode,
+        web::{self, Bytes, Data},
+        App, HttpRequest, HttpResponse, HttpServer,
+    };
+    use serde_json::Value;
+
+    #[derive(Debug, Clone)]
+    pub struct StubRequest {
+        pub method: String,
+        pub path: String,
+        pub headers: HashMap<String, String>,
+        pub body: Value,
+    }
+
+    #[derive(Default)]
+    struct StubState {
+        statuses: Vec<StatusCode>,
+        requests: Vec<StubRequest>,
+    }
+
+    pub struct StubServer {
+        pub url: String,
+        state: Arc<Mutex<StubState>>,
+    }
+
+    impl StubServer {
+        /// Starts a server that responds with the statuses in order, and with the
+        /// last one once they are exhausted.
+        pub fn start(statuses: &[StatusCode]) -> Self {
+            let state = Arc::new(Mutex::new(StubState {
+                statuses: statuses.to_vec(),
+                requests: vec![],
+            }));
+            let app_state = Data::from(state.clone());
+            let server = HttpServer::new(move || {
+                App::new()
+                    .app_data(app_state.clone())
+                    .default_service(web::to(record))
+            })
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+
+            let url = format!("http://{}", server.addrs()[0]);
+            actix_web::rt::spawn(server.run());
+            Self { url, state }
+        }
+
+        pub fn requests(&self) -> Vec<StubRequest> {
+            self.state.lock().unwrap().requests.clone()
+        }
+    }
+
+    async fn record(state: Data<Mutex<StubState>>, req: HttpRequest, body: Bytes) -> HttpResponse {
+        let mut state = state.lock().unwrap();
+        let headers = req
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_owned()))
+            .collect();
+        state.requests.push(StubRequest {
+            method: req.method().to_string(),
+            path: req.uri().to_string(),
+            headers,
+            body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        });
+
+        let index = (state.requests.len() - 1).min(state.statuses.len().saturating_sub(1));
+        let status = state.statuses.get(index).copied().unwrap_or(StatusCode::OK);
+        HttpResponse::build(status).json(serde_json::json!({}))
+    }
+}
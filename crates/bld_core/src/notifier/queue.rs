@@ -0,0 +1,71 @@
+use actix::spawn;
+use bld_config::{BldNotificationConfig, BldNotificationEvent};
+use bld_models::pipeline_runs::PipelineRuns;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    oneshot,
+};
+
+use super::{CommitState, CommitStatusNotifier, RunNotifier};
+
+enum Delivery {
+    CommitStatus(CommitState),
+    SetNotifications(Vec<BldNotificationConfig>),
+    Notification(BldNotificationEvent, Box<PipelineRuns>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sends the commit statuses and notifications of a run from a separate task so that
+/// slow or unavailable providers don't block the run. The deliveries are sent in the
+/// order that they were queued so the statuses of a commit are reported in sequence.
+#[derive(Clone)]
+pub struct DeliveryQueue {
+    tx: UnboundedSender<Delivery>,
+}
+
+impl DeliveryQueue {
+    pub fn new(commit_status: Option<CommitStatusNotifier>, mut notifier: RunNotifier) -> Self {
+        let (tx, mut rx) = unbounded_channel();
+
+        spawn(async move {
+            while let Some(delivery) = rx.recv().await {
+                match delivery {
+                    Delivery::CommitStatus(state) => {
+                        if let Some(commit_status) = commit_status.as_ref() {
+                            commit_status.notify(state).await;
+                        }
+                    }
+                    Delivery::SetNotifications(notifications) => {
+                        notifier.set_notifications(notifications);
+                    }
+                    Delivery::Notification(event, run) => notifier.notify(event, &run).await,
+                    Delivery::Flush(resp_tx) => {
+                        let _ = resp_tx.send(());
+                    }
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn commit_status(&self, state: CommitState) {
+        let _ = self.tx.send(Delivery::CommitStatus(state));
+    }
+
+    pub fn set_notifications(&self, notifications: Vec<BldNotificationConfig>) {
+        let _ = self.tx.send(Delivery::SetNotifications(notifications));
+    }
+
+    pub fn notify(&self, event: BldNotificationEvent, run: PipelineRuns) {
+        let _ = self.tx.send(Delivery::Notification(event, Box::new(run)));
+    }
+
+    /// Waits until everything that was queued before the call has been delivered.
+    pub async fn flush(&self) {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        if self.tx.send(Delivery::Flush(resp_tx)).is_ok() {
+            let _ = resp_rx.await;
+        }
+    }
+}
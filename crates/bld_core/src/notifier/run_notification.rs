@@ -0,0 +1,648 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use bld_config::{
+    BldConfig, BldNotificationConfig, BldNotificationEvent, BldNotificationTarget, BldSmtpTls,
+};
+use bld_http::Request;
+use bld_models::{
+    pipeline_run_notifications::{
+        self, InsertPipelineRunNotification, PRN_STATE_DELIVERED, PRN_STATE_FAILED,
+    },
+    pipeline_runs::PipelineRuns,
+};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Value};
+use tokio::time::sleep;
+use tracing::{debug, error, info};
+use url::form_urlencoded::byte_serialize;
+use uuid::Uuid;
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The details of a completed run that are included in every notification.
+struct RunSummary {
+    event: BldNotificationEvent,
+    run_id: String,
+    pipeline: String,
+    user: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    url: String,
+}
+
+impl RunSummary {
+    fn new(config: &BldConfig, event: BldNotificationEvent, run: &PipelineRuns) -> Self {
+        Self {
+            event,
+            run_id: run.id.to_owned(),
+            pipeline: run.name.to_owned(),
+            user: run.app_user.to_owned(),
+            start_date: run.start_date.map(|x| x.format("%F %X").to_string()),
+            end_date: run.end_date.map(|x| x.format("%F %X").to_string()),
+            url: format!(
+                "{}/monit?id={}",
+                config.local.server.base_url_http(),
+                run.id
+            ),
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.event {
+            BldNotificationEvent::Success => {
+                format!("Pipeline {} finished successfully", self.pipeline)
+            }
+            BldNotificationEvent::Failure => format!("Pipeline {} failed", self.pipeline),
+        }
+    }
+
+    fn text(&self) -> String {
+        if self.user.is_empty() {
+            format!("{} (run {})", self.title(), self.run_id)
+        } else {
+            format!(
+                "{} (run {} started by {})",
+                self.title(),
+                self.run_id,
+                self.user
+            )
+        }
+    }
+
+    fn webhook(&self) -> Value {
+        json!({
+            "event": self.event,
+            "run_id": self.run_id,
+            "pipeline": self.pipeline,
+            "user": self.user,
+            "start_date": self.start_date,
+            "end_date": self.end_date,
+            "url": self.url,
+        })
+    }
+
+    fn slack(&self) -> Value {
+        json!({
+            "text": format!("{} <{}|View run>", self.text(), self.url),
+        })
+    }
+
+    fn teams(&self) -> Value {
+        let color = match self.event {
+            BldNotificationEvent::Success => "2EB886",
+            BldNotificationEvent::Failure => "E01E5A",
+        };
+        json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": self.title(),
+            "themeColor": color,
+            "title": self.title(),
+            "text": self.text(),
+            "potentialAction": [{
+                "@type": "OpenUri",
+                "name": "View run",
+                "targets": [{ "os": "default", "uri": self.url }],
+            }],
+        })
+    }
+
+    fn matrix(&self) -> Value {
+        json!({
+            "msgtype": "m.text",
+            "body": format!("{} {}", self.text(), self.url),
+            "format": "org.matrix.custom.html",
+            "formatted_body": format!(
+                "{} <a href=\"{}\">View run</a>",
+                escape_html(&self.text()),
+                escape_html(&self.url)
+            ),
+        })
+    }
+
+    fn email(&self) -> String {
+        format!(
+            "{}\n\nRun id: {}\nUser: {}\nStart date: {}\nEnd date: {}\n\n{}\n",
+            self.title(),
+            self.run_id,
+            self.user,
+            self.start_date.as_deref().unwrap_or_default(),
+            self.end_date.as_deref().unwrap_or_default(),
+            self.url
+        )
+    }
+}
+
+/// Sends the notifications of a run when it finishes or fails and records every
+/// delivery attempt for the run. The notifications of the server configuration are
+/// used unless the pipeline declares its own.
+pub struct RunNotifier {
+    config: Arc<BldConfig>,
+    conn: Arc<DatabaseConnection>,
+    notifications: Vec<BldNotificationConfig>,
+}
+
+impl RunNotifier {
+    pub fn new(config: Arc<BldConfig>, conn: Arc<DatabaseConnection>) -> Self {
+        let notifications = config.local.server.notifications.clone();
+        Self {
+            config,
+            conn,
+            notifications,
+        }
+    }
+
+    pub fn set_notifications(&mut self, notifications: Vec<BldNotificationConfig>) {
+        self.notifications = notifications;
+    }
+
+    /// Sends the notifications for the event, a notification that can't be delivered
+    /// is logged without affecting the run.
+    pub async fn notify(&self, event: BldNotificationEvent, run: &PipelineRuns) {
+        let summary = RunSummary::new(&self.config, event, run);
+        for notification in self.notifications.iter().filter(|x| x.is_for(event)) {
+            self.send_with_retries(notification, &summary).await;
+        }
+    }
+
+    async fn send_with_retries(&self, notification: &BldNotificationConfig, summary: &RunSummary) {
+        // the transaction id is kept across attempts so that a matrix homeserver
+        // that received an attempt which appeared to fail doesn't post it twice.
+        let txn_id = Uuid::new_v4().to_string();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = self.send(&notification.target, summary, &txn_id).await;
+
+            match &result {
+                Ok(_) => info!(
+                    "delivered {} notification for run {}",
+                    notification.target, summary.run_id
+                ),
+                Err(e) => error!(
+                    "{} notification attempt {attempt} for run {} failed due to: {e}",
+                    notification.target, summary.run_id
+                ),
+            }
+
+            let model = InsertPipelineRunNotification {
+                id: Uuid::new_v4().to_string(),
+                run_id: summary.run_id.to_owned(),
+                target: notification.target.to_string(),
+                event: summary.event.to_string(),
+                attempt: attempt as i32,
+                state: if result.is_ok() {
+                    PRN_STATE_DELIVERED.to_owned()
+                } else {
+                    PRN_STATE_FAILED.to_owned()
+                },
+                error: result.as_ref().err().map(|e| e.to_string()),
+            };
+            let _ = pipeline_run_notifications::insert(self.conn.as_ref(), model).await;
+
+            if result.is_ok() || attempt > notification.retries {
+                break;
+            }
+            sleep(RETRY_DELAY * attempt as u32).await;
+        }
+    }
+
+    async fn send(
+        &self,
+        target: &BldNotificationTarget,
+        summary: &RunSummary,
+        txn_id: &str,
+    ) -> Result<()> {
+        debug!("sending {target} notification for run {}", summary.run_id);
+        match target {
+            BldNotificationTarget::Webhook { url, headers } => {
+                let mut request = Request::post(url);
+                for (key, value) in headers.iter() {
+                    request = request.header(key, value);
+                }
+                request.text_with_data(&summary.webhook()).await?;
+            }
+
+            BldNotificationTarget::Slack { url } => {
+                Request::post(url).text_with_data(&summary.slack()).await?;
+            }
+
+            BldNotificationTarget::Teams { url } => {
+                Request::post(url).text_with_data(&summary.teams()).await?;
+            }
+
+            BldNotificationTarget::Matrix {
+                homeserver,
+                room_id,
+                access_token,
+            } => {
+                let room: String = byte_serialize(room_id.as_bytes()).collect();
+                let url = format!(
+                    "{}/_matrix/client/v3/rooms/{room}/send/m.room.message/{txn_id}",
+                    homeserver.trim_end_matches('/')
+                );
+                Request::put(&url)
+                    .header("Authorization", &format!("Bearer {access_token}"))
+                    .text_with_data(&summary.matrix())
+                    .await?;
+            }
+
+            BldNotificationTarget::Email { to } => self.send_email(to, summary).await?,
+        }
+        Ok(())
+    }
+
+    async fn send_email(&self, to: &[String], summary: &RunSummary) -> Result<()> {
+        let smtp = self
+            .config
+            .local
+            .server
+            .smtp
+            .as_ref()
+            .ok_or_else(|| anyhow!("no smtp server in the server configuration"))?;
+
+        let mut builder = match smtp.tls {
+            BldSmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+            BldSmtpTls::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?
+            }
+            BldSmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+        };
+        if let Some(port) = smtp.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+            builder =
+                builder.credentials(Credentials::new(username.to_owned(), password.to_owned()));
+        }
+
+        let mut message = Message::builder()
+            .from(smtp.from.parse()?)
+            .subject(summary.title())
+            .header(ContentType::TEXT_PLAIN);
+        for address in to.iter() {
+            message = message.to(address.parse()?);
+        }
+        let message = message.body(summary.email())?;
+
+        builder.build().send(message).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env::temp_dir, path::PathBuf};
+
+    use actix_web::http::StatusCode;
+    use bld_config::path;
+    use bld_models::{
+        new_connection_pool,
+        pipeline_runs::{self, InsertPipelineRun},
+    };
+    use bld_utils::sync::IntoArc;
+
+    use crate::notifier::stub::{StubRequest, StubServer};
+
+    use super::*;
+
+    const PIPELINE: &str = "<b>deploy</b> & 'release'.yaml";
+
+    struct TestNotifier {
+        dir: PathBuf,
+        config: Arc<BldConfig>,
+        conn: Arc<DatabaseConnection>,
+        run: PipelineRuns,
+    }
+
+    impl TestNotifier {
+        async fn new() -> Self {
+            let dir = path![temp_dir(), format!("bld-notifier-{}", Uuid::new_v4())];
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut config = BldConfig::default();
+            config.local.server.db = Some(format!(
+                "sqlite://{}?mode=rwc",
+                path![&dir, "test.db"].display()
+            ));
+            let config = config.into_arc();
+            let conn = new_connection_pool(config.clone())
+                .await
+                .unwrap()
+                .into_arc();
+
+            let run_id = Uuid::new_v4().to_string();
+            let run = InsertPipelineRun {
+                id: run_id.clone(),
+                name: PIPELINE.to_owned(),
+                app_user: "alice".to_owned(),
+                hook: None,
+            };
+            pipeline_runs::insert(conn.as_ref(), run).await.unwrap();
+            let run = pipeline_runs::select_by_id(conn.as_ref(), &run_id)
+                .await
+                .unwrap();
+
+            Self {
+                dir,
+                config,
+                conn,
+                run,
+            }
+        }
+
+        async fn notify(&self, event: BldNotificationEvent, targets: Vec<BldNotificationTarget>) {
+            let mut notifier = RunNotifier::new(self.config.clone(), self.conn.clone());
+            notifier.set_notifications(
+                targets
+                    .into_iter()
+                    .map(|target| BldNotificationConfig {
+                        target,
+                        events: vec![],
+                        retries: 0,
+                    })
+                    .collect(),
+            );
+            notifier.notify(event, &self.run).await;
+        }
+
+        fn url(&self) -> String {
+            format!(
+                "{}/monit?id={}",
+                self.config.local.server.base_url_http(),
+                self.run.id
+            )
+        }
+
+        fn text(&self, title: &str) -> String {
+            format!("{title} (run {} started by alice)", self.run.id)
+        }
+
+        async fn attempts(&self) -> Vec<(String, String)> {
+            pipeline_run_notifications::select_by_run_id(self.conn.as_ref(), &self.run.id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|x| (x.target, x.state))
+                .collect()
+        }
+    }
+
+    impl Drop for TestNotifier {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn single(server: &StubServer) -> StubRequest {
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1, "{requests:?}");
+        requests.into_iter().next().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn webhook_sends_the_details_of_the_run() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::OK]);
+        let target = BldNotificationTarget::Webhook {
+            url: format!("{}/runs", server.url),
+            headers: HashMap::from([("X-Api-Key".to_owned(), "key".to_owned())]),
+        };
+
+        notifier
+            .notify(BldNotificationEvent::Success, vec![target])
+            .await;
+
+        let request = single(&server);
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/runs");
+        assert_eq!(request.headers["x-api-key"], "key");
+        assert_eq!(
+            request.body,
+            json!({
+                "event": "success",
+                "run_id": notifier.run.id,
+                "pipeline": PIPELINE,
+                "user": "alice",
+                "start_date": null,
+                "end_date": null,
+                "url": notifier.url(),
+            })
+        );
+        assert_eq!(
+            notifier.attempts().await,
+            vec![("webhook".to_owned(), PRN_STATE_DELIVERED.to_owned())]
+        );
+    }
+
+    #[actix_web::test]
+    async fn slack_sends_a_message_with_a_link_to_the_run() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::OK]);
+        let target = BldNotificationTarget::Slack {
+            url: server.url.to_owned(),
+        };
+
+        notifier
+            .notify(BldNotificationEvent::Failure, vec![target])
+            .await;
+
+        let request = single(&server);
+        let text = notifier.text(&format!("Pipeline {PIPELINE} failed"));
+        assert_eq!(
+            request.body,
+            json!({ "text": format!("{text} <{}|View run>", notifier.url()) })
+        );
+    }
+
+    #[actix_web::test]
+    async fn teams_sends_a_message_card() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::OK]);
+        let target = BldNotificationTarget::Teams {
+            url: server.url.to_owned(),
+        };
+
+        notifier
+            .notify(BldNotificationEvent::Success, vec![target])
+            .await;
+
+        let request = single(&server);
+        let title = format!("Pipeline {PIPELINE} finished successfully");
+        assert_eq!(request.body["@type"], "MessageCard");
+        assert_eq!(request.body["summary"], title.as_str());
+        assert_eq!(request.body["title"], title.as_str());
+        assert_eq!(request.body["themeColor"], "2EB886");
+        assert_eq!(request.body["text"], notifier.text(&title).as_str());
+        assert_eq!(
+            request.body["potentialAction"][0]["targets"][0]["uri"],
+            notifier.url().as_str()
+        );
+    }
+
+    #[actix_web::test]
+    async fn matrix_sends_an_escaped_message_to_the_room() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::OK]);
+        let target = BldNotificationTarget::Matrix {
+            homeserver: format!("{}/", server.url),
+            room_id: "!room:example.com".to_owned(),
+            access_token: "matrix-token".to_owned(),
+        };
+
+        notifier
+            .notify(BldNotificationEvent::Failure, vec![target])
+            .await;
+
+        let request = single(&server);
+        assert_eq!(request.method, "PUT");
+        assert!(
+            request
+                .path
+                .starts_with("/_matrix/client/v3/rooms/%21room%3Aexample.com/send/m.room.message/"),
+            "{}",
+            request.path
+        );
+        assert_eq!(request.headers["authorization"], "Bearer matrix-token");
+
+        let text = notifier.text(&format!("Pipeline {PIPELINE} failed"));
+        assert_eq!(request.body["msgtype"], "m.text");
+        assert_eq!(
+            request.body["body"],
+            format!("{text} {}", notifier.url()).as_str()
+        );
+        assert_eq!(request.body["format"], "org.matrix.custom.html");
+        assert_eq!(
+            request.body["formatted_body"],
+            format!(
+                "Pipeline &lt;b&gt;deploy&lt;/b&gt; &amp; &#39;release&#39;.yaml failed (run {} started by alice) <a href=\"{}\">View run</a>",
+                notifier.run.id,
+                notifier.url()
+            )
+            .as_str()
+        );
+    }
+
+    #[actix_web::test]
+    async fn matrix_retries_use_the_same_transaction_id() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::BAD_GATEWAY, StatusCode::OK]);
+        let target = BldNotificationTarget::Matrix {
+            homeserver: server.url.to_owned(),
+            room_id: "!room:example.com".to_owned(),
+            access_token: "matrix-token".to_owned(),
+        };
+        let mut runner = RunNotifier::new(notifier.config.clone(), notifier.conn.clone());
+        runner.set_notifications(vec![BldNotificationConfig {
+            target,
+            events: vec![],
+            retries: 1,
+        }]);
+
+        runner
+            .notify(BldNotificationEvent::Success, &notifier.run)
+            .await;
+        runner
+            .notify(BldNotificationEvent::Success, &notifier.run)
+            .await;
+
+        let paths: Vec<String> = server.requests().into_iter().map(|x| x.path).collect();
+        assert_eq!(paths.len(), 3, "{paths:?}");
+        assert_eq!(paths[0], paths[1]);
+        assert_ne!(paths[1], paths[2]);
+        assert_eq!(
+            notifier.attempts().await,
+            vec![
+                ("matrix".to_owned(), PRN_STATE_FAILED.to_owned()),
+                ("matrix".to_owned(), PRN_STATE_DELIVERED.to_owned()),
+                ("matrix".to_owned(), PRN_STATE_DELIVERED.to_owned()),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn email_requires_an_smtp_server() {
+        let notifier = TestNotifier::new().await;
+        let target = BldNotificationTarget::Email {
+            to: vec!["alice@example.com".to_owned()],
+        };
+
+        notifier
+            .notify(BldNotificationEvent::Success, vec![target])
+            .await;
+
+        let attempts =
+            pipeline_run_notifications::select_by_run_id(notifier.conn.as_ref(), &notifier.run.id)
+                .await
+                .unwrap();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].state, PRN_STATE_FAILED);
+        assert_eq!(
+            attempts[0].error.as_deref(),
+            Some("no smtp server in the server configuration")
+        );
+    }
+
+    #[actix_web::test]
+    async fn email_contains_the_details_of_the_run() {
+        let notifier = TestNotifier::new().await;
+        let summary = RunSummary::new(
+            &notifier.config,
+            BldNotificationEvent::Failure,
+            &notifier.run,
+        );
+
+        assert_eq!(summary.title(), format!("Pipeline {PIPELINE} failed"));
+        assert_eq!(
+            summary.email(),
+            format!(
+                "Pipeline {PIPELINE} failed\n\nRun id: {}\nUser: alice\nStart date: \nEnd date: \n\n{}\n",
+                notifier.run.id,
+                notifier.url()
+            )
+        );
+    }
+
+    #[actix_web::test]
+    async fn notifications_are_only_sent_for_their_events() {
+        let notifier = TestNotifier::new().await;
+        let server = StubServer::start(&[StatusCode::OK]);
+        let mut runner = RunNotifier::new(notifier.config.clone(), notifier.conn.clone());
+        runner.set_notifications(vec![BldNotificationConfig {
+            target: BldNotificationTarget::Slack {
+                url: server.url.to_owned(),
+            },
+            events: vec![BldNotificationEvent::Failure],
+            retries: 0,
+        }]);
+
+        runner
+            .notify(BldNotificationEvent::Success, &notifier.run)
+            .await;
+        assert!(server.requests().is_empty());
+
+        runner
+            .notify(BldNotificationEvent::Failure, &notifier.run)
+            .await;
+        assert_eq!(server.requests().len(), 1);
+    }
+}
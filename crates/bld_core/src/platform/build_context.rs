@@ -0,0 +1,285 @@
+use std::{
+    fs::{read_to_string, remove_file, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use flate2::{write::GzEncoder, Compression};
+use glob::{MatchOptions, Pattern};
+use tar::{Builder, Header};
+use tracing::debug;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+const DOCKER_IGNORE_FILE: &str = ".dockerignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// The patterns of a `.dockerignore` file, evaluated in order so that a later
+/// pattern overrides the result of a previous one.
+struct DockerIgnore {
+    patterns: Vec<(Pattern, bool)>,
+}
+
+impl DockerIgnore {
+    fn load(context: &Path) -> Result<Self> {
+        let path = context.join(DOCKER_IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(Self { patterns: vec![] });
+        }
+
+        let mut patterns = vec![];
+        for line in read_to_string(&path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (value, exclude) = match line.strip_prefix('!') {
+                Some(value) => (value.trim(), false),
+                None => (line, true),
+            };
+
+            let value = value.trim_start_matches("./").trim_matches('/');
+            if value.is_empty() {
+                continue;
+            }
+
+            let pattern = Pattern::new(value)
+                .map_err(|e| anyhow!("invalid pattern {value} in {DOCKER_IGNORE_FILE}. {e}"))?;
+            patterns.push((pattern, exclude));
+        }
+
+        Ok(Self { patterns })
+    }
+
+    /// Checks if a path relative to the context is excluded, a pattern that matches
+    /// a directory applies to all of its contents.
+    fn is_excluded(&self, path: &Path) -> bool {
+        let mut excluded = false;
+        for (pattern, exclude) in self.patterns.iter() {
+            let matched = path
+                .ancestors()
+                .filter(|x| !x.as_os_str().is_empty())
+                .any(|x| pattern.matches_path_with(x, MATCH_OPTIONS));
+            if matched {
+                excluded = *exclude;
+            }
+        }
+        excluded
+    }
+
+    /// Checks if an excluded directory can be skipped without walking its contents,
+    /// which isn't the case when an exception pattern could match any of them.
+    fn is_pruned(&self, dir: &Path) -> bool {
+        let dir: Vec<String> = dir
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy().into_owned())
+            .collect();
+
+        self.patterns
+            .iter()
+            .filter(|(_, exclude)| !exclude)
+            .all(|(pattern, _)| {
+                let pattern = pattern.as_str();
+                if pattern.contains("**") {
+                    return false;
+                }
+                let parts: Vec<&str> = pattern.split('/').collect();
+                parts.len() <= dir.len()
+                    || !parts.iter().zip(dir.iter()).all(|(part, name)| {
+                        Pattern::new(part).is_ok_and(|x| x.matches_with(name, MATCH_OPTIONS))
+                    })
+            })
+    }
+}
+
+/// A gzipped tarball with the files of a build context that is written to a temporary
+/// file so that it can be streamed to the daemon, the file is removed once dropped.
+pub struct BuildContext {
+    pub path: PathBuf,
+}
+
+impl BuildContext {
+    /// Creates the archive of the context directory excluding the paths that match
+    /// the `.dockerignore` file, while the dockerfile is always added with the
+    /// provided name. When no context is provided only the dockerfile is archived.
+    pub fn create(context: Option<&Path>, dockerfile: &str, dockerfile_name: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("bld-context-{}.tar.gz", Uuid::new_v4()));
+        let build_context = Self { path };
+
+        let file = File::create(&build_context.path)?;
+        let mut tar = Builder::new(GzEncoder::new(file, Compression::default()));
+        tar.follow_symlinks(false);
+
+        if let Some(context) = context {
+            Self::append_context(&mut tar, context)?;
+        }
+
+        let mut header = Header::new_gnu();
+        header.set_path(dockerfile_name)?;
+        header.set_size(dockerfile.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append(&header, dockerfile.as_bytes())?;
+
+        tar.into_inner()?.finish()?;
+        Ok(build_context)
+    }
+
+    fn append_context<W: std::io::Write>(tar: &mut Builder<W>, context: &Path) -> Result<()> {
+        if !context.is_dir() {
+            bail!("build context {} isn't a directory", context.display());
+        }
+
+        let ignore = DockerIgnore::load(context)?;
+
+        // excluded directories are skipped as a whole, so that large directories
+        // such as dependencies or build outputs aren't walked.
+        let entries = WalkDir::new(context)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|x| {
+                let Ok(relative) = x.path().strip_prefix(context) else {
+                    return true;
+                };
+                let is_pruned = x.file_type().is_dir()
+                    && ignore.is_excluded(relative)
+                    && ignore.is_pruned(relative);
+                if is_pruned {
+                    debug!("excluding {} from build context", relative.display());
+                }
+                !is_pruned
+            });
+
+        for entry in entries {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(context)?;
+
+            if relative != Path::new(DOCKER_IGNORE_FILE) && ignore.is_excluded(relative) {
+                debug!("excluding {} from build context", relative.display());
+                continue;
+            }
+
+            let file_type = entry.file_type();
+            if file_type.is_dir() {
+                tar.append_dir(relative, entry.path())?;
+            } else {
+                tar.append_path_with_name(entry.path(), relative)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for BuildContext {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        fs::{create_dir_all, write},
+    };
+
+    use flate2::read::GzDecoder;
+    use tar::Archive;
+
+    use super::*;
+
+    fn context(files: &[&str], ignore: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bld-context-test-{}", Uuid::new_v4()));
+        for file in files {
+            let path = dir.join(file);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, file).unwrap();
+        }
+        write(dir.join(DOCKER_IGNORE_FILE), ignore).unwrap();
+        dir
+    }
+
+    fn files(build_context: &BuildContext) -> HashSet<String> {
+        let file = File::open(&build_context.path).unwrap();
+        Archive::new(GzDecoder::new(file))
+            .entries()
+            .unwrap()
+            .map(|x| x.unwrap())
+            .filter(|x| x.header().entry_type().is_file())
+            .map(|x| x.path().unwrap().display().to_string())
+            .collect()
+    }
+
+    fn set(values: &[&str]) -> HashSet<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn ignored_paths_are_excluded_from_the_context() {
+        let dir = context(
+            &[
+                "src/main.rs",
+                "target/debug/app",
+                "node_modules/lib/index.js",
+                "app.log",
+            ],
+            "target\n./node_modules/\n*.log\n",
+        );
+
+        let build_context = BuildContext::create(Some(&dir), "FROM alpine", "Dockerfile").unwrap();
+        assert_eq!(
+            files(&build_context),
+            set(&["src/main.rs", ".dockerignore", "Dockerfile"])
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn exceptions_include_paths_of_excluded_directories() {
+        let dir = context(
+            &[
+                "target/debug/app",
+                "target/release/app",
+                "docs/guide.md",
+                "docs/api.md",
+            ],
+            "target\n!target/release\ndocs\n!**/guide.md\n",
+        );
+
+        let build_context = BuildContext::create(Some(&dir), "FROM alpine", "Dockerfile").unwrap();
+        assert_eq!(
+            files(&build_context),
+            set(&[
+                "target/release/app",
+                "docs/guide.md",
+                ".dockerignore",
+                "Dockerfile"
+            ])
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn excluded_directories_are_pruned_unless_an_exception_matches_their_contents() {
+        let ignore = DockerIgnore {
+            patterns: vec![
+                (Pattern::new("target").unwrap(), true),
+                (Pattern::new("build").unwrap(), true),
+                (Pattern::new("build/*/keep").unwrap(), false),
+            ],
+        };
+        assert!(ignore.is_pruned(Path::new("target")));
+        assert!(!ignore.is_pruned(Path::new("build")));
+        assert!(!ignore.is_pruned(Path::new("build/debug")));
+        assert!(ignore.is_pruned(Path::new("build/debug/keep")));
+    }
+}
@@ -1,21 +1,36 @@
 use std::{collections::HashMap, sync::Arc};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use bld_config::BldConfig;
 use bld_utils::sync::IntoArc;
 use sea_orm::DatabaseConnection;
+use tracing::error;
+use uuid::Uuid;
 
 use crate::{
     logger::Logger,
-    platform::{Container, Image, Machine, Platform, Ssh, SshConnectOptions, SshExecutionOptions},
+    platform::{
+        docker, Container, Image, KubernetesPod, KubernetesPodOptions, Machine, Platform,
+        ServiceOptions, Services, Ssh, SshConnectOptions, SshExecutionOptions,
+    },
 };
 
-use super::{context::PlatformContext, ContainerOptions};
+use super::{context::PlatformContext, ContainerHostOptions, ContainerOptions};
 
 pub enum PlatformOptions<'a> {
     Container {
         image: Image<'a>,
         docker_url: Option<&'a str>,
+        host: ContainerHostOptions<'a>,
+    },
+    Kubernetes {
+        name: &'a str,
+        image: &'a str,
+        namespace: Option<&'a str>,
+        requests: Option<&'a HashMap<String, String>>,
+        limits: Option<&'a HashMap<String, String>>,
+        node_selector: Option<&'a HashMap<String, String>>,
+        service_account: Option<&'a str>,
     },
     Ssh(SshConnectOptions<'a>),
     Machine,
@@ -36,6 +51,7 @@ pub struct PlatformBuilder<'a> {
     environment: Option<Arc<HashMap<String, String>>>,
     logger: Option<Arc<Logger>>,
     conn: Option<Arc<DatabaseConnection>>,
+    services: Vec<ServiceOptions<'a>>,
 }
 
 impl<'a> PlatformBuilder<'a> {
@@ -74,6 +90,11 @@ impl<'a> PlatformBuilder<'a> {
         self
     }
 
+    pub fn services(mut self, services: Vec<ServiceOptions<'a>>) -> Self {
+        self.services = services;
+        self
+    }
+
     pub async fn build(self) -> Result<Arc<Platform>> {
         let run_id = self
             .run_id
@@ -95,34 +116,106 @@ impl<'a> PlatformBuilder<'a> {
             .logger
             .ok_or_else(|| anyhow!("no logger provided for target platform builder"))?;
 
+        let services = if self.services.is_empty() {
+            None
+        } else {
+            let docker_url = match &self.options {
+                PlatformOptions::Container { docker_url, .. } => *docker_url,
+                PlatformOptions::Machine => None,
+                PlatformOptions::Ssh(_) => bail!("services aren't supported for ssh platforms"),
+                PlatformOptions::Kubernetes { .. } => {
+                    bail!("services aren't supported for kubernetes platforms")
+                }
+            };
+            let client = docker(&config, docker_url)?;
+            let services =
+                Services::start(client, run_id, self.conn.clone(), &logger, self.services).await?;
+            Some(services)
+        };
+
         let platform = match self.options {
-            PlatformOptions::Container { image, docker_url } => {
+            PlatformOptions::Container {
+                image,
+                docker_url,
+                host,
+            } => {
+                let network = services.as_ref().map(|x| x.network());
+                if network.is_some() && host.network.is_some() {
+                    bail!("the network of a container can't be set together with services");
+                }
                 let context = PlatformContext::new(run_id, self.conn);
                 let options = ContainerOptions {
                     config,
                     docker_url,
                     image,
+                    host: ContainerHostOptions {
+                        network: host.network.or(network),
+                        ..host
+                    },
                     pipeline_env,
                     env,
                     logger,
                     context,
                 };
-                let container = Container::new(options).await?;
-                Platform::container(Box::new(container))
+                Container::new(options)
+                    .await
+                    .map(|x| Platform::container(Box::new(x)))
+            }
+
+            PlatformOptions::Kubernetes {
+                name,
+                image,
+                namespace,
+                requests,
+                limits,
+                node_selector,
+                service_account,
+            } => {
+                let options = KubernetesPodOptions {
+                    run_id,
+                    config,
+                    kubernetes: name,
+                    image,
+                    namespace,
+                    requests,
+                    limits,
+                    node_selector,
+                    service_account,
+                    pipeline_env,
+                    env,
+                    logger,
+                };
+                KubernetesPod::new(options)
+                    .await
+                    .map(|x| Platform::kubernetes(Box::new(x)))
             }
 
             PlatformOptions::Ssh(connect) => {
                 let execution = SshExecutionOptions::new(config, pipeline_env, env);
-                let ssh = Ssh::new(connect, execution).await?;
-                Platform::ssh(Box::new(ssh))
+                Ssh::new(connect, execution)
+                    .await
+                    .map(|x| Platform::ssh(Box::new(x)))
             }
 
             PlatformOptions::Machine => {
-                let machine = Machine::new(run_id, config, pipeline_env, env).await?;
-                Platform::machine(Box::new(machine))
+                // every machine platform gets its own directory since jobs that override
+                // runs_on create additional platforms for the same run.
+                let id = format!("{run_id}-{}", Uuid::new_v4());
+                Machine::new(&id, config, pipeline_env, env)
+                    .await
+                    .map(|x| Platform::machine(Box::new(x)))
+            }
+        };
+
+        let platform = match platform {
+            Ok(platform) => platform.with_services(services).into_arc(),
+            Err(e) => {
+                if let Some(services) = services {
+                    let _ = services.dispose().await.map_err(|e| error!("{e}"));
+                }
+                return Err(e);
             }
-        }
-        .into_arc();
+        };
 
         Ok(platform)
     }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, path::Path, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::Path, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::{anyhow, bail, Result};
 use bld_config::{path, BldConfig};
@@ -8,21 +8,93 @@ use bollard::{
         StartContainerOptions, UploadToContainerOptions,
     },
     exec::{CreateExecOptions, StartExecResults},
+    service::{HostConfig, Mount, MountTypeEnum},
     Docker,
 };
 use futures::StreamExt;
 use tar::{Archive, Builder};
+use tokio::time::{sleep, timeout as with_timeout};
 use tracing::{debug, error};
 use uuid::Uuid;
 
 use crate::logger::Logger;
 
-use super::{context::PlatformContext, docker, Image};
+use super::{
+    cache_archive_name, context::PlatformContext, docker, kill_process_group_command,
+    process_group_script, restore_cache_command, save_cache_command, ExitCodeError, Image,
+};
+
+const EXEC_INSPECT_RETRIES: usize = 50;
+const EXEC_INSPECT_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct ContainerMountOptions<'a> {
+    pub source: Option<&'a str>,
+    pub target: &'a str,
+    pub kind: &'a str,
+    pub read_only: bool,
+}
+
+/// Options that change how the container is created such as mounts, resource limits
+/// and its network.
+#[derive(Default)]
+pub struct ContainerHostOptions<'a> {
+    pub volumes: Vec<&'a str>,
+    pub mounts: Vec<ContainerMountOptions<'a>>,
+    pub network: Option<&'a str>,
+    pub user: Option<&'a str>,
+    pub privileged: bool,
+    pub nano_cpus: Option<i64>,
+    pub memory: Option<i64>,
+    pub extra_hosts: Vec<&'a str>,
+}
+
+impl ContainerHostOptions<'_> {
+    fn host_config(&self) -> Result<HostConfig> {
+        let mut mounts = vec![];
+        for mount in self.mounts.iter() {
+            let typ = mount
+                .kind
+                .parse::<MountTypeEnum>()
+                .map_err(|e| anyhow!("invalid mount type {}. {e}", mount.kind))?;
+            mounts.push(Mount {
+                source: mount.source.map(|x| x.to_owned()),
+                target: Some(mount.target.to_owned()),
+                typ: Some(typ),
+                read_only: Some(mount.read_only),
+                ..Default::default()
+            });
+        }
+
+        let to_owned = |values: &[&str]| -> Option<Vec<String>> {
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.iter().map(|x| x.to_string()).collect())
+            }
+        };
+
+        Ok(HostConfig {
+            binds: to_owned(&self.volumes),
+            mounts: if mounts.is_empty() {
+                None
+            } else {
+                Some(mounts)
+            },
+            network_mode: self.network.map(|x| x.to_owned()),
+            privileged: Some(self.privileged),
+            nano_cpus: self.nano_cpus,
+            memory: self.memory,
+            extra_hosts: to_owned(&self.extra_hosts),
+            ..Default::default()
+        })
+    }
+}
 
 pub struct ContainerOptions<'a> {
     pub config: Arc<BldConfig>,
     pub docker_url: Option<&'a str>,
     pub image: Image<'a>,
+    pub host: ContainerHostOptions<'a>,
     pub pipeline_env: &'a HashMap<String, String>,
     pub env: Arc<HashMap<String, String>>,
     pub logger: Arc<Logger>,
@@ -39,7 +111,12 @@ pub struct Container {
 }
 
 impl Container {
-    async fn create(client: &Docker, image: &str, env: Vec<&str>) -> Result<(String, String)> {
+    async fn create(
+        client: &Docker,
+        image: &str,
+        env: Vec<&str>,
+        host: &ContainerHostOptions<'_>,
+    ) -> Result<(String, String)> {
         let name = Uuid::new_v4().to_string();
         let options = CreateContainerOptions {
             name: &name,
@@ -49,6 +126,8 @@ impl Container {
             image: Some(image),
             tty: Some(true),
             env: Some(env),
+            user: host.user,
+            host_config: Some(host.host_config()?),
             ..Default::default()
         };
 
@@ -86,9 +165,17 @@ impl Container {
             .image
             .create(&client, options.logger.as_ref())
             .await?;
-        let (id, name) = Container::create(&client, options.image.name(), container_env).await?;
+        let digest = options
+            .image
+            .resolve_digest(&client, options.logger.as_ref())
+            .await?;
+        let (id, name) =
+            Container::create(&client, options.image.name(), container_env, &options.host).await?;
 
-        options.context.add(&id).await?;
+        options
+            .context
+            .add(&id, options.image.name(), digest.as_deref())
+            .await?;
 
         Ok(Self {
             id,
@@ -132,9 +219,10 @@ impl Container {
         }
         let content = tar.into_inner()?;
 
+        // podman rejects an empty value for the flag so it's always set explicitly.
         let options = UploadToContainerOptions {
             path: to,
-            ..Default::default()
+            no_overwrite_dir_non_dir: "false",
         };
         self.client
             .upload_to_container(&self.name, Some(options), content.into())
@@ -143,11 +231,54 @@ impl Container {
         Ok(())
     }
 
+    pub async fn restore_cache(&self, logger: Arc<Logger>, archive: &Path) -> Result<()> {
+        let from = archive
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct cache archive path"))?;
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        self.copy_into(from, "/tmp").await?;
+        self.sh(logger, &None, &restore_cache_command(&remote_archive), None)
+            .await
+    }
+
+    pub async fn save_cache(
+        &self,
+        logger: Arc<Logger>,
+        paths: &[String],
+        archive: &Path,
+    ) -> Result<()> {
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        let to = archive
+            .parent()
+            .and_then(|x| x.to_str())
+            .ok_or_else(|| anyhow!("unable to construct cache directory path"))?;
+
+        let command = save_cache_command(paths, &remote_archive);
+        self.sh(logger.clone(), &None, &command, None).await?;
+        let result = self.copy_from(&remote_archive, to).await;
+        self.sh(logger, &None, &format!("rm -f {remote_archive}"), None)
+            .await?;
+        result
+    }
+
     pub async fn sh(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
         input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.sh_with_env(logger, working_dir, input, &HashMap::new(), timeout)
+            .await
+    }
+
+    pub async fn sh_with_env(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        input: &str,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         let input = working_dir
             .as_ref()
@@ -155,9 +286,24 @@ impl Container {
             .or_else(|| Some(input.to_string()))
             .unwrap();
 
-        let env = self.environment.iter().map(String::as_str).collect();
+        // when a timeout is set the process group of the command is kept in a file
+        // so that the command can be killed if the timeout is reached.
+        let pid_file = format!("/tmp/bld-{}.pid", Uuid::new_v4());
+        let pid_script = process_group_script("bash");
+        let cmd = match timeout {
+            Some(_) => vec!["sh", "-c", &pid_script, &input, &pid_file],
+            None => vec!["bash", "-c", &input],
+        };
+
+        let env_vars: Vec<String> = env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let env = self
+            .environment
+            .iter()
+            .chain(env_vars.iter())
+            .map(String::as_str)
+            .collect();
         let options = CreateExecOptions {
-            cmd: Some(vec!["bash", "-c", &input]),
+            cmd: Some(cmd),
             env: Some(env),
             attach_stdout: Some(true),
             attach_stderr: Some(true),
@@ -171,31 +317,69 @@ impl Container {
             return Ok(());
         };
 
-        while let Some(result) = output.next().await {
-            let Ok(output) = result else {
-                continue;
-            };
-
-            let chunk: Vec<u8> = match output {
-                LogOutput::StdOut { message } => message.into(),
-                LogOutput::StdErr { message } => message.into(),
-                LogOutput::StdIn { .. } | LogOutput::Console { .. } => continue,
-            };
+        let read_output = async {
+            while let Some(result) = output.next().await {
+                let Ok(output) = result else {
+                    continue;
+                };
+
+                match output {
+                    LogOutput::StdOut { message } => {
+                        logger.write(String::from_utf8(message.into())?).await?;
+                    }
+                    LogOutput::StdErr { message } => {
+                        logger.stderr(String::from_utf8(message.into())?).await?;
+                    }
+                    LogOutput::StdIn { .. } | LogOutput::Console { .. } => continue,
+                }
+            }
+            Ok::<(), anyhow::Error>(())
+        };
 
-            let chunk_str = String::from_utf8(chunk)?;
+        match timeout {
+            Some(duration) => {
+                if let Ok(result) = with_timeout(duration, read_output).await {
+                    result?;
+                } else {
+                    self.kill(&pid_file).await?;
+                    bail!("command timed out");
+                }
+            }
+            None => read_output.await?,
+        }
 
-            logger.write(chunk_str).await?;
+        let exit_code = self.exit_code(&exec.id).await?;
+        if exit_code != 0 {
+            return Err(ExitCodeError(exit_code).into());
         }
 
-        let inspect = self.client.inspect_exec(&exec.id).await?;
-        let Some(exit_code) = inspect.exit_code else {
-            bail!("unable to confirm exit code");
-        };
+        Ok(())
+    }
 
-        if exit_code != 0 {
-            bail!("command finished with exit code: {exit_code}");
+    /// Podman can report an exec as running for a short period after its output has
+    /// been closed, so the exit code is read once the exec is no longer running.
+    async fn exit_code(&self, exec_id: &str) -> Result<i64> {
+        for _ in 0..EXEC_INSPECT_RETRIES {
+            let inspect = self.client.inspect_exec(exec_id).await?;
+            if !inspect.running.unwrap_or_default() {
+                return inspect
+                    .exit_code
+                    .ok_or_else(|| anyhow!("unable to confirm exit code"));
+            }
+            sleep(EXEC_INSPECT_INTERVAL).await;
         }
+        bail!("unable to confirm exit code")
+    }
 
+    async fn kill(&self, pid_file: &str) -> Result<()> {
+        debug!("killing process group with id from file {pid_file}");
+        let input = kill_process_group_command(pid_file);
+        let options = CreateExecOptions {
+            cmd: Some(vec!["sh", "-c", &input]),
+            ..Default::default()
+        };
+        let exec = self.client.create_exec(&self.name, options).await?;
+        self.client.start_exec(&exec.id, None).await?;
         Ok(())
     }
 
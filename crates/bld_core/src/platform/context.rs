@@ -52,7 +52,12 @@ impl PlatformContext {
             .map(|_| ())
     }
 
-    pub async fn add(&mut self, container_id: &str) -> Result<()> {
+    pub async fn add(
+        &mut self,
+        container_id: &str,
+        image: &str,
+        image_digest: Option<&str>,
+    ) -> Result<()> {
         let Self::Server {
             conn,
             entity_id,
@@ -69,6 +74,8 @@ impl PlatformContext {
                 run_id: run_id.to_owned(),
                 container_id: container_id.to_owned(),
                 state: PRC_STATE_ACTIVE.to_owned(),
+                image: Some(image.to_owned()),
+                image_digest: image_digest.map(|x| x.to_owned()),
             },
         )
         .await
@@ -1,5 +1,5 @@
-use anyhow::Result;
-use bld_config::BldConfig;
+use anyhow::{bail, Result};
+use bld_config::{BldConfig, ContainerEngine};
 use bollard::{Docker, API_DEFAULT_VERSION};
 
 fn uses_http(url: &str) -> bool {
@@ -10,8 +10,7 @@ fn sanitize_socket_path(url: &str) -> &str {
     url.strip_prefix("unix:/").unwrap_or(url)
 }
 
-pub fn docker(config: &BldConfig, name: Option<&str>) -> Result<Docker> {
-    let url = config.local.docker_url.get_url_or_default(name)?;
+fn connect(url: &str) -> Result<Docker> {
     let docker = if uses_http(url) {
         Docker::connect_with_http(url, 120, API_DEFAULT_VERSION)?
     } else {
@@ -20,3 +19,21 @@ pub fn docker(config: &BldConfig, name: Option<&str>) -> Result<Docker> {
     };
     Ok(docker)
 }
+
+/// Creates a client for the configured container engine. Podman is used through its
+/// docker compatible api so the same client can be used for both engines.
+pub fn docker(config: &BldConfig, name: Option<&str>) -> Result<Docker> {
+    match config.local.container_engine {
+        ContainerEngine::Docker => {
+            let url = config.local.docker_url.get_url_or_default(name)?;
+            connect(url)
+        }
+        ContainerEngine::Podman => {
+            if let Some(name) = name {
+                bail!("docker url {name} can't be used with the podman container engine");
+            }
+            let url = config.local.podman.socket_url()?;
+            connect(&url)
+        }
+    }
+}
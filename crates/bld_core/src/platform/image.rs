@@ -1,40 +1,84 @@
-use std::io::Write;
+use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::{bail, Result};
 use bld_config::RegistryConfig;
 use bollard::{
     auth::DockerCredentials,
-    image::{BuildImageOptions, CreateImageOptions},
-    service::{BuildInfo, CreateImageInfo},
+    image::{BuildImageOptions, CreateImageOptions, PushImageOptions, TagImageOptions},
+    service::{BuildInfo, CreateImageInfo, PushImageInfo},
     Docker,
 };
-use flate2::{write::GzEncoder, Compression};
 use futures::TryStreamExt;
-use tar::{Builder, Header};
-use tokio::fs::read_to_string;
+use hyper::Body;
+use tokio::{
+    fs::{read_to_string, File},
+    task::spawn_blocking,
+};
+use tokio_util::io::ReaderStream;
+use tracing::error;
+use uuid::Uuid;
 
 use crate::logger::Logger;
 
+use super::build_context::BuildContext;
+
+fn registry_credentials(registry: Option<&RegistryConfig>) -> Option<DockerCredentials> {
+    registry.map(|registry| DockerCredentials {
+        username: registry.username.as_ref().map(|x| x.to_owned()),
+        password: registry.password.as_ref().map(|x| x.to_owned()),
+        serveraddress: Some(registry.url.to_owned()),
+        ..Default::default()
+    })
+}
+
+/// Splits an image reference into its repository and its tag or digest. The tag
+/// defaults to latest since the daemon pulls all tags of a repository otherwise,
+/// while a tag is dropped when a digest is present since the digest identifies
+/// the image on its own.
+fn split_reference(image: &str) -> (&str, &str) {
+    if let Some((name, digest)) = image.split_once('@') {
+        let (repository, _) = split_tag(name);
+        return (repository, digest);
+    }
+    let (repository, tag) = split_tag(image);
+    (repository, tag.unwrap_or("latest"))
+}
+
+fn split_tag(image: &str) -> (&str, Option<&str>) {
+    let name_start = image.rfind('/').map(|x| x + 1).unwrap_or_default();
+    match image[name_start..].rfind(':') {
+        Some(index) => (
+            &image[..name_start + index],
+            Some(&image[name_start + index + 1..]),
+        ),
+        None => (image, None),
+    }
+}
+
 pub struct PullImage<'a> {
     image: &'a str,
     registry: Option<&'a RegistryConfig>,
+    if_not_present: bool,
 }
 
 impl<'a> PullImage<'a> {
     pub async fn pull(&self, client: &Docker, logger: &Logger) -> Result<()> {
         let image = self.image;
+
+        if self.if_not_present && client.inspect_image(image).await.is_ok() {
+            return logger
+                .write_line(format!("{:<15}: {image}", "Image present"))
+                .await;
+        }
+
+        let (repository, tag) = split_reference(image);
         let opts = CreateImageOptions {
-            from_image: image,
+            from_image: repository,
+            tag,
             ..Default::default()
         };
 
-        let credentials = self.registry.map(|registry| DockerCredentials {
-            username: registry.username.as_ref().map(|x| x.to_owned()),
-            password: registry.password.as_ref().map(|x| x.to_owned()),
-            serveraddress: Some(registry.url.to_owned()),
-            ..Default::default()
-        });
-
+        let credentials = registry_credentials(self.registry);
         let mut stream = client.create_image(Some(opts), None, credentials);
 
         loop {
@@ -68,40 +112,173 @@ impl<'a> PullImage<'a> {
     }
 }
 
+/// Tags a local image into a repository and pushes each of the tags to the registry
+/// of the repository.
+pub struct PushImage<'a> {
+    image: &'a str,
+    repository: &'a str,
+    tags: Vec<&'a str>,
+    registry: Option<&'a RegistryConfig>,
+}
+
+impl<'a> PushImage<'a> {
+    pub fn new(
+        image: &'a str,
+        repository: &'a str,
+        tags: Vec<&'a str>,
+        registry: Option<&'a RegistryConfig>,
+    ) -> Self {
+        Self {
+            image,
+            repository,
+            tags,
+            registry,
+        }
+    }
+
+    pub async fn push(&self, client: &Docker, logger: &Logger) -> Result<()> {
+        for tag in self.tags.iter().copied() {
+            logger
+                .write_line(format!(
+                    "{:<15}: {}:{tag}",
+                    "Pushing image", self.repository
+                ))
+                .await?;
+
+            let opts = TagImageOptions {
+                repo: self.repository,
+                tag,
+            };
+            client.tag_image(self.image, Some(opts)).await?;
+
+            let opts = PushImageOptions { tag };
+            let credentials = registry_credentials(self.registry);
+            let mut stream = client.push_image(self.repository, Some(opts), credentials);
+
+            loop {
+                let item = stream.try_next().await?;
+
+                match item {
+                    Some(PushImageInfo {
+                        error: Some(error), ..
+                    }) => {
+                        bail!(error);
+                    }
+
+                    Some(PushImageInfo {
+                        status, progress, ..
+                    }) => {
+                        let status = status.unwrap_or_default();
+                        let progress = progress.unwrap_or_default();
+                        let msg = format!("{status} {progress}");
+                        logger.write_line(msg).await?;
+                    }
+
+                    None => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ImageBuildOptions<'a> {
+    pub context: Option<&'a str>,
+    pub build_args: Option<&'a HashMap<String, String>>,
+    pub target: Option<&'a str>,
+    pub no_cache: bool,
+    pub labels: Option<&'a HashMap<String, String>>,
+}
+
 pub struct BuildImage<'a> {
     name: String,
     dockerfile: &'a str,
+    options: ImageBuildOptions<'a>,
 }
 
 impl<'a> BuildImage<'a> {
-    pub fn new(name: &str, dockerfile: &'a str, tag: &str) -> Self {
+    pub fn new(name: &str, dockerfile: &'a str, tag: &str, options: ImageBuildOptions<'a>) -> Self {
         let name = format!("{name}:{tag}");
-        Self { name, dockerfile }
+        Self {
+            name,
+            dockerfile,
+            options,
+        }
+    }
+
+    /// The build api of the client doesn't support a target stage, so the stages after
+    /// the target are removed from the dockerfile instead.
+    fn until_target(content: &str, target: &str) -> Result<String> {
+        let mut lines = vec![];
+        let mut found = false;
+
+        for line in content.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let is_from = words
+                .first()
+                .is_some_and(|x| x.eq_ignore_ascii_case("from"));
+
+            if is_from && found {
+                break;
+            }
+
+            if is_from && words.len() >= 4 {
+                let alias = &words[words.len() - 2..];
+                found =
+                    alias[0].eq_ignore_ascii_case("as") && alias[1].eq_ignore_ascii_case(target);
+            }
+
+            lines.push(line);
+        }
+
+        if !found {
+            bail!("build target {target} not found in dockerfile");
+        }
+
+        Ok(lines.join("\n"))
     }
 
     pub async fn build(&self, client: &Docker, logger: &Logger) -> Result<()> {
-        let content = read_to_string(&self.dockerfile).await?;
+        let mut content = read_to_string(&self.dockerfile).await?;
+        if let Some(target) = self.options.target {
+            content = Self::until_target(&content, target)?;
+        }
 
-        let mut header = Header::new_gnu();
-        header.set_path("Dockerfile")?;
-        header.set_size(content.len() as u64);
-        header.set_mode(0o755);
-        header.set_cksum();
+        let context = self.options.context.map(PathBuf::from);
+        let dockerfile = if context.is_some() {
+            format!(".bld-{}.Dockerfile", Uuid::new_v4())
+        } else {
+            "Dockerfile".to_owned()
+        };
 
-        let mut tar = Builder::new(vec![]);
-        tar.append(&header, content.as_bytes())?;
+        let name = dockerfile.clone();
+        let build_context =
+            spawn_blocking(move || BuildContext::create(context.as_deref(), &content, &name))
+                .await??;
 
-        let uncompressed = tar.into_inner()?;
-        let mut gz = GzEncoder::new(vec![], Compression::default());
-        gz.write_all(&uncompressed)?;
-        let compressed = gz.finish()?;
+        let file = File::open(&build_context.path).await?;
+        let body = Body::wrap_stream(ReaderStream::new(file));
 
+        let to_options = |values: Option<&'a HashMap<String, String>>| {
+            values
+                .map(|x| x.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect())
+                .unwrap_or_default()
+        };
+
+        // the dockerfile is set explicitly since podman doesn't fall back to the
+        // default name when an empty value is provided.
         let opts = BuildImageOptions {
             t: self.name.as_str(),
+            dockerfile: dockerfile.as_str(),
+            nocache: self.options.no_cache,
+            buildargs: to_options(self.options.build_args),
+            labels: to_options(self.options.labels),
             ..Default::default()
         };
 
-        let mut stream = client.build_image(opts, None, Some(compressed.into()));
+        let mut stream = client.build_image(opts, None, Some(body));
 
         loop {
             let item = stream.try_next().await?;
@@ -149,11 +326,28 @@ pub enum Image<'a> {
 
 impl<'a> Image<'a> {
     pub fn pull(image: &'a str, registry: Option<&'a RegistryConfig>) -> Self {
-        Self::Pull(PullImage { image, registry })
+        Self::Pull(PullImage {
+            image,
+            registry,
+            if_not_present: false,
+        })
+    }
+
+    pub fn pull_if_not_present(image: &'a str, registry: Option<&'a RegistryConfig>) -> Self {
+        Self::Pull(PullImage {
+            image,
+            registry,
+            if_not_present: true,
+        })
     }
 
-    pub fn build(name: &str, dockerfile: &'a str, tag: &str) -> Self {
-        Self::Build(BuildImage::new(name, dockerfile, tag))
+    pub fn build(
+        name: &str,
+        dockerfile: &'a str,
+        tag: &str,
+        options: ImageBuildOptions<'a>,
+    ) -> Self {
+        Self::Build(BuildImage::new(name, dockerfile, tag, options))
     }
 
     pub fn name(&self) -> &str {
@@ -170,4 +364,123 @@ impl<'a> Image<'a> {
             Self::Build(instance) => instance.build(client, logger).await,
         }
     }
+
+    /// Resolves the registry digest of the local image that a container will be created
+    /// from, preferring the digest of its own repository. Images without a registry
+    /// digest, such as the ones built locally, have no digest since their id can't be
+    /// used to pull the exact same image again.
+    pub async fn digest(&self, client: &Docker) -> Result<Option<String>> {
+        let name = self.name();
+        let inspect = client.inspect_image(name).await?;
+        let (repository, _) = split_reference(name);
+        let suffix = format!("/{repository}");
+
+        let repo_digests = inspect.repo_digests.unwrap_or_default();
+        let digest = repo_digests
+            .iter()
+            .find(|x| {
+                x.split_once('@')
+                    .is_some_and(|(x, _)| x == repository || x.ends_with(&suffix))
+            })
+            .or(repo_digests.first())
+            .cloned();
+
+        Ok(digest)
+    }
+
+    /// Resolves the digest of the image and writes it to the logs of the run, a digest
+    /// that can't be resolved isn't considered an error since it's only informative.
+    pub async fn resolve_digest(&self, client: &Docker, logger: &Logger) -> Result<Option<String>> {
+        let digest = self
+            .digest(client)
+            .await
+            .map_err(|e| error!("could not resolve digest of image {}, {e}", self.name()))
+            .ok()
+            .flatten();
+
+        if let Some(digest) = digest.as_ref() {
+            logger
+                .write_line(format!("{:<15}: {digest}", "Image digest"))
+                .await?;
+        }
+
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_reference_with_tag_or_digest() {
+        assert_eq!(split_reference("alpine"), ("alpine", "latest"));
+        assert_eq!(split_reference("alpine:3.19"), ("alpine", "3.19"));
+        assert_eq!(
+            split_reference("localhost:5000/team/app"),
+            ("localhost:5000/team/app", "latest")
+        );
+        assert_eq!(
+            split_reference("localhost:5000/team/app:1.0"),
+            ("localhost:5000/team/app", "1.0")
+        );
+        assert_eq!(
+            split_reference("alpine@sha256:abc"),
+            ("alpine", "sha256:abc")
+        );
+        assert_eq!(
+            split_reference("alpine:3.19@sha256:abc"),
+            ("alpine", "sha256:abc")
+        );
+        assert_eq!(
+            split_reference("localhost:5000/app:1.0@sha256:abc"),
+            ("localhost:5000/app", "sha256:abc")
+        );
+    }
+
+    const DOCKERFILE: &str = "FROM rust:latest AS Builder
+RUN cargo build --release
+
+from debian:stable as runtime
+COPY --from=builder /target/release/app /app
+
+FROM runtime
+CMD [\"/app\"]";
+
+    #[test]
+    fn until_target_removes_the_stages_after_the_target() {
+        let content = BuildImage::until_target(DOCKERFILE, "runtime").unwrap();
+        assert_eq!(
+            content,
+            "FROM rust:latest AS Builder
+RUN cargo build --release
+
+from debian:stable as runtime
+COPY --from=builder /target/release/app /app
+"
+        );
+    }
+
+    #[test]
+    fn until_target_matches_the_alias_ignoring_case() {
+        let content = BuildImage::until_target(DOCKERFILE, "builder").unwrap();
+        assert_eq!(
+            content,
+            "FROM rust:latest AS Builder
+RUN cargo build --release
+"
+        );
+
+        let content = BuildImage::until_target(DOCKERFILE, "RUNTIME").unwrap();
+        assert!(content.ends_with("COPY --from=builder /target/release/app /app\n"));
+    }
+
+    #[test]
+    fn until_target_fails_for_a_missing_target() {
+        let error = BuildImage::until_target(DOCKERFILE, "tests").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "build target tests not found in dockerfile"
+        );
+    }
 }
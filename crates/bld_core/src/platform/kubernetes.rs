@@ -0,0 +1,1090 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix::clock::{sleep, timeout as with_timeout, Instant};
+use actix_web::web::Bytes;
+use anyhow::{anyhow, bail, Result};
+use awc::{
+    http::Method,
+    ws::{Frame, Message},
+    Client, Connector,
+};
+use bld_config::{path, BldConfig, KubernetesConfig};
+use bld_utils::{
+    sync::IntoArc,
+    tls::{load_private_key, load_root_certificates, load_server_certificate},
+};
+use futures::{SinkExt, StreamExt};
+use rustls::{ClientConfig, RootCertStore};
+use serde_json::{json, Value};
+use tar::{Archive, Builder};
+use tracing::{debug, error};
+use url::form_urlencoded::Serializer;
+use uuid::Uuid;
+
+use crate::logger::Logger;
+
+use super::{
+    cache_archive_name, kill_process_group_command, process_group_script, restore_cache_command,
+    save_cache_command, stdin_env_command, ExitCodeError,
+};
+
+const CONTAINER_NAME: &str = "bld";
+const EXEC_PROTOCOL: &str = "v4.channel.k8s.io";
+const EXEC_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const RESPONSE_LIMIT: usize = 10 * 1024 * 1024;
+const STDIN_CHUNK_SIZE: usize = 32 * 1024;
+const POD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const STDIN_CHANNEL: u8 = 0;
+const STDOUT_CHANNEL: u8 = 1;
+const STDERR_CHANNEL: u8 = 2;
+const ERROR_CHANNEL: u8 = 3;
+
+/// Waiting reasons of a container that won't recover without a change to the pod.
+const POD_FAILURE_REASONS: [&str; 5] = [
+    "ErrImagePull",
+    "ImagePullBackOff",
+    "InvalidImageName",
+    "CreateContainerConfigError",
+    "CreateContainerError",
+];
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+enum ExecOutput<'a> {
+    Logger(&'a Logger),
+    Buffer {
+        stdout: &'a mut Vec<u8>,
+        stderr: &'a mut Vec<u8>,
+    },
+}
+
+struct KubernetesClient {
+    url: String,
+    token: Option<String>,
+    tls: Arc<ClientConfig>,
+}
+
+impl KubernetesClient {
+    fn new(config: &KubernetesConfig) -> Result<Self> {
+        let token = match (&config.token, &config.token_file) {
+            (Some(token), _) => Some(token.to_owned()),
+            (None, Some(path)) => Some(std::fs::read_to_string(path)?.trim().to_owned()),
+            (None, None) => None,
+        };
+
+        let roots = match &config.ca_cert {
+            Some(path) => {
+                let mut store = RootCertStore::empty();
+                for cert in load_server_certificate(path)? {
+                    store.add(&cert)?;
+                }
+                store
+            }
+            None => load_root_certificates()?,
+        };
+
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        let tls = match (&config.client_cert, &config.client_key) {
+            (Some(cert), Some(key)) => {
+                builder.with_single_cert(load_server_certificate(cert)?, load_private_key(key)?)?
+            }
+            _ => builder.with_no_client_auth(),
+        };
+
+        Ok(Self {
+            url: config.url.trim_end_matches('/').to_owned(),
+            token,
+            tls: tls.into_arc(),
+        })
+    }
+
+    fn client(&self) -> Client {
+        let connector = Connector::new().rustls(self.tls.clone());
+        Client::builder()
+            .connector(connector)
+            .timeout(REQUEST_TIMEOUT)
+            .finish()
+    }
+
+    async fn request(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = format!("{}{path}", self.url);
+        debug!("sending {method} request to {url}");
+
+        let mut request = self.client().request(method, url);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let mut response = match body {
+            Some(body) => request.send_json(body).await,
+            None => request.send().await,
+        }
+        .map_err(|e| anyhow!(e.to_string()))?;
+
+        let status = response.status();
+        let body = response
+            .body()
+            .limit(RESPONSE_LIMIT)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<Value>(&body)
+                .ok()
+                .and_then(|x| x["message"].as_str().map(|x| x.to_owned()))
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).to_string());
+            bail!("kubernetes api responded with {status}, {message}");
+        }
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Executes a command in the container of a pod using the websocket protocol of
+    /// the api server, where each frame is prefixed with the byte of its channel and
+    /// the status of the command is sent on the error channel once it exits.
+    async fn exec(
+        &self,
+        pod_path: &str,
+        command: &[&str],
+        stdin: Option<&[u8]>,
+        mut output: ExecOutput<'_>,
+    ) -> Result<i64> {
+        let mut query = Serializer::new(String::new());
+        query.append_pair("container", CONTAINER_NAME);
+        for arg in command {
+            query.append_pair("command", arg);
+        }
+        query.append_pair("stdin", if stdin.is_some() { "true" } else { "false" });
+        query.append_pair("stdout", "true");
+        query.append_pair("stderr", "true");
+
+        let url = format!("{}{pod_path}/exec?{}", self.url, query.finish());
+        debug!("starting exec in pod {pod_path}");
+
+        let mut request = self
+            .client()
+            .ws(url)
+            .protocols([EXEC_PROTOCOL])
+            .max_frame_size(EXEC_MAX_FRAME_SIZE);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let (_, mut framed) = request
+            .connect()
+            .await
+            .map_err(|e| anyhow!("unable to exec in pod, {e}"))?;
+
+        for chunk in stdin.iter().flat_map(|x| x.chunks(STDIN_CHUNK_SIZE)) {
+            let mut frame = Vec::with_capacity(chunk.len() + 1);
+            frame.push(STDIN_CHANNEL);
+            frame.extend_from_slice(chunk);
+            framed
+                .send(Message::Binary(Bytes::from(frame)))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))?;
+        }
+
+        let mut status = None;
+        while let Some(frame) = framed.next().await {
+            let data = match frame.map_err(|e| anyhow!(e.to_string()))? {
+                Frame::Binary(data) if !data.is_empty() => data,
+                Frame::Ping(data) => {
+                    framed
+                        .send(Message::Pong(data))
+                        .await
+                        .map_err(|e| anyhow!(e.to_string()))?;
+                    continue;
+                }
+                Frame::Close(_) => break,
+                _ => continue,
+            };
+
+            let channel = data[0];
+            let data = data.slice(1..);
+            match (channel, &mut output) {
+                (STDOUT_CHANNEL, ExecOutput::Logger(logger)) => {
+                    logger
+                        .write(String::from_utf8_lossy(&data).to_string())
+                        .await?
+                }
+                (STDERR_CHANNEL, ExecOutput::Logger(logger)) => {
+                    logger
+                        .stderr(String::from_utf8_lossy(&data).to_string())
+                        .await?
+                }
+                (STDOUT_CHANNEL, ExecOutput::Buffer { stdout, .. }) => stdout.extend(&data[..]),
+                (STDERR_CHANNEL, ExecOutput::Buffer { stderr, .. }) => stderr.extend(&data[..]),
+                (ERROR_CHANNEL, _) => status = Some(serde_json::from_slice::<Value>(&data)?),
+                _ => {}
+            }
+        }
+
+        let Some(status) = status else {
+            bail!("unable to confirm exit code");
+        };
+
+        if status["status"] == "Success" {
+            return Ok(0);
+        }
+
+        status["details"]["causes"]
+            .as_array()
+            .and_then(|causes| causes.iter().find(|x| x["reason"] == "ExitCode"))
+            .and_then(|cause| cause["message"].as_str())
+            .and_then(|code| code.parse::<i64>().ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "{}",
+                    status["message"]
+                        .as_str()
+                        .unwrap_or("command failed in pod")
+                )
+            })
+    }
+}
+
+pub struct KubernetesPodOptions<'a> {
+    pub run_id: &'a str,
+    pub config: Arc<BldConfig>,
+    pub kubernetes: &'a str,
+    pub image: &'a str,
+    pub namespace: Option<&'a str>,
+    pub requests: Option<&'a HashMap<String, String>>,
+    pub limits: Option<&'a HashMap<String, String>>,
+    pub node_selector: Option<&'a HashMap<String, String>>,
+    pub service_account: Option<&'a str>,
+    pub pipeline_env: &'a HashMap<String, String>,
+    pub env: Arc<HashMap<String, String>>,
+    pub logger: Arc<Logger>,
+}
+
+/// A pod that is created for a run in a kubernetes cluster, with a single container
+/// that steps are executed in.
+pub struct KubernetesPod {
+    client: KubernetesClient,
+    namespace: String,
+    name: String,
+}
+
+impl KubernetesPod {
+    pub async fn new(options: KubernetesPodOptions<'_>) -> Result<Self> {
+        let config = options.config.kubernetes(options.kubernetes)?;
+        let client = KubernetesClient::new(config)?;
+        let namespace = options.namespace.unwrap_or(&config.namespace).to_owned();
+        let name = format!("bld-{}", Uuid::new_v4().simple());
+
+        options
+            .logger
+            .write_line(format!("{:<15}: {namespace}/{name}", "Creating pod"))
+            .await?;
+
+        let spec = Self::spec(&name, &options);
+        client
+            .request(
+                Method::POST,
+                &format!("/api/v1/namespaces/{namespace}/pods"),
+                Some(&spec),
+            )
+            .await?;
+
+        let pod = Self {
+            client,
+            namespace,
+            name,
+        };
+
+        let timeout = Duration::from_secs(config.pod_timeout);
+        if let Err(e) = pod.wait_until_running(timeout).await {
+            let _ = pod.dispose().await.map_err(|e| error!("{e}"));
+            return Err(e);
+        }
+
+        Ok(pod)
+    }
+
+    fn spec(name: &str, options: &KubernetesPodOptions<'_>) -> Value {
+        let mut env: HashMap<&str, &str> = HashMap::new();
+        for (k, v) in options.pipeline_env.iter().chain(options.env.iter()) {
+            env.insert(k, v);
+        }
+        let env: Vec<Value> = env
+            .into_iter()
+            .map(|(k, v)| json!({ "name": k, "value": v }))
+            .collect();
+
+        // the container is started with an open stdin and a tty so that the default
+        // shell of the image stays alive, similar to the containers of docker.
+        let mut container = json!({
+            "name": CONTAINER_NAME,
+            "image": options.image,
+            "env": env,
+            "stdin": true,
+            "tty": true,
+        });
+        if let Some(requests) = options.requests {
+            container["resources"]["requests"] = json!(requests);
+        }
+        if let Some(limits) = options.limits {
+            container["resources"]["limits"] = json!(limits);
+        }
+
+        let mut spec = json!({
+            "restartPolicy": "Never",
+            "containers": [container],
+        });
+        if let Some(node_selector) = options.node_selector {
+            spec["nodeSelector"] = json!(node_selector);
+        }
+        if let Some(service_account) = options.service_account {
+            spec["serviceAccountName"] = json!(service_account);
+        }
+
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": name,
+                "labels": {
+                    "app.kubernetes.io/managed-by": "bld",
+                    "bld/run-id": options.run_id,
+                },
+            },
+            "spec": spec,
+        })
+    }
+
+    fn path(&self) -> String {
+        format!("/api/v1/namespaces/{}/pods/{}", self.namespace, self.name)
+    }
+
+    async fn wait_until_running(&self, timeout: Duration) -> Result<()> {
+        let start = Instant::now();
+
+        loop {
+            let pod = self.client.request(Method::GET, &self.path(), None).await?;
+            let status = &pod["status"];
+
+            match status["phase"].as_str() {
+                Some("Running") => return Ok(()),
+                Some("Succeeded") | Some("Failed") => {
+                    bail!("pod {} terminated before starting", self.name)
+                }
+                _ => {}
+            }
+
+            let waiting = status["containerStatuses"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|x| &x["state"]["waiting"])
+                .find(|x| {
+                    x["reason"]
+                        .as_str()
+                        .is_some_and(|x| POD_FAILURE_REASONS.contains(&x))
+                });
+            if let Some(waiting) = waiting {
+                bail!(
+                    "pod {} failed to start, {} {}",
+                    self.name,
+                    waiting["reason"].as_str().unwrap_or_default(),
+                    waiting["message"].as_str().unwrap_or_default()
+                );
+            }
+
+            if start.elapsed() > timeout {
+                bail!("timed out while waiting for pod {} to start", self.name);
+            }
+
+            sleep(POD_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn exec_with_output(&self, command: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut stdout = vec![];
+        let mut stderr = vec![];
+        let output = ExecOutput::Buffer {
+            stdout: &mut stdout,
+            stderr: &mut stderr,
+        };
+        let exit_code = self
+            .client
+            .exec(&self.path(), command, stdin, output)
+            .await?;
+        if exit_code != 0 {
+            bail!(
+                "command finished with exit code: {exit_code}, {}",
+                String::from_utf8_lossy(&stderr).trim()
+            );
+        }
+        Ok(stdout)
+    }
+
+    pub async fn copy_from(&self, from: &str, to: &str) -> Result<()> {
+        let path = Path::new(from);
+        let parent = path
+            .parent()
+            .and_then(|x| x.to_str())
+            .filter(|x| !x.is_empty())
+            .unwrap_or(".");
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or_else(|| anyhow!("unable to retrieve filename for path {from}"))?;
+
+        let command = format!("tar -cf - -C {} {}", shell_quote(parent), shell_quote(name));
+        let bytes = self.exec_with_output(&["sh", "-c", &command], None).await?;
+
+        let mut archive = Archive::new(&bytes[..]);
+        archive.unpack(Path::new(to))?;
+        Ok(())
+    }
+
+    /// Uploads an archive of the path through the stdin of the command, while the size
+    /// of the archive is used to read it since the protocol can't signal its end.
+    pub async fn copy_into(&self, from: &str, to: &str) -> Result<()> {
+        let mut tar = Builder::new(Vec::new());
+        let path = path![from];
+
+        let filename = path
+            .file_name()
+            .ok_or_else(|| anyhow!("unable to retrieve filename for path {from}"))?;
+
+        if path.is_file() {
+            tar.append_path_with_name(from, filename)?;
+        } else {
+            tar.append_dir_all(filename, from)?;
+        }
+        let content = tar.into_inner()?;
+
+        let command = format!(
+            "mkdir -p {to} && head -c {} | tar -xf - -C {to}",
+            content.len(),
+            to = shell_quote(to)
+        );
+        self.exec_with_output(&["sh", "-c", &command], Some(&content))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn restore_cache(&self, logger: Arc<Logger>, archive: &Path) -> Result<()> {
+        let from = archive
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct cache archive path"))?;
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        self.copy_into(from, "/tmp").await?;
+        self.sh(logger, &None, &restore_cache_command(&remote_archive), None)
+            .await
+    }
+
+    pub async fn save_cache(
+        &self,
+        logger: Arc<Logger>,
+        paths: &[String],
+        archive: &Path,
+    ) -> Result<()> {
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        let to = archive
+            .parent()
+            .and_then(|x| x.to_str())
+            .ok_or_else(|| anyhow!("unable to construct cache directory path"))?;
+
+        let command = save_cache_command(paths, &remote_archive);
+        self.sh(logger.clone(), &None, &command, None).await?;
+        let result = self.copy_from(&remote_archive, to).await;
+        self.sh(logger, &None, &format!("rm -f {remote_archive}"), None)
+            .await?;
+        result
+    }
+
+    pub async fn sh(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.sh_with_env(logger, working_dir, input, &HashMap::new(), timeout)
+            .await
+    }
+
+    /// Runs the command with additional environment variables, which are sent through
+    /// the standard input of the command since an exec can't set its environment.
+    pub async fn sh_with_env(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        input: &str,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        let input = working_dir
+            .as_ref()
+            .map(|wd| format!("cd {wd} && {input}"))
+            .unwrap_or_else(|| input.to_string());
+
+        let (input, stdin) = if env.is_empty() {
+            (input, None)
+        } else {
+            let (input, stdin) = stdin_env_command(&input, env)?;
+            (input, Some(stdin))
+        };
+
+        // the process group is kept in a file so that the command can be killed when
+        // the timeout is reached, since closing the connection doesn't stop it.
+        let pid_file = format!("/tmp/bld-{}.pid", Uuid::new_v4());
+        let pid_script = process_group_script("sh");
+        let cmd = match timeout {
+            Some(_) => vec!["sh", "-c", &pid_script, &input, &pid_file],
+            None => vec!["sh", "-c", &input],
+        };
+
+        let path = self.path();
+        let exec = self
+            .client
+            .exec(&path, &cmd, stdin.as_deref(), ExecOutput::Logger(&logger));
+
+        let exit_code = match timeout {
+            Some(duration) => {
+                if let Ok(result) = with_timeout(duration, exec).await {
+                    result?
+                } else {
+                    self.kill(&pid_file).await?;
+                    bail!("command timed out");
+                }
+            }
+            None => exec.await?,
+        };
+
+        if exit_code != 0 {
+            return Err(ExitCodeError(exit_code).into());
+        }
+
+        Ok(())
+    }
+
+    async fn kill(&self, pid_file: &str) -> Result<()> {
+        debug!("killing process group with id from file {pid_file}");
+        let input = kill_process_group_command(pid_file);
+        self.exec_with_output(&["sh", "-c", &input], None)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn dispose(&self) -> Result<()> {
+        debug!("deleting pod {}", self.name);
+        let path = format!("{}?gracePeriodSeconds=0", self.path());
+        self.client
+            .request(Method::DELETE, &path, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                error!("could not delete pod {}, {e}", self.name);
+                e
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use actix::{Actor, ActorContext, StreamHandler};
+    use actix_web::{
+        web::{self, Data, Payload},
+        App, HttpRequest, HttpResponse, HttpServer,
+    };
+    use actix_web_actors::ws::{self, WebsocketContext, WsResponseBuilder};
+
+    use super::*;
+
+    const TOKEN: &str = "mock-token";
+    const MISSING_IMAGE: &str = "missing:latest";
+
+    #[derive(Default)]
+    struct MockState {
+        authorization: Vec<String>,
+        pods: HashMap<String, Value>,
+        deleted: Vec<String>,
+        execs: Vec<MockExecRecord>,
+    }
+
+    struct MockExecRecord {
+        command: Vec<String>,
+        stdin: Vec<u8>,
+    }
+
+    type SharedState = Data<Mutex<MockState>>;
+
+    fn record_authorization(state: &SharedState, req: &HttpRequest) {
+        let authorization = req
+            .headers()
+            .get("Authorization")
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        state.lock().unwrap().authorization.push(authorization);
+    }
+
+    async fn create_pod(
+        state: SharedState,
+        req: HttpRequest,
+        body: web::Json<Value>,
+    ) -> HttpResponse {
+        record_authorization(&state, &req);
+        let name = body["metadata"]["name"].as_str().unwrap().to_owned();
+        state.lock().unwrap().pods.insert(name, body.0.clone());
+        HttpResponse::Created().json(body.0)
+    }
+
+    async fn get_pod(state: SharedState, path: web::Path<(String, String)>) -> HttpResponse {
+        let (_, name) = path.into_inner();
+        let state = state.lock().unwrap();
+        let Some(pod) = state.pods.get(&name) else {
+            return HttpResponse::NotFound().json(json!({ "message": "pod not found" }));
+        };
+
+        if pod["spec"]["containers"][0]["image"] == MISSING_IMAGE {
+            return HttpResponse::Ok().json(json!({
+                "status": {
+                    "phase": "Pending",
+                    "containerStatuses": [{
+                        "state": {
+                            "waiting": {
+                                "reason": "ErrImagePull",
+                                "message": "image not found",
+                            },
+                        },
+                    }],
+                },
+            }));
+        }
+
+        HttpResponse::Ok().json(json!({ "status": { "phase": "Running" } }))
+    }
+
+    async fn delete_pod(state: SharedState, req: HttpRequest) -> HttpResponse {
+        let path = format!("{}?{}", req.path(), req.query_string());
+        state.lock().unwrap().deleted.push(path);
+        HttpResponse::Ok().json(json!({}))
+    }
+
+    async fn exec(state: SharedState, req: HttpRequest, stream: Payload) -> HttpResponse {
+        record_authorization(&state, &req);
+        let query: Vec<(String, String)> =
+            url::form_urlencoded::parse(req.query_string().as_bytes())
+                .into_owned()
+                .collect();
+        let command: Vec<String> = query
+            .iter()
+            .filter(|(k, _)| k == "command")
+            .map(|(_, v)| v.to_owned())
+            .collect();
+        let has_stdin = query.iter().any(|(k, v)| k == "stdin" && v == "true");
+
+        let actor = MockExec {
+            state: state.clone(),
+            command,
+            has_stdin,
+            stdin: vec![],
+        };
+        WsResponseBuilder::new(actor, &req, stream)
+            .protocols(&[EXEC_PROTOCOL])
+            .start()
+            .unwrap()
+    }
+
+    /// Responds to the commands that the pod sends similar to the api server, based on
+    /// the script of the command.
+    struct MockExec {
+        state: SharedState,
+        command: Vec<String>,
+        has_stdin: bool,
+        stdin: Vec<u8>,
+    }
+
+    impl MockExec {
+        fn script(&self) -> &str {
+            self.command.get(2).map(|x| x.as_str()).unwrap_or_default()
+        }
+
+        fn stdin_len(&self) -> usize {
+            self.script()
+                .split_once("head -c ")
+                .and_then(|(_, x)| x.split_whitespace().next())
+                .and_then(|x| x.parse().ok())
+                .unwrap_or_default()
+        }
+
+        fn respond(&mut self, ctx: &mut WebsocketContext<Self>) {
+            self.state.lock().unwrap().execs.push(MockExecRecord {
+                command: self.command.clone(),
+                stdin: self.stdin.clone(),
+            });
+
+            let script = self.command[2..].join(" ");
+            if script.contains("sleep") {
+                return;
+            }
+
+            let status = if script.contains("exit 3") {
+                Self::send(ctx, STDERR_CHANNEL, b"failed\n");
+                json!({
+                    "status": "Failure",
+                    "message": "command terminated with non-zero exit code",
+                    "reason": "NonZeroExitCode",
+                    "details": { "causes": [{ "reason": "ExitCode", "message": "3" }] },
+                })
+            } else if script.starts_with("tar -cf") {
+                Self::send(ctx, STDOUT_CHANNEL, &archive("report.txt", b"from pod"));
+                json!({ "status": "Success" })
+            } else if !self.has_stdin {
+                Self::send(ctx, STDOUT_CHANNEL, b"hello ");
+                Self::send(ctx, STDOUT_CHANNEL, b"from pod\n");
+                json!({ "status": "Success" })
+            } else {
+                json!({ "status": "Success" })
+            };
+
+            Self::send(ctx, ERROR_CHANNEL, status.to_string().as_bytes());
+            ctx.close(Some(ws::CloseCode::Normal.into()));
+            ctx.stop();
+        }
+
+        fn send(ctx: &mut WebsocketContext<Self>, channel: u8, data: &[u8]) {
+            let mut frame = vec![channel];
+            frame.extend_from_slice(data);
+            ctx.binary(frame);
+        }
+    }
+
+    impl Actor for MockExec {
+        type Context = WebsocketContext<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            if !self.has_stdin {
+                self.respond(ctx);
+            }
+        }
+    }
+
+    impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MockExec {
+        fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+            let Ok(ws::Message::Binary(data)) = msg else {
+                return;
+            };
+            if data.first() == Some(&STDIN_CHANNEL) {
+                self.stdin.extend_from_slice(&data[1..]);
+                if self.stdin.len() >= self.stdin_len() {
+                    self.respond(ctx);
+                }
+            }
+        }
+    }
+
+    fn archive(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let mut tar = Builder::new(Vec::new());
+        tar.append_data(&mut header, name, content).unwrap();
+        tar.into_inner().unwrap()
+    }
+
+    fn start_server() -> (String, SharedState) {
+        let state = Data::new(Mutex::new(MockState::default()));
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route(
+                    "/api/v1/namespaces/{namespace}/pods",
+                    web::post().to(create_pod),
+                )
+                .route(
+                    "/api/v1/namespaces/{namespace}/pods/{name}",
+                    web::get().to(get_pod),
+                )
+                .route(
+                    "/api/v1/namespaces/{namespace}/pods/{name}",
+                    web::delete().to(delete_pod),
+                )
+                .route(
+                    "/api/v1/namespaces/{namespace}/pods/{name}/exec",
+                    web::get().to(exec),
+                )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+        (url, state)
+    }
+
+    fn kubernetes_config(url: &str) -> KubernetesConfig {
+        serde_json::from_value(json!({ "url": url, "token": TOKEN })).unwrap()
+    }
+
+    fn pod(url: &str) -> KubernetesPod {
+        KubernetesPod {
+            client: KubernetesClient::new(&kubernetes_config(url)).unwrap(),
+            namespace: "default".to_owned(),
+            name: "bld-test".to_owned(),
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        let path = std::env::temp_dir().join(format!("bld-kubernetes-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    async fn create_pod_with_image(
+        url: &str,
+        image: &str,
+        pipeline_env: &HashMap<String, String>,
+        requests: &HashMap<String, String>,
+    ) -> Result<KubernetesPod> {
+        let mut config = BldConfig::default();
+        config
+            .local
+            .kubernetes
+            .insert("mock".to_owned(), kubernetes_config(url));
+
+        KubernetesPod::new(KubernetesPodOptions {
+            run_id: "run-1",
+            config: config.into_arc(),
+            kubernetes: "mock",
+            image,
+            namespace: Some("builds"),
+            requests: Some(requests),
+            limits: None,
+            node_selector: None,
+            service_account: Some("builder"),
+            pipeline_env,
+            env: HashMap::from([("CI".to_owned(), "true".to_owned())]).into_arc(),
+            logger: Logger::in_memory().into_arc(),
+        })
+        .await
+    }
+
+    #[actix_web::test]
+    async fn creates_and_disposes_pod() {
+        let (url, state) = start_server();
+        let pipeline_env = HashMap::from([("NAME".to_owned(), "bld".to_owned())]);
+        let requests = HashMap::from([("cpu".to_owned(), "500m".to_owned())]);
+
+        let pod = create_pod_with_image(&url, "alpine:latest", &pipeline_env, &requests)
+            .await
+            .unwrap();
+        assert!(pod.name.starts_with("bld-"));
+        assert_eq!(pod.namespace, "builds");
+
+        {
+            let state = state.lock().unwrap();
+            assert!(state
+                .authorization
+                .iter()
+                .all(|x| x == &format!("Bearer {TOKEN}")));
+
+            let spec = &state.pods[&pod.name];
+            assert_eq!(spec["metadata"]["labels"]["bld/run-id"], "run-1");
+            assert_eq!(spec["spec"]["restartPolicy"], "Never");
+            assert_eq!(spec["spec"]["serviceAccountName"], "builder");
+
+            let container = &spec["spec"]["containers"][0];
+            assert_eq!(container["name"], CONTAINER_NAME);
+            assert_eq!(container["image"], "alpine:latest");
+            assert_eq!(container["resources"]["requests"]["cpu"], "500m");
+            let env = container["env"].as_array().unwrap();
+            assert!(env.contains(&json!({ "name": "NAME", "value": "bld" })));
+            assert!(env.contains(&json!({ "name": "CI", "value": "true" })));
+        }
+
+        pod.dispose().await.unwrap();
+        let state = state.lock().unwrap();
+        assert_eq!(
+            state.deleted,
+            vec![format!(
+                "/api/v1/namespaces/builds/pods/{}?gracePeriodSeconds=0",
+                pod.name
+            )]
+        );
+    }
+
+    #[actix_web::test]
+    async fn deletes_pod_that_fails_to_start() {
+        let (url, state) = start_server();
+
+        let error = create_pod_with_image(&url, MISSING_IMAGE, &HashMap::new(), &HashMap::new())
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("ErrImagePull image not found"));
+
+        let state = state.lock().unwrap();
+        let name = state.pods.keys().next().unwrap();
+        assert_eq!(
+            state.deleted,
+            vec![format!(
+                "/api/v1/namespaces/builds/pods/{name}?gracePeriodSeconds=0"
+            )]
+        );
+    }
+
+    #[actix_web::test]
+    async fn executes_command_in_pod() {
+        let (url, state) = start_server();
+        let pod = pod(&url);
+        let logger = Logger::in_memory().into_arc();
+
+        pod.sh(
+            logger.clone(),
+            &Some("/work".to_owned()),
+            "echo hello",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            logger.try_retrieve_output().await.unwrap(),
+            "hello from pod\n"
+        );
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.authorization, vec![format!("Bearer {TOKEN}")]);
+        assert_eq!(
+            state.execs[0].command,
+            vec!["sh", "-c", "cd /work && echo hello"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn sends_the_environment_of_a_command_through_stdin() {
+        let (url, state) = start_server();
+        let pod = pod(&url);
+        let logger = Logger::in_memory().into_arc();
+        let env = HashMap::from([("BLD_TOKEN".to_owned(), "secret".to_owned())]);
+
+        pod.sh_with_env(logger, &None, "git fetch", &env, None)
+            .await
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        let command = state.execs[0].command.join(" ");
+        assert!(!command.contains("secret"), "{command}");
+        assert!(command.contains("read -r BLD_TOKEN"), "{command}");
+        assert!(command.contains("git fetch"), "{command}");
+        assert_eq!(state.execs[0].stdin, b"secret\n");
+    }
+
+    #[actix_web::test]
+    async fn returns_exit_code_of_failed_command() {
+        let (url, _) = start_server();
+        let pod = pod(&url);
+        let logger = Logger::in_memory().into_arc();
+
+        let error = pod
+            .sh(logger.clone(), &None, "exit 3", None)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.downcast_ref::<ExitCodeError>().unwrap().0, 3);
+
+        let entries = logger.try_retrieve_entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "failed\n");
+        assert_eq!(entries[0].stream, bld_models::dtos::LogStream::Stderr);
+    }
+
+    #[actix_web::test]
+    async fn kills_process_group_of_timed_out_command() {
+        let (url, state) = start_server();
+        let pod = pod(&url);
+        let logger = Logger::in_memory().into_arc();
+
+        let error = pod
+            .sh(logger, &None, "sleep 60", Some(Duration::from_millis(500)))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "command timed out");
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.execs.len(), 2);
+        let command = &state.execs[0].command;
+        assert_eq!(command[3], "sleep 60");
+        let pid_file = &command[4];
+        assert_eq!(
+            state.execs[1].command,
+            vec!["sh", "-c", &kill_process_group_command(pid_file)]
+        );
+    }
+
+    #[actix_web::test]
+    async fn copies_file_into_pod() {
+        let (url, state) = start_server();
+        let pod = pod(&url);
+        let dir = temp_dir();
+        let file = dir.join("input.txt");
+        std::fs::write(&file, "from host").unwrap();
+
+        pod.copy_into(file.to_str().unwrap(), "/work dir")
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let state = state.lock().unwrap();
+        let exec = &state.execs[0];
+        assert_eq!(
+            exec.command[2],
+            format!(
+                "mkdir -p '/work dir' && head -c {} | tar -xf - -C '/work dir'",
+                exec.stdin.len()
+            )
+        );
+
+        let mut archive = Archive::new(&exec.stdin[..]);
+        let mut entries = archive.entries().unwrap();
+        let mut entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("input.txt"));
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut entry, &mut content).unwrap();
+        assert_eq!(content, "from host");
+    }
+
+    #[actix_web::test]
+    async fn copies_file_from_pod() {
+        let (url, state) = start_server();
+        let pod = pod(&url);
+        let dir = temp_dir();
+
+        pod.copy_from("/reports/report.txt", dir.to_str().unwrap())
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(dir.join("report.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content, "from pod");
+
+        let state = state.lock().unwrap();
+        assert_eq!(
+            state.execs[0].command,
+            vec!["sh", "-c", "tar -cf - -C '/reports' 'report.txt'"]
+        );
+    }
+}
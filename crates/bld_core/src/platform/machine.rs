@@ -1,19 +1,32 @@
-use crate::logger::Logger;
-use anyhow::{bail, Result};
+use crate::{
+    logger::Logger,
+    platform::{ExitCodeError, CACHE_ROOT_DIR},
+};
+use anyhow::{anyhow, bail, Result};
 use bld_config::{path, BldConfig};
 use bld_utils::shell::get_shell;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use std::{
     collections::HashMap,
-    fmt::Write,
     path::{Path, PathBuf},
-    process::ExitStatus,
+    process::{ExitStatus, Stdio},
     sync::Arc,
+    time::Duration,
+};
+use tar::{Archive, Builder};
+use tokio::{
+    fs::{copy, create_dir_all, remove_dir_all},
+    io::{AsyncBufReadExt, BufReader},
+    select,
+    task::spawn_blocking,
+    time::timeout as with_timeout,
 };
-use tokio::fs::{copy, create_dir_all, remove_dir_all};
+use walkdir::WalkDir;
 
 pub struct Machine {
     tmp_dir: String,
     env: HashMap<String, String>,
+    tag_stderr: bool,
 }
 
 impl Machine {
@@ -30,6 +43,7 @@ impl Machine {
         Ok(Self {
             tmp_dir: tmp_path.display().to_string(),
             env: Self::create_environment(pipeline_env, env),
+            tag_stderr: config.local.tag_stderr,
         })
     }
 
@@ -63,11 +77,131 @@ impl Machine {
         self.copy(from, to).await
     }
 
+    pub async fn copy_from_into_dir(&self, from: &str, dir: &str) -> Result<()> {
+        let from = path![from];
+        let name = from
+            .file_name()
+            .ok_or_else(|| anyhow!("unable to retrieve file name for path {}", from.display()))?;
+        let to = path![dir, name];
+
+        if from.is_file() {
+            copy(&from, &to).await?;
+            return Ok(());
+        }
+
+        for entry in WalkDir::new(&from) {
+            let entry = entry?;
+            let target = path![&to, entry.path().strip_prefix(&from)?];
+            if entry.file_type().is_dir() {
+                create_dir_all(&target).await?;
+            } else {
+                copy(entry.path(), &target).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn restore_cache(&self, archive: &Path) -> Result<()> {
+        let tmp_dir = self.tmp_dir.clone();
+        let archive = archive.to_owned();
+        spawn_blocking(move || {
+            let file = std::fs::File::open(archive)?;
+            let mut archive = Archive::new(GzDecoder::new(file));
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let path = entry.path()?.into_owned();
+                let Ok(path) = path.strip_prefix(CACHE_ROOT_DIR) else {
+                    entry.unpack_in(&tmp_dir)?;
+                    continue;
+                };
+                let path = path!["/", path];
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                entry.unpack(path)?;
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Returns the path of a cache entry in the machine along with its name in the
+    /// archive, absolute paths and paths of the home directory are kept under the
+    /// root directory of the archive.
+    fn cache_path(&self, cache_path: &str) -> Result<(PathBuf, PathBuf)> {
+        let home = || {
+            self.env
+                .get("HOME")
+                .cloned()
+                .or_else(|| std::env::var("HOME").ok())
+                .ok_or_else(|| {
+                    anyhow!("unable to find the home directory for cache path {cache_path}")
+                })
+        };
+
+        let full_path = if cache_path == "~" {
+            path![home()?]
+        } else if let Some(rest) = cache_path.strip_prefix("~/") {
+            path![home()?, rest]
+        } else if Path::new(cache_path).is_absolute() {
+            path![cache_path]
+        } else {
+            return Ok((path![&self.tmp_dir, cache_path], path![cache_path]));
+        };
+
+        let name = path![CACHE_ROOT_DIR, full_path.strip_prefix("/")?];
+        Ok((full_path, name))
+    }
+
+    pub async fn save_cache(&self, paths: &[String], archive: &Path) -> Result<()> {
+        let mut entries = vec![];
+        for path in paths {
+            let (full_path, name) = self.cache_path(path)?;
+            if full_path.exists() {
+                entries.push((name, full_path));
+            }
+        }
+
+        if entries.is_empty() {
+            bail!("none of the cache paths exist");
+        }
+
+        let archive = archive.to_owned();
+        spawn_blocking(move || {
+            let file = std::fs::File::create(archive)?;
+            let mut tar = Builder::new(GzEncoder::new(file, Compression::default()));
+            for (name, full_path) in entries {
+                if full_path.is_dir() {
+                    tar.append_dir_all(name, full_path)?;
+                } else {
+                    tar.append_path_with_name(full_path, name)?;
+                }
+            }
+            tar.into_inner()?.finish()?;
+            Ok(())
+        })
+        .await?
+    }
+
     pub async fn sh(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
         input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.sh_with_env(logger, working_dir, input, &HashMap::new(), timeout)
+            .await
+    }
+
+    pub async fn sh_with_env(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        input: &str,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         let current_dir = working_dir.as_ref().unwrap_or(&self.tmp_dir).to_string();
         let current_dir = if Path::new(&current_dir).is_relative() {
@@ -78,26 +212,108 @@ impl Machine {
 
         let mut shell = get_shell(&mut vec![input])?;
         shell.envs(&self.env);
+        shell.envs(env);
         shell.current_dir(current_dir);
+        shell.stdout(Stdio::piped());
+        shell.stderr(Stdio::piped());
+        shell.kill_on_drop(true);
+
+        // the command is started in a new session so that every process it started
+        // is in the same process group and can be killed when the timeout is reached.
+        #[cfg(target_family = "unix")]
+        unsafe {
+            shell.pre_exec(|| {
+                nix::unistd::setsid()
+                    .map(|_| ())
+                    .map_err(std::io::Error::from)
+            });
+        }
+
+        let mut process = shell.spawn()?;
+        let pid = process.id();
+        let stdout = process
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("unable to capture stdout of command"))?;
+        let stderr = process
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("unable to capture stderr of command"))?;
+
+        // both streams are read in the same loop so that the lines are written
+        // to the logger in the order that the command produced them.
+        let read_output = async {
+            let mut stdout = BufReader::new(stdout).split(b'\n');
+            let mut stderr = BufReader::new(stderr).split(b'\n');
+            let mut stdout_open = true;
+            let mut stderr_open = true;
 
-        let process = shell.output().await?;
-        let mut output = String::new();
+            while stdout_open || stderr_open {
+                select! {
+                    line = stdout.next_segment(), if stdout_open => match line? {
+                        Some(line) => logger.write_line(Self::line(&line)).await?,
+                        None => stdout_open = false,
+                    },
+                    line = stderr.next_segment(), if stderr_open => match line? {
+                        Some(line) => logger.stderr_line(self.stderr_line(&line)).await?,
+                        None => stderr_open = false,
+                    },
+                }
+            }
 
-        if !process.stderr.is_empty() {
-            writeln!(output, "{}", String::from_utf8_lossy(&process.stderr))?;
+            Ok::<ExitStatus, anyhow::Error>(process.wait().await?)
+        };
+
+        let status = match timeout {
+            Some(duration) => match with_timeout(duration, read_output).await {
+                Ok(status) => status?,
+                Err(_) => {
+                    Self::kill_process_group(pid);
+                    bail!("command timed out");
+                }
+            },
+            None => read_output.await?,
+        };
+
+        if let Some(code) = status.code().filter(|x| *x != 0) {
+            return Err(ExitCodeError(code.into()).into());
         }
 
-        if !process.stdout.is_empty() {
-            writeln!(output, "{}", String::from_utf8_lossy(&process.stdout))?;
+        if !ExitStatus::success(&status) {
+            bail!("command finished with {}", status);
         }
 
-        logger.write(output).await?;
+        Ok(())
+    }
+
+    #[cfg(target_family = "unix")]
+    fn kill_process_group(pid: Option<u32>) {
+        use nix::{
+            sys::signal::{killpg, Signal},
+            unistd::Pid,
+        };
 
-        if !ExitStatus::success(&process.status) {
-            bail!("command finished with {}", process.status);
+        if let Some(pid) = pid {
+            let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
         }
+    }
 
-        Ok(())
+    #[cfg(not(target_family = "unix"))]
+    fn kill_process_group(_pid: Option<u32>) {}
+
+    fn line(bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes)
+            .trim_end_matches('\r')
+            .to_string()
+    }
+
+    fn stderr_line(&self, bytes: &[u8]) -> String {
+        let line = Self::line(bytes);
+        if self.tag_stderr {
+            format!("[stderr] {line}")
+        } else {
+            line
+        }
     }
 
     pub async fn dispose(&self) -> Result<()> {
@@ -105,3 +321,192 @@ impl Machine {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use bld_utils::sync::IntoArc;
+    use tokio::time::{sleep, Instant};
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn machine(tag_stderr: bool) -> Machine {
+        let tmp_dir = path![temp_dir(), format!("bld-machine-{}", Uuid::new_v4())];
+        std::fs::create_dir_all(&tmp_dir).unwrap();
+        Machine {
+            tmp_dir: tmp_dir.display().to_string(),
+            env: HashMap::new(),
+            tag_stderr,
+        }
+    }
+
+    #[actix_web::test]
+    async fn exit_code_of_failed_command() {
+        let machine = machine(false);
+        let logger = Logger::in_memory().into_arc();
+
+        let error = machine.sh(logger, &None, "exit 3", None).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<ExitCodeError>().map(|x| x.0), Some(3));
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn environment_is_set_for_a_single_command() {
+        let machine = machine(false);
+        let logger = Logger::in_memory().into_arc();
+        let env = HashMap::from([("BLD_TOKEN".to_owned(), "it's a $secret".to_owned())]);
+
+        machine
+            .sh_with_env(logger.clone(), &None, "echo \"$BLD_TOKEN\"", &env, None)
+            .await
+            .unwrap();
+        machine
+            .sh(logger.clone(), &None, "echo \"${BLD_TOKEN:-unset}\"", None)
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "it's a $secret\nunset\n");
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn timeout_kills_the_process_group() {
+        let machine = machine(false);
+        let logger = Logger::in_memory().into_arc();
+        let marker = path![&machine.tmp_dir, "marker"];
+        let command = format!("(sleep 1 && touch {}) & sleep 10", marker.display());
+
+        let start = Instant::now();
+        let error = machine
+            .sh(logger, &None, &command, Some(Duration::from_millis(300)))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "command timed out");
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "a process of the command survived");
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn output_is_written_in_order() {
+        let machine = machine(true);
+        let logger = Logger::in_memory().into_arc();
+        let command =
+            "echo one; sleep 0.1; echo two >&2; sleep 0.1; echo three; sleep 0.1; printf four >&2";
+
+        machine
+            .sh(logger.clone(), &None, command, None)
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "one\n[stderr] two\nthree\n[stderr] four\n");
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn stderr_is_untagged_by_default() {
+        let machine = machine(false);
+        let logger = Logger::in_memory().into_arc();
+
+        machine
+            .sh(logger.clone(), &None, "echo one >&2", None)
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "one\n");
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn output_is_streamed_while_running() {
+        let machine = machine(false);
+        let logger = Logger::in_memory().into_arc();
+        let marker = path![&machine.tmp_dir, "marker"];
+        let command = format!(
+            "echo started; while [ ! -f {} ]; do sleep 0.05; done; echo finished",
+            marker.display()
+        );
+
+        let running = async {
+            machine
+                .sh(
+                    logger.clone(),
+                    &None,
+                    &command,
+                    Some(Duration::from_secs(5)),
+                )
+                .await
+        };
+        let check = async {
+            let start = Instant::now();
+            loop {
+                let output = logger.try_retrieve_output().await.unwrap();
+                if output.contains("started") {
+                    assert!(!output.contains("finished"));
+                    break;
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(3),
+                    "no output streamed"
+                );
+                sleep(Duration::from_millis(20)).await;
+            }
+            std::fs::write(&marker, "").unwrap();
+        };
+
+        let (result, _) = tokio::join!(running, check);
+        result.unwrap();
+
+        machine.dispose().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn cache_restores_relative_absolute_and_home_paths() {
+        let mut machine = machine(false);
+        let dir = path![temp_dir(), format!("bld-machine-cache-{}", Uuid::new_v4())];
+        let home = path![&dir, "home"];
+        let absolute = path![&dir, "absolute"];
+        machine
+            .env
+            .insert("HOME".to_owned(), home.display().to_string());
+
+        let work_dir = path![&machine.tmp_dir];
+        for x in [&work_dir, &home, &absolute] {
+            std::fs::create_dir_all(path![x, "deps"]).unwrap();
+            std::fs::write(path![x, "deps", "file"], x.display().to_string()).unwrap();
+        }
+
+        let archive = path![&dir, "cache.tar.gz"];
+        let paths = vec![
+            "deps".to_owned(),
+            path![&absolute, "deps"].display().to_string(),
+            "~/deps".to_owned(),
+            "missing".to_owned(),
+        ];
+        machine.save_cache(&paths, &archive).await.unwrap();
+        for x in [&work_dir, &home, &absolute] {
+            std::fs::remove_dir_all(path![x, "deps"]).unwrap();
+        }
+
+        machine.restore_cache(&archive).await.unwrap();
+        for x in [&work_dir, &home, &absolute] {
+            let content = std::fs::read_to_string(path![x, "deps", "file"]).unwrap();
+            assert_eq!(content, x.display().to_string());
+        }
+
+        machine.dispose().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
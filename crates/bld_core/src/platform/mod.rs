@@ -1,32 +1,153 @@
+mod build_context;
 pub mod builder;
 mod container;
 mod context;
 mod docker;
 mod image;
+mod kubernetes;
 mod machine;
+mod service;
 mod ssh;
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 pub use container::*;
 pub use context::*;
 pub use docker::*;
 use futures::channel::oneshot;
 pub use image::*;
+pub use kubernetes::*;
 pub use machine::*;
+pub use service::*;
 pub use ssh::*;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use uuid::Uuid;
 
 use actix::spawn;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use tracing::{debug, error};
 
 use crate::logger::Logger;
 
+/// Error returned by a platform when a shell command exits with a non zero code.
+#[derive(Debug)]
+pub struct ExitCodeError(pub i64);
+
+impl Display for ExitCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command finished with exit code: {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitCodeError {}
+
+/// Builds a shell script that runs the command given as `$0` in a new session, using
+/// `setsid` when it's available, and keeps the id of the session's process group in
+/// the pid file given as `$1` so that every process started by the command can be
+/// killed. The pid file is moved into place once written so that it's never read
+/// while empty, and it's removed once the command exits.
+fn process_group_script(shell: &str) -> String {
+    format!(
+        "s=; command -v setsid >/dev/null 2>&1 && s=setsid; $s sh -c 'echo $$ > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"; exec {shell} -c \"$0\"' \"$0\" \"$1\"; code=$?; rm -f \"$1\"; exit $code"
+    )
+}
+
+/// Builds a shell command that kills the process group kept in the pid file, or only
+/// the process when it couldn't be started in its own session, and removes the file.
+fn kill_process_group_command(pid_file: &str) -> String {
+    format!(
+        "pid=$(cat {pid_file}) && {{ kill -9 -$pid 2>/dev/null || kill -9 $pid; }}; rm -f {pid_file}"
+    )
+}
+
+/// Builds a shell command that reads the values of the environment variables from
+/// its standard input, one line per variable, before running the input, for the
+/// platforms that can't set the environment of a single command. The values are
+/// returned as the content of the standard input so that they aren't part of the
+/// command.
+fn stdin_env_command(input: &str, env: &HashMap<String, String>) -> Result<(String, Vec<u8>)> {
+    let mut names: Vec<&String> = env.keys().collect();
+    names.sort();
+
+    let mut reads = vec![];
+    let mut stdin = vec![];
+    for name in names {
+        let value = &env[name];
+        if value.contains('\n') {
+            bail!("the value of the environment variable {name} can't contain new lines");
+        }
+        reads.push(format!("IFS= read -r {name} && export {name}"));
+        stdin.extend_from_slice(value.as_bytes());
+        stdin.push(b'\n');
+    }
+
+    let command = format!("{} && {{ {input}\n}} </dev/null", reads.join(" && "));
+    Ok((command, stdin))
+}
+
+/// The directory of a cache archive that keeps the entries of absolute paths, since
+/// the entries of an archive are relative to the working directory.
+const CACHE_ROOT_DIR: &str = ".bld-root";
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Builds a shell command that archives the paths of a cache entry that exist on the
+/// platform into the provided archive. Relative paths are archived from the working
+/// directory while absolute paths and paths of the home directory are kept under
+/// the root directory of the archive.
+fn save_cache_command(paths: &[String], archive: &str) -> String {
+    let mut has_absolute = false;
+    let paths = paths
+        .iter()
+        .map(|x| {
+            if x == "~" {
+                has_absolute = true;
+                "\"$HOME\"".to_string()
+            } else if let Some(rest) = x.strip_prefix("~/") {
+                has_absolute = true;
+                format!("\"$HOME\"/{}", quote(rest))
+            } else {
+                has_absolute |= x.starts_with('/');
+                quote(x)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let options = if has_absolute {
+        format!("-czPf {archive} --transform 's,^/,{CACHE_ROOT_DIR}/,S'")
+    } else {
+        format!("-czf {archive}")
+    };
+    format!(
+        "set --; for p in {paths}; do if [ -e \"$p\" ]; then set -- \"$@\" \"$p\"; fi; done; tar {options} \"$@\""
+    )
+}
+
+fn restore_cache_command(archive: &str) -> String {
+    format!(
+        "if tar -tzf {archive} | grep -q '^\\{CACHE_ROOT_DIR}/'; then tar -xzPf {archive} --transform 's,^\\{CACHE_ROOT_DIR}/,/,S'; else tar -xzf {archive}; fi; status=$?; rm -f {archive}; exit $status"
+    )
+}
+
+fn cache_archive_name(archive: &Path) -> Result<&str> {
+    archive
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or_else(|| anyhow!("unable to retrieve file name for cache archive"))
+}
+
 pub enum PlatformArtifactsAction {
     Push,
     Get,
+    GetIntoDir,
 }
 
 pub enum PlatformMessage {
@@ -40,6 +161,19 @@ pub enum PlatformMessage {
         logger: Arc<Logger>,
         working_dir: Option<String>,
         command: String,
+        env: HashMap<String, String>,
+        timeout: Option<Duration>,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
+    RestoreCache {
+        logger: Arc<Logger>,
+        archive: PathBuf,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
+    SaveCache {
+        logger: Arc<Logger>,
+        paths: Vec<String>,
+        archive: PathBuf,
         resp_tx: oneshot::Sender<Result<()>>,
     },
     Dispose {
@@ -50,6 +184,7 @@ pub enum PlatformMessage {
 pub enum PlatformType {
     Machine(Box<Machine>),
     Container(Box<Container>),
+    Kubernetes(Box<KubernetesPod>),
     Ssh(Sender<PlatformMessage>),
 }
 
@@ -75,6 +210,9 @@ impl PlatformBackend {
                     let res = match action {
                         PlatformArtifactsAction::Push => self.push(from, to).await,
                         PlatformArtifactsAction::Get => self.get(from, to).await,
+                        PlatformArtifactsAction::GetIntoDir => {
+                            self.ssh.copy_from_into_dir(&from, &to).await
+                        }
                     };
                     resp_tx
                         .send(res)
@@ -85,9 +223,34 @@ impl PlatformBackend {
                     logger,
                     working_dir,
                     command,
+                    env,
+                    timeout,
                     resp_tx,
                 } => {
-                    let res = self.shell(logger, working_dir, command).await;
+                    let res = self.shell(logger, working_dir, command, env, timeout).await;
+                    resp_tx
+                        .send(res)
+                        .map_err(|_| anyhow!("oneshot channel closed"))?;
+                }
+
+                PlatformMessage::RestoreCache {
+                    logger,
+                    archive,
+                    resp_tx,
+                } => {
+                    let res = self.ssh.restore_cache(logger, &archive).await;
+                    resp_tx
+                        .send(res)
+                        .map_err(|_| anyhow!("oneshot channel closed"))?;
+                }
+
+                PlatformMessage::SaveCache {
+                    logger,
+                    paths,
+                    archive,
+                    resp_tx,
+                } => {
+                    let res = self.ssh.save_cache(logger, &paths, &archive).await;
                     resp_tx
                         .send(res)
                         .map_err(|_| anyhow!("oneshot channel closed"))?;
@@ -119,8 +282,12 @@ impl PlatformBackend {
         logger: Arc<Logger>,
         working_dir: Option<String>,
         command: String,
+        env: HashMap<String, String>,
+        timeout: Option<Duration>,
     ) -> Result<()> {
-        self.ssh.sh(logger, &working_dir, &command).await
+        self.ssh
+            .sh_with_env(logger, &working_dir, &command, &env, timeout)
+            .await
     }
 
     pub async fn dispose(&mut self) -> Result<()> {
@@ -131,6 +298,7 @@ impl PlatformBackend {
 pub struct Platform {
     id: String,
     inner: PlatformType,
+    services: Option<Services>,
 }
 
 impl Platform {
@@ -139,6 +307,7 @@ impl Platform {
         Self {
             id,
             inner: PlatformType::Machine(machine),
+            services: None,
         }
     }
 
@@ -147,6 +316,16 @@ impl Platform {
         Self {
             id,
             inner: PlatformType::Container(container),
+            services: None,
+        }
+    }
+
+    pub fn kubernetes(pod: Box<KubernetesPod>) -> Self {
+        let id = Uuid::new_v4().to_string();
+        Self {
+            id,
+            inner: PlatformType::Kubernetes(pod),
+            services: None,
         }
     }
 
@@ -164,9 +343,15 @@ impl Platform {
         Self {
             id,
             inner: PlatformType::Ssh(tx),
+            services: None,
         }
     }
 
+    pub fn with_services(mut self, services: Option<Services>) -> Self {
+        self.services = services;
+        self
+    }
+
     pub fn id(&self) -> &str {
         self.id.as_str()
     }
@@ -179,6 +364,7 @@ impl Platform {
         match &self.inner {
             PlatformType::Machine(machine) => machine.copy_into(from, to).await,
             PlatformType::Container(container) => container.copy_into(from, to).await,
+            PlatformType::Kubernetes(pod) => pod.copy_into(from, to).await,
             PlatformType::Ssh(ssh) => {
                 let (resp_tx, resp_rx) = oneshot::channel();
 
@@ -199,6 +385,7 @@ impl Platform {
         match &self.inner {
             PlatformType::Machine(machine) => machine.copy_from(from, to).await,
             PlatformType::Container(container) => container.copy_from(from, to).await,
+            PlatformType::Kubernetes(pod) => pod.copy_from(from, to).await,
             PlatformType::Ssh(ssh) => {
                 let (resp_tx, resp_rx) = oneshot::channel();
 
@@ -215,15 +402,65 @@ impl Platform {
         }
     }
 
+    /// Copies a file or directory of the platform into a local directory, keeping
+    /// its name.
+    pub async fn get_into_dir(&self, from: &str, dir: &str) -> Result<()> {
+        match &self.inner {
+            PlatformType::Machine(machine) => machine.copy_from_into_dir(from, dir).await,
+            PlatformType::Container(container) => container.copy_from(from, dir).await,
+            PlatformType::Kubernetes(pod) => pod.copy_from(from, dir).await,
+            PlatformType::Ssh(ssh) => {
+                let (resp_tx, resp_rx) = oneshot::channel();
+
+                ssh.send(PlatformMessage::Artifacts {
+                    action: PlatformArtifactsAction::GetIntoDir,
+                    from: from.to_string(),
+                    to: dir.to_string(),
+                    resp_tx,
+                })
+                .await?;
+
+                resp_rx.await?
+            }
+        }
+    }
+
     pub async fn shell(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
         command: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.shell_with_env(logger, working_dir, command, &HashMap::new(), timeout)
+            .await
+    }
+
+    /// Runs the command with additional environment variables that are only set for
+    /// this command, so that values such as credentials aren't part of the command.
+    pub async fn shell_with_env(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        command: &str,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         match &self.inner {
-            PlatformType::Machine(machine) => machine.sh(logger, working_dir, command).await,
-            PlatformType::Container(container) => container.sh(logger, working_dir, command).await,
+            PlatformType::Machine(machine) => {
+                machine
+                    .sh_with_env(logger, working_dir, command, env, timeout)
+                    .await
+            }
+            PlatformType::Container(container) => {
+                container
+                    .sh_with_env(logger, working_dir, command, env, timeout)
+                    .await
+            }
+            PlatformType::Kubernetes(pod) => {
+                pod.sh_with_env(logger, working_dir, command, env, timeout)
+                    .await
+            }
             PlatformType::Ssh(ssh) => {
                 let (resp_tx, resp_rx) = oneshot::channel();
 
@@ -231,6 +468,60 @@ impl Platform {
                     logger,
                     working_dir: working_dir.clone(),
                     command: command.to_string(),
+                    env: env.clone(),
+                    timeout,
+                    resp_tx,
+                })
+                .await?;
+
+                resp_rx.await?
+            }
+        }
+    }
+
+    /// Extracts a cache archive from the local cache store into the working directory
+    /// of the platform.
+    pub async fn restore_cache(&self, logger: Arc<Logger>, archive: &Path) -> Result<()> {
+        match &self.inner {
+            PlatformType::Machine(machine) => machine.restore_cache(archive).await,
+            PlatformType::Container(container) => container.restore_cache(logger, archive).await,
+            PlatformType::Kubernetes(pod) => pod.restore_cache(logger, archive).await,
+            PlatformType::Ssh(ssh) => {
+                let (resp_tx, resp_rx) = oneshot::channel();
+
+                ssh.send(PlatformMessage::RestoreCache {
+                    logger,
+                    archive: archive.to_owned(),
+                    resp_tx,
+                })
+                .await?;
+
+                resp_rx.await?
+            }
+        }
+    }
+
+    /// Archives the provided paths of the platform and writes the archive to the
+    /// local cache store.
+    pub async fn save_cache(
+        &self,
+        logger: Arc<Logger>,
+        paths: &[String],
+        archive: &Path,
+    ) -> Result<()> {
+        match &self.inner {
+            PlatformType::Machine(machine) => machine.save_cache(paths, archive).await,
+            PlatformType::Container(container) => {
+                container.save_cache(logger, paths, archive).await
+            }
+            PlatformType::Kubernetes(pod) => pod.save_cache(logger, paths, archive).await,
+            PlatformType::Ssh(ssh) => {
+                let (resp_tx, resp_rx) = oneshot::channel();
+
+                ssh.send(PlatformMessage::SaveCache {
+                    logger,
+                    paths: paths.to_vec(),
+                    archive: archive.to_owned(),
                     resp_tx,
                 })
                 .await?;
@@ -241,6 +532,9 @@ impl Platform {
     }
 
     pub async fn keep_alive(&self) -> Result<()> {
+        if let Some(services) = &self.services {
+            services.keep_alive().await?;
+        }
         match &self.inner {
             PlatformType::Container(container) => container.keep_alive().await,
             _ => Ok(()),
@@ -248,11 +542,20 @@ impl Platform {
     }
 
     pub async fn dispose(&self, in_child_runner: bool) -> Result<()> {
+        let result = self.dispose_inner(in_child_runner).await;
+        match &self.services {
+            Some(services) => result.and(services.dispose().await),
+            None => result,
+        }
+    }
+
+    async fn dispose_inner(&self, in_child_runner: bool) -> Result<()> {
         match &self.inner {
             // checking if the runner is a child in order to not cleanup the temp dir for the whole run
             PlatformType::Machine(machine) if !in_child_runner => machine.dispose().await,
             PlatformType::Machine(_) => Ok(()),
             PlatformType::Container(container) => container.dispose().await,
+            PlatformType::Kubernetes(pod) => pod.dispose().await,
             PlatformType::Ssh(ssh) => {
                 let (resp_tx, resp_rx) = oneshot::channel();
                 ssh.send(PlatformMessage::Dispose { resp_tx }).await?;
@@ -261,3 +564,165 @@ impl Platform {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        process::Command,
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    use bld_config::path;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn run_in_process_group(command: &str, pid_file: &str) -> Command {
+        let mut process = Command::new("sh");
+        process.args(["-c", &process_group_script("sh"), command, pid_file]);
+        process
+    }
+
+    #[test]
+    fn process_group_script_keeps_the_exit_code() {
+        let pid_file = path![temp_dir(), format!("bld-{}.pid", Uuid::new_v4())];
+        let pid_file = pid_file.display().to_string();
+
+        let output = run_in_process_group("echo \"$0 it's\" && exit 3", &pid_file)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "sh it's\n");
+        assert!(!Path::new(&pid_file).exists());
+    }
+
+    #[test]
+    fn kill_process_group_command_kills_every_process() {
+        let dir = path![temp_dir(), format!("bld-{}", Uuid::new_v4())];
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = path![&dir, "pid"].display().to_string();
+        let marker = path![&dir, "marker"];
+        let command = format!("(sleep 1 && touch {}) & sleep 10", marker.display());
+
+        let start = Instant::now();
+        let mut process = run_in_process_group(&command, &pid_file).spawn().unwrap();
+        while !Path::new(&pid_file).exists() {
+            sleep(Duration::from_millis(10));
+        }
+
+        let status = Command::new("sh")
+            .args(["-c", &kill_process_group_command(&pid_file)])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        process.wait().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        sleep(Duration::from_millis(1500));
+        assert!(!marker.exists(), "a process of the command survived");
+        assert!(!Path::new(&pid_file).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn sh_in(dir: &Path, home: &Path, command: &str) {
+        let status = Command::new("sh")
+            .args(["-c", command])
+            .current_dir(dir)
+            .env("HOME", home)
+            .status()
+            .unwrap();
+        assert!(status.success(), "{command}");
+    }
+
+    #[test]
+    fn cache_commands_restore_relative_absolute_and_home_paths() {
+        let dir = path![temp_dir(), format!("bld-{}", Uuid::new_v4())];
+        let work_dir = path![&dir, "work"];
+        let home = path![&dir, "home"];
+        let absolute = path![&dir, "absolute"];
+        for x in [&work_dir, &home, &absolute] {
+            std::fs::create_dir_all(path![x, "it's"]).unwrap();
+            std::fs::write(path![x, "it's", "file"], x.display().to_string()).unwrap();
+        }
+
+        let archive = path![&dir, "cache.tar.gz"].display().to_string();
+        let paths = vec![
+            "it's".to_owned(),
+            absolute.display().to_string(),
+            "~/it's".to_owned(),
+            "missing".to_owned(),
+        ];
+        sh_in(&work_dir, &home, &save_cache_command(&paths, &archive));
+        for x in [&work_dir, &home, &absolute] {
+            std::fs::remove_dir_all(path![x, "it's"]).unwrap();
+        }
+
+        sh_in(&work_dir, &home, &restore_cache_command(&archive));
+        for x in [&work_dir, &home, &absolute] {
+            let content = std::fs::read_to_string(path![x, "it's", "file"]).unwrap();
+            assert_eq!(content, x.display().to_string());
+        }
+        assert!(!Path::new(&archive).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cache_commands_of_relative_paths_use_portable_options() {
+        let paths = vec!["target".to_owned(), "node_modules".to_owned()];
+        let command = save_cache_command(&paths, "/tmp/cache.tar.gz");
+        assert!(command.ends_with("tar -czf /tmp/cache.tar.gz \"$@\""));
+        assert!(command.contains("for p in 'target' 'node_modules';"));
+    }
+
+    fn run_with_stdin(command: &str, stdin: &[u8]) -> String {
+        let mut process = Command::new("sh")
+            .args(["-c", command])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::io::Write::write_all(&mut process.stdin.take().unwrap(), stdin).unwrap();
+        let output = process.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn stdin_env_command_keeps_the_values_out_of_the_command() {
+        let env = HashMap::from([
+            ("BLD_TOKEN".to_owned(), "it's a $secret".to_owned()),
+            ("BLD_USER".to_owned(), " user ".to_owned()),
+        ]);
+        let (command, stdin) =
+            stdin_env_command("echo \"[$BLD_USER]\" && echo \"$BLD_TOKEN\"", &env).unwrap();
+        assert!(!command.contains("secret"), "{command}");
+        assert!(!command.contains(" user "), "{command}");
+
+        let output = run_with_stdin(&command, &stdin);
+        assert_eq!(output, "[ user ]\nit's a $secret\n");
+    }
+
+    #[test]
+    fn stdin_env_command_closes_the_input_of_the_command() {
+        let env = HashMap::from([("BLD_TOKEN".to_owned(), "secret".to_owned())]);
+        let (command, mut stdin) = stdin_env_command("cat; echo done", &env).unwrap();
+        stdin.extend_from_slice(b"left over\n");
+
+        let output = run_with_stdin(&command, &stdin);
+        assert_eq!(output, "done\n");
+    }
+
+    #[test]
+    fn stdin_env_command_rejects_values_with_new_lines() {
+        let env = HashMap::from([("BLD_TOKEN".to_owned(), "one\ntwo".to_owned())]);
+        let error = stdin_env_command("true", &env).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the value of the environment variable BLD_TOKEN can't contain new lines"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use actix::clock::{sleep, Instant};
+use anyhow::{bail, Result};
+use bollard::{
+    container::{
+        Config as ContainerConfig, CreateContainerOptions, NetworkingConfig, StartContainerOptions,
+    },
+    network::CreateNetworkOptions,
+    service::{EndpointSettings, HealthConfig, HealthStatusEnum, HostConfig, PortBinding},
+    Docker,
+};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::logger::Logger;
+
+use super::{context::PlatformContext, Image};
+
+pub struct ServiceHealthCheckOptions<'a> {
+    pub command: &'a str,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub retries: u64,
+}
+
+pub struct ServiceOptions<'a> {
+    pub name: &'a str,
+    pub image: Image<'a>,
+    pub env: Vec<String>,
+    pub command: Option<Vec<&'a str>>,
+    pub ports: Vec<&'a str>,
+    pub health_check: Option<ServiceHealthCheckOptions<'a>>,
+}
+
+struct ServiceContainer {
+    name: String,
+    container: String,
+    context: PlatformContext,
+}
+
+/// Sidecar containers, such as databases or caches, that are started on a docker
+/// network of the run so they are reachable by their name from the container of
+/// a platform.
+pub struct Services {
+    client: Docker,
+    network: String,
+    containers: Vec<ServiceContainer>,
+}
+
+impl Services {
+    pub async fn start(
+        client: Docker,
+        run_id: &str,
+        conn: Option<Arc<DatabaseConnection>>,
+        logger: &Logger,
+        options: Vec<ServiceOptions<'_>>,
+    ) -> Result<Self> {
+        let network = format!("bld-{run_id}-{}", &Uuid::new_v4().simple().to_string()[..8]);
+        debug!("creating network {network} for services");
+        let network_options = CreateNetworkOptions {
+            name: network.as_str(),
+            check_duplicate: true,
+            ..Default::default()
+        };
+        client.create_network(network_options).await?;
+
+        let mut services = Self {
+            client,
+            network,
+            containers: vec![],
+        };
+
+        for service in options {
+            let context = PlatformContext::new(run_id, conn.clone());
+            if let Err(e) = services.start_service(logger, service, context).await {
+                let _ = services.dispose().await.map_err(|e| error!("{e}"));
+                return Err(e);
+            }
+        }
+
+        Ok(services)
+    }
+
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    fn port_bindings(ports: &[&str]) -> Result<HashMap<String, Option<Vec<PortBinding>>>> {
+        let mut bindings = HashMap::new();
+        for port in ports {
+            let Some((host, container)) = port.split_once(':') else {
+                bail!("invalid port {port} for service");
+            };
+            let container = if container.contains('/') {
+                container.to_owned()
+            } else {
+                format!("{container}/tcp")
+            };
+            bindings.insert(
+                container,
+                Some(vec![PortBinding {
+                    host_ip: None,
+                    host_port: Some(host.to_owned()),
+                }]),
+            );
+        }
+        Ok(bindings)
+    }
+
+    async fn start_service(
+        &mut self,
+        logger: &Logger,
+        service: ServiceOptions<'_>,
+        mut context: PlatformContext,
+    ) -> Result<()> {
+        logger
+            .write_line(format!(
+                "{:<15}: {} ({})",
+                "Service",
+                service.name,
+                service.image.name()
+            ))
+            .await?;
+
+        service.image.create(&self.client, logger).await?;
+        let digest = service.image.resolve_digest(&self.client, logger).await?;
+
+        let port_bindings = Self::port_bindings(&service.ports)?;
+        let ports: Vec<String> = port_bindings.keys().cloned().collect();
+        let exposed_ports = ports
+            .iter()
+            .map(|x| (x.as_str(), HashMap::new()))
+            .collect::<HashMap<&str, HashMap<(), ()>>>();
+
+        let healthcheck = service.health_check.as_ref().map(|x| HealthConfig {
+            test: Some(vec!["CMD-SHELL".to_owned(), x.command.to_owned()]),
+            interval: Some(x.interval.as_nanos() as i64),
+            timeout: Some(x.timeout.as_nanos() as i64),
+            retries: Some(x.retries as i64),
+            ..Default::default()
+        });
+
+        let endpoint = EndpointSettings {
+            aliases: Some(vec![service.name.to_owned()]),
+            ..Default::default()
+        };
+
+        let name = Uuid::new_v4().to_string();
+        let options = CreateContainerOptions {
+            name: name.as_str(),
+            platform: None,
+        };
+        let config = ContainerConfig {
+            image: Some(service.image.name()),
+            env: Some(service.env.iter().map(|x| x.as_str()).collect()),
+            cmd: service.command,
+            exposed_ports: Some(exposed_ports),
+            healthcheck,
+            host_config: Some(HostConfig {
+                network_mode: Some(self.network.to_owned()),
+                port_bindings: Some(port_bindings),
+                ..Default::default()
+            }),
+            networking_config: Some(NetworkingConfig {
+                endpoints_config: HashMap::from([(self.network.as_str(), endpoint)]),
+            }),
+            ..Default::default()
+        };
+
+        let response = self.client.create_container(Some(options), config).await?;
+        self.client
+            .start_container(&name, None::<StartContainerOptions<String>>)
+            .await?;
+
+        context
+            .add(&response.id, service.image.name(), digest.as_deref())
+            .await?;
+        self.containers.push(ServiceContainer {
+            name: service.name.to_owned(),
+            container: name,
+            context,
+        });
+
+        if let Some(health_check) = service.health_check {
+            self.wait_until_healthy(logger, service.name, &health_check)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn wait_until_healthy(
+        &self,
+        logger: &Logger,
+        name: &str,
+        health_check: &ServiceHealthCheckOptions<'_>,
+    ) -> Result<()> {
+        let Some(service) = self.containers.iter().find(|x| x.name == name) else {
+            bail!("service {name} not found");
+        };
+
+        let attempts = health_check.retries as u32 + 2;
+        let max_duration = (health_check.interval + health_check.timeout) * attempts;
+        let start = Instant::now();
+
+        loop {
+            let inspect = self
+                .client
+                .inspect_container(&service.container, None)
+                .await?;
+            let state = inspect.state.unwrap_or_default();
+
+            if !state.running.unwrap_or_default() {
+                bail!("service {name} exited before becoming healthy");
+            }
+
+            match state.health.and_then(|x| x.status) {
+                Some(HealthStatusEnum::HEALTHY) => break,
+                Some(HealthStatusEnum::UNHEALTHY) => bail!("service {name} is unhealthy"),
+                _ => {}
+            }
+
+            if start.elapsed() > max_duration {
+                bail!("timed out while waiting for service {name} to become healthy");
+            }
+
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        logger
+            .write_line(format!("{:<15}: {name} is healthy", "Service"))
+            .await
+    }
+
+    pub async fn keep_alive(&self) -> Result<()> {
+        for service in self.containers.iter() {
+            service.context.keep_alive().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn dispose(&self) -> Result<()> {
+        let mut result = Ok(());
+
+        for service in self.containers.iter() {
+            debug!("removing container of service {}", service.name);
+            let removed = async {
+                self.client.stop_container(&service.container, None).await?;
+                self.client.remove_container(&service.container, None).await
+            }
+            .await;
+
+            if let Err(e) = removed {
+                error!(
+                    "could not remove container of service {}, {e}",
+                    service.name
+                );
+                let _ = service
+                    .context
+                    .set_as_faulted()
+                    .await
+                    .map_err(|e| error!("could not set container as faulted, {e}"));
+                result = result.and(Err(e.into()));
+                continue;
+            }
+
+            let _ = service
+                .context
+                .set_as_removed()
+                .await
+                .map_err(|e| error!("could not set container as removed, {e}"));
+        }
+
+        debug!("removing network {}", self.network);
+        if let Err(e) = self.client.remove_network(&self.network).await {
+            error!("could not remove network {}, {e}", self.network);
+            result = result.and(Err(e.into()));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_port(bindings: &HashMap<String, Option<Vec<PortBinding>>>, port: &str) -> String {
+        bindings[port].as_ref().unwrap()[0]
+            .host_port
+            .clone()
+            .unwrap()
+    }
+
+    #[test]
+    fn ports_are_published_on_the_host() {
+        let bindings = Services::port_bindings(&["15432:5432", "5353:53/udp"]).unwrap();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(host_port(&bindings, "5432/tcp"), "15432");
+        assert_eq!(host_port(&bindings, "53/udp"), "5353");
+    }
+
+    #[test]
+    fn ports_without_a_host_port_are_rejected() {
+        let error = Services::port_bindings(&["5432"]).unwrap_err();
+        assert_eq!(error.to_string(), "invalid port 5432 for service");
+    }
+}
@@ -1,9 +1,19 @@
 use std::{
-    collections::HashMap, future::Future, net::SocketAddr, path::PathBuf, pin::Pin, sync::Arc,
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail, Result};
-use async_ssh2_lite::{AsyncSession, AsyncSftp, TokioTcpStream};
+use async_ssh2_lite::{
+    ssh2::{CheckResult, HashType, HostKeyType, KnownHostFileKind, Session},
+    AsyncSession, AsyncSftp, TokioTcpStream,
+};
+use base64::{prelude::BASE64_STANDARD_NO_PAD, Engine};
 use bld_config::{path, BldConfig};
 use bld_utils::sync::IntoArc;
 use futures_util::{
@@ -12,11 +22,19 @@ use futures_util::{
 use tokio::{
     fs::{create_dir, File, OpenOptions},
     io::{AsyncReadExt, AsyncWriteExt},
+    time::timeout as with_timeout,
 };
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
 use walkdir::WalkDir;
 
-use crate::logger::Logger;
+use crate::{
+    logger::Logger,
+    platform::{
+        cache_archive_name, kill_process_group_command, process_group_script,
+        restore_cache_command, save_cache_command, stdin_env_command, ExitCodeError,
+    },
+};
 
 type RecursiveFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
 
@@ -31,20 +49,36 @@ pub enum SshAuthOptions<'a> {
     Agent,
 }
 
+pub enum SshHostKeyOptions<'a> {
+    Fingerprint(&'a str),
+    KnownHosts {
+        path: Option<&'a str>,
+        trust_on_first_use: bool,
+    },
+}
+
 pub struct SshConnectOptions<'a> {
     pub host: &'a str,
     pub port: u16,
     pub user: &'a str,
     pub auth: SshAuthOptions<'a>,
+    pub host_key: SshHostKeyOptions<'a>,
 }
 
 impl<'a> SshConnectOptions<'a> {
-    pub fn new(host: &'a str, port: u16, user: &'a str, auth: SshAuthOptions<'a>) -> Self {
+    pub fn new(
+        host: &'a str,
+        port: u16,
+        user: &'a str,
+        auth: SshAuthOptions<'a>,
+        host_key: SshHostKeyOptions<'a>,
+    ) -> Self {
         Self {
             host,
             port,
             user,
             auth,
+            host_key,
         }
     }
 }
@@ -69,6 +103,128 @@ impl<'a> SshExecutionOptions<'a> {
     }
 }
 
+/// The host key that the server presented during the handshake.
+struct HostKey<'a> {
+    key: &'a [u8],
+    key_type: HostKeyType,
+    fingerprint: String,
+}
+
+fn default_known_hosts() -> Result<PathBuf> {
+    let home = std::env::var("HOME").map_err(|_| {
+        anyhow!("unable to find the home directory for the default known_hosts file")
+    })?;
+    Ok(path![home, ".ssh", "known_hosts"])
+}
+
+fn verify_host_key(
+    host: &str,
+    port: u16,
+    host_key: &HostKey<'_>,
+    options: &SshHostKeyOptions<'_>,
+) -> Result<()> {
+    let fingerprint = &host_key.fingerprint;
+
+    let (path, trust_on_first_use) = match options {
+        SshHostKeyOptions::Fingerprint(expected) => {
+            let expected = expected.trim_end_matches('=');
+            let expected = expected.strip_prefix("SHA256:").unwrap_or(expected);
+            if fingerprint.strip_prefix("SHA256:") != Some(expected) {
+                bail!("host key verification failed for {host}:{port}, expected fingerprint SHA256:{expected} but the server presented {fingerprint}");
+            }
+            return Ok(());
+        }
+        SshHostKeyOptions::KnownHosts {
+            path,
+            trust_on_first_use,
+        } => {
+            let path = match path {
+                Some(path) => path![path],
+                None => default_known_hosts()?,
+            };
+            (path, *trust_on_first_use)
+        }
+    };
+
+    // the known hosts of libssh2 don't depend on the connection so a new session
+    // is used to read and check the file.
+    let session = Session::new()?;
+    let mut known_hosts = session.known_hosts()?;
+    if path.is_file() {
+        known_hosts.read_file(&path, KnownHostFileKind::OpenSSH)?;
+    }
+
+    match known_hosts.check_port(host, port, host_key.key) {
+        CheckResult::Match => Ok(()),
+
+        CheckResult::Mismatch => bail!(
+            "host key verification failed for {host}:{port}, the host key has changed and the server presented {fingerprint}. If the change is expected remove the old key from {}",
+            path.display()
+        ),
+
+        CheckResult::NotFound if trust_on_first_use => {
+            let name = if port == 22 {
+                host.to_string()
+            } else {
+                format!("[{host}]:{port}")
+            };
+            append_known_host(&session, &path, &name, host_key)?;
+            warn!(
+                "trusting host key {fingerprint} of {host}:{port} on first use and adding it to {}",
+                path.display()
+            );
+            Ok(())
+        }
+
+        CheckResult::NotFound => bail!(
+            "host key verification failed for {host}:{port}, the host isn't in {} and the server presented {fingerprint}",
+            path.display()
+        ),
+
+        CheckResult::Failure => bail!(
+            "host key verification failed for {host}:{port}, unable to check the host key against {}",
+            path.display()
+        ),
+    }
+}
+
+/// Appends only the entry of the new host to the known_hosts file so that any
+/// existing entries, comments or formatting of the file are left untouched.
+fn append_known_host(
+    session: &Session,
+    path: &Path,
+    name: &str,
+    host_key: &HostKey<'_>,
+) -> Result<()> {
+    let mut known_hosts = session.known_hosts()?;
+    known_hosts.add(name, host_key.key, "added by bld", host_key.key_type.into())?;
+    let host = known_hosts
+        .hosts()?
+        .pop()
+        .ok_or_else(|| anyhow!("unable to create the known_hosts entry for {name}"))?;
+    let mut line = known_hosts.write_string(&host, KnownHostFileKind::OpenSSH)?;
+    if !line.ends_with('\n') {
+        line.push('\n');
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let ends_with_newline = std::fs::read(path)
+        .map(|x| x.last().map(|c| *c == b'\n').unwrap_or(true))
+        .unwrap_or(true);
+    if !ends_with_newline {
+        line.insert(0, '\n');
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    std::io::Write::write_all(&mut file, line.as_bytes())?;
+    Ok(())
+}
+
 pub struct Ssh {
     session: AsyncSession<TokioTcpStream>,
     env: HashMap<String, String>,
@@ -83,6 +239,16 @@ impl Ssh {
         let mut session = AsyncSession::<TokioTcpStream>::connect(addr, None).await?;
         session.handshake().await?;
 
+        let (key, key_type) = session
+            .host_key()
+            .ok_or_else(|| anyhow!("unable to retrieve the host key of the server"))?;
+        let host_key = HostKey {
+            key,
+            key_type,
+            fingerprint: Self::host_key_fingerprint(&session)?,
+        };
+        verify_host_key(connect.host, connect.port, &host_key, &connect.host_key)?;
+
         let mut instance = Self {
             session,
             env: HashMap::new(),
@@ -93,6 +259,13 @@ impl Ssh {
         Ok(instance)
     }
 
+    fn host_key_fingerprint(session: &AsyncSession<TokioTcpStream>) -> Result<String> {
+        let hash = session
+            .host_key_hash(HashType::Sha256)
+            .ok_or_else(|| anyhow!("unable to retrieve the host key of the server"))?;
+        Ok(format!("SHA256:{}", BASE64_STANDARD_NO_PAD.encode(hash)))
+    }
+
     async fn set_auth<'a>(&mut self, user: &'a str, auth: &SshAuthOptions<'a>) -> Result<()> {
         match auth {
             SshAuthOptions::Agent => {
@@ -136,8 +309,8 @@ impl Ssh {
     ) -> Result<()> {
         debug!("fetching content of remote file {}", from.display());
         let mut remote_file = sftp.open(&from).await?;
-        let mut content = String::new();
-        remote_file.read_to_string(&mut content).await?;
+        let mut content = vec![];
+        remote_file.read_to_end(&mut content).await?;
 
         debug!("writing content to local file {}", to.display());
         let to = path![to];
@@ -150,8 +323,7 @@ impl Ssh {
         } else {
             File::create(to).await?
         };
-        let bytes = content.as_bytes();
-        local_file.write_all(bytes).await?;
+        local_file.write_all(&content).await?;
         local_file.flush().await?;
 
         debug!("finished copying remote file from server");
@@ -212,8 +384,8 @@ impl Ssh {
         let to = path![to];
         let remote_path = sftp.realpath(&from).await?;
 
-        debug!("remote path {} is a file", from.display());
-        if remote_path.is_file() {
+        if sftp.stat(&remote_path).await?.is_file() {
+            debug!("remote path {} is a file", from.display());
             Self::copy_file_from(sftp, from, to).await?;
             return Ok(());
         }
@@ -233,6 +405,19 @@ impl Ssh {
         Ok(())
     }
 
+    pub async fn copy_from_into_dir(&self, from: &str, dir: &str) -> Result<()> {
+        let name = path![from]
+            .file_name()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_owned())
+            .ok_or_else(|| anyhow!("unable to retrieve file name for remote path {from}"))?;
+        let to = path![dir, name];
+        let to = to
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct destination path (to)"))?;
+        self.copy_from(from, to).await
+    }
+
     async fn copy_file_into(
         &self,
         sftp: &AsyncSftp<TokioTcpStream>,
@@ -240,9 +425,8 @@ impl Ssh {
         to: &str,
     ) -> Result<()> {
         let mut local_file = File::open(&from).await?;
-        let mut content = String::new();
-        local_file.read_to_string(&mut content).await?;
-        let bytes = content.as_bytes();
+        let mut content = vec![];
+        local_file.read_to_end(&mut content).await?;
 
         let to = path![to];
         let mut remote_path_iter = to.iter().peekable();
@@ -265,7 +449,7 @@ impl Ssh {
         debug!("creating target file {} using sftp", remote_path.display());
         let mut remote_file = sftp.create(&remote_path).await?;
         debug!("writing content to remote file");
-        remote_file.write_all(bytes).await?;
+        remote_file.write_all(&content).await?;
         debug!("flushing remote file");
         remote_file.flush().await?;
 
@@ -315,11 +499,56 @@ impl Ssh {
         Ok(())
     }
 
+    pub async fn restore_cache(&self, logger: Arc<Logger>, archive: &Path) -> Result<()> {
+        let from = archive
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct cache archive path"))?;
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        self.copy_into(from, &remote_archive).await?;
+        self.sh(logger, &None, &restore_cache_command(&remote_archive), None)
+            .await
+    }
+
+    pub async fn save_cache(
+        &self,
+        logger: Arc<Logger>,
+        paths: &[String],
+        archive: &Path,
+    ) -> Result<()> {
+        let remote_archive = format!("/tmp/{}", cache_archive_name(archive)?);
+        let to = archive
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct cache archive path"))?;
+
+        let command = save_cache_command(paths, &remote_archive);
+        self.sh(logger.clone(), &None, &command, None).await?;
+        let result = self.copy_from(&remote_archive, to).await;
+        self.sh(logger, &None, &format!("rm -f {remote_archive}"), None)
+            .await?;
+        result
+    }
+
     pub async fn sh(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
         input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.sh_with_env(logger, working_dir, input, &HashMap::new(), timeout)
+            .await
+    }
+
+    /// Runs the command with additional environment variables, which are sent through
+    /// the standard input of the command since servers usually only accept a few
+    /// variables from clients.
+    pub async fn sh_with_env(
+        &self,
+        logger: Arc<Logger>,
+        working_dir: &Option<String>,
+        input: &str,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         let mut command = String::new();
         if let Some(wd) = working_dir {
@@ -327,6 +556,23 @@ impl Ssh {
         }
         command.push_str(input);
 
+        let stdin = if env.is_empty() {
+            None
+        } else {
+            let (input, stdin) = stdin_env_command(&command, env)?;
+            command = input;
+            Some(stdin)
+        };
+
+        // when a timeout is set the process group of the command is kept in a file
+        // so that the command can be killed if the timeout is reached.
+        let pid_file = format!("/tmp/bld-{}.pid", Uuid::new_v4());
+        if timeout.is_some() {
+            let script = process_group_script("sh").replace('\'', "'\\''");
+            let escaped = command.replace('\'', "'\\''");
+            command = format!("sh -c '{script}' '{escaped}' {pid_file}");
+        }
+
         let mut channel = self.session.channel_session().await?;
 
         for (k, v) in self.env.iter() {
@@ -335,22 +581,43 @@ impl Ssh {
 
         channel.exec(&command).await?;
 
-        let mut output = String::new();
+        if let Some(stdin) = stdin {
+            FuturesUtilAsyncWriteExt::write_all(&mut channel, &stdin).await?;
+            channel.send_eof().await?;
+        }
+
+        let read_output = async {
+            let mut output = String::new();
 
-        let mut stdout = String::new();
-        FuturesUtilAsyncReadExt::read_to_string(&mut channel, &mut stdout).await?;
-        output.push_str(&stdout);
+            let mut stdout = String::new();
+            FuturesUtilAsyncReadExt::read_to_string(&mut channel, &mut stdout).await?;
+            output.push_str(&stdout);
 
-        let mut stderr = String::new();
-        let mut channel_stderr = channel.stderr();
-        FuturesUtilAsyncReadExt::read_to_string(&mut channel_stderr, &mut stderr).await?;
-        output.push_str(&stderr);
+            let mut stderr = String::new();
+            let mut channel_stderr = channel.stderr();
+            FuturesUtilAsyncReadExt::read_to_string(&mut channel_stderr, &mut stderr).await?;
+            output.push_str(&stderr);
+
+            Ok::<String, anyhow::Error>(output)
+        };
+
+        let output = match timeout {
+            Some(duration) => {
+                let Ok(output) = with_timeout(duration, read_output).await else {
+                    self.kill(&pid_file).await?;
+                    let _ = channel.close().await;
+                    bail!("command timed out");
+                };
+                output?
+            }
+            None => read_output.await?,
+        };
 
         logger.write(output).await?;
 
         let exit_status = channel.exit_status()?;
         if exit_status != 0 {
-            bail!("command finished with status {exit_status}");
+            return Err(ExitCodeError(exit_status.into()).into());
         }
 
         channel.close().await?;
@@ -358,8 +625,238 @@ impl Ssh {
         Ok(())
     }
 
+    async fn kill(&self, pid_file: &str) -> Result<()> {
+        debug!("killing remote process group with id from file {pid_file}");
+        let mut channel = self.session.channel_session().await?;
+        channel.exec(&kill_process_group_command(pid_file)).await?;
+        channel.close().await?;
+        Ok(())
+    }
+
     pub async fn dispose(&mut self) -> Result<()> {
         self.session.disconnect(None, "", None).await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use base64::prelude::BASE64_STANDARD;
+
+    use super::*;
+
+    /// The blob of an ed25519 public key made up of the given byte.
+    fn ed25519_key(byte: u8) -> Vec<u8> {
+        let mut key = vec![0, 0, 0, 11];
+        key.extend_from_slice(b"ssh-ed25519");
+        key.extend_from_slice(&[0, 0, 0, 32]);
+        key.extend_from_slice(&[byte; 32]);
+        key
+    }
+
+    fn host_key(key: &[u8]) -> HostKey<'_> {
+        HostKey {
+            key,
+            key_type: HostKeyType::Ed255219,
+            fingerprint: "SHA256:c2VydmVyLWtleQ".to_owned(),
+        }
+    }
+
+    fn known_host_line(name: &str, key: &[u8]) -> String {
+        format!("{name} ssh-ed25519 {}\n", BASE64_STANDARD.encode(key))
+    }
+
+    struct KnownHostsFile {
+        dir: PathBuf,
+    }
+
+    impl KnownHostsFile {
+        fn new(content: Option<&str>) -> Self {
+            let dir = path![temp_dir(), format!("bld-ssh-{}", Uuid::new_v4())];
+            fs::create_dir_all(&dir).unwrap();
+            let file = Self { dir };
+            if let Some(content) = content {
+                fs::write(file.path(), content).unwrap();
+            }
+            file
+        }
+
+        fn path(&self) -> PathBuf {
+            path![&self.dir, "known_hosts"]
+        }
+
+        fn path_str(&self) -> String {
+            self.path().display().to_string()
+        }
+
+        fn read(&self) -> String {
+            fs::read_to_string(self.path()).unwrap_or_default()
+        }
+    }
+
+    impl Drop for KnownHostsFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn known_hosts(path: &str, trust_on_first_use: bool) -> SshHostKeyOptions<'_> {
+        SshHostKeyOptions::KnownHosts {
+            path: Some(path),
+            trust_on_first_use,
+        }
+    }
+
+    #[test]
+    fn pinned_fingerprints_are_matched() {
+        let key = ed25519_key(1);
+        let host_key = host_key(&key);
+
+        for expected in [
+            "SHA256:c2VydmVyLWtleQ",
+            "SHA256:c2VydmVyLWtleQ=",
+            "c2VydmVyLWtleQ",
+        ] {
+            let options = SshHostKeyOptions::Fingerprint(expected);
+            verify_host_key("127.0.0.1", 22, &host_key, &options).unwrap();
+        }
+
+        let options = SshHostKeyOptions::Fingerprint("SHA256:b3RoZXIta2V5");
+        let error = verify_host_key("127.0.0.1", 22, &host_key, &options)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "host key verification failed for 127.0.0.1:22, expected fingerprint SHA256:b3RoZXIta2V5 but the server presented SHA256:c2VydmVyLWtleQ"
+        );
+    }
+
+    #[test]
+    fn known_hosts_are_matched() {
+        let key = ed25519_key(1);
+        let content = format!(
+            "{}{}",
+            known_host_line("127.0.0.1", &key),
+            known_host_line("[127.0.0.1]:2222", &key)
+        );
+        let file = KnownHostsFile::new(Some(&content));
+        let path = file.path_str();
+
+        verify_host_key("127.0.0.1", 22, &host_key(&key), &known_hosts(&path, false)).unwrap();
+        verify_host_key(
+            "127.0.0.1",
+            2222,
+            &host_key(&key),
+            &known_hosts(&path, false),
+        )
+        .unwrap();
+        assert_eq!(file.read(), content);
+    }
+
+    #[test]
+    fn changed_host_keys_are_rejected() {
+        let key = ed25519_key(1);
+        let content = known_host_line("127.0.0.1", &ed25519_key(2));
+        let file = KnownHostsFile::new(Some(&content));
+        let path = file.path_str();
+
+        let error = verify_host_key("127.0.0.1", 22, &host_key(&key), &known_hosts(&path, true))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with(
+                "host key verification failed for 127.0.0.1:22, the host key has changed"
+            ),
+            "{error}"
+        );
+        assert!(error.contains(&path), "{error}");
+        assert_eq!(file.read(), content);
+    }
+
+    #[test]
+    fn unknown_hosts_are_rejected_without_trust_on_first_use() {
+        let key = ed25519_key(1);
+        let content = known_host_line("10.0.0.1", &key);
+        let file = KnownHostsFile::new(Some(&content));
+        let path = file.path_str();
+
+        let error = verify_host_key("127.0.0.1", 22, &host_key(&key), &known_hosts(&path, false))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            format!("host key verification failed for 127.0.0.1:22, the host isn't in {path} and the server presented SHA256:c2VydmVyLWtleQ")
+        );
+        assert_eq!(file.read(), content);
+
+        let file = KnownHostsFile::new(None);
+        let path = file.path_str();
+        assert!(
+            verify_host_key("127.0.0.1", 22, &host_key(&key), &known_hosts(&path, false)).is_err()
+        );
+        assert!(!file.path().exists());
+    }
+
+    #[test]
+    fn unknown_hosts_are_trusted_on_first_use() {
+        let key = ed25519_key(1);
+        let content = format!(
+            "# managed hosts\n{}{}",
+            known_host_line("10.0.0.1", &ed25519_key(2)),
+            known_host_line("10.0.0.2", &ed25519_key(3)).trim_end()
+        );
+        let file = KnownHostsFile::new(Some(&content));
+        let path = file.path_str();
+
+        verify_host_key(
+            "127.0.0.1",
+            2222,
+            &host_key(&key),
+            &known_hosts(&path, true),
+        )
+        .unwrap();
+
+        let updated = file.read();
+        let (existing, added) = updated.split_at(content.len());
+        assert_eq!(existing, content);
+        let added: Vec<&str> = added.split_whitespace().collect();
+        assert_eq!(
+            added,
+            vec![
+                "[127.0.0.1]:2222",
+                "ssh-ed25519",
+                &BASE64_STANDARD.encode(&key),
+                "added",
+                "by",
+                "bld"
+            ]
+        );
+
+        // the host is now known so the following connections are matched.
+        verify_host_key(
+            "127.0.0.1",
+            2222,
+            &host_key(&key),
+            &known_hosts(&path, false),
+        )
+        .unwrap();
+        assert_eq!(file.read(), updated);
+    }
+
+    #[test]
+    fn known_hosts_file_is_created_on_first_use() {
+        let key = ed25519_key(1);
+        let file = KnownHostsFile::new(None);
+        let path = path![&file.dir, ".ssh", "known_hosts"]
+            .display()
+            .to_string();
+
+        verify_host_key("127.0.0.1", 22, &host_key(&key), &known_hosts(&path, true)).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 1, "{content}");
+        assert!(content.starts_with("127.0.0.1 ssh-ed25519 "), "{content}");
+    }
+}
@@ -1,6 +1,7 @@
 use actix::spawn;
 use anyhow::{anyhow, Result};
 use bld_config::BldConfig;
+use bld_models::dtos::LogEntry;
 use std::path::PathBuf;
 use tokio::{
     fs::File,
@@ -14,7 +15,7 @@ use tracing::error;
 
 #[derive(Debug)]
 enum FileScannerMessage {
-    Next(oneshot::Sender<Vec<String>>),
+    Next(oneshot::Sender<Vec<LogEntry>>),
 }
 
 struct FileScannerBackend {
@@ -56,8 +57,8 @@ impl FileScannerBackend {
         self.file_handle.as_mut()
     }
 
-    async fn next(&mut self, resp_tx: oneshot::Sender<Vec<String>>) -> Result<()> {
-        let mut content: Vec<String> = vec![];
+    async fn next(&mut self, resp_tx: oneshot::Sender<Vec<LogEntry>>) -> Result<()> {
+        let mut content: Vec<LogEntry> = vec![];
         let Some(file_handle) = self.try_file_handle().await else {
             resp_tx
                 .send(content)
@@ -69,7 +70,7 @@ impl FileScannerBackend {
         let mut lines = reader.lines();
         let mut next = lines.next_line().await?;
         while let Some(line) = next {
-            content.push(line);
+            content.push(LogEntry::parse(&line));
             next = lines.next_line().await?;
         }
 
@@ -92,7 +93,7 @@ impl FileScanner {
         Self { tx }
     }
 
-    pub async fn scan(&self) -> Result<Vec<String>> {
+    pub async fn scan(&self) -> Result<Vec<LogEntry>> {
         let (resp_tx, resp_rx) = oneshot::channel();
         self.tx.send(FileScannerMessage::Next(resp_tx)).await?;
         resp_rx.await.map_err(|e| anyhow!(e))
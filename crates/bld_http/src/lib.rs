@@ -10,20 +10,24 @@ use awc::ws::WebsocketsRequest;
 use awc::{Client, ClientRequest, Connector, SendClientRequest};
 use bld_config::BldConfig;
 use bld_models::dtos::{
-    AddJobRequest, AuthTokens, CronJobResponse, ExecClientMessage, HistQueryParams, HistoryEntry,
-    JobFiltersParams, PipelineInfoQueryParams, PipelinePathRequest, PipelineQueryParams,
-    PullResponse, PushInfo, RefreshTokenParams, UpdateJobRequest,
+    AddJobRequest, AddSecretRequest, ArtifactResponse, AuthTokens, CronJobResponse,
+    ExecClientMessage, HistQueryParams, HistoryEntry, HistoryImageEntry, HistoryJobEntry,
+    HistoryNotificationEntry, JobFiltersParams, PipelineInfoQueryParams, PipelinePathRequest,
+    PipelineQueryParams, PullResponse, PushInfo, RefreshTokenParams, SecretResponse,
+    UpdateJobRequest,
 };
 use bld_utils::fs::{read_tokens, write_tokens};
 use bld_utils::sync::IntoArc;
 use bld_utils::tls::load_root_certificates;
+use futures_util::StreamExt;
 use rustls::ClientConfig;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio::{fs::File, io::AsyncWriteExt};
 use tracing::{debug, error};
 
 #[derive(Debug)]
-struct RequestError {
+pub struct RequestError {
     text: String,
     status: StatusCode,
 }
@@ -35,6 +39,10 @@ impl RequestError {
             status,
         }
     }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
 }
 
 impl Display for RequestError {
@@ -62,6 +70,12 @@ impl Request {
         }
     }
 
+    pub fn put(url: &str) -> Self {
+        Self {
+            request: Client::new().put(url).insert_header(("User-Agent", "bld")),
+        }
+    }
+
     pub fn patch(url: &str) -> Self {
         Self {
             request: Client::new()
@@ -121,12 +135,41 @@ impl Request {
         Self::request_with_json::<V>(send_request).await
     }
 
+    /// Sends the request and writes the body of the response to the provided path.
+    pub async fn download(self, path: &Path) -> Result<()> {
+        let mut response = self
+            .request
+            .send()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        let status = response.status();
+        debug!("response from server status: {status}");
+
+        if status != StatusCode::OK {
+            let body = response.body().await.map_err(|e| anyhow!(e))?;
+            let text = if status == StatusCode::BAD_REQUEST {
+                String::from_utf8_lossy(&body).to_string()
+            } else {
+                format!("request failed with status code: {status}")
+            };
+            return Err(RequestError::new(&text, status).into());
+        }
+
+        let mut file = File::create(path).await?;
+        while let Some(chunk) = response.next().await {
+            let chunk = chunk.map_err(|e| anyhow!(e))?;
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
     async fn request_with_text(send_request: SendClientRequest) -> Result<String> {
         let mut response = send_request.await.map_err(|e| anyhow!(e.to_string()))?;
         let status = response.status();
 
         match status {
-            StatusCode::OK => {
+            status if status.is_success() => {
                 debug!("response from server status: {status}");
                 response
                     .body()
@@ -326,6 +369,57 @@ impl HttpClient {
         }
     }
 
+    async fn hist_steps_inner(&self, run_id: &str) -> Result<Vec<HistoryJobEntry>> {
+        let url = format!("{}/v1/hist/{run_id}/steps", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn hist_steps(&self, run_id: &str) -> Result<Vec<HistoryJobEntry>> {
+        let response = self.hist_steps_inner(run_id).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.hist_steps_inner(run_id).await
+        } else {
+            response
+        }
+    }
+
+    async fn hist_images_inner(&self, run_id: &str) -> Result<Vec<HistoryImageEntry>> {
+        let url = format!("{}/v1/hist/{run_id}/images", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn hist_images(&self, run_id: &str) -> Result<Vec<HistoryImageEntry>> {
+        let response = self.hist_images_inner(run_id).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.hist_images_inner(run_id).await
+        } else {
+            response
+        }
+    }
+
+    async fn hist_notifications_inner(
+        &self,
+        run_id: &str,
+    ) -> Result<Vec<HistoryNotificationEntry>> {
+        let url = format!("{}/v1/hist/{run_id}/notifications", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn hist_notifications(&self, run_id: &str) -> Result<Vec<HistoryNotificationEntry>> {
+        let response = self.hist_notifications_inner(run_id).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.hist_notifications_inner(run_id).await
+        } else {
+            response
+        }
+    }
+
     async fn print_inner(&self, params: &PipelineInfoQueryParams) -> Result<String> {
         let url = format!("{}/v1/print", self.base_url);
         Request::get(&url)
@@ -574,6 +668,100 @@ impl HttpClient {
         }
     }
 
+    async fn secrets_list_inner(&self) -> Result<Vec<SecretResponse>> {
+        let url = format!("{}/v1/secrets", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn secrets_list(&self) -> Result<Vec<SecretResponse>> {
+        let response = self.secrets_list_inner().await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_list_inner().await
+        } else {
+            response
+        }
+    }
+
+    async fn secrets_add_inner(&self, body: &AddSecretRequest) -> Result<()> {
+        let url = format!("{}/v1/secrets", self.base_url);
+        Request::post(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn secrets_add(&self, body: &AddSecretRequest) -> Result<()> {
+        let response = self.secrets_add_inner(body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_add_inner(body).await
+        } else {
+            response
+        }
+    }
+
+    async fn secrets_remove_inner(&self, name: &str) -> Result<()> {
+        let url = format!("{}/v1/secrets/{name}", self.base_url);
+        Request::delete(&url)
+            .auth(&self.auth_path)
+            .await
+            .json()
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn secrets_remove(&self, name: &str) -> Result<()> {
+        let response = self.secrets_remove_inner(name).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_remove_inner(name).await
+        } else {
+            response
+        }
+    }
+
+    async fn artifacts_list_inner(&self, run_id: &str) -> Result<Vec<ArtifactResponse>> {
+        let url = format!("{}/v1/artifacts/{run_id}", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn artifacts_list(&self, run_id: &str) -> Result<Vec<ArtifactResponse>> {
+        let response = self.artifacts_list_inner(run_id).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.artifacts_list_inner(run_id).await
+        } else {
+            response
+        }
+    }
+
+    async fn artifacts_download_inner(&self, run_id: &str, id: &str, path: &Path) -> Result<()> {
+        let url = format!("{}/v1/artifacts/{run_id}/{id}", self.base_url);
+        Request::get(&url)
+            .auth(&self.auth_path)
+            .await
+            .download(path)
+            .await
+    }
+
+    pub async fn artifacts_download(&self, run_id: &str, id: &str, path: &Path) -> Result<()> {
+        let response = self.artifacts_download_inner(run_id, id, path).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.artifacts_download_inner(run_id, id, path).await
+        } else {
+            response
+        }
+    }
+
     async fn copy_inner(&self, data: &PipelinePathRequest) -> Result<()> {
         let url = format!("{}/v1/copy", self.base_url);
         Request::post(&url)
@@ -15,6 +15,13 @@ mod m20230907_190009_create_cron_jobs_table;
 mod m20230907_190403_create_cron_job_variables_table;
 mod m20230907_190709_create_cron_job_environment_variables_table;
 mod m20240630_162930_login_attempts;
+mod m20261018_101500_create_pipeline_run_jobs_table;
+mod m20261018_101600_create_pipeline_run_steps_table;
+mod m20261018_102000_create_secrets_table;
+mod m20261018_103000_create_pipeline_run_artifacts_table;
+mod m20261018_104000_add_image_to_pipeline_run_containers_table;
+mod m20261018_105000_create_pipeline_run_notifications_table;
+mod m20261018_106000_add_hook_to_pipeline_runs_table;
 
 pub struct Migrator;
 
@@ -37,6 +44,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20230907_190403_create_cron_job_variables_table::Migration),
             Box::new(m20230907_190709_create_cron_job_environment_variables_table::Migration),
             Box::new(m20240630_162930_login_attempts::Migration),
+            Box::new(m20261018_101500_create_pipeline_run_jobs_table::Migration),
+            Box::new(m20261018_101600_create_pipeline_run_steps_table::Migration),
+            Box::new(m20261018_102000_create_secrets_table::Migration),
+            Box::new(m20261018_103000_create_pipeline_run_artifacts_table::Migration),
+            Box::new(m20261018_104000_add_image_to_pipeline_run_containers_table::Migration),
+            Box::new(m20261018_105000_create_pipeline_run_notifications_table::Migration),
+            Box::new(m20261018_106000_add_hook_to_pipeline_runs_table::Migration),
         ]
     }
 }
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261018_101500_create_pipeline_run_jobs_table::PipelineRunJobs;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunSteps::Table)
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::Id)
+                            .string()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunSteps::JobId).string().not_null())
+                    .col(ColumnDef::new(PipelineRunSteps::Name).string())
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::Position)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunSteps::State).string().not_null())
+                    .col(ColumnDef::new(PipelineRunSteps::ExitCode).big_integer())
+                    .col(ColumnDef::new(PipelineRunSteps::StartDate).date_time())
+                    .col(ColumnDef::new(PipelineRunSteps::EndDate).date_time())
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunSteps::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunSteps::Table)
+                            .from_col(PipelineRunSteps::JobId)
+                            .to_tbl(PipelineRunJobs::Table)
+                            .to_col(PipelineRunJobs::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunSteps::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunSteps {
+    Table,
+    Id,
+    JobId,
+    Name,
+    Position,
+    State,
+    ExitCode,
+    StartDate,
+    EndDate,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Secrets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Secrets::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Secrets::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Secrets::Value).text().not_null())
+                    .col(ColumnDef::new(Secrets::DateCreated).date_time().not_null())
+                    .col(ColumnDef::new(Secrets::DateUpdated).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Secrets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Secrets {
+    Table,
+    Id,
+    Name,
+    Value,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunArtifacts::Table)
+                    .col(
+                        ColumnDef::new(PipelineRunArtifacts::Id)
+                            .string()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunArtifacts::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunArtifacts::Name)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunArtifacts::Size)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunArtifacts::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunArtifacts::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunArtifacts::Table)
+                            .from_col(PipelineRunArtifacts::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunArtifacts::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PipelineRunArtifacts {
+    Table,
+    Id,
+    RunId,
+    Name,
+    Size,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the columns are added with separate statements since sqlite doesn't
+        // support altering multiple columns at once.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PipelineRunContainers::Table)
+                    .add_column(ColumnDef::new(PipelineRunContainers::Image).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PipelineRunContainers::Table)
+                    .add_column(
+                        ColumnDef::new(PipelineRunContainers::ImageDigest)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PipelineRunContainers::Table)
+                    .drop_column(PipelineRunContainers::ImageDigest)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PipelineRunContainers::Table)
+                    .drop_column(PipelineRunContainers::Image)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunContainers {
+    Table,
+    Image,
+    ImageDigest,
+}
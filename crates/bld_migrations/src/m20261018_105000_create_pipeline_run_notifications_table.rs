@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunNotifications::Table)
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::Id)
+                            .string()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::Target)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::Event)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::Attempt)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::State)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunNotifications::Error).string())
+                    .col(
+                        ColumnDef::new(PipelineRunNotifications::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunNotifications::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunNotifications::Table)
+                            .from_col(PipelineRunNotifications::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(PipelineRunNotifications::Table)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PipelineRunNotifications {
+    Table,
+    Id,
+    RunId,
+    Target,
+    Event,
+    Attempt,
+    State,
+    Error,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the columns are added with separate statements since sqlite doesn't
+        // support altering multiple columns at once.
+        for column in [
+            PipelineRuns::HookProvider,
+            PipelineRuns::HookRepository,
+            PipelineRuns::HookCommitSha,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(PipelineRuns::Table)
+                        .add_column(ColumnDef::new(column).string().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            PipelineRuns::HookCommitSha,
+            PipelineRuns::HookRepository,
+            PipelineRuns::HookProvider,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(PipelineRuns::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRuns {
+    Table,
+    HookProvider,
+    HookRepository,
+    HookCommitSha,
+}
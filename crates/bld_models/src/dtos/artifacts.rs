@@ -0,0 +1,29 @@
+#[cfg(feature = "database")]
+use crate::pipeline_run_artifacts::PipelineRunArtifacts;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtifactResponse {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub size: i64,
+    pub date_created: String,
+}
+
+#[cfg(feature = "database")]
+impl From<PipelineRunArtifacts> for ArtifactResponse {
+    fn from(value: PipelineRunArtifacts) -> Self {
+        Self {
+            id: value.id,
+            run_id: value.run_id,
+            name: value.name,
+            size: value.size,
+            date_created: value
+                .date_updated
+                .unwrap_or(value.date_created)
+                .format("%F %X")
+                .to_string(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaLogEntry {
+    pub index: i32,
+    pub term: i32,
+    pub payload_type: String,
+    pub payload: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppendEntriesRequest {
+    pub term: i32,
+    pub leader_id: i32,
+    pub prev_log_index: i32,
+    pub prev_log_term: i32,
+    pub entries: Vec<HaLogEntry>,
+    pub leader_commit: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppendEntriesResponse {
+    pub term: i32,
+    pub success: bool,
+    pub last_log_index: i32,
+}
+
+/// A snapshot of the state sent by the leader to a member whose next log
+/// entries have already been removed from the log of the leader.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstallSnapshotRequest {
+    pub term: i32,
+    pub leader_id: i32,
+    pub last_included_index: i32,
+    pub last_included_term: i32,
+    pub members: Vec<i32>,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstallSnapshotResponse {
+    pub term: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoteRequest {
+    pub term: i32,
+    pub candidate_id: i32,
+    pub last_log_index: i32,
+    pub last_log_term: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoteResponse {
+    pub term: i32,
+    pub vote_granted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HaStatusResponse {
+    pub node_id: i32,
+    pub role: String,
+    pub term: i32,
+    pub leader: Option<i32>,
+    pub last_log_index: i32,
+    pub commit_index: i32,
+    pub last_applied: i32,
+}
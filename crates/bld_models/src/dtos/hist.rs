@@ -1,5 +1,9 @@
 #[cfg(feature = "database")]
-use crate::pipeline_runs::PipelineRuns;
+use crate::{
+    pipeline_run_containers::PipelineRunContainers, pipeline_run_jobs::PipelineRunJobs,
+    pipeline_run_notifications::PipelineRunNotifications, pipeline_run_steps::PipelineRunSteps,
+    pipeline_runs::PipelineRuns,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
@@ -25,6 +29,76 @@ impl HistoryEntry {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryStepEntry {
+    pub name: Option<String>,
+    pub position: i32,
+    pub state: String,
+    pub exit_code: Option<i64>,
+    pub start_date_time: Option<String>,
+    pub end_date_time: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryJobEntry {
+    pub name: String,
+    pub state: String,
+    pub start_date_time: Option<String>,
+    pub end_date_time: Option<String>,
+    pub steps: Vec<HistoryStepEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryImageEntry {
+    pub container_id: String,
+    pub image: String,
+    pub digest: Option<String>,
+    pub date_time: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryNotificationEntry {
+    pub id: String,
+    pub target: String,
+    pub event: String,
+    pub attempt: i32,
+    pub state: String,
+    pub error: Option<String>,
+    pub date_time: String,
+}
+
+#[cfg(feature = "database")]
+impl From<PipelineRunSteps> for HistoryStepEntry {
+    fn from(value: PipelineRunSteps) -> Self {
+        Self {
+            name: value.name,
+            position: value.position,
+            state: value.state,
+            exit_code: value.exit_code,
+            start_date_time: value.start_date.map(|x| x.format("%F %X").to_string()),
+            end_date_time: value.end_date.map(|x| x.format("%F %X").to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "database")]
+impl HistoryJobEntry {
+    pub fn new(job: PipelineRunJobs, steps: &[PipelineRunSteps]) -> Self {
+        Self {
+            steps: steps
+                .iter()
+                .filter(|x| x.job_id == job.id)
+                .cloned()
+                .map(HistoryStepEntry::from)
+                .collect(),
+            name: job.name,
+            state: job.state,
+            start_date_time: job.start_date.map(|x| x.format("%F %X").to_string()),
+            end_date_time: job.end_date.map(|x| x.format("%F %X").to_string()),
+        }
+    }
+}
+
 #[cfg(feature = "database")]
 impl From<PipelineRuns> for HistoryEntry {
     fn from(value: PipelineRuns) -> Self {
@@ -38,3 +112,30 @@ impl From<PipelineRuns> for HistoryEntry {
         }
     }
 }
+
+#[cfg(feature = "database")]
+impl From<PipelineRunContainers> for HistoryImageEntry {
+    fn from(value: PipelineRunContainers) -> Self {
+        Self {
+            container_id: value.container_id,
+            image: value.image.unwrap_or_default(),
+            digest: value.image_digest,
+            date_time: value.date_created.format("%F %X").to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "database")]
+impl From<PipelineRunNotifications> for HistoryNotificationEntry {
+    fn from(value: PipelineRunNotifications) -> Self {
+        Self {
+            id: value.id,
+            target: value.target,
+            event: value.event,
+            attempt: value.attempt,
+            state: value.state,
+            error: value.error,
+            date_time: value.date_created.format("%F %X").to_string(),
+        }
+    }
+}
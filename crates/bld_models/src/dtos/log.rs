@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    #[default]
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: Option<String>,
+    pub job: Option<String>,
+    pub step: Option<String>,
+    #[serde(default)]
+    pub stream: LogStream,
+    #[serde(default)]
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Parses a line of a log file, falling back to a plain text entry for
+    /// log files that were written before the structured format.
+    pub fn parse(line: &str) -> Self {
+        serde_json::from_str(line).unwrap_or_else(|_| Self {
+            message: line.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    pub fn is_match(&self, job: Option<&str>, step: Option<&str>) -> bool {
+        let job_matches = job.map(|x| self.job.as_deref() == Some(x)).unwrap_or(true);
+        let step_matches = step
+            .map(|x| self.step.as_deref() == Some(x))
+            .unwrap_or(true);
+        job_matches && step_matches
+    }
+}
+
+impl Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
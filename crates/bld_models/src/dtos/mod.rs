@@ -1,12 +1,16 @@
+mod artifacts;
 mod auth;
 mod common;
 mod cron;
+mod ha;
 mod hist;
 mod kpis;
 mod list;
+mod log;
 mod login;
 mod pull;
 mod push;
+mod secrets;
 
 #[cfg(feature = "web_socket")]
 mod exec;
@@ -17,15 +21,19 @@ mod monit;
 #[cfg(feature = "web_socket")]
 mod supervisor;
 
+pub use artifacts::*;
 pub use auth::*;
 pub use common::*;
 pub use cron::*;
+pub use ha::*;
 pub use hist::*;
 pub use kpis::*;
 pub use list::*;
+pub use log::*;
 pub use login::*;
 pub use pull::*;
 pub use push::*;
+pub use secrets::*;
 
 #[cfg(feature = "web_socket")]
 pub use exec::*;
@@ -7,10 +7,36 @@ pub struct MonitInfo {
     pub id: Option<String>,
     pub name: Option<String>,
     pub last: bool,
+    #[serde(default)]
+    pub job: Option<String>,
+    #[serde(default)]
+    pub step: Option<String>,
+    #[serde(default)]
+    pub structured: bool,
 }
 
 impl MonitInfo {
     pub fn new(id: Option<String>, name: Option<String>, last: bool) -> Self {
-        Self { id, name, last }
+        Self {
+            id,
+            name,
+            last,
+            ..Default::default()
+        }
+    }
+
+    pub fn job(mut self, job: Option<String>) -> Self {
+        self.job = job;
+        self
+    }
+
+    pub fn step(mut self, step: Option<String>) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn structured(mut self, structured: bool) -> Self {
+        self.structured = structured;
+        self
     }
 }
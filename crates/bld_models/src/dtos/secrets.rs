@@ -0,0 +1,34 @@
+#[cfg(feature = "database")]
+use crate::secrets::Secret;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddSecretRequest {
+    pub name: String,
+    pub value: String,
+}
+
+impl AddSecretRequest {
+    pub fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+}
+
+/// The response of the secrets endpoint, the value is never returned.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecretResponse {
+    pub name: String,
+    pub date_created: String,
+    pub date_updated: Option<String>,
+}
+
+#[cfg(feature = "database")]
+impl From<Secret> for SecretResponse {
+    fn from(value: Secret) -> Self {
+        Self {
+            name: value.name,
+            date_created: value.date_created.format("%F %X").to_string(),
+            date_updated: value.date_updated.map(|x| x.format("%F %X").to_string()),
+        }
+    }
+}
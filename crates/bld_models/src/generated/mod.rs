@@ -15,5 +15,10 @@ pub mod high_availability_snapshot;
 pub mod high_availability_state_machine;
 pub mod login_attempts;
 pub mod pipeline;
+pub mod pipeline_run_artifacts;
 pub mod pipeline_run_containers;
+pub mod pipeline_run_jobs;
+pub mod pipeline_run_notifications;
+pub mod pipeline_run_steps;
 pub mod pipeline_runs;
+pub mod secrets;
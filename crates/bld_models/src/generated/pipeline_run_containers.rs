@@ -10,6 +10,8 @@ pub struct Model {
     pub run_id: String,
     pub container_id: String,
     pub state: String,
+    pub image: Option<String>,
+    pub image_digest: Option<String>,
     pub date_created: DateTime,
     pub date_updated: DateTime,
 }
@@ -14,12 +14,17 @@ pub struct Model {
     pub end_date: Option<DateTime>,
     pub date_created: DateTime,
     pub date_updated: Option<DateTime>,
+    pub hook_provider: Option<String>,
+    pub hook_repository: Option<String>,
+    pub hook_commit_sha: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::pipeline_run_containers::Entity")]
     PipelineRunContainers,
+    #[sea_orm(has_many = "super::pipeline_run_jobs::Entity")]
+    PipelineRunJobs,
 }
 
 impl Related<super::pipeline_run_containers::Entity> for Entity {
@@ -28,4 +33,10 @@ impl Related<super::pipeline_run_containers::Entity> for Entity {
     }
 }
 
+impl Related<super::pipeline_run_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunJobs.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -14,5 +14,10 @@ pub use super::high_availability_snapshot::Entity as HighAvailabilitySnapshot;
 pub use super::high_availability_state_machine::Entity as HighAvailabilityStateMachine;
 pub use super::login_attempts::Entity as LoginAttempts;
 pub use super::pipeline::Entity as Pipeline;
+pub use super::pipeline_run_artifacts::Entity as PipelineRunArtifacts;
 pub use super::pipeline_run_containers::Entity as PipelineRunContainers;
+pub use super::pipeline_run_jobs::Entity as PipelineRunJobs;
+pub use super::pipeline_run_notifications::Entity as PipelineRunNotifications;
+pub use super::pipeline_run_steps::Entity as PipelineRunSteps;
 pub use super::pipeline_runs::Entity as PipelineRuns;
+pub use super::secrets::Entity as Secrets;
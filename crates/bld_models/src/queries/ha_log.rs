@@ -114,6 +114,7 @@ pub async fn select_between_ids<C: ConnectionTrait + TransactionTrait>(
     HighAvailLogEntity::find()
         .filter(high_availability_log::Column::Id.gte(lg_start_id))
         .filter(high_availability_log::Column::Id.lte(lg_end_id))
+        .order_by_asc(high_availability_log::Column::Id)
         .all(conn)
         .await
         .inspect(|_| {
@@ -244,6 +245,7 @@ pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
             term: Set(m.term),
             payload: Set(m.payload),
             payload_type: Set(m.payload_type),
+            date_created: Set(Utc::now().naive_utc()),
             ..Default::default()
         })
         .collect();
@@ -285,7 +287,7 @@ pub async fn delete_by_ids<C: ConnectionTrait + TransactionTrait>(
     for lg_id in lg_ids {
         HighAvailLogEntity::delete_many()
             .filter(high_availability_log::Column::Id.eq(lg_id))
-            .exec(conn)
+            .exec(&txn)
             .await
             .map(|_| debug!("deleted high availability log entries successfully"))
             .map_err(|e| {
@@ -309,12 +311,16 @@ pub async fn delete_from_id<C: ConnectionTrait + TransactionTrait>(
         "deleting high availability log entries starting from id: {}",
         lg_id
     );
-    HighAvailLogEntity::delete_by_id(lg_id)
+    HighAvailLogEntity::delete_many()
+        .filter(high_availability_log::Column::Id.gte(lg_id))
         .exec(conn)
         .await
-        .map(|_| debug!("deleted high availability log entry successfully"))
+        .map(|_| debug!("deleted high availability log entries successfully"))
         .map_err(|e| {
-            error!("could not delete high availability log entry due to: {}", e);
+            error!(
+                "could not delete high availability log entries due to: {}",
+                e
+            );
             anyhow!(e)
         })
 }
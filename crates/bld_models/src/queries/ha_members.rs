@@ -100,3 +100,15 @@ pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
     debug!("inserted multiple high availability members successfully");
     Ok(())
 }
+
+pub async fn delete<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<()> {
+    debug!("deleting all high availability members");
+    HighAvailMembersEntity::delete_many()
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted all high availability members successfully"))
+        .map_err(|e| {
+            error!("could not delete high availability members due to: {}", e);
+            anyhow!(e)
+        })
+}
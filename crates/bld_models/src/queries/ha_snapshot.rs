@@ -1,7 +1,8 @@
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ConnectionTrait, EntityTrait, QueryOrder, TransactionTrait,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
 };
 use tracing::{debug, error};
 
@@ -71,3 +72,19 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     debug!("inserted high availability snapshot successfully");
     Ok(())
 }
+
+pub async fn delete_until_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    sn_id: i32,
+) -> Result<()> {
+    debug!("deleting high availability snapshots less than: {}", sn_id);
+    HighAvailSnapshotEntity::delete_many()
+        .filter(high_availability_snapshot::Column::Id.lt(sn_id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted high availability snapshots successfully"))
+        .map_err(|e| {
+            error!("could not delete high availability snapshots due to: {}", e);
+            anyhow!(e)
+        })
+}
@@ -11,8 +11,13 @@ pub mod ha_snapshot;
 pub mod ha_state_machine;
 pub mod login_attempts;
 pub mod pipeline;
+pub mod pipeline_run_artifacts;
 pub mod pipeline_run_containers;
+pub mod pipeline_run_jobs;
+pub mod pipeline_run_notifications;
+pub mod pipeline_run_steps;
 pub mod pipeline_runs;
+pub mod secrets;
 
 use anyhow::{bail, Result};
 use bld_config::BldConfig;
@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Result};
+use bld_migrations::{Alias, Expr};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_artifacts::Model as PipelineRunArtifacts;
+use crate::generated::pipeline_run_artifacts::{self, Entity as PipelineRunArtifactsEntity};
+
+#[derive(Debug)]
+pub struct InsertPipelineRunArtifact {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub size: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+pub struct PipelineRunArtifactsPerRun {
+    pub run_id: String,
+    pub last_date_created: NaiveDateTime,
+}
+
+pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pra_id: &str,
+) -> Result<PipelineRunArtifacts> {
+    debug!("loading pipeline run artifact with id: {pra_id}");
+
+    PipelineRunArtifactsEntity::find_by_id(pra_id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run artifact. {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load pipeline run artifact. Not found");
+            anyhow!("pipeline run artifact not found")
+        })
+        .inspect(|_| {
+            debug!("loaded pipeline run artifact successfully");
+        })
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunArtifacts>> {
+    debug!("loading pipeline run artifacts of run with id: {run_id}");
+
+    PipelineRunArtifactsEntity::find()
+        .filter(pipeline_run_artifacts::Column::RunId.eq(run_id))
+        .order_by_asc(pipeline_run_artifacts::Column::Name)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run artifacts successfully");
+        })
+        .map_err(|e| {
+            error!("could not load pipeline run artifacts, {e}");
+            anyhow!(e)
+        })
+}
+
+/// Loads the ids of all runs that have artifacts along with the date of their
+/// latest artifact, ordered from the most recent run to the oldest one.
+pub async fn select_runs<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<PipelineRunArtifactsPerRun>> {
+    debug!("loading runs with pipeline run artifacts");
+
+    PipelineRunArtifactsEntity::find()
+        .select_only()
+        .column(pipeline_run_artifacts::Column::RunId)
+        .column_as(
+            pipeline_run_artifacts::Column::DateCreated.max(),
+            "last_date_created",
+        )
+        .group_by(pipeline_run_artifacts::Column::RunId)
+        .order_by_desc(Expr::col(Alias::new("last_date_created")))
+        .into_model::<PipelineRunArtifactsPerRun>()
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded runs with pipeline run artifacts successfully");
+        })
+        .map_err(|e| {
+            error!("could not load runs with pipeline run artifacts, {e}");
+            anyhow!(e)
+        })
+}
+
+/// Inserts a new artifact for a run or updates the size of an existing one
+/// with the same name.
+pub async fn upsert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunArtifact,
+) -> Result<PipelineRunArtifacts> {
+    debug!(
+        "upserting pipeline run artifact {} of run {}",
+        model.name, model.run_id
+    );
+
+    let current_date = Utc::now().naive_utc();
+    let existing = PipelineRunArtifactsEntity::find()
+        .filter(pipeline_run_artifacts::Column::RunId.eq(&model.run_id))
+        .filter(pipeline_run_artifacts::Column::Name.eq(&model.name))
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run artifact. {e}");
+            anyhow!(e)
+        })?;
+
+    if let Some(existing) = existing {
+        PipelineRunArtifactsEntity::update_many()
+            .col_expr(
+                pipeline_run_artifacts::Column::Size,
+                Expr::value(model.size),
+            )
+            .col_expr(
+                pipeline_run_artifacts::Column::DateUpdated,
+                Expr::value(current_date),
+            )
+            .filter(pipeline_run_artifacts::Column::Id.eq(&existing.id))
+            .exec(conn)
+            .await
+            .map_err(|e| {
+                error!("could not update pipeline run artifact. {e}");
+                anyhow!(e)
+            })?;
+
+        debug!("updated pipeline run artifact successfully");
+        return select_by_id(conn, &existing.id).await;
+    }
+
+    let id = model.id;
+    let model = pipeline_run_artifacts::ActiveModel {
+        id: Set(id.to_owned()),
+        run_id: Set(model.run_id),
+        name: Set(model.name),
+        size: Set(model.size),
+        date_created: Set(current_date),
+        ..Default::default()
+    };
+
+    PipelineRunArtifactsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run artifact. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run artifact successfully");
+    select_by_id(conn, &id).await
+}
+
+pub async fn delete_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<()> {
+    debug!("deleting pipeline run artifacts of run with id: {run_id}");
+
+    PipelineRunArtifactsEntity::delete_many()
+        .filter(pipeline_run_artifacts::Column::RunId.eq(run_id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("deleted pipeline run artifacts successfully");
+        })
+        .map_err(|e| {
+            error!("could not delete pipeline run artifacts, {e}");
+            anyhow!(e)
+        })
+}
@@ -3,7 +3,7 @@ use bld_migrations::Expr;
 use chrono::Utc;
 use sea_orm::{
     ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, EntityTrait, JoinType, QueryFilter,
-    QuerySelect, RelationTrait, TransactionTrait,
+    QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
 };
 use tracing::{debug, error};
 
@@ -25,6 +25,8 @@ pub struct InsertPipelineRunContainer {
     pub run_id: String,
     pub container_id: String,
     pub state: String,
+    pub image: Option<String>,
+    pub image_digest: Option<String>,
 }
 
 pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
@@ -51,6 +53,26 @@ pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
         })
 }
 
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    prc_run_id: &str,
+) -> Result<Vec<PipelineRunContainers>> {
+    debug!("loading pipeline run containers of run with id: {prc_run_id}");
+
+    PipelineRunContainersEntity::find()
+        .filter(pipeline_run_containers::Column::RunId.eq(prc_run_id))
+        .order_by_asc(pipeline_run_containers::Column::DateCreated)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run containers successfully");
+        })
+        .map_err(|e| {
+            error!("could not load pipeline run containers, {e}");
+            anyhow!(e)
+        })
+}
+
 pub async fn select_in_invalid_state<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
 ) -> Result<Vec<PipelineRunContainers>> {
@@ -107,6 +129,8 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
         run_id: Set(model.run_id),
         container_id: Set(model.container_id),
         state: Set(model.state),
+        image: Set(model.image),
+        image_digest: Set(model.image_digest),
         date_created: Set(Utc::now().naive_utc()),
         date_updated: Set(Utc::now().naive_utc()),
     };
@@ -0,0 +1,135 @@
+use anyhow::{anyhow, Result};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_jobs::Model as PipelineRunJobs;
+use crate::generated::pipeline_run_jobs::{self, Entity as PipelineRunJobsEntity};
+
+pub const PRJ_STATE_RUNNING: &str = "running";
+pub const PRJ_STATE_FINISHED: &str = "finished";
+pub const PRJ_STATE_FAULTED: &str = "faulted";
+pub const PRJ_STATE_SKIPPED: &str = "skipped";
+
+#[derive(Debug)]
+pub struct InsertPipelineRunJob {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub state: String,
+}
+
+pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    prj_id: &str,
+) -> Result<PipelineRunJobs> {
+    debug!("loading pipeline run job with id: {prj_id}");
+
+    PipelineRunJobsEntity::find_by_id(prj_id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run job. {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load pipeline run job. Not found");
+            anyhow!("pipeline run job not found")
+        })
+        .inspect(|_| {
+            debug!("loaded pipeline run job successfully");
+        })
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    prj_run_id: &str,
+) -> Result<Vec<PipelineRunJobs>> {
+    debug!("loading pipeline run jobs of run with id: {prj_run_id}");
+
+    PipelineRunJobsEntity::find()
+        .filter(pipeline_run_jobs::Column::RunId.eq(prj_run_id))
+        .order_by_asc(pipeline_run_jobs::Column::DateCreated)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run jobs successfully");
+        })
+        .map_err(|e| {
+            error!("could not load pipeline run jobs, {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunJob,
+) -> Result<PipelineRunJobs> {
+    debug!("inserting pipeline run job");
+
+    let id = model.id;
+    let current_date = Utc::now().naive_utc();
+    let start_date = if model.state == PRJ_STATE_RUNNING {
+        Some(current_date)
+    } else {
+        None
+    };
+    let model = pipeline_run_jobs::ActiveModel {
+        id: Set(id.to_owned()),
+        run_id: Set(model.run_id),
+        name: Set(model.name),
+        state: Set(model.state),
+        start_date: Set(start_date),
+        date_created: Set(current_date),
+        ..Default::default()
+    };
+
+    PipelineRunJobsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run job. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run job successfully");
+    select_by_id(conn, &id).await
+}
+
+pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+) -> Result<()> {
+    debug!("updating pipeline run job with id: {id} with new state: {state}");
+    let current_date = Utc::now().naive_utc();
+    let mut update_statement = PipelineRunJobsEntity::update_many()
+        .col_expr(pipeline_run_jobs::Column::State, Expr::value(state))
+        .col_expr(
+            pipeline_run_jobs::Column::DateUpdated,
+            Expr::value(current_date),
+        );
+
+    if state == PRJ_STATE_FINISHED || state == PRJ_STATE_FAULTED {
+        update_statement = update_statement.col_expr(
+            pipeline_run_jobs::Column::EndDate,
+            Expr::value(current_date),
+        );
+    }
+
+    update_statement
+        .filter(pipeline_run_jobs::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("updated pipeline run job successfully");
+        })
+        .map_err(|e| {
+            error!("could not update pipeline run job. {e}");
+            anyhow!(e)
+        })
+}
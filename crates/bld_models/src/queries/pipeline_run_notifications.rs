@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_notifications::Model as PipelineRunNotifications;
+use crate::generated::pipeline_run_notifications::{
+    self, Entity as PipelineRunNotificationsEntity,
+};
+
+pub const PRN_STATE_DELIVERED: &str = "delivered";
+pub const PRN_STATE_FAILED: &str = "failed";
+
+#[derive(Debug)]
+pub struct InsertPipelineRunNotification {
+    pub id: String,
+    pub run_id: String,
+    pub target: String,
+    pub event: String,
+    pub attempt: i32,
+    pub state: String,
+    pub error: Option<String>,
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunNotifications>> {
+    debug!("loading pipeline run notifications of run with id: {run_id}");
+
+    PipelineRunNotificationsEntity::find()
+        .filter(pipeline_run_notifications::Column::RunId.eq(run_id))
+        .order_by_asc(pipeline_run_notifications::Column::DateCreated)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run notifications successfully");
+        })
+        .map_err(|e| {
+            error!("could not load pipeline run notifications, {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunNotification,
+) -> Result<()> {
+    debug!(
+        "inserting {} notification attempt {} of run {}",
+        model.target, model.attempt, model.run_id
+    );
+
+    let model = pipeline_run_notifications::ActiveModel {
+        id: Set(model.id),
+        run_id: Set(model.run_id),
+        target: Set(model.target),
+        event: Set(model.event),
+        attempt: Set(model.attempt),
+        state: Set(model.state),
+        error: Set(model.error),
+        date_created: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    PipelineRunNotificationsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("inserted pipeline run notification successfully");
+        })
+        .map_err(|e| {
+            error!("could not insert pipeline run notification. {e}");
+            anyhow!(e)
+        })
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationTrait, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_steps::Model as PipelineRunSteps;
+use crate::generated::{
+    pipeline_run_jobs,
+    pipeline_run_steps::{self, Entity as PipelineRunStepsEntity},
+};
+
+pub const PRS_STATE_RUNNING: &str = "running";
+pub const PRS_STATE_FINISHED: &str = "finished";
+pub const PRS_STATE_FAULTED: &str = "faulted";
+pub const PRS_STATE_SKIPPED: &str = "skipped";
+
+#[derive(Debug)]
+pub struct InsertPipelineRunStep {
+    pub id: String,
+    pub job_id: String,
+    pub name: Option<String>,
+    pub position: i32,
+    pub state: String,
+}
+
+pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    prs_id: &str,
+) -> Result<PipelineRunSteps> {
+    debug!("loading pipeline run step with id: {prs_id}");
+
+    PipelineRunStepsEntity::find_by_id(prs_id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run step. {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load pipeline run step. Not found");
+            anyhow!("pipeline run step not found")
+        })
+        .inspect(|_| {
+            debug!("loaded pipeline run step successfully");
+        })
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunSteps>> {
+    debug!("loading pipeline run steps of run with id: {run_id}");
+
+    PipelineRunStepsEntity::find()
+        .join(
+            JoinType::InnerJoin,
+            pipeline_run_steps::Relation::PipelineRunJobs.def(),
+        )
+        .filter(pipeline_run_jobs::Column::RunId.eq(run_id))
+        .order_by_asc(pipeline_run_steps::Column::Position)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run steps successfully");
+        })
+        .map_err(|e| {
+            error!("could not load pipeline run steps, {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunStep,
+) -> Result<PipelineRunSteps> {
+    debug!("inserting pipeline run step");
+
+    let id = model.id;
+    let current_date = Utc::now().naive_utc();
+    let start_date = if model.state == PRS_STATE_RUNNING {
+        Some(current_date)
+    } else {
+        None
+    };
+    let model = pipeline_run_steps::ActiveModel {
+        id: Set(id.to_owned()),
+        job_id: Set(model.job_id),
+        name: Set(model.name),
+        position: Set(model.position),
+        state: Set(model.state),
+        start_date: Set(start_date),
+        date_created: Set(current_date),
+        ..Default::default()
+    };
+
+    PipelineRunStepsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run step. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run step successfully");
+    select_by_id(conn, &id).await
+}
+
+pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+    exit_code: Option<i64>,
+) -> Result<()> {
+    debug!("updating pipeline run step with id: {id} with new state: {state}");
+    let current_date = Utc::now().naive_utc();
+    let mut update_statement = PipelineRunStepsEntity::update_many()
+        .col_expr(pipeline_run_steps::Column::State, Expr::value(state))
+        .col_expr(pipeline_run_steps::Column::ExitCode, Expr::value(exit_code))
+        .col_expr(
+            pipeline_run_steps::Column::DateUpdated,
+            Expr::value(current_date),
+        );
+
+    if state == PRS_STATE_FINISHED || state == PRS_STATE_FAULTED {
+        update_statement = update_statement.col_expr(
+            pipeline_run_steps::Column::EndDate,
+            Expr::value(current_date),
+        );
+    }
+
+    update_statement
+        .filter(pipeline_run_steps::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("updated pipeline run step successfully");
+        })
+        .map_err(|e| {
+            error!("could not update pipeline run step. {e}");
+            anyhow!(e)
+        })
+}
@@ -2,10 +2,11 @@ use anyhow::{anyhow, Result};
 use bld_migrations::Expr;
 use chrono::{Duration, Utc};
 use sea_orm::{
-    prelude::DateTime, ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait,
+    prelude::DateTime, ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait,
     DatabaseBackend, DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
     QueryOrder, QuerySelect, Statement, TransactionTrait,
 };
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
 pub use crate::generated::pipeline_runs::Model as PipelineRuns;
@@ -17,10 +18,45 @@ pub const PR_STATE_RUNNING: &str = "running";
 pub const PR_STATE_FINISHED: &str = "finished";
 pub const PR_STATE_FAULTED: &str = "faulted";
 
+/// The origin of a run that was started by a hook delivery, it's recorded by the
+/// server so that commit statuses are only reported for the runs of hooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineRunHook {
+    pub provider: String,
+    pub repository: String,
+    pub commit_sha: String,
+}
+
 pub struct InsertPipelineRun {
     pub id: String,
     pub name: String,
     pub app_user: String,
+    pub hook: Option<PipelineRunHook>,
+}
+
+#[derive(Debug)]
+pub struct UpsertPipelineRun {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    pub app_user: String,
+    pub start_date: Option<DateTime>,
+    pub end_date: Option<DateTime>,
+    pub date_created: DateTime,
+    pub date_updated: Option<DateTime>,
+    pub hook_provider: Option<String>,
+    pub hook_repository: Option<String>,
+    pub hook_commit_sha: Option<String>,
+}
+
+impl PipelineRuns {
+    pub fn hook(&self) -> Option<PipelineRunHook> {
+        Some(PipelineRunHook {
+            provider: self.hook_provider.clone()?,
+            repository: self.hook_repository.clone()?,
+            commit_sha: self.hook_commit_sha.clone()?,
+        })
+    }
 }
 
 #[derive(Debug, FromQueryResult)]
@@ -150,6 +186,28 @@ pub async fn select_with_filters<C: ConnectionTrait + TransactionTrait>(
         })
 }
 
+pub async fn select_changed_after<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    date: &DateTime,
+) -> Result<Vec<PipelineRuns>> {
+    debug!("loading pipeline runs created or updated after {date}");
+
+    PipelineRunsEntity::find()
+        .filter(
+            Condition::any()
+                .add(pipeline_runs::Column::DateCreated.gt(*date))
+                .add(pipeline_runs::Column::DateUpdated.gt(*date)),
+        )
+        .order_by_asc(pipeline_runs::Column::DateCreated)
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded changed pipeline runs successfully"))
+        .map_err(|e| {
+            error!("could not load changed pipeline runs due to: {e}");
+            anyhow!(e)
+        })
+}
+
 pub async fn count_queued(conn: &DatabaseConnection) -> Result<u64> {
     debug!("getting the count of pipelines that are queued");
     PipelineRunsEntity::find()
@@ -487,6 +545,9 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
         app_user: Set(model.app_user.to_owned()),
         state: Set(PR_STATE_INITIAL.to_owned()),
         date_created: Set(Utc::now().naive_utc()),
+        hook_provider: Set(model.hook.as_ref().map(|x| x.provider.to_owned())),
+        hook_repository: Set(model.hook.as_ref().map(|x| x.repository.to_owned())),
+        hook_commit_sha: Set(model.hook.as_ref().map(|x| x.commit_sha.to_owned())),
         ..Default::default()
     };
 
@@ -502,6 +563,60 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     Ok(())
 }
 
+/// Inserts the pipeline run or updates it if it already exists, an existing
+/// run is only updated when the model contains more recent changes.
+pub async fn upsert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: UpsertPipelineRun,
+) -> Result<()> {
+    debug!("upserting pipeline run with id: {}", model.id);
+
+    let current = PipelineRunsEntity::find_by_id(&model.id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run due to: {e}");
+            anyhow!(e)
+        })?;
+
+    let last_change = model.date_updated.unwrap_or(model.date_created);
+    let is_stale = current
+        .as_ref()
+        .is_some_and(|c| c.date_updated.unwrap_or(c.date_created) >= last_change);
+
+    if is_stale {
+        debug!("pipeline run is already up to date");
+        return Ok(());
+    }
+
+    let active_model = pipeline_runs::ActiveModel {
+        id: Set(model.id),
+        name: Set(model.name),
+        state: Set(model.state),
+        app_user: Set(model.app_user),
+        start_date: Set(model.start_date),
+        end_date: Set(model.end_date),
+        date_created: Set(model.date_created),
+        date_updated: Set(model.date_updated),
+        hook_provider: Set(model.hook_provider),
+        hook_repository: Set(model.hook_repository),
+        hook_commit_sha: Set(model.hook_commit_sha),
+    };
+
+    let result = if current.is_some() {
+        active_model.update(conn).await.map(|_| ())
+    } else {
+        active_model.insert(conn).await.map(|_| ())
+    };
+
+    result
+        .map(|_| debug!("upserted pipeline run successfully"))
+        .map_err(|e| {
+            error!("could not upsert pipeline run due to: {e}");
+            anyhow!(e)
+        })
+}
+
 pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
     id: &str,
@@ -0,0 +1,233 @@
+use crate::generated::secrets::{self, Entity as SecretEntity};
+use anyhow::{anyhow, bail, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::Utc;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
+use std::{collections::HashMap, num::NonZeroU32};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub use crate::generated::secrets::Model as Secret;
+
+const SALT_LEN: usize = 16;
+const KDF_ITERATIONS: u32 = 100_000;
+
+/// Derives the encryption key of a secret from the secrets key of the config
+/// with PBKDF2 and the random salt that is stored along with the secret.
+fn cipher(key: &str, salt: &[u8]) -> Result<LessSafeKey> {
+    let iterations =
+        NonZeroU32::new(KDF_ITERATIONS).ok_or_else(|| anyhow!("invalid kdf iterations"))?;
+    let mut derived = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        key.as_bytes(),
+        &mut derived,
+    );
+    UnboundKey::new(&AES_256_GCM, &derived)
+        .map(LessSafeKey::new)
+        .map_err(|_| anyhow!("invalid secrets key"))
+}
+
+/// Encrypts the value with AES-256-GCM and returns the salt and the nonce followed by
+/// the cipher text encoded in base64, which is the format stored in the database.
+fn encrypt(key: &str, value: &str) -> Result<String> {
+    let rng = SystemRandom::new();
+    let mut salt = [0; SALT_LEN];
+    rng.fill(&mut salt)
+        .map_err(|_| anyhow!("unable to generate salt for secret"))?;
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill(&mut nonce)
+        .map_err(|_| anyhow!("unable to generate nonce for secret"))?;
+
+    let mut data = value.as_bytes().to_vec();
+    cipher(key, &salt)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow!("unable to encrypt secret"))?;
+
+    let mut encrypted = salt.to_vec();
+    encrypted.extend(nonce);
+    encrypted.extend(data);
+    Ok(BASE64_STANDARD.encode(encrypted))
+}
+
+fn decrypt(key: &str, value: &str) -> Result<String> {
+    let data = BASE64_STANDARD.decode(value)?;
+    if data.len() < SALT_LEN + NONCE_LEN {
+        bail!("invalid encrypted secret");
+    }
+
+    let (salt, data) = data.split_at(SALT_LEN);
+    let (nonce, data) = data.split_at(NONCE_LEN);
+    let nonce =
+        Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid encrypted secret"))?;
+
+    let mut data = data.to_vec();
+    let decrypted = cipher(key, salt)?
+        .open_in_place(nonce, Aad::empty(), &mut data)
+        .map_err(|_| anyhow!("unable to decrypt secret, check the secrets key in the config"))?;
+
+    Ok(String::from_utf8(decrypted.to_vec())?)
+}
+
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<Vec<Secret>> {
+    debug!("loading all secrets from the database");
+    SecretEntity::find()
+        .order_by_asc(secrets::Column::Name)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded all secrets successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't load secrets due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn select_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    sec_name: &str,
+) -> Result<Secret> {
+    debug!("loading secret with name: {sec_name} from the database");
+
+    let model = SecretEntity::find()
+        .filter(secrets::Column::Name.eq(sec_name))
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("couldn't load secret due to {e}");
+            anyhow!(e)
+        })?;
+
+    model
+        .ok_or_else(|| {
+            error!("couldn't load secret due to not found");
+            anyhow!("secret not found")
+        })
+        .inspect(|_| {
+            debug!("loaded secret successfully");
+        })
+}
+
+/// Loads and decrypts all the secrets, returning a map of their names to their values.
+pub async fn select_all_decrypted<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    key: &str,
+) -> Result<HashMap<String, String>> {
+    let mut decrypted = HashMap::new();
+    for model in select_all(conn).await? {
+        let value = decrypt(key, &model.value)?;
+        decrypted.insert(model.name, value);
+    }
+    Ok(decrypted)
+}
+
+/// Inserts a new secret or replaces the value of an existing one with the same name.
+pub async fn upsert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    key: &str,
+    sec_name: &str,
+    sec_value: &str,
+) -> Result<()> {
+    debug!("saving secret with name: {sec_name} to the database");
+    let value = encrypt(key, sec_value)?;
+
+    let result = match select_by_name(conn, sec_name).await {
+        Ok(model) => {
+            let mut active_model: secrets::ActiveModel = model.into();
+            active_model.value = Set(value);
+            active_model.date_updated = Set(Some(Utc::now().naive_utc()));
+            active_model.update(conn).await.map(|_| ())
+        }
+        Err(_) => {
+            let active_model = secrets::ActiveModel {
+                id: Set(Uuid::new_v4().to_string()),
+                name: Set(sec_name.to_owned()),
+                value: Set(value),
+                date_created: Set(Utc::now().naive_utc()),
+                ..Default::default()
+            };
+            active_model.insert(conn).await.map(|_| ())
+        }
+    };
+
+    result
+        .map(|_| {
+            debug!("saved secret successfully");
+        })
+        .map_err(|e| {
+            error!("could not save secret due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    sec_name: &str,
+) -> Result<()> {
+    debug!("deleting secret with name: {sec_name} from the database");
+    let model = select_by_name(conn, sec_name).await?;
+    model
+        .delete(conn)
+        .await
+        .map(|_| {
+            debug!("secret deleted successfully");
+        })
+        .map_err(|e| {
+            error!("could not delete secret due to {e}");
+            anyhow!(e)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_secret_is_decrypted_with_the_same_key() {
+        let encrypted = encrypt("secrets-key", "value of the secret").unwrap();
+        assert!(!encrypted.contains("value of the secret"));
+        assert_eq!(
+            decrypt("secrets-key", &encrypted).unwrap(),
+            "value of the secret"
+        );
+    }
+
+    #[test]
+    fn same_value_is_encrypted_with_a_different_salt() {
+        let first = encrypt("secrets-key", "value").unwrap();
+        let second = encrypt("secrets-key", "value").unwrap();
+        assert_ne!(first, second);
+
+        let first = BASE64_STANDARD.decode(first).unwrap();
+        let second = BASE64_STANDARD.decode(second).unwrap();
+        assert_ne!(first[..SALT_LEN], second[..SALT_LEN]);
+    }
+
+    #[test]
+    fn secret_isnt_decrypted_with_another_key() {
+        let encrypted = encrypt("secrets-key", "value").unwrap();
+        assert!(decrypt("other-key", &encrypted).is_err());
+    }
+
+    #[test]
+    fn tampered_secret_isnt_decrypted() {
+        let encrypted = encrypt("secrets-key", "value").unwrap();
+        let mut data = BASE64_STANDARD.decode(encrypted).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        let tampered = BASE64_STANDARD.encode(data);
+        assert!(decrypt("secrets-key", &tampered).is_err());
+        assert!(decrypt("secrets-key", "c2hvcnQ=").is_err());
+    }
+}
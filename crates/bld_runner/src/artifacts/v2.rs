@@ -10,7 +10,7 @@ use crate::token_context::v2::PipelineContext;
 pub struct Artifacts {
     pub method: String,
     pub from: String,
-    pub to: String,
+    pub to: Option<String>,
     pub ignore_errors: Option<bool>,
     pub after: Option<String>,
 }
@@ -19,7 +19,10 @@ impl Artifacts {
     #[cfg(feature = "all")]
     pub async fn apply_tokens<'a>(&mut self, context: &'a PipelineContext<'a>) -> Result<()> {
         self.from = context.transform(self.from.to_owned()).await?;
-        self.to = context.transform(self.to.to_owned()).await?;
+
+        if let Some(to) = self.to.as_mut() {
+            self.to = Some(context.transform(to.to_owned()).await?);
+        }
 
         if let Some(after) = self.after.as_mut() {
             self.after = Some(context.transform(after.to_owned()).await?);
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cache {
+    pub key: String,
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub jobs: Vec<String>,
+}
+
+impl Cache {
+    /// Checks if the cache entry should be used for a job, an entry without any
+    /// jobs is used by all of them.
+    pub fn is_for(&self, job: &str) -> bool {
+        self.jobs.is_empty() || self.jobs.iter().any(|x| x == job)
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &'a PipelineContext<'a>) -> Result<()> {
+        self.key = context.transform(self.key.to_owned()).await?;
+
+        for path in self.paths.iter_mut() {
+            *path = context.transform(path.to_owned()).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use std::collections::HashMap;
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+#[cfg(feature = "all")]
+use bld_config::GitConfig;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkout {
+    pub repository: String,
+    #[serde(rename(serialize = "ref", deserialize = "ref"))]
+    pub git_ref: Option<String>,
+    pub path: Option<String>,
+    pub depth: Option<u64>,
+    #[serde(default)]
+    pub submodules: bool,
+    pub credentials: Option<String>,
+}
+
+impl Checkout {
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(".")
+    }
+
+    /// The scheme and host of the repository, used to scope the credentials
+    /// so they aren't sent to the hosts of any submodules.
+    pub fn origin(&self) -> Option<&str> {
+        let (scheme, rest) = self.repository.split_once("://")?;
+        if scheme != "http" && scheme != "https" {
+            return None;
+        }
+        let end = rest.find('/').map(|x| scheme.len() + 3 + x + 1)?;
+        Some(&self.repository[..end])
+    }
+
+    /// The encoded credentials for the basic authorization header.
+    #[cfg(feature = "all")]
+    pub fn basic_auth(credentials: &GitConfig) -> String {
+        let value = format!("{}:{}", credentials.username, credentials.password);
+        BASE64_STANDARD.encode(value)
+    }
+
+    /// The configuration environment variables of git that set the authorization
+    /// header for the origin of the repository. They are passed to the platform
+    /// along with the command of the checkout, so the credentials are never part
+    /// of the command or the arguments of the git processes.
+    #[cfg(feature = "all")]
+    pub fn env(&self, basic_auth: Option<&str>) -> HashMap<String, String> {
+        let (Some(auth), Some(origin)) = (basic_auth, self.origin()) else {
+            return HashMap::new();
+        };
+        HashMap::from([
+            ("GIT_CONFIG_COUNT".to_owned(), "1".to_owned()),
+            (
+                "GIT_CONFIG_KEY_0".to_owned(),
+                format!("http.{origin}.extraHeader"),
+            ),
+            (
+                "GIT_CONFIG_VALUE_0".to_owned(),
+                format!("Authorization: Basic {auth}"),
+            ),
+        ])
+    }
+
+    /// Creates the shell command that fetches the ref of the repository into the
+    /// checkout path and leaves the working tree on the fetched commit.
+    #[cfg(feature = "all")]
+    pub fn command(&self) -> String {
+        let path = quote(self.path());
+        let repository = quote(&self.repository);
+        let git_ref = quote(self.git_ref.as_deref().unwrap_or("HEAD"));
+        let depth = self
+            .depth
+            .map(|x| format!(" --depth {x}"))
+            .unwrap_or_default();
+
+        let mut commands = vec![
+            format!("mkdir -p {path}"),
+            format!("cd {path}"),
+            "git init -q".to_owned(),
+            format!("(git remote add origin {repository} 2>/dev/null || git remote set-url origin {repository})"),
+            format!("git fetch -q --no-tags{depth} origin {git_ref}"),
+            "git checkout -q --force --detach FETCH_HEAD".to_owned(),
+        ];
+        if self.submodules {
+            commands.push(format!("git submodule -q update --init --recursive{depth}"));
+        }
+        commands.join(" && ")
+    }
+
+    #[cfg(feature = "all")]
+    pub fn commit_command(&self) -> String {
+        format!("git -C {} rev-parse HEAD", quote(self.path()))
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &PipelineContext<'a>) -> Result<()> {
+        self.repository = context.transform(self.repository.to_owned()).await?;
+        if let Some(git_ref) = self.git_ref.as_mut() {
+            *git_ref = context.transform(git_ref.to_owned()).await?;
+        }
+        if let Some(path) = self.path.as_mut() {
+            *path = context.transform(path.to_owned()).await?;
+        }
+        if let Some(credentials) = self.credentials.as_mut() {
+            *credentials = context.transform(credentials.to_owned()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "all")]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use super::*;
+
+    fn checkout(repository: &str) -> Checkout {
+        Checkout {
+            repository: repository.to_owned(),
+            git_ref: Some("main".to_owned()),
+            path: Some("src".to_owned()),
+            depth: Some(1),
+            submodules: true,
+            credentials: Some("github".to_owned()),
+        }
+    }
+
+    fn credentials() -> GitConfig {
+        GitConfig {
+            username: "bld".to_owned(),
+            password: "access-token".to_owned(),
+        }
+    }
+
+    #[test]
+    fn credentials_are_never_part_of_the_command() {
+        let checkout = checkout("https://github.com/owner/repo.git");
+        let basic_auth = Checkout::basic_auth(&credentials());
+
+        let command = checkout.command();
+        assert!(!command.contains(&basic_auth), "{command}");
+        assert!(!command.contains("access-token"), "{command}");
+        assert!(!command.contains("Authorization"), "{command}");
+        assert!(!command.contains("GIT_CONFIG"), "{command}");
+
+        let env = checkout.env(Some(&basic_auth));
+        assert_eq!(env["GIT_CONFIG_COUNT"], "1");
+        assert_eq!(
+            env["GIT_CONFIG_KEY_0"],
+            "http.https://github.com/.extraHeader"
+        );
+        assert_eq!(
+            env["GIT_CONFIG_VALUE_0"],
+            format!("Authorization: Basic {basic_auth}")
+        );
+    }
+
+    #[test]
+    fn credentials_are_only_used_for_http_repositories() {
+        let basic_auth = Checkout::basic_auth(&credentials());
+
+        let ssh = checkout("git@github.com:owner/repo.git");
+        assert!(ssh.env(Some(&basic_auth)).is_empty());
+
+        let https = checkout("https://github.com/owner/repo.git");
+        assert!(https.env(None).is_empty());
+    }
+
+    #[test]
+    fn command_fetches_the_ref_into_the_path() {
+        let command = checkout("https://github.com/owner/repo.git").command();
+        assert_eq!(
+            command,
+            "mkdir -p 'src' && cd 'src' && git init -q && (git remote add origin 'https://github.com/owner/repo.git' 2>/dev/null || git remote set-url origin 'https://github.com/owner/repo.git') && git fetch -q --no-tags --depth 1 origin 'main' && git checkout -q --force --detach FETCH_HEAD && git submodule -q update --init --recursive --depth 1"
+        );
+    }
+}
@@ -0,0 +1,582 @@
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use anyhow::{anyhow, bail, Result};
+
+const FN_SUCCESS: &str = "success";
+const FN_FAILURE: &str = "failure";
+const FN_ALWAYS: &str = "always";
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    OpenParen,
+    CloseParen,
+    Not,
+    And,
+    Or,
+    Equals,
+    NotEquals,
+    Text(String),
+    Symbol(String),
+    Word(String),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => bail!("expected '{expected}' but found '{c}'"),
+            None => bail!("expected '{expected}' but reached the end of the expression"),
+        }
+    }
+
+    fn text(&mut self, quote: char) -> Result<Token> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => break,
+                Some('\\') => {
+                    let c = self
+                        .chars
+                        .next()
+                        .ok_or_else(|| anyhow!("unterminated string literal"))?;
+                    value.push(c);
+                }
+                Some(c) => value.push(c),
+                None => bail!("unterminated string literal"),
+            }
+        }
+        Ok(Token::Text(value))
+    }
+
+    fn symbol(&mut self) -> Result<Token> {
+        self.expect('{')?;
+        self.expect('{')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('}') => break,
+                Some(c) => value.push(c),
+                None => bail!("unterminated symbol expression"),
+            }
+        }
+        self.expect('}')?;
+
+        let value = value.trim();
+        let is_valid = value
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+        if value.is_empty() || !is_valid {
+            bail!("invalid symbol '{value}'");
+        }
+
+        Ok(Token::Symbol(value.to_owned()))
+    }
+
+    fn word(&mut self, first: char) -> Token {
+        let mut value = String::from(first);
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.' || *c == '-')
+        {
+            value.push(c);
+        }
+        Token::Word(value)
+    }
+
+    fn tokens(mut self) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        while let Some(c) = self.chars.next() {
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                '(' => Token::OpenParen,
+                ')' => Token::CloseParen,
+                '!' if self.chars.next_if_eq(&'=').is_some() => Token::NotEquals,
+                '!' => Token::Not,
+                '=' => {
+                    self.expect('=')?;
+                    Token::Equals
+                }
+                '&' => {
+                    self.expect('&')?;
+                    Token::And
+                }
+                '|' => {
+                    self.expect('|')?;
+                    Token::Or
+                }
+                '\'' | '"' => self.text(c)?,
+                '$' => self.symbol()?,
+                c if c.is_alphanumeric() || c == '_' => self.word(c),
+                c => bail!("unexpected character '{c}'"),
+            };
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+}
+
+/// The parsed form of a condition used by the `if` field of jobs and steps.
+#[derive(Debug)]
+pub enum Expression {
+    Text(String),
+    Boolean(bool),
+    Symbol(String),
+    Function(String),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Equals(Box<Expression>, Box<Expression>),
+    NotEquals(Box<Expression>, Box<Expression>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn next_if(&mut self, token: Token) -> bool {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression> {
+        let mut left = self.and()?;
+        while self.next_if(Token::Or) {
+            left = Expression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expression> {
+        let mut left = self.unary()?;
+        while self.next_if(Token::And) {
+            left = Expression::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression> {
+        if self.next_if(Token::Not) {
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expression> {
+        let left = self.primary()?;
+        if self.next_if(Token::Equals) {
+            return Ok(Expression::Equals(
+                Box::new(left),
+                Box::new(self.primary()?),
+            ));
+        }
+        if self.next_if(Token::NotEquals) {
+            return Ok(Expression::NotEquals(
+                Box::new(left),
+                Box::new(self.primary()?),
+            ));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Expression> {
+        let expression = match self.next() {
+            Some(Token::OpenParen) => {
+                let expression = self.or()?;
+                if !self.next_if(Token::CloseParen) {
+                    bail!("expected ')'");
+                }
+                expression
+            }
+            Some(Token::Text(value)) => Expression::Text(value.to_owned()),
+            Some(Token::Symbol(value)) => Expression::Symbol(value.to_owned()),
+            Some(Token::Word(value)) if value == "true" => Expression::Boolean(true),
+            Some(Token::Word(value)) if value == "false" => Expression::Boolean(false),
+            Some(Token::Word(value)) => {
+                let value = value.to_owned();
+                if !self.next_if(Token::OpenParen) {
+                    Expression::Text(value)
+                } else if !self.next_if(Token::CloseParen) {
+                    bail!("expected ')' after function {value}");
+                } else if [FN_SUCCESS, FN_FAILURE, FN_ALWAYS].contains(&value.as_str()) {
+                    Expression::Function(value)
+                } else {
+                    bail!("unknown function {value}()");
+                }
+            }
+            Some(token) => bail!("unexpected token {token:?}"),
+            None => bail!("unexpected end of expression"),
+        };
+        Ok(expression)
+    }
+}
+
+/// The values available while evaluating an expression, which are the
+/// pipeline symbols and the status of the previous steps or jobs.
+pub struct ExpressionContext<'a> {
+    pub symbols: &'a HashMap<String, String>,
+    pub has_failed: bool,
+}
+
+impl<'a> ExpressionContext<'a> {
+    pub fn new(symbols: &'a HashMap<String, String>, has_failed: bool) -> Self {
+        Self {
+            symbols,
+            has_failed,
+        }
+    }
+
+    /// Decides if a job or step should run. When no condition is provided or
+    /// the condition doesn't use a status function, the job or step will run
+    /// only if nothing has failed before it.
+    pub fn should_run(&self, condition: Option<&str>) -> Result<bool> {
+        let Some(condition) = condition else {
+            return Ok(!self.has_failed);
+        };
+        let expression = Expression::parse(condition)?;
+        if !expression.has_status_function() && self.has_failed {
+            return Ok(false);
+        }
+        expression.evaluate(self)
+    }
+}
+
+enum Value {
+    Text(String),
+    Boolean(bool),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Self::Boolean(value) => *value,
+            Self::Text(value) => !value.is_empty() && value != "false",
+        }
+    }
+
+    fn into_text(self) -> String {
+        match self {
+            Self::Boolean(value) => value.to_string(),
+            Self::Text(value) => value,
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = Lexer::new(input).tokens()?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expression = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected token {token:?}");
+        }
+        Ok(expression)
+    }
+
+    pub fn symbols(&self) -> Vec<&str> {
+        match self {
+            Self::Symbol(value) => vec![value.as_str()],
+            Self::Text(_) | Self::Boolean(_) | Self::Function(_) => vec![],
+            Self::Not(inner) => inner.symbols(),
+            Self::And(left, right)
+            | Self::Or(left, right)
+            | Self::Equals(left, right)
+            | Self::NotEquals(left, right) => {
+                let mut symbols = left.symbols();
+                symbols.append(&mut right.symbols());
+                symbols
+            }
+        }
+    }
+
+    pub fn has_status_function(&self) -> bool {
+        match self {
+            Self::Function(_) => true,
+            Self::Text(_) | Self::Boolean(_) | Self::Symbol(_) => false,
+            Self::Not(inner) => inner.has_status_function(),
+            Self::And(left, right)
+            | Self::Or(left, right)
+            | Self::Equals(left, right)
+            | Self::NotEquals(left, right) => {
+                left.has_status_function() || right.has_status_function()
+            }
+        }
+    }
+
+    fn value(&self, context: &ExpressionContext) -> Result<Value> {
+        let value = match self {
+            Self::Text(value) => Value::Text(value.to_owned()),
+            Self::Boolean(value) => Value::Boolean(*value),
+            Self::Symbol(name) => context
+                .symbols
+                .get(name)
+                .map(|x| Value::Text(x.to_owned()))
+                .ok_or_else(|| anyhow!("symbol {name} isn't a keyword or variable"))?,
+            Self::Function(name) => match name.as_str() {
+                FN_SUCCESS => Value::Boolean(!context.has_failed),
+                FN_FAILURE => Value::Boolean(context.has_failed),
+                FN_ALWAYS => Value::Boolean(true),
+                _ => bail!("unknown function {name}()"),
+            },
+            Self::Not(inner) => Value::Boolean(!inner.value(context)?.is_truthy()),
+            Self::And(left, right) => Value::Boolean(
+                left.value(context)?.is_truthy() && right.value(context)?.is_truthy(),
+            ),
+            Self::Or(left, right) => Value::Boolean(
+                left.value(context)?.is_truthy() || right.value(context)?.is_truthy(),
+            ),
+            Self::Equals(left, right) => Value::Boolean(
+                left.value(context)?.into_text() == right.value(context)?.into_text(),
+            ),
+            Self::NotEquals(left, right) => Value::Boolean(
+                left.value(context)?.into_text() != right.value(context)?.into_text(),
+            ),
+        };
+        Ok(value)
+    }
+
+    pub fn evaluate(&self, context: &ExpressionContext) -> Result<bool> {
+        self.value(context).map(|x| x.is_truthy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols() -> HashMap<String, String> {
+        HashMap::from([
+            ("bld_branch".to_owned(), "main".to_owned()),
+            ("matrix.os".to_owned(), "linux".to_owned()),
+            ("empty".to_owned(), String::new()),
+        ])
+    }
+
+    fn evaluate(input: &str, has_failed: bool) -> bool {
+        let symbols = symbols();
+        let context = ExpressionContext::new(&symbols, has_failed);
+        Expression::parse(input)
+            .unwrap()
+            .evaluate(&context)
+            .unwrap()
+    }
+
+    fn parse_error(input: &str) -> String {
+        Expression::parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn lexer_tokens() {
+        let tokens = Lexer::new(r#"!(${{ a.b }} == 'x' || "y" != z) && w-1"#)
+            .tokens()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Not,
+                Token::OpenParen,
+                Token::Symbol("a.b".to_owned()),
+                Token::Equals,
+                Token::Text("x".to_owned()),
+                Token::Or,
+                Token::Text("y".to_owned()),
+                Token::NotEquals,
+                Token::Word("z".to_owned()),
+                Token::CloseParen,
+                Token::And,
+                Token::Word("w-1".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexer_string_escapes() {
+        let tokens = Lexer::new(r#"'it\'s' "a \"b\"" '\\'"#).tokens().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("it's".to_owned()),
+                Token::Text(r#"a "b""#.to_owned()),
+                Token::Text(r"\".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let expression = Expression::parse("a || b && c").unwrap();
+        assert!(matches!(
+            &expression,
+            Expression::Or(left, right)
+                if matches!(left.as_ref(), Expression::Text(x) if x == "a")
+                    && matches!(right.as_ref(), Expression::And(_, _))
+        ));
+
+        assert!(evaluate("true || false && false", false));
+        assert!(!evaluate("(true || false) && false", false));
+    }
+
+    #[test]
+    fn not_applies_to_the_comparison() {
+        let expression = Expression::parse("!a == b").unwrap();
+        assert!(matches!(
+            &expression,
+            Expression::Not(inner) if matches!(inner.as_ref(), Expression::Equals(_, _))
+        ));
+
+        assert!(evaluate("!'a' == 'b'", false));
+        assert!(!evaluate("!!false", false));
+    }
+
+    #[test]
+    fn operators_are_left_associative() {
+        let expression = Expression::parse("a && b && c").unwrap();
+        assert!(matches!(
+            &expression,
+            Expression::And(left, right)
+                if matches!(left.as_ref(), Expression::And(_, _))
+                    && matches!(right.as_ref(), Expression::Text(x) if x == "c")
+        ));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(evaluate("${{ bld_branch }} == 'main'", false));
+        assert!(evaluate("${{bld_branch}} == main", false));
+        assert!(evaluate("${{ matrix.os }} != 'windows'", false));
+        assert!(!evaluate("${{ matrix.os }} == 'windows'", false));
+        assert!(evaluate("true == 'true'", false));
+        assert!(evaluate(
+            "${{ bld_branch }} == 'main' && (${{ matrix.os }} == 'windows' || ${{ matrix.os }} == 'linux')",
+            false
+        ));
+    }
+
+    #[test]
+    fn truthiness() {
+        assert!(evaluate("'value'", false));
+        assert!(!evaluate("''", false));
+        assert!(!evaluate("'false'", false));
+        assert!(!evaluate("${{ empty }}", false));
+        assert!(evaluate("!${{ empty }}", false));
+    }
+
+    #[test]
+    fn symbols_and_status_functions() {
+        let expression =
+            Expression::parse("${{ a }} == 'x' || !(${{ b.c }} != ${{ a }}) && failure()").unwrap();
+        assert_eq!(expression.symbols(), vec!["a", "b.c", "a"]);
+        assert!(expression.has_status_function());
+
+        let expression = Expression::parse("${{ a }} == 'success'").unwrap();
+        assert!(!expression.has_status_function());
+    }
+
+    #[test]
+    fn status_functions() {
+        assert!(evaluate("success()", false));
+        assert!(!evaluate("success()", true));
+        assert!(!evaluate("failure()", false));
+        assert!(evaluate("failure()", true));
+        assert!(evaluate("always()", true));
+        assert!(evaluate("failure ( )", true));
+    }
+
+    #[test]
+    fn should_run() {
+        let symbols = symbols();
+        let context = ExpressionContext::new(&symbols, false);
+        assert!(context.should_run(None).unwrap());
+        assert!(context
+            .should_run(Some("${{ bld_branch }} == 'main'"))
+            .unwrap());
+        assert!(!context.should_run(Some("failure()")).unwrap());
+
+        let context = ExpressionContext::new(&symbols, true);
+        assert!(!context.should_run(None).unwrap());
+        assert!(!context
+            .should_run(Some("${{ bld_branch }} == 'main'"))
+            .unwrap());
+        assert!(context.should_run(Some("always()")).unwrap());
+        assert!(context
+            .should_run(Some("failure() && ${{ bld_branch }} == 'main'"))
+            .unwrap());
+        assert!(!context
+            .should_run(Some("failure() && ${{ bld_branch }} == 'dev'"))
+            .unwrap());
+    }
+
+    #[test]
+    fn unknown_symbol() {
+        let symbols = symbols();
+        let context = ExpressionContext::new(&symbols, false);
+        let error = Expression::parse("${{ missing }} == 'x'")
+            .unwrap()
+            .evaluate(&context)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "symbol missing isn't a keyword or variable"
+        );
+    }
+
+    #[test]
+    fn lexer_errors() {
+        assert_eq!(parse_error("'abc"), "unterminated string literal");
+        assert_eq!(parse_error(r"'abc\"), "unterminated string literal");
+        assert_eq!(parse_error("${{ a b }}"), "invalid symbol 'a b'");
+        assert_eq!(parse_error("${{ }}"), "invalid symbol ''");
+        assert_eq!(parse_error("${{ a"), "unterminated symbol expression");
+        assert_eq!(parse_error("${ a }}"), "expected '{' but found ' '");
+        assert_eq!(parse_error("a = b"), "expected '=' but found ' '");
+        assert_eq!(
+            parse_error("a &"),
+            "expected '&' but reached the end of the expression"
+        );
+        assert_eq!(parse_error("a | b"), "expected '|' but found ' '");
+        assert_eq!(parse_error("a > b"), "unexpected character '>'");
+    }
+
+    #[test]
+    fn parser_errors() {
+        assert_eq!(parse_error(""), "unexpected end of expression");
+        assert_eq!(parse_error("a &&"), "unexpected end of expression");
+        assert_eq!(parse_error("(a || b"), "expected ')'");
+        assert_eq!(parse_error("a b"), "unexpected token Word(\"b\")");
+        assert_eq!(parse_error("a == b == c"), "unexpected token Equals");
+        assert_eq!(parse_error(")"), "unexpected token CloseParen");
+        assert_eq!(parse_error("deploy()"), "unknown function deploy()");
+        assert_eq!(
+            parse_error("success(a)"),
+            "expected ')' after function success"
+        );
+    }
+}
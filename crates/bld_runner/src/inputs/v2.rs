@@ -0,0 +1,325 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::HashMap, fmt::Display, fmt::Write};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputType {
+    #[default]
+    String,
+    Bool,
+    Number,
+    Choice,
+}
+
+impl Display for InputType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String => write!(f, "string"),
+            Self::Bool => write!(f, "bool"),
+            Self::Number => write!(f, "number"),
+            Self::Choice => write!(f, "choice"),
+        }
+    }
+}
+
+/// A scalar yaml value so that defaults and options can be written
+/// as `true` or `3` instead of quoted strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum InputValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl From<InputValue> for String {
+    fn from(value: InputValue) -> Self {
+        match value {
+            InputValue::Bool(value) => value.to_string(),
+            InputValue::Integer(value) => value.to_string(),
+            InputValue::Float(value) => value.to_string(),
+            InputValue::String(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Input {
+    #[serde(rename(serialize = "type", deserialize = "type"), default)]
+    pub input_type: InputType,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, deserialize_with = "Input::deserialize_default")]
+    pub default: Option<String>,
+    #[serde(default, deserialize_with = "Input::deserialize_options")]
+    pub options: Vec<String>,
+}
+
+impl Input {
+    fn deserialize_default<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Option<InputValue> = Option::deserialize(deserializer)?;
+        Ok(value.map(String::from))
+    }
+
+    fn deserialize_options<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values: Vec<InputValue> = Vec::deserialize(deserializer)?;
+        Ok(values.into_iter().map(String::from).collect())
+    }
+
+    /// Checks that the value can be used for the type of the input.
+    pub fn check(&self, value: &str) -> Result<()> {
+        match self.input_type {
+            InputType::String => {}
+            InputType::Bool => {
+                if value != "true" && value != "false" {
+                    bail!("expected true or false but found {value:?}");
+                }
+            }
+            InputType::Number => {
+                if !value.parse::<f64>().is_ok_and(f64::is_finite) {
+                    bail!("expected a finite number but found {value:?}");
+                }
+            }
+            InputType::Choice => {
+                if !self.options.iter().any(|x| x == value) {
+                    bail!(
+                        "expected one of {} but found {value:?}",
+                        self.options.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The value used when the input isn't provided and has no default.
+    fn fallback(&self) -> &str {
+        match self.input_type {
+            InputType::Bool => "false",
+            _ => "",
+        }
+    }
+
+    /// Resolves the value of every input from the provided variables or the
+    /// defaults and reports all the missing or invalid inputs together.
+    pub fn resolve(
+        inputs: &HashMap<String, Input>,
+        variables: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let mut values = HashMap::new();
+        let mut errors = String::new();
+
+        for (name, input) in inputs.iter() {
+            let value = match variables.get(name).or(input.default.as_ref()) {
+                Some(value) => {
+                    if let Err(e) = input.check(value) {
+                        let _ = writeln!(errors, "invalid value for input {name}, {e}");
+                        continue;
+                    }
+                    value.as_str()
+                }
+                None if input.required => {
+                    let _ = writeln!(errors, "input {name} is required");
+                    continue;
+                }
+                None => input.fallback(),
+            };
+
+            values.insert(name.to_owned(), value.to_owned());
+        }
+
+        if !errors.is_empty() {
+            bail!(errors.trim_end().to_owned());
+        }
+
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(input_type: InputType) -> Input {
+        Input {
+            input_type,
+            description: None,
+            required: false,
+            default: None,
+            options: vec![],
+        }
+    }
+
+    fn choice(options: &[&str]) -> Input {
+        Input {
+            options: options.iter().map(|x| x.to_string()).collect(),
+            ..input(InputType::Choice)
+        }
+    }
+
+    fn resolve(
+        inputs: &[(&str, Input)],
+        variables: &[(&str, &str)],
+    ) -> Result<HashMap<String, String>> {
+        let inputs = inputs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        let variables = variables
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Input::resolve(&inputs, &variables)
+    }
+
+    #[test]
+    fn string_inputs_accept_any_value() {
+        let input = input(InputType::String);
+        input.check("").unwrap();
+        input.check("any value").unwrap();
+    }
+
+    #[test]
+    fn bool_inputs_accept_only_true_or_false() {
+        let input = input(InputType::Bool);
+        input.check("true").unwrap();
+        input.check("false").unwrap();
+        for value in ["True", "yes", "1", ""] {
+            assert_eq!(
+                input.check(value).unwrap_err().to_string(),
+                format!("expected true or false but found {value:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn number_inputs_accept_only_finite_numbers() {
+        let input = input(InputType::Number);
+        for value in ["3", "-2", "1.5", "1e3"] {
+            input.check(value).unwrap();
+        }
+        for value in [
+            "NaN", "nan", "inf", "-inf", "infinity", "1e400", "three", "",
+        ] {
+            assert_eq!(
+                input.check(value).unwrap_err().to_string(),
+                format!("expected a finite number but found {value:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn choice_inputs_accept_only_their_options() {
+        let input = choice(&["staging", "production"]);
+        input.check("staging").unwrap();
+        assert_eq!(
+            input.check("dev").unwrap_err().to_string(),
+            "expected one of staging, production but found \"dev\""
+        );
+    }
+
+    #[test]
+    fn inputs_are_resolved_from_variables_defaults_and_fallbacks() {
+        let with_default = |input_type, default: &str| Input {
+            default: Some(default.to_owned()),
+            ..input(input_type)
+        };
+        let inputs = [
+            ("name", input(InputType::String)),
+            ("greeting", with_default(InputType::String, "hello")),
+            ("debug", input(InputType::Bool)),
+            ("release", with_default(InputType::Bool, "true")),
+            ("count", input(InputType::Number)),
+            ("ratio", with_default(InputType::Number, "0.5")),
+            ("target", choice(&["staging", "production"])),
+        ];
+        let variables = [("name", "bld"), ("count", "3"), ("target", "production")];
+
+        let values = resolve(&inputs, &variables).unwrap();
+        let expected: HashMap<String, String> = [
+            ("name", "bld"),
+            ("greeting", "hello"),
+            ("debug", "false"),
+            ("release", "true"),
+            ("count", "3"),
+            ("ratio", "0.5"),
+            ("target", "production"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn variables_replace_the_defaults() {
+        let inputs = [(
+            "count",
+            Input {
+                default: Some("1".to_owned()),
+                ..input(InputType::Number)
+            },
+        )];
+        let values = resolve(&inputs, &[("count", "2")]).unwrap();
+        assert_eq!(values["count"], "2");
+    }
+
+    #[test]
+    fn missing_and_invalid_inputs_are_reported_together() {
+        let inputs = [
+            (
+                "name",
+                Input {
+                    required: true,
+                    ..input(InputType::String)
+                },
+            ),
+            ("debug", input(InputType::Bool)),
+            (
+                "count",
+                Input {
+                    default: Some("NaN".to_owned()),
+                    ..input(InputType::Number)
+                },
+            ),
+            ("target", choice(&["staging"])),
+        ];
+        let variables = [("debug", "yes"), ("target", "dev")];
+
+        let error = resolve(&inputs, &variables).unwrap_err().to_string();
+        let mut lines: Vec<&str> = error.lines().collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "input name is required",
+                "invalid value for input count, expected a finite number but found \"NaN\"",
+                "invalid value for input debug, expected true or false but found \"yes\"",
+                "invalid value for input target, expected one of staging but found \"dev\"",
+            ]
+        );
+    }
+
+    #[cfg(feature = "all")]
+    #[test]
+    fn non_finite_defaults_are_rejected() {
+        let pipeline: HashMap<String, Input> =
+            serde_yaml::from_str("count:\n  type: number\n  default: .nan\n").unwrap();
+        let error = Input::resolve(&pipeline, &HashMap::new())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "invalid value for input count, expected a finite number but found \"NaN\""
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{
+    matrix::v2::{Matrix, MatrixInstance},
+    runs_on::v2::RunsOn,
+    step::v2::BuildStep,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use bld_config::BldConfig;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BuildJob {
+    Steps(Vec<BuildStep>),
+    Full {
+        #[serde(default)]
+        needs: Vec<String>,
+        #[serde(rename(serialize = "if", deserialize = "if"))]
+        condition: Option<String>,
+        runs_on: Option<Box<RunsOn>>,
+        matrix: Option<Box<Matrix>>,
+        #[serde(skip)]
+        instance: Option<MatrixInstance>,
+        #[serde(default)]
+        steps: Vec<BuildStep>,
+    },
+}
+
+impl BuildJob {
+    pub fn steps(&self) -> &[BuildStep] {
+        match self {
+            Self::Steps(steps) | Self::Full { steps, .. } => steps,
+        }
+    }
+
+    pub fn needs(&self) -> &[String] {
+        match self {
+            Self::Steps(_) => &[],
+            Self::Full { needs, .. } => needs,
+        }
+    }
+
+    pub fn condition(&self) -> Option<&str> {
+        match self {
+            Self::Steps(_) => None,
+            Self::Full { condition, .. } => condition.as_deref(),
+        }
+    }
+
+    pub fn runs_on(&self) -> Option<&RunsOn> {
+        match self {
+            Self::Steps(_) => None,
+            Self::Full { runs_on, .. } => runs_on.as_deref(),
+        }
+    }
+
+    pub fn matrix(&self) -> Option<&Matrix> {
+        match self {
+            Self::Steps(_) => None,
+            Self::Full { matrix, .. } => matrix.as_deref(),
+        }
+    }
+
+    pub fn instance(&self) -> Option<&MatrixInstance> {
+        match self {
+            Self::Steps(_) => None,
+            Self::Full { instance, .. } => instance.as_ref(),
+        }
+    }
+
+    pub fn origin<'a>(&'a self, name: &'a str) -> &'a str {
+        self.instance().map(|x| x.origin.as_str()).unwrap_or(name)
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn expand<'a>(
+        self,
+        name: String,
+        context: &PipelineContext<'a>,
+    ) -> Result<Vec<(String, Self)>> {
+        let Self::Full {
+            needs,
+            condition,
+            runs_on,
+            matrix: Some(matrix),
+            steps,
+            ..
+        } = self
+        else {
+            return Ok(vec![(name, self)]);
+        };
+
+        let jobs = matrix
+            .instances(&name, context)
+            .await?
+            .into_iter()
+            .map(|instance| {
+                let job = Self::Full {
+                    needs: needs.clone(),
+                    condition: condition.clone(),
+                    runs_on: runs_on.clone(),
+                    matrix: None,
+                    instance: Some(instance.clone()),
+                    steps: steps.clone(),
+                };
+                (instance.name(), job)
+            })
+            .collect();
+
+        Ok(jobs)
+    }
+
+    #[cfg(feature = "all")]
+    pub fn local_dependencies(&self, config: &BldConfig) -> Vec<String> {
+        self.steps()
+            .iter()
+            .flat_map(|s| s.local_dependencies(config))
+            .collect()
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &PipelineContext<'a>) -> Result<()> {
+        let matrix_context = self.instance().map(|x| context.with_matrix(&x.values));
+        let context = matrix_context.as_ref().unwrap_or(context);
+        match self {
+            Self::Steps(steps) => {
+                for step in steps.iter_mut() {
+                    step.apply_tokens(context).await?;
+                }
+            }
+            Self::Full { runs_on, steps, .. } => {
+                if let Some(runs_on) = runs_on.as_mut() {
+                    runs_on.apply_tokens(context).await?;
+                }
+                for step in steps.iter_mut() {
+                    step.apply_tokens(context).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
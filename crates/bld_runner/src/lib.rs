@@ -1,10 +1,20 @@
 pub mod artifacts;
+pub mod cache;
+pub mod checkout;
 pub mod external;
+pub mod inputs;
+pub mod job;
+pub mod matrix;
 pub mod pipeline;
+pub mod publish;
 pub mod registry;
 pub mod runs_on;
+pub mod services;
 pub mod step;
 
+#[cfg(feature = "all")]
+mod expression;
+
 #[cfg(feature = "all")]
 mod runner;
 
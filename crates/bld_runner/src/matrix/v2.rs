@@ -0,0 +1,331 @@
+use bld_config::definitions::KEYWORD_MATRIX_V2;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::inputs::v2::InputValue;
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Matrix {
+    #[serde(default, deserialize_with = "Matrix::deserialize_entries")]
+    pub include: Vec<HashMap<String, String>>,
+
+    #[serde(default, deserialize_with = "Matrix::deserialize_entries")]
+    pub exclude: Vec<HashMap<String, String>>,
+
+    pub max_parallel: Option<usize>,
+
+    #[serde(flatten, deserialize_with = "Matrix::deserialize_values")]
+    pub values: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MatrixInstance {
+    pub origin: String,
+    pub values: BTreeMap<String, String>,
+    pub max_parallel: Option<usize>,
+}
+
+impl MatrixInstance {
+    pub fn name(&self) -> String {
+        let values: Vec<&str> = self.values.values().map(|x| x.as_str()).collect();
+        format!("{} ({})", self.origin, values.join(", "))
+    }
+
+    pub fn symbols(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|(k, v)| (format!("{KEYWORD_MATRIX_V2}.{k}"), v.to_owned()))
+            .collect()
+    }
+}
+
+impl Matrix {
+    fn deserialize_entries<'de, D>(
+        deserializer: D,
+    ) -> Result<Vec<HashMap<String, String>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let entries: Vec<HashMap<String, InputValue>> = Vec::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| entry.into_iter().map(|(k, v)| (k, v.into())).collect())
+            .collect())
+    }
+
+    fn deserialize_values<'de, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, Vec<String>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values: BTreeMap<String, Vec<InputValue>> = BTreeMap::deserialize(deserializer)?;
+        Ok(values
+            .into_iter()
+            .map(|(k, v)| (k, v.into_iter().map(String::from).collect()))
+            .collect())
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.values.keys().map(|x| x.as_str()).collect();
+        for entry in self.include.iter() {
+            for key in entry.keys() {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys
+    }
+
+    fn is_excluded(&self, combination: &BTreeMap<String, String>) -> bool {
+        self.exclude.iter().any(|entry| {
+            entry
+                .iter()
+                .all(|(k, v)| combination.get(k).map(|x| x == v).unwrap_or_default())
+        })
+    }
+
+    pub fn combinations(&self) -> Vec<BTreeMap<String, String>> {
+        let mut combinations = vec![];
+
+        if !self.values.is_empty() {
+            combinations.push(BTreeMap::new());
+        }
+
+        for (key, values) in self.values.iter() {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(key.to_owned(), value.to_owned());
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        combinations.retain(|x| !self.is_excluded(x));
+
+        for entry in self.include.iter() {
+            let combination: BTreeMap<String, String> = entry
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect();
+            if !combinations.contains(&combination) {
+                combinations.push(combination);
+            }
+        }
+
+        combinations
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn instances<'a>(
+        &self,
+        origin: &str,
+        context: &PipelineContext<'a>,
+    ) -> Result<Vec<MatrixInstance>> {
+        let mut instances = vec![];
+        for combination in self.combinations() {
+            let mut values = BTreeMap::new();
+            for (k, v) in combination {
+                values.insert(k, context.transform(v).await?);
+            }
+            instances.push(MatrixInstance {
+                origin: origin.to_owned(),
+                values,
+                max_parallel: self.max_parallel,
+            });
+        }
+        Ok(instances)
+    }
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use super::*;
+
+    fn matrix(yaml: &str) -> Matrix {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn combination(values: &[(&str, &str)]) -> BTreeMap<String, String> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn cartesian_product() {
+        let matrix = matrix("os: [linux, windows]\nversion: ['1', '2']");
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux"), ("version", "1")]),
+                combination(&[("os", "linux"), ("version", "2")]),
+                combination(&[("os", "windows"), ("version", "1")]),
+                combination(&[("os", "windows"), ("version", "2")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn exclude_removes_matching_combinations() {
+        let matrix = matrix(
+            r"
+os: [linux, windows, macos]
+version: ['1', '2']
+exclude:
+  - os: windows
+    version: '1'
+  - os: macos
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux"), ("version", "1")]),
+                combination(&[("os", "linux"), ("version", "2")]),
+                combination(&[("os", "windows"), ("version", "2")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn exclude_with_unknown_values_keeps_everything() {
+        let matrix = matrix(
+            r"
+os: [linux, windows]
+exclude:
+  - os: macos
+  - arch: arm
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux")]),
+                combination(&[("os", "windows")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn include_appends_new_combinations() {
+        let matrix = matrix(
+            r"
+os: [linux]
+version: ['1']
+include:
+  - os: macos
+    version: '3'
+    arch: arm
+  - os: linux
+    version: '1'
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux"), ("version", "1")]),
+                combination(&[("arch", "arm"), ("os", "macos"), ("version", "3")]),
+            ]
+        );
+        assert_eq!(matrix.keys(), vec!["os", "version", "arch"]);
+    }
+
+    #[test]
+    fn include_is_not_excluded() {
+        let matrix = matrix(
+            r"
+os: [linux, windows]
+exclude:
+  - os: windows
+include:
+  - os: windows
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux")]),
+                combination(&[("os", "windows")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_include_entries() {
+        let matrix = matrix(
+            r"
+include:
+  - os: linux
+  - os: windows
+    version: '2'
+max_parallel: 1
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("os", "linux")]),
+                combination(&[("os", "windows"), ("version", "2")]),
+            ]
+        );
+        assert_eq!(matrix.max_parallel, Some(1));
+    }
+
+    #[test]
+    fn empty_matrix() {
+        assert!(Matrix::default().combinations().is_empty());
+    }
+
+    #[test]
+    fn instance_name_and_symbols() {
+        let instance = MatrixInstance {
+            origin: "build".to_owned(),
+            values: combination(&[("os", "linux"), ("version", "2")]),
+            max_parallel: None,
+        };
+        assert_eq!(instance.name(), "build (linux, 2)");
+        assert_eq!(
+            instance.symbols(),
+            HashMap::from([
+                (format!("{KEYWORD_MATRIX_V2}.os"), "linux".to_owned()),
+                (format!("{KEYWORD_MATRIX_V2}.version"), "2".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn scalar_values() {
+        let matrix = matrix(
+            r"
+version: [1, 2.5]
+debug: [true]
+name: [app]
+exclude:
+  - version: 2.5
+include:
+  - version: 3
+    debug: false
+",
+        );
+        assert_eq!(
+            matrix.combinations(),
+            vec![
+                combination(&[("debug", "true"), ("name", "app"), ("version", "1")]),
+                combination(&[("debug", "false"), ("version", "3")]),
+            ]
+        );
+    }
+}
@@ -1,7 +1,12 @@
 use crate::artifacts::v2::Artifacts;
+use crate::cache::v2::Cache;
 use crate::external::v2::External;
+use crate::inputs::v2::Input;
+use crate::job::v2::BuildJob;
+use crate::publish::v2::Publish;
 use crate::runs_on::v2::RunsOn;
-use crate::step::v2::BuildStep;
+use crate::services::v2::Service;
+use bld_config::BldNotificationConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -17,7 +22,7 @@ use bld_config::BldConfig;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pipeline {
     pub name: Option<String>,
-    pub runs_on: RunsOn,
+    pub runs_on: Box<RunsOn>,
 
     pub cron: Option<String>,
 
@@ -30,14 +35,28 @@ pub struct Pipeline {
     #[serde(default)]
     pub variables: HashMap<String, String>,
 
+    #[serde(default)]
+    pub inputs: HashMap<String, Input>,
+
     #[serde(default)]
     pub artifacts: Vec<Artifacts>,
 
+    #[serde(default)]
+    pub cache: Vec<Cache>,
+
+    #[serde(default)]
+    pub services: HashMap<String, Service>,
+
+    #[serde(default)]
+    pub publish: Vec<Publish>,
+
     #[serde(default)]
     pub external: Vec<External>,
 
     #[serde(default)]
-    pub jobs: HashMap<String, Vec<BuildStep>>,
+    pub jobs: HashMap<String, BuildJob>,
+
+    pub notifications: Option<Vec<BldNotificationConfig>>,
 }
 
 impl Pipeline {
@@ -49,9 +68,8 @@ impl Pipeline {
     pub fn local_dependencies(&self, config: &BldConfig) -> Vec<String> {
         let from_steps = self
             .jobs
-            .iter()
-            .flat_map(|(_, steps)| steps)
-            .flat_map(|s| s.local_dependencies(config));
+            .values()
+            .flat_map(|job| job.local_dependencies(config));
 
         let from_external = self
             .external
@@ -82,8 +100,30 @@ impl Pipeline {
             entry.apply_tokens(context).await?;
         }
 
-        for step in self.jobs.iter_mut().flat_map(|(_, steps)| steps) {
-            step.apply_tokens(context).await?;
+        for entry in self.cache.iter_mut() {
+            entry.apply_tokens(context).await?;
+        }
+
+        for (_, service) in self.services.iter_mut() {
+            service.apply_tokens(context).await?;
+        }
+
+        for entry in self.publish.iter_mut() {
+            entry.apply_tokens(context).await?;
+        }
+
+        for entry in self.notifications.iter_mut().flatten() {
+            for value in entry.target.values_mut() {
+                *value = context.transform(value.to_owned()).await?;
+            }
+        }
+
+        let jobs = std::mem::take(&mut self.jobs);
+        for (name, job) in jobs.into_iter() {
+            for (name, mut job) in job.expand(name, context).await? {
+                job.apply_tokens(context).await?;
+                self.jobs.insert(name, job);
+            }
         }
 
         Ok(())
@@ -1,5 +1,6 @@
 use super::v1;
 use super::v2;
+use crate::inputs::v2::Input;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -138,6 +139,14 @@ impl VersionedPipeline {
         }
     }
 
+    /// Checks that the variables of a run satisfy the inputs of the pipeline.
+    pub fn validate_inputs(&self, variables: &HashMap<String, String>) -> anyhow::Result<()> {
+        if let Self::Version2(pip) = self {
+            Input::resolve(&pip.inputs, variables)?;
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "all")]
     pub async fn validate_with_verbose_errors(
         &self,
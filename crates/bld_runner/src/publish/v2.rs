@@ -0,0 +1,105 @@
+use crate::registry::v2::Registry;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Publish {
+    pub image: String,
+    pub repository: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub registry: Option<Registry>,
+    pub docker_url: Option<String>,
+    pub after: Option<String>,
+}
+
+impl Publish {
+    /// Checks if the image should be published once a job completes, an entry
+    /// without a job is published once all jobs of the run have completed.
+    pub fn is_after(&self, job: Option<&str>) -> bool {
+        self.after.as_deref() == job
+    }
+
+    /// The tags that will be pushed, defaulting to the tag of the local image.
+    pub fn tags(&self) -> Vec<&str> {
+        if !self.tags.is_empty() {
+            return self.tags.iter().map(|x| x.as_str()).collect();
+        }
+        let name = self.image.rsplit('/').next().unwrap_or_default();
+        let tag = name.split_once(':').map(|(_, tag)| tag).unwrap_or("latest");
+        vec![tag]
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &'a PipelineContext<'a>) -> Result<()> {
+        self.image = context.transform(self.image.to_owned()).await?;
+        self.repository = context.transform(self.repository.to_owned()).await?;
+
+        for tag in self.tags.iter_mut() {
+            *tag = context.transform(tag.to_owned()).await?;
+        }
+
+        if let Some(registry) = self.registry.as_mut() {
+            registry.apply_tokens(context).await?;
+        }
+
+        if let Some(docker_url) = self.docker_url.as_mut() {
+            *docker_url = context.transform(docker_url.to_owned()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish(image: &str, tags: &[&str], after: Option<&str>) -> Publish {
+        Publish {
+            image: image.to_owned(),
+            repository: "registry.example.com/app".to_owned(),
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            registry: None,
+            docker_url: None,
+            after: after.map(|x| x.to_owned()),
+        }
+    }
+
+    #[test]
+    fn tags_default_to_the_tag_of_the_local_image() {
+        assert_eq!(publish("app", &[], None).tags(), vec!["latest"]);
+        assert_eq!(publish("app:1.0", &[], None).tags(), vec!["1.0"]);
+        assert_eq!(
+            publish("localhost:5000/app", &[], None).tags(),
+            vec!["latest"]
+        );
+        assert_eq!(
+            publish("localhost:5000/team/app:nightly", &[], None).tags(),
+            vec!["nightly"]
+        );
+    }
+
+    #[test]
+    fn explicit_tags_replace_the_tag_of_the_local_image() {
+        let entry = publish("app:1.0", &["1.0.1", "stable"], None);
+        assert_eq!(entry.tags(), vec!["1.0.1", "stable"]);
+    }
+
+    #[test]
+    fn entries_are_published_after_their_job() {
+        let entry = publish("app", &[], Some("build"));
+        assert!(entry.is_after(Some("build")));
+        assert!(!entry.is_after(Some("test")));
+        assert!(!entry.is_after(None));
+
+        let entry = publish("app", &[], None);
+        assert!(entry.is_after(None));
+        assert!(!entry.is_after(Some("build")));
+    }
+}
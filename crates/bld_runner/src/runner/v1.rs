@@ -340,7 +340,7 @@ impl Runner {
 
         debug!("executing shell command {}", command);
         self.platform
-            .shell(self.logger.clone(), &working_dir, &command)
+            .shell(self.logger.clone(), &working_dir, &command, None)
             .await?;
 
         Ok(())
@@ -1,41 +1,367 @@
-use std::{collections::HashMap, fmt::Write, pin::Pin, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 
-use actix::{clock::sleep, io::SinkWrite, spawn, Actor, StreamHandler};
+use actix::{
+    clock::{sleep, timeout as with_timeout, Instant},
+    io::SinkWrite,
+    spawn, Actor, StreamHandler,
+};
 use anyhow::{anyhow, bail, Result};
 use bld_config::{
-    definitions::{GET, PUSH},
-    BldConfig, SshUserAuth,
+    definitions::{GET, KEYWORD_CHECKOUT_COMMIT_V2, PUSH},
+    path, BldConfig, SshConfig, SshUserAuth,
 };
 use bld_core::{
+    cache::CacheStore,
     context::Context,
     fs::FileSystem,
     logger::Logger,
     platform::{
         builder::{PlatformBuilder, PlatformOptions},
-        Image, Platform, SshAuthOptions, SshConnectOptions,
+        docker, ContainerHostOptions, ContainerMountOptions, ExitCodeError, Image,
+        ImageBuildOptions, Platform, PushImage, ServiceHealthCheckOptions, ServiceOptions,
+        SshAuthOptions, SshConnectOptions, SshHostKeyOptions,
     },
     regex::RegexCache,
     signals::{UnixSignal, UnixSignalMessage, UnixSignalsBackend},
 };
 use bld_http::WebSocket;
-use bld_models::dtos::{ExecClientMessage, WorkerMessages};
+use bld_models::{
+    dtos::{ExecClientMessage, WorkerMessages},
+    pipeline_run_jobs::{
+        PRJ_STATE_FAULTED, PRJ_STATE_FINISHED, PRJ_STATE_RUNNING, PRJ_STATE_SKIPPED,
+    },
+    pipeline_run_steps::{
+        PRS_STATE_FAULTED, PRS_STATE_FINISHED, PRS_STATE_RUNNING, PRS_STATE_SKIPPED,
+    },
+};
 use bld_sock::ExecClient;
 use bld_utils::sync::IntoArc;
 use futures::{Future, StreamExt};
-use tokio::{sync::mpsc::Sender, task::JoinHandle};
+use regex::Regex;
+use tokio::{
+    fs::{create_dir_all, remove_file},
+    sync::{mpsc::Sender, RwLock},
+    task::JoinHandle,
+};
 use tracing::debug;
+use walkdir::WalkDir;
 
 use crate::{
+    cache::v2::Cache,
+    checkout::v2::Checkout,
+    expression::v2::ExpressionContext,
     external::v2::External,
+    job::v2::BuildJob,
     pipeline::v2::Pipeline,
+    publish::v2::Publish,
     registry::v2::Registry,
-    runs_on::v2::RunsOn,
+    runs_on::v2::{ContainerSettings, PullPolicy, RunsOn},
+    services::v2::Service,
     step::v2::{BuildStep, BuildStepExec},
     RunnerBuilder,
 };
 
 type RecursiveFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
 
+fn container_host_options(settings: &ContainerSettings) -> Result<ContainerHostOptions<'_>> {
+    let mounts = settings
+        .mounts
+        .iter()
+        .flatten()
+        .map(|x| ContainerMountOptions {
+            source: x.source.as_deref(),
+            target: &x.target,
+            kind: x.kind(),
+            read_only: x.read_only.unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(ContainerHostOptions {
+        volumes: settings
+            .volumes
+            .iter()
+            .flatten()
+            .map(|x| x.as_str())
+            .collect(),
+        mounts,
+        network: settings.network.as_deref(),
+        user: settings.user.as_deref(),
+        privileged: settings.privileged.unwrap_or_default(),
+        nano_cpus: settings.nano_cpus(),
+        memory: settings.memory_in_bytes()?,
+        extra_hosts: settings
+            .extra_hosts
+            .iter()
+            .flatten()
+            .map(|x| x.as_str())
+            .collect(),
+    })
+}
+
+fn pull_image<'a>(
+    config: &'a BldConfig,
+    image: &'a str,
+    policy: PullPolicy,
+    registry: Option<&'a Registry>,
+) -> Image<'a> {
+    let registry = match registry {
+        Some(Registry::FromConfig(value)) => config.registry(value),
+        Some(Registry::Full(config)) => Some(config),
+        None => None,
+    };
+    match policy {
+        PullPolicy::Always => Image::pull(image, registry),
+        PullPolicy::IfNotPresent => Image::pull_if_not_present(image, registry),
+        PullPolicy::Never => Image::Use(image),
+    }
+}
+
+fn service_options<'a>(
+    config: &'a BldConfig,
+    services: &'a HashMap<String, Service>,
+) -> Vec<ServiceOptions<'a>> {
+    services
+        .iter()
+        .map(|(name, service)| {
+            let policy = PullPolicy::resolve(service.pull_policy, service.pull);
+            let image = pull_image(config, &service.image, policy, service.registry.as_ref());
+
+            let health_check = service
+                .health_check
+                .as_ref()
+                .map(|x| ServiceHealthCheckOptions {
+                    command: &x.command,
+                    interval: Duration::from_secs(x.interval()),
+                    timeout: Duration::from_secs(x.timeout()),
+                    retries: x.retries(),
+                });
+
+            ServiceOptions {
+                name,
+                image,
+                env: service
+                    .environment
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect(),
+                command: service
+                    .command
+                    .as_ref()
+                    .map(|x| x.iter().map(|x| x.as_str()).collect()),
+                ports: service.ports.iter().map(|x| x.as_str()).collect(),
+                health_check,
+            }
+        })
+        .collect()
+}
+
+fn platform_options<'a>(config: &'a BldConfig, runs_on: &'a RunsOn) -> Result<PlatformOptions<'a>> {
+    let options = match runs_on {
+        RunsOn::ContainerOrMachine(image) if image == "machine" => PlatformOptions::Machine,
+
+        RunsOn::ContainerOrMachine(image) => PlatformOptions::Container {
+            image: Image::Use(image),
+            docker_url: None,
+            host: ContainerHostOptions::default(),
+        },
+
+        RunsOn::Kubernetes(settings) => PlatformOptions::Kubernetes {
+            name: &settings.kubernetes,
+            image: &settings.image,
+            namespace: settings.namespace.as_deref(),
+            requests: settings
+                .resources
+                .as_ref()
+                .and_then(|x| x.requests.as_ref()),
+            limits: settings.resources.as_ref().and_then(|x| x.limits.as_ref()),
+            node_selector: settings.node_selector.as_ref(),
+            service_account: settings.service_account.as_deref(),
+        },
+
+        RunsOn::Pull {
+            image,
+            pull,
+            pull_policy,
+            docker_url,
+            registry,
+            settings,
+        } => {
+            let policy = PullPolicy::resolve(*pull_policy, *pull);
+            let image = pull_image(config, image, policy, registry.as_ref());
+            PlatformOptions::Container {
+                docker_url: docker_url.as_deref(),
+                image,
+                host: container_host_options(settings)?,
+            }
+        }
+
+        RunsOn::Build {
+            name,
+            tag,
+            dockerfile,
+            context,
+            build_args,
+            target,
+            no_cache,
+            labels,
+            docker_url,
+            settings,
+        } => PlatformOptions::Container {
+            image: Image::build(
+                name,
+                dockerfile,
+                tag,
+                ImageBuildOptions {
+                    context: context.as_deref(),
+                    build_args: build_args.as_ref(),
+                    target: target.as_deref(),
+                    no_cache: no_cache.unwrap_or_default(),
+                    labels: labels.as_ref(),
+                },
+            ),
+            docker_url: docker_url.as_deref(),
+            host: container_host_options(settings)?,
+        },
+
+        RunsOn::SshFromGlobalConfig { ssh_config } => {
+            let config = config.ssh(ssh_config)?;
+            let port = config.port.parse::<u16>()?;
+            let auth = match &config.userauth {
+                SshUserAuth::Agent => SshAuthOptions::Agent,
+                SshUserAuth::Password { password } => SshAuthOptions::Password { password },
+                SshUserAuth::Keys {
+                    public_key,
+                    private_key,
+                } => SshAuthOptions::Keys {
+                    public_key: public_key.as_deref(),
+                    private_key,
+                },
+            };
+            PlatformOptions::Ssh(SshConnectOptions::new(
+                &config.host,
+                port,
+                &config.user,
+                auth,
+                ssh_host_key_options(config),
+            ))
+        }
+
+        RunsOn::Ssh(config) => {
+            let port = config.port.parse::<u16>()?;
+            let auth = match &config.userauth {
+                SshUserAuth::Agent => SshAuthOptions::Agent,
+                SshUserAuth::Password { password } => SshAuthOptions::Password { password },
+                SshUserAuth::Keys {
+                    public_key,
+                    private_key,
+                } => SshAuthOptions::Keys {
+                    public_key: public_key.as_deref(),
+                    private_key,
+                },
+            };
+            PlatformOptions::Ssh(SshConnectOptions::new(
+                &config.host,
+                port,
+                &config.user,
+                auth,
+                ssh_host_key_options(config),
+            ))
+        }
+    };
+    Ok(options)
+}
+
+fn ssh_host_key_options(config: &SshConfig) -> SshHostKeyOptions<'_> {
+    match config.fingerprint.as_deref() {
+        Some(fingerprint) => SshHostKeyOptions::Fingerprint(fingerprint),
+        None => SshHostKeyOptions::KnownHosts {
+            path: config.known_hosts.as_deref(),
+            trust_on_first_use: config.trust_on_first_use,
+        },
+    }
+}
+
+/// Pushes the images of the publish entries that should be published after the
+/// provided job, or after all jobs when no job is provided.
+async fn publish_images(
+    config: &BldConfig,
+    logger: &Logger,
+    entries: &[Publish],
+    job: Option<&str>,
+) -> Result<()> {
+    for entry in entries.iter().filter(|x| x.is_after(job)) {
+        let registry = match entry.registry.as_ref() {
+            Some(Registry::FromConfig(value)) => Some(
+                config
+                    .registry(value)
+                    .ok_or_else(|| anyhow!("registry {value} not found in the config file"))?,
+            ),
+            Some(Registry::Full(config)) => Some(config),
+            None => None,
+        };
+
+        debug!("publishing image {} to {}", entry.image, entry.repository);
+        let client = docker(config, entry.docker_url.as_deref())?;
+        PushImage::new(&entry.image, &entry.repository, entry.tags(), registry)
+            .push(&client, logger)
+            .await
+            .map_err(|e| anyhow!("unable to publish image {}, {e}", entry.image))?;
+    }
+
+    Ok(())
+}
+
+async fn create_platform(
+    run_id: &str,
+    config: Arc<BldConfig>,
+    pipeline: &Pipeline,
+    runs_on: &RunsOn,
+    env: Arc<HashMap<String, String>>,
+    logger: Arc<Logger>,
+    context: &Context,
+) -> Result<Arc<Platform>> {
+    let options = platform_options(&config, runs_on)?;
+    let conn = context.get_conn();
+    let platform = PlatformBuilder::default()
+        .run_id(run_id)
+        .config(config.clone())
+        .options(options)
+        .pipeline_environment(&pipeline.environment)
+        .environment(env)
+        .logger(logger)
+        .conn(conn)
+        .services(service_options(&config, &pipeline.services))
+        .build()
+        .await?;
+
+    context.add_platform(platform.clone()).await?;
+    Ok(platform)
+}
+
+async fn dispose_platform(
+    context: &Context,
+    platform: &Platform,
+    dispose: bool,
+    is_child: bool,
+) -> Result<()> {
+    if dispose {
+        debug!("executing dispose operations for platform");
+        platform.dispose(is_child).await?;
+    } else {
+        debug!("keeping platform alive");
+        platform.keep_alive().await?;
+    }
+
+    context.remove_platform(platform.id()).await
+}
+
 struct Job {
     pub job_name: String,
     pub run_id: String,
@@ -46,56 +372,332 @@ struct Job {
     pub pipeline: Arc<Pipeline>,
     pub context: Arc<Context>,
     pub platform: Option<Arc<Platform>>,
+    pub env: Arc<HashMap<String, String>>,
+    pub symbols: Arc<HashMap<String, String>>,
+    pub is_child: bool,
+    pub job_id: Option<String>,
+    pub checkout_commit: RwLock<Option<String>>,
+    pub regex_cache: Arc<RegexCache>,
 }
 
 impl Job {
-    pub async fn run(self) -> Result<Self> {
-        let (_, steps) = self
-            .pipeline
+    pub async fn run(mut self) -> Result<Self> {
+        self.job_id = self
+            .context
+            .add_job(self.job_name.clone(), PRJ_STATE_RUNNING)
+            .await?;
+
+        let result = self.execute().await;
+
+        if let Some(id) = self.job_id.clone() {
+            let state = if result.is_ok() {
+                PRJ_STATE_FINISHED
+            } else {
+                PRJ_STATE_FAULTED
+            };
+            self.context.set_job_state(id, state).await?;
+        }
+
+        result.map(|_| self)
+    }
+
+    async fn execute(&mut self) -> Result<()> {
+        let pipeline = self.pipeline.clone();
+        let job = pipeline
             .jobs
-            .iter()
-            .find(|(name, _)| **name == self.job_name)
+            .get(&self.job_name)
             .ok_or_else(|| anyhow!("unable to find job with name {}", self.job_name))?;
 
+        let Some(runs_on) = job.runs_on() else {
+            return self.steps(job).await;
+        };
+
+        self.logger
+            .write_line(format!("{:<15}: {runs_on}", "Runs on"))
+            .await?;
+
+        let platform = create_platform(
+            &self.run_id,
+            self.config.clone(),
+            &self.pipeline,
+            runs_on,
+            self.env.clone(),
+            self.logger.clone(),
+            &self.context,
+        )
+        .await?;
+        self.platform = Some(platform.clone());
+
+        // the result of the steps is returned over an error while disposing the platform
+        // since it's the one that explains why the job failed.
+        let result = self.steps(job).await;
+        let disposed =
+            dispose_platform(&self.context, &platform, pipeline.dispose, self.is_child).await;
+        match (result, disposed) {
+            (Err(e), Err(dispose_error)) => {
+                self.logger
+                    .error_line(format!(
+                        "unable to dispose the platform of the job, {dispose_error}"
+                    ))
+                    .await?;
+                Err(e)
+            }
+            (result, disposed) => result.and(disposed),
+        }
+    }
+
+    async fn steps(&self, job: &BuildJob) -> Result<()> {
         self.artifacts(None).await?;
+        let restored = self.restore_cache(job).await?;
 
         debug!("starting execution of pipeline steps");
-        for step in steps.iter() {
-            self.step(step).await?;
+        let mut result = Ok(());
+        for (position, step) in (0..).zip(job.steps().iter()) {
+            let context = ExpressionContext::new(&self.symbols, result.is_err());
+            if !context.should_run(step.condition())? {
+                self.skipped_step(step, position).await?;
+                continue;
+            }
+            if let Err(e) = self.step(step, position).await {
+                result = result.and(Err(e));
+            }
         }
+        result?;
 
-        self.artifacts(Some(&self.job_name)).await?;
+        self.save_cache(job, &restored).await?;
+        self.artifacts(Some(job.origin(&self.job_name))).await
+    }
 
-        Ok(self)
+    fn cache_entries<'a>(&'a self, job: &'a BuildJob) -> impl Iterator<Item = &'a Cache> {
+        let origin = job.origin(&self.job_name);
+        self.pipeline.cache.iter().filter(move |x| x.is_for(origin))
     }
 
-    async fn exec(&self, exec: &BuildStepExec, working_dir: &Option<String>) -> Result<()> {
-        match exec {
-            BuildStepExec::Shell(cmd) => self.shell(working_dir, cmd).await,
-            BuildStepExec::External { value } => self.external(value).await,
+    /// Restores the cache entries of the job and returns the keys that were restored.
+    async fn restore_cache(&self, job: &BuildJob) -> Result<HashSet<String>> {
+        let Some(platform) = self.platform.as_ref() else {
+            bail!("no platform instance for runner");
+        };
+
+        let mut restored = HashSet::new();
+        let store = CacheStore::new(&self.config);
+        for entry in self.cache_entries(job) {
+            let Some(archive) = store.find(&entry.key).await? else {
+                self.logger
+                    .write_line(format!("{:<15}: {}", "Cache miss", entry.key))
+                    .await?;
+                continue;
+            };
+
+            debug!("restoring cache archive {}", archive.display());
+            let result = platform.restore_cache(self.logger.clone(), &archive).await;
+
+            // a cache that can't be restored shouldn't fail the job since the
+            // steps are expected to produce the same paths without it.
+            match result {
+                Ok(_) => {
+                    restored.insert(entry.key.to_owned());
+                    self.logger
+                        .write_line(format!("{:<15}: {}", "Cache restored", entry.key))
+                        .await?
+                }
+                Err(e) => {
+                    self.logger
+                        .error_line(format!("unable to restore cache {}, {e}", entry.key))
+                        .await?
+                }
+            }
         }
+
+        Ok(restored)
     }
 
-    async fn step(&self, step: &BuildStep) -> Result<()> {
+    /// Saves the cache entries of the job, the entries whose key was restored are
+    /// skipped since the archive of the key already exists.
+    async fn save_cache(&self, job: &BuildJob, restored: &HashSet<String>) -> Result<()> {
+        let Some(platform) = self.platform.as_ref() else {
+            bail!("no platform instance for runner");
+        };
+
+        let store = CacheStore::new(&self.config);
+        for entry in self.cache_entries(job) {
+            if restored.contains(&entry.key) {
+                debug!("skipping save of restored cache {}", entry.key);
+                continue;
+            }
+
+            let staging = store.staging_path().await?;
+
+            debug!("saving cache archive {}", staging.display());
+            let result = platform
+                .save_cache(self.logger.clone(), &entry.paths, &staging)
+                .await;
+
+            let result = match result {
+                Ok(_) => store.commit(&staging, &entry.key).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(_) => {
+                    self.logger
+                        .write_line(format!("{:<15}: {}", "Cache saved", entry.key))
+                        .await?
+                }
+                Err(e) => {
+                    if staging.is_file() {
+                        remove_file(&staging).await?;
+                    }
+                    self.logger
+                        .error_line(format!("unable to save cache {}, {e}", entry.key))
+                        .await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_step(
+        &self,
+        step: &BuildStep,
+        position: i32,
+        state: &str,
+    ) -> Result<Option<String>> {
+        let Some(job_id) = self.job_id.clone() else {
+            return Ok(None);
+        };
+        let name = step.name().map(|x| x.to_owned());
+        self.context.add_step(job_id, name, position, state).await
+    }
+
+    async fn skipped_step(&self, step: &BuildStep, position: i32) -> Result<()> {
+        self.add_step(step, position, PRS_STATE_SKIPPED).await?;
+        let BuildStep::Many {
+            name: Some(name), ..
+        } = step
+        else {
+            return Ok(());
+        };
+        let mut message = String::new();
+        writeln!(message, "{:<15}: {name}", "Skipped step")?;
+        self.logger.write_line(message).await
+    }
+
+    async fn exec(
+        &self,
+        exec: &BuildStepExec,
+        working_dir: &Option<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        match (exec, timeout) {
+            (BuildStepExec::Shell(cmd), _) => self.shell(working_dir, cmd, timeout).await,
+            (BuildStepExec::External { value }, Some(duration)) => {
+                with_timeout(duration, self.external(value))
+                    .await
+                    .map_err(|_| anyhow!("external pipeline timed out"))?
+            }
+            (BuildStepExec::External { value }, None) => self.external(value).await,
+            (BuildStepExec::Checkout { settings }, _) => {
+                self.checkout(working_dir, settings, timeout).await
+            }
+        }
+    }
+
+    async fn exec_all(
+        &self,
+        exec: &[BuildStepExec],
+        working_dir: &Option<String>,
+        timeout: Option<u64>,
+    ) -> Result<()> {
+        let deadline = timeout.map(|x| Instant::now() + Duration::from_secs(x));
+        for exec in exec.iter() {
+            let remaining = deadline.map(|x| x.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|x| x.is_zero()) {
+                bail!("step timed out after {}s", timeout.unwrap_or_default());
+            }
+            self.exec(exec, working_dir, remaining).await?;
+        }
+        Ok(())
+    }
+
+    async fn step(&self, step: &BuildStep, position: i32) -> Result<()> {
+        let step_id = self.add_step(step, position, PRS_STATE_RUNNING).await?;
+
+        let job_name = Some(self.job_name.clone());
+        let name = step.name().map(|x| x.to_owned());
+        self.logger.set_scope(job_name.clone(), name).await?;
+        let result = self.step_inner(step).await;
+        self.logger.set_scope(job_name, None).await?;
+
+        if let Some(id) = step_id {
+            let (state, exit_code) = match &result {
+                Ok(outcome) => outcome.state(),
+                Err(e) => (PRS_STATE_FAULTED, StepOutcome::exit_code(e)),
+            };
+            self.context.set_step_state(id, state, exit_code).await?;
+        }
+
+        result.map(|_| ())
+    }
+
+    async fn step_inner(&self, step: &BuildStep) -> Result<StepOutcome> {
+        let mut outcome = StepOutcome::Succeeded;
         match step {
-            BuildStep::One(exec) => self.exec(exec, &None).await?,
+            BuildStep::One(exec) => self.exec(exec, &None, None).await?,
             BuildStep::Many {
                 name,
                 working_dir,
                 exec,
+                retries,
+                retry_delay,
+                timeout,
+                continue_on_error,
+                ..
             } => {
                 if let Some(name) = name {
                     let mut message = String::new();
                     writeln!(message, "{:<15}: {name}", "Step")?;
                     self.logger.write_line(message).await?;
                 }
-                for exec in exec.iter() {
-                    self.exec(exec, working_dir).await?
+
+                let attempts = retries.unwrap_or_default() + 1;
+                let mut attempt = 1;
+                let result = loop {
+                    let result = self.exec_all(exec, working_dir, *timeout).await;
+                    if result.is_ok() || attempt >= attempts {
+                        break result;
+                    }
+                    attempt += 1;
+                    if let Err(e) = result {
+                        self.logger.error_line(e.to_string()).await?;
+                    }
+                    self.logger
+                        .write_line(format!(
+                            "{:<15}: attempt {attempt} of {attempts}",
+                            "Retrying step"
+                        ))
+                        .await?;
+                    sleep(Duration::from_secs(retry_delay.unwrap_or_default())).await;
+                };
+
+                match result {
+                    Err(e) if continue_on_error.unwrap_or_default() => {
+                        self.logger.error_line(e.to_string()).await?;
+                        self.logger
+                            .write_line(format!("{:<15}: continuing on error", "Step failed"))
+                            .await?;
+                        outcome = StepOutcome::FailedAndContinued {
+                            exit_code: StepOutcome::exit_code(&e),
+                        };
+                    }
+                    result => result?,
                 }
+
                 self.artifacts(name.as_ref().map(|x| x.as_str())).await?;
             }
         }
-        Ok(())
+        Ok(outcome)
     }
 
     async fn artifacts(&self, name: Option<&str>) -> Result<()> {
@@ -114,23 +716,35 @@ impl Job {
             let can_continue = artifact.method == *PUSH || artifact.method == *GET;
 
             if can_continue {
-                self.logger
-                    .write_line(format!(
-                        "Copying artifacts from: {} into container to: {}",
-                        artifact.from, artifact.to
-                    ))
-                    .await?;
-
-                let result = match &artifact.method[..] {
-                    PUSH => {
+                let result = match (&artifact.method[..], artifact.to.as_deref()) {
+                    (PUSH, Some(to)) => {
+                        self.logger
+                            .write_line(format!(
+                                "Copying artifacts from: {} into container to: {to}",
+                                artifact.from
+                            ))
+                            .await?;
                         debug!("executing {PUSH} artifact operation");
-                        platform.push(&artifact.from, &artifact.to).await
+                        platform.push(&artifact.from, to).await
                     }
-                    GET => {
+                    (GET, Some(to)) => {
+                        self.logger
+                            .write_line(format!(
+                                "Copying artifacts from: {} into container to: {to}",
+                                artifact.from
+                            ))
+                            .await?;
                         debug!("executing {GET} artifact operation");
-                        platform.get(&artifact.from, &artifact.to).await
+                        platform.get(&artifact.from, to).await
                     }
-                    _ => unreachable!(),
+                    (GET, None) => {
+                        self.logger
+                            .write_line(format!("Storing artifacts from: {}", artifact.from))
+                            .await?;
+                        debug!("executing {GET} artifact operation to the artifacts directory");
+                        self.store_artifacts(platform, &artifact.from).await
+                    }
+                    _ => Err(anyhow!("no destination set for {PUSH} artifact operation")),
                 };
 
                 if !artifact.ignore_errors.unwrap_or_default() {
@@ -142,6 +756,40 @@ impl Job {
         Ok(())
     }
 
+    /// Copies artifacts of the platform into the artifacts directory of the run and
+    /// records every stored file in the execution context.
+    async fn store_artifacts(&self, platform: &Platform, from: &str) -> Result<()> {
+        let dir = self.config.artifacts_full_path(&self.run_id);
+        create_dir_all(&dir).await?;
+
+        let dir_path = dir
+            .to_str()
+            .ok_or_else(|| anyhow!("unable to construct artifacts directory path"))?;
+        platform.get_into_dir(from, dir_path).await?;
+
+        let name = Path::new(from)
+            .file_name()
+            .ok_or_else(|| anyhow!("unable to retrieve file name for path {from}"))?;
+
+        for entry in WalkDir::new(path![&dir, name]) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(&dir)?
+                .components()
+                .filter_map(|x| x.as_os_str().to_str())
+                .collect::<Vec<&str>>()
+                .join("/");
+            let size = entry.metadata()?.len();
+            self.context.add_artifact(name, size as i64).await?;
+        }
+
+        Ok(())
+    }
+
     async fn external(&self, value: &str) -> Result<()> {
         debug!("starting execution of external section {value}");
 
@@ -234,19 +882,151 @@ impl Job {
         Ok(())
     }
 
-    async fn shell(&self, working_dir: &Option<String>, command: &str) -> Result<()> {
+    async fn shell(
+        &self,
+        working_dir: &Option<String>,
+        command: &str,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
         debug!("start execution of exec section for step");
         let Some(platform) = self.platform.as_ref() else {
             bail!("no platform instance for runner");
         };
 
+        let command = self.checkout_commit_transform(command).await?;
+
         debug!("executing shell command {}", command);
         platform
-            .shell(self.logger.clone(), working_dir, command)
+            .shell(self.logger.clone(), working_dir, &command, timeout)
             .await?;
 
         Ok(())
     }
+
+    /// Replaces the checkout commit keyword with the commit of the latest checkout
+    /// step, since it's only known after the step has been executed.
+    async fn checkout_commit_transform(&self, command: &str) -> Result<String> {
+        let pattern = format!(
+            r"\$\{{\{{\s*{}\s*\}}\}}",
+            regex::escape(KEYWORD_CHECKOUT_COMMIT_V2)
+        );
+        let re = match self.regex_cache.get(pattern.clone()).await? {
+            Some(re) => re,
+            None => {
+                let re = Regex::new(&pattern)?.into_arc();
+                self.regex_cache.set(pattern, re.clone()).await?;
+                re
+            }
+        };
+        if !re.is_match(command) {
+            return Ok(command.to_owned());
+        }
+
+        let commit = self.checkout_commit.read().await;
+        let Some(commit) = commit.as_deref() else {
+            bail!("{KEYWORD_CHECKOUT_COMMIT_V2} used before a checkout step");
+        };
+        Ok(re.replace_all(command, commit).to_string())
+    }
+
+    async fn checkout(
+        &self,
+        working_dir: &Option<String>,
+        checkout: &Checkout,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        let Some(platform) = self.platform.as_ref() else {
+            bail!("no platform instance for runner");
+        };
+
+        self.logger
+            .write_line(format!("{:<15}: {}", "Checkout", checkout.repository))
+            .await?;
+
+        let basic_auth = match checkout.credentials.as_deref() {
+            Some(name) => {
+                let credentials = self.config.git(name)?;
+                let basic_auth = Checkout::basic_auth(credentials);
+                self.logger
+                    .add_secrets(vec![credentials.password.to_owned(), basic_auth.to_owned()])
+                    .await?;
+                Some(basic_auth)
+            }
+            None => None,
+        };
+
+        let env = checkout.env(basic_auth.as_deref());
+        platform
+            .shell_with_env(
+                self.logger.clone(),
+                working_dir,
+                &checkout.command(),
+                &env,
+                timeout,
+            )
+            .await?;
+
+        let output = Logger::in_memory().into_arc();
+        platform
+            .shell(
+                output.clone(),
+                working_dir,
+                &checkout.commit_command(),
+                None,
+            )
+            .await?;
+        let commit = output.try_retrieve_output().await?.trim().to_owned();
+
+        self.logger
+            .write_line(format!("{:<15}: {commit}", "Commit"))
+            .await?;
+        *self.checkout_commit.write().await = Some(commit);
+
+        Ok(())
+    }
+}
+
+/// The outcome of a job that has completed or was skipped. A job that was skipped
+/// because one of its dependencies failed carries the failure over to its own
+/// dependents, while a job skipped by its condition doesn't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobOutcome {
+    Succeeded,
+    Failed,
+    Skipped { after_failure: bool },
+}
+
+impl JobOutcome {
+    fn has_failed(&self) -> bool {
+        matches!(
+            self,
+            Self::Failed
+                | Self::Skipped {
+                    after_failure: true
+                }
+        )
+    }
+}
+
+/// The outcome of a step that didn't stop its job. A step that failed with
+/// continue_on_error is stored as faulted even though the job keeps running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepOutcome {
+    Succeeded,
+    FailedAndContinued { exit_code: Option<i64> },
+}
+
+impl StepOutcome {
+    fn exit_code(error: &anyhow::Error) -> Option<i64> {
+        error.downcast_ref::<ExitCodeError>().map(|x| x.0)
+    }
+
+    fn state(&self) -> (&'static str, Option<i64>) {
+        match self {
+            Self::Succeeded => (PRS_STATE_FINISHED, Some(0)),
+            Self::FailedAndContinued { exit_code } => (PRS_STATE_FAULTED, *exit_code),
+        }
+    }
 }
 
 struct RunningJob {
@@ -278,6 +1058,8 @@ pub struct Runner {
     pub env: Arc<HashMap<String, String>>,
     pub context: Arc<Context>,
     pub platform: Option<Arc<Platform>>,
+    pub symbols: Arc<HashMap<String, String>>,
+    pub secrets: Arc<Vec<String>>,
     pub is_child: bool,
     pub has_faulted: bool,
 }
@@ -285,6 +1067,11 @@ pub struct Runner {
 impl Runner {
     async fn register_start(&self) -> Result<()> {
         if !self.is_child {
+            if let Some(notifications) = self.pipeline.notifications.as_ref() {
+                self.context
+                    .set_notifications(notifications.clone())
+                    .await?;
+            }
             debug!("setting the pipeline as running in the execution context");
             self.context
                 .set_pipeline_as_running(self.run_id.to_owned())
@@ -310,121 +1097,44 @@ impl Runner {
     }
 
     async fn create_platform(&mut self) -> Result<()> {
-        let options = match &self.pipeline.runs_on {
-            RunsOn::ContainerOrMachine(image) if image == "machine" => PlatformOptions::Machine,
-
-            RunsOn::ContainerOrMachine(image) => PlatformOptions::Container {
-                image: Image::Use(image),
-                docker_url: None,
-            },
-
-            RunsOn::Pull {
-                image,
-                pull,
-                docker_url,
-                registry,
-            } => {
-                let image = if pull.unwrap_or_default() {
-                    match registry.as_ref() {
-                        Some(Registry::FromConfig(value)) => {
-                            Image::pull(image, self.config.registry(value))
-                        }
-                        Some(Registry::Full(config)) => Image::pull(image, Some(config)),
-                        None => Image::pull(image, None),
-                    }
-                } else {
-                    Image::Use(image)
-                };
-                PlatformOptions::Container {
-                    docker_url: docker_url.as_deref(),
-                    image,
-                }
-            }
-
-            RunsOn::Build {
-                name,
-                tag,
-                dockerfile,
-                docker_url,
-            } => PlatformOptions::Container {
-                image: Image::build(name, dockerfile, tag),
-                docker_url: docker_url.as_deref(),
-            },
-
-            RunsOn::SshFromGlobalConfig { ssh_config } => {
-                let config = self.config.ssh(ssh_config)?;
-                let port = config.port.parse::<u16>()?;
-                let auth = match &config.userauth {
-                    SshUserAuth::Agent => SshAuthOptions::Agent,
-                    SshUserAuth::Password { password } => SshAuthOptions::Password { password },
-                    SshUserAuth::Keys {
-                        public_key,
-                        private_key,
-                    } => SshAuthOptions::Keys {
-                        public_key: public_key.as_deref(),
-                        private_key,
-                    },
-                };
-                PlatformOptions::Ssh(SshConnectOptions::new(
-                    &config.host,
-                    port,
-                    &config.user,
-                    auth,
-                ))
-            }
-
-            RunsOn::Ssh(config) => {
-                let port = config.port.parse::<u16>()?;
-                let auth = match &config.userauth {
-                    SshUserAuth::Agent => SshAuthOptions::Agent,
-                    SshUserAuth::Password { password } => SshAuthOptions::Password { password },
-                    SshUserAuth::Keys {
-                        public_key,
-                        private_key,
-                    } => SshAuthOptions::Keys {
-                        public_key: public_key.as_deref(),
-                        private_key,
-                    },
-                };
-                PlatformOptions::Ssh(SshConnectOptions::new(
-                    &config.host,
-                    port,
-                    &config.user,
-                    auth,
-                ))
-            }
-        };
+        let needs_platform = self.pipeline.jobs.is_empty()
+            || self
+                .pipeline
+                .jobs
+                .values()
+                .any(|job| job.runs_on().is_none());
+
+        if !needs_platform {
+            debug!("all jobs override runs_on so skipping the pipeline platform");
+            return Ok(());
+        }
 
-        let conn = self.context.get_conn();
-        let platform = PlatformBuilder::default()
-            .run_id(&self.run_id)
-            .config(self.config.clone())
-            .options(options)
-            .pipeline_environment(&self.pipeline.environment)
-            .environment(self.env.clone())
-            .logger(self.logger.clone())
-            .conn(conn)
-            .build()
-            .await?;
+        let platform = create_platform(
+            &self.run_id,
+            self.config.clone(),
+            &self.pipeline,
+            &self.pipeline.runs_on,
+            self.env.clone(),
+            self.logger.clone(),
+            &self.context,
+        )
+        .await?;
 
-        self.context.add_platform(platform.clone()).await?;
         self.platform = Some(platform);
         Ok(())
     }
 
     async fn dispose_platform(&self) -> Result<()> {
         let Some(platform) = self.platform.as_ref() else {
-            bail!("no platform instance for runner");
+            return Ok(());
         };
-        if self.pipeline.dispose {
-            debug!("executing dispose operations for platform");
-            platform.dispose(self.is_child).await?;
-        } else {
-            debug!("keeping platform alive");
-            platform.keep_alive().await?;
-        }
-
-        self.context.remove_platform(platform.id()).await
+        dispose_platform(
+            &self.context,
+            platform,
+            self.pipeline.dispose,
+            self.is_child,
+        )
+        .await
     }
 
     async fn ipc_send_completed(&self) -> Result<()> {
@@ -477,21 +1187,129 @@ impl Runner {
             logger,
             context: self.context.clone(),
             platform: self.platform.clone(),
+            env: self.env.clone(),
+            symbols: self.job_symbols(name),
+            is_child: self.is_child,
+            job_id: None,
+            checkout_commit: RwLock::new(None),
+            regex_cache: self.regex_cache.clone(),
         }
     }
 
-    async fn prepare_jobs(&self) -> Result<Vec<Option<RunningJob>>> {
-        let mut jobs = Vec::new();
-        for name in self.pipeline.jobs.keys() {
-            self.logger
-                .write_line(format!("{:<15}: {}", "Running job", name))
-                .await?;
-            let logger = Logger::in_memory().into_arc();
-            let job = self.create_job(name, logger.clone());
-            let handle = spawn(job.run());
-            jobs.push(Some(RunningJob::new(name, handle, logger)));
+    async fn skipped_job(&self, name: &str) -> Result<()> {
+        self.context
+            .add_job(name.to_owned(), PRJ_STATE_SKIPPED)
+            .await?;
+        self.logger
+            .write_line(format!("{:<15}: {}", "Skipped job", name))
+            .await
+    }
+
+    fn job_symbols(&self, name: &str) -> Arc<HashMap<String, String>> {
+        let Some(instance) = self.pipeline.jobs.get(name).and_then(|job| job.instance()) else {
+            return self.symbols.clone();
+        };
+        let mut symbols = self.symbols.as_ref().clone();
+        symbols.extend(instance.symbols());
+        symbols.into_arc()
+    }
+
+    fn job_needs(&self, name: &str) -> Vec<&str> {
+        let needs = self
+            .pipeline
+            .jobs
+            .get(name)
+            .map(|job| job.needs())
+            .unwrap_or_default();
+
+        self.pipeline
+            .jobs
+            .iter()
+            .filter(|(k, job)| needs.iter().any(|x| x == job.origin(k)))
+            .map(|(k, _)| k.as_str())
+            .collect()
+    }
+
+    fn is_job_throttled(&self, name: &str, running_jobs: &[Option<RunningJob>]) -> bool {
+        let Some(instance) = self.pipeline.jobs.get(name).and_then(|job| job.instance()) else {
+            return false;
+        };
+        let Some(max_parallel) = instance.max_parallel else {
+            return false;
+        };
+        let running = running_jobs
+            .iter()
+            .flatten()
+            .filter_map(|x| {
+                self.pipeline
+                    .jobs
+                    .get(&x.name)
+                    .map(|job| job.origin(&x.name))
+            })
+            .filter(|x| *x == instance.origin)
+            .count();
+        running >= max_parallel
+    }
+
+    fn can_run_job(&self, name: &str, has_failed: bool) -> Result<bool> {
+        let condition = self.pipeline.jobs.get(name).and_then(|job| job.condition());
+        let symbols = self.job_symbols(name);
+        ExpressionContext::new(&symbols, has_failed).should_run(condition)
+    }
+
+    async fn start_job(&self, name: &str) -> Result<RunningJob> {
+        self.logger
+            .write_line(format!("{:<15}: {}", "Running job", name))
+            .await?;
+        let logger = Logger::in_memory().into_arc();
+        logger.add_secrets(self.secrets.to_vec()).await?;
+        logger.set_scope(Some(name.to_owned()), None).await?;
+        let job = self.create_job(name, logger.clone());
+        let handle = spawn(job.run());
+        Ok(RunningJob::new(name, handle, logger))
+    }
+
+    async fn start_ready_jobs(
+        &self,
+        pending: &mut Vec<String>,
+        finished: &mut HashMap<String, JobOutcome>,
+        running_jobs: &mut Vec<Option<RunningJob>>,
+    ) -> Result<()> {
+        let mut index = 0;
+        while index < pending.len() {
+            let needs = self.job_needs(&pending[index]);
+
+            if !needs.iter().all(|x| finished.contains_key(*x)) {
+                index += 1;
+                continue;
+            }
+
+            let has_failed = needs
+                .iter()
+                .any(|x| finished.get(*x).is_some_and(JobOutcome::has_failed));
+
+            if !has_failed && self.is_job_throttled(&pending[index], running_jobs) {
+                index += 1;
+                continue;
+            }
+
+            let name = pending.remove(index);
+
+            if self.can_run_job(&name, has_failed)? {
+                running_jobs.push(Some(self.start_job(&name).await?));
+                continue;
+            }
+
+            self.skipped_job(&name).await?;
+            finished.insert(
+                name,
+                JobOutcome::Skipped {
+                    after_failure: has_failed,
+                },
+            );
+            index = 0;
         }
-        Ok(jobs)
+        Ok(())
     }
 
     async fn run_first_job(&self) -> Result<()> {
@@ -499,17 +1317,73 @@ impl Runner {
             bail!("unable to retrieve job");
         };
         debug!("found only one job so running it in the current context");
-        self.create_job(name, self.logger.clone())
-            .run()
-            .await
-            .map(|_| ())
+        if !self.can_run_job(name, false)? {
+            return self.skipped_job(name).await;
+        }
+        self.logger.set_scope(Some(name.to_owned()), None).await?;
+        let result = self.create_job(name, self.logger.clone()).run().await;
+        self.logger.set_scope(None, None).await?;
+        result?;
+
+        let finished = HashMap::from([(name.to_owned(), JobOutcome::Succeeded)]);
+        self.publish_after_job(name, &finished).await
+    }
+
+    /// Publishes the entries that should be published after the origin of the job,
+    /// once every instance of a matrix job has completed successfully.
+    async fn publish_after_job(
+        &self,
+        name: &str,
+        finished: &HashMap<String, JobOutcome>,
+    ) -> Result<()> {
+        let origin = self.job_origin(name);
+        let has_succeeded = self
+            .pipeline
+            .jobs
+            .keys()
+            .filter(|x| self.job_origin(x) == origin)
+            .all(|x| finished.get(x) == Some(&JobOutcome::Succeeded));
+
+        if !has_succeeded {
+            return Ok(());
+        }
+
+        publish_images(
+            &self.config,
+            &self.logger,
+            &self.pipeline.publish,
+            Some(origin),
+        )
+        .await
+    }
+
+    fn job_origin<'a>(&'a self, name: &'a str) -> &'a str {
+        self.pipeline
+            .jobs
+            .get(name)
+            .map(|job| job.origin(name))
+            .unwrap_or(name)
     }
 
     async fn run_all_jobs(&self) -> Result<()> {
         let mut result = Ok(());
-        let mut running_jobs = self.prepare_jobs().await?;
+        let mut pending: Vec<String> = self.pipeline.jobs.keys().cloned().collect();
+        let mut finished: HashMap<String, JobOutcome> = HashMap::new();
+        let mut running_jobs: Vec<Option<RunningJob>> = Vec::new();
+
+        pending.sort();
+
+        loop {
+            self.start_ready_jobs(&mut pending, &mut finished, &mut running_jobs)
+                .await?;
+
+            if running_jobs.iter().all(|x| x.is_none()) {
+                if !pending.is_empty() {
+                    bail!("unable to resolve the dependencies of jobs {pending:?}");
+                }
+                break;
+            }
 
-        while running_jobs.iter().any(|x| x.is_some()) {
             for job in running_jobs.iter_mut() {
                 let is_finished = job
                     .as_ref()
@@ -532,13 +1406,27 @@ impl Runner {
                     self.logger.write_line(message).await?;
 
                     self.logger
-                        .write_line(running_job.logger.try_retrieve_output().await?)
+                        .write_entries(running_job.logger.try_retrieve_entries().await?)
                         .await?;
 
+                    let outcome = if handle_result.is_ok() {
+                        JobOutcome::Succeeded
+                    } else {
+                        JobOutcome::Failed
+                    };
+                    finished.insert(running_job.name.to_owned(), outcome);
                     result = result.and(handle_result.map(|_| ()));
+
+                    if outcome == JobOutcome::Succeeded {
+                        if let Err(e) = self.publish_after_job(&running_job.name, &finished).await {
+                            self.logger.write_line(e.to_string()).await?;
+                            result = Err(e);
+                        }
+                    }
                 }
             }
 
+            running_jobs.retain(|x| x.is_some());
             sleep(Duration::from_millis(200)).await;
         }
 
@@ -553,13 +1441,22 @@ impl Runner {
         }
     }
 
+    async fn publish(&self) -> Result<()> {
+        publish_images(&self.config, &self.logger, &self.pipeline.publish, None).await
+    }
+
     async fn execute(mut self) -> Result<()> {
         self.start().await?;
 
         // using let expression to log the errors and let an empty string be used
         // by the final print_error of main.
 
-        let Err(e) = self.jobs().await else {
+        let result = match self.jobs().await {
+            Ok(_) => self.publish().await,
+            Err(e) => Err(e),
+        };
+
+        let Err(e) = result else {
             self.stop().await?;
             return Ok(());
         };
@@ -627,3 +1524,660 @@ impl Runner {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use bld_models::{
+        new_connection_pool, pipeline_run_steps,
+        pipeline_runs::{self, InsertPipelineRun},
+        secrets,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// A temporary project with a .bld directory that pipelines are run from.
+    struct TestProject {
+        dir: PathBuf,
+    }
+
+    impl TestProject {
+        fn new() -> Self {
+            let dir = path![temp_dir(), format!("bld-runner-{}", Uuid::new_v4())];
+            fs::create_dir_all(path![&dir, ".bld"]).unwrap();
+            Self { dir }
+        }
+
+        fn path(&self, name: &str) -> String {
+            path![&self.dir, name].display().to_string()
+        }
+
+        fn read(&self, name: &str) -> String {
+            fs::read_to_string(path![&self.dir, name]).unwrap_or_default()
+        }
+
+        fn config(&self) -> Arc<BldConfig> {
+            BldConfig {
+                root_dir: self.path(".bld"),
+                project_dir: self.dir.display().to_string(),
+                ..Default::default()
+            }
+            .into_arc()
+        }
+
+        /// Runs the pipeline and returns its result along with the output of the run.
+        async fn run(&self, pipeline: &str) -> (Result<()>, String) {
+            let context = Context::local(self.config()).into_arc();
+            self.run_in_context(pipeline, context, &Uuid::new_v4().to_string())
+                .await
+        }
+
+        async fn run_in_context(
+            &self,
+            pipeline: &str,
+            context: Arc<Context>,
+            run_id: &str,
+        ) -> (Result<()>, String) {
+            fs::write(path![&self.dir, ".bld", "test.yaml"], pipeline).unwrap();
+            let config = self.config();
+            let logger = Logger::in_memory().into_arc();
+            let result = async {
+                RunnerBuilder::default()
+                    .run_id(run_id)
+                    .config(config.clone())
+                    .fs(FileSystem::local(config.clone()).into_arc())
+                    .logger(logger.clone())
+                    .pipeline("test.yaml")
+                    .environment(HashMap::new().into_arc())
+                    .variables(HashMap::new().into_arc())
+                    .context(context)
+                    .build()
+                    .await?
+                    .run()
+                    .await
+            }
+            .await;
+            let output = logger.try_retrieve_output().await.unwrap();
+            (result, output)
+        }
+
+        /// Creates the database of a server with the given secrets along with a run
+        /// and returns a server context for it.
+        async fn server_context(&self, secrets: &[(&str, &str)]) -> (Arc<Context>, String) {
+            let mut config = BldConfig {
+                root_dir: self.path(".bld"),
+                project_dir: self.dir.display().to_string(),
+                ..Default::default()
+            };
+            config.local.server.db = Some(format!("sqlite://{}?mode=rwc", self.path("test.db")));
+            config.local.server.secrets_key = Some("secrets-key".to_owned());
+            let config = config.into_arc();
+            let conn = new_connection_pool(config.clone())
+                .await
+                .unwrap()
+                .into_arc();
+            for (name, value) in secrets {
+                secrets::upsert(conn.as_ref(), "secrets-key", name, value)
+                    .await
+                    .unwrap();
+            }
+
+            let run_id = Uuid::new_v4().to_string();
+            let run = InsertPipelineRun {
+                id: run_id.clone(),
+                name: "test.yaml".to_owned(),
+                app_user: "test".to_owned(),
+                hook: None,
+            };
+            pipeline_runs::insert(conn.as_ref(), run).await.unwrap();
+            let run = pipeline_runs::select_by_id(conn.as_ref(), &run_id)
+                .await
+                .unwrap();
+            let context = Context::server(config, conn, &run).into_arc();
+            (context, run_id)
+        }
+    }
+
+    impl Drop for TestProject {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn position(order: &[&str], job: &str) -> usize {
+        order
+            .iter()
+            .position(|x| *x == job)
+            .unwrap_or_else(|| panic!("job {job} didn't run, order was {order:?}"))
+    }
+
+    #[actix_web::test]
+    async fn jobs_run_after_their_needs() {
+        let project = TestProject::new();
+        let order = project.path("order");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  deploy:
+    needs: [test, lint]
+    steps:
+    - echo deploy >> {order}
+  test:
+    needs: [build]
+    steps:
+    - sleep 0.3 && echo test >> {order}
+  lint:
+    needs: [build]
+    steps:
+    - echo lint >> {order}
+  build:
+  - sleep 0.3 && echo build >> {order}
+"
+        );
+
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_ok(), "{output}");
+
+        let content = project.read("order");
+        let order: Vec<&str> = content.lines().collect();
+        assert_eq!(order.len(), 4);
+        assert!(position(&order, "build") < position(&order, "test"));
+        assert!(position(&order, "build") < position(&order, "lint"));
+        assert!(position(&order, "test") < position(&order, "deploy"));
+        assert!(position(&order, "lint") < position(&order, "deploy"));
+    }
+
+    #[actix_web::test]
+    async fn failed_needs_skip_their_dependents() {
+        let project = TestProject::new();
+        let order = project.path("order");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  build:
+  - exit 1
+  test:
+    needs: [build]
+    steps:
+    - echo test >> {order}
+  deploy:
+    needs: [test]
+    steps:
+    - echo deploy >> {order}
+  report:
+    needs: [test]
+    if: failure()
+    steps:
+    - echo report >> {order}
+  other:
+  - echo other >> {order}
+"
+        );
+
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_err());
+        assert!(output.contains("Skipped job    : test"), "{output}");
+        assert!(output.contains("Skipped job    : deploy"), "{output}");
+
+        let content = project.read("order");
+        let mut order: Vec<&str> = content.lines().collect();
+        order.sort();
+        assert_eq!(order, vec!["other", "report"]);
+    }
+
+    #[test]
+    fn services_are_converted_to_platform_options() {
+        let yaml = r"
+db:
+  image: postgres:16
+  pull: true
+  environment:
+    POSTGRES_PASSWORD: password
+  command: [postgres, -c, fsync=off]
+  ports:
+  - 5432:5432
+  health_check:
+    command: pg_isready
+    interval: 2
+cache:
+  image: redis:7
+";
+        let services: HashMap<String, Service> = serde_yaml::from_str(yaml).unwrap();
+        let config = BldConfig::default();
+        let mut options = service_options(&config, &services);
+        options.sort_by_key(|x| x.name);
+
+        let cache = &options[0];
+        assert_eq!(cache.name, "cache");
+        assert!(matches!(cache.image, Image::Use("redis:7")));
+        assert!(cache.env.is_empty());
+        assert!(cache.command.is_none());
+        assert!(cache.ports.is_empty());
+        assert!(cache.health_check.is_none());
+
+        let db = &options[1];
+        assert_eq!(db.name, "db");
+        assert!(matches!(db.image, Image::Pull(_)));
+        assert_eq!(db.image.name(), "postgres:16");
+        assert_eq!(db.env, vec!["POSTGRES_PASSWORD=password"]);
+        assert_eq!(db.command, Some(vec!["postgres", "-c", "fsync=off"]));
+        assert_eq!(db.ports, vec!["5432:5432"]);
+        let health_check = db.health_check.as_ref().unwrap();
+        assert_eq!(health_check.command, "pg_isready");
+        assert_eq!(health_check.interval, Duration::from_secs(2));
+        assert_eq!(health_check.timeout, Duration::from_secs(5));
+        assert_eq!(health_check.retries, 10);
+    }
+
+    #[actix_web::test]
+    async fn restored_cache_is_not_saved_again() {
+        let project = TestProject::new();
+        let content = project.path("content");
+        let pipeline = |step: &str| {
+            format!(
+                r"
+version: 2
+runs_on: machine
+cache:
+- key: deps
+  paths:
+  - deps
+jobs:
+  main:
+  - {step}
+"
+            )
+        };
+
+        let (result, output) = project
+            .run(&pipeline("mkdir -p deps && echo first > deps/file"))
+            .await;
+        assert!(result.is_ok(), "{output}");
+        assert!(output.contains("Cache miss     : deps"), "{output}");
+        assert!(output.contains("Cache saved    : deps"), "{output}");
+
+        let step = format!("cat deps/file > {content} && echo second > deps/file");
+        let (result, output) = project.run(&pipeline(&step)).await;
+        assert!(result.is_ok(), "{output}");
+        assert!(output.contains("Cache restored : deps"), "{output}");
+        assert!(!output.contains("Cache saved"), "{output}");
+        assert_eq!(project.read("content").trim(), "first");
+
+        let (result, output) = project
+            .run(&pipeline(&format!("cat deps/file > {content}")))
+            .await;
+        assert!(result.is_ok(), "{output}");
+        assert_eq!(project.read("content").trim(), "first");
+    }
+
+    #[actix_web::test]
+    async fn step_retries_until_it_succeeds() {
+        let project = TestProject::new();
+        let counter = project.path("counter");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - retries: 3
+    exec:
+    - n=$(cat {counter} 2>/dev/null || echo 0); echo $((n + 1)) > {counter}; [ $n -ge 2 ]
+"
+        );
+
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_ok(), "{output}");
+        assert_eq!(project.read("counter").trim(), "3");
+        assert!(
+            output.contains("Retrying step  : attempt 2 of 4"),
+            "{output}"
+        );
+        assert!(
+            output.contains("Retrying step  : attempt 3 of 4"),
+            "{output}"
+        );
+        assert!(!output.contains("attempt 4 of 4"), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn step_fails_once_retries_are_exhausted() {
+        let project = TestProject::new();
+        let counter = project.path("counter");
+        let marker = project.path("marker");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - retries: 1
+    exec:
+    - echo attempt >> {counter} && exit 1
+  - touch {marker}
+"
+        );
+
+        let (result, _) = project.run(&pipeline).await;
+        assert!(result.is_err());
+        assert_eq!(project.read("counter").lines().count(), 2);
+        assert!(!Path::new(&marker).exists());
+    }
+
+    #[actix_web::test]
+    async fn step_timeout_stops_the_step() {
+        let project = TestProject::new();
+        let marker = project.path("marker");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - timeout: 1
+    exec:
+    - (sleep 2 && touch {marker}) & sleep 10
+"
+        );
+
+        let start = Instant::now();
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_err());
+        assert!(output.contains("command timed out"), "{output}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        sleep(Duration::from_millis(2500)).await;
+        assert!(!Path::new(&marker).exists());
+    }
+
+    #[actix_web::test]
+    async fn step_continues_on_error() {
+        let project = TestProject::new();
+        let marker = project.path("marker");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - continue_on_error: true
+    exec:
+    - exit 4
+  - touch {marker}
+"
+        );
+
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_ok(), "{output}");
+        assert!(
+            output.contains("Step failed    : continuing on error"),
+            "{output}"
+        );
+        assert!(Path::new(&marker).exists());
+    }
+
+    #[actix_web::test]
+    async fn continued_step_is_stored_as_faulted() {
+        let project = TestProject::new();
+        let (context, run_id) = project.server_context(&[]).await;
+        let conn = context.get_conn().unwrap();
+
+        let pipeline = r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - name: failing
+    continue_on_error: true
+    exec:
+    - exit 4
+  - name: following
+    exec:
+    - echo following
+";
+        let (result, output) = project.run_in_context(pipeline, context, &run_id).await;
+        assert!(result.is_ok(), "{output}");
+        assert!(output.contains("following"), "{output}");
+
+        // the state of a step is stored without waiting for a response.
+        let mut steps = vec![];
+        for _ in 0..50 {
+            steps = pipeline_run_steps::select_by_run_id(conn.as_ref(), &run_id)
+                .await
+                .unwrap();
+            if steps.iter().all(|x| x.state != PRS_STATE_RUNNING) {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        steps.sort_by_key(|x| x.position);
+        let states: Vec<(Option<&str>, &str, Option<i64>)> = steps
+            .iter()
+            .map(|x| (x.name.as_deref(), x.state.as_str(), x.exit_code))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (Some("failing"), PRS_STATE_FAULTED, Some(4)),
+                (Some("following"), PRS_STATE_FINISHED, Some(0)),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn secrets_are_masked_in_the_output() {
+        let project = TestProject::new();
+        let (context, run_id) = project.server_context(&[("token", "s3cr3t-value")]).await;
+        let pipeline = r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - echo token is ${{ secrets.token }}
+  - printf s3cr3t- && printf value
+";
+        let (result, output) = project.run_in_context(pipeline, context, &run_id).await;
+        assert!(result.is_ok(), "{output}");
+        assert!(output.contains("token is ***"), "{output}");
+        assert!(!output.contains("s3cr3t"), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn unknown_secrets_fail_the_run() {
+        let project = TestProject::new();
+        let (context, run_id) = project.server_context(&[("token", "value")]).await;
+        let marker = project.path("marker");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  main:
+  - touch {marker}
+  - echo ${{{{ secrets.other }}}}
+"
+        );
+        let (result, output) = project.run_in_context(&pipeline, context, &run_id).await;
+        let error = result.unwrap_err().to_string();
+        assert_eq!(error, "secret other isn't available to the run", "{output}");
+        assert!(!Path::new(&marker).exists());
+    }
+
+    #[actix_web::test]
+    async fn matrix_instances_run_with_scalar_values() {
+        let project = TestProject::new();
+        let output_file = project.path("output");
+        let pipeline = format!(
+            r"
+version: 2
+runs_on: machine
+jobs:
+  build:
+    matrix:
+      version: [1, 2]
+      debug: [true, false]
+      exclude:
+      - version: 2
+        debug: false
+    steps:
+    - echo ${{{{ matrix.version }}}}-${{{{ matrix.debug }}}} >> {output_file}
+  other:
+  - echo other
+"
+        );
+
+        let (result, output) = project.run(&pipeline).await;
+        assert!(result.is_ok(), "{output}");
+
+        let content = project.read("output");
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.sort();
+        assert_eq!(lines, vec!["1-false", "1-true", "2-true"]);
+    }
+
+    #[actix_web::test]
+    async fn jobs_override_the_platform_of_the_pipeline() {
+        let project = TestProject::new();
+        let pipeline = r"
+version: 2
+runs_on:
+  image: bld-missing-image:latest
+  pull_policy: never
+jobs:
+  lint:
+    runs_on: machine
+    steps:
+    - echo lint
+  build:
+    runs_on: machine
+    steps:
+    - echo build
+";
+
+        let (result, output) = project.run(pipeline).await;
+        assert!(result.is_ok(), "{output}");
+        assert!(output.contains("Runs on        : machine"), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn job_platforms_have_their_own_directory() {
+        let project = TestProject::new();
+        let pipeline = r"
+version: 2
+runs_on: machine
+jobs:
+  first:
+    runs_on: machine
+    steps:
+    - touch first && sleep 0.2
+  second:
+    runs_on: machine
+    steps:
+    - touch second && sleep 0.6 && [ ! -f first ] && touch done
+  third:
+  - sleep 0.8 && [ ! -f first ] && [ ! -f second ] && touch done
+";
+
+        let (result, output) = project.run(pipeline).await;
+        assert!(result.is_ok(), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn step_error_is_kept_when_disposing_fails() {
+        let project = TestProject::new();
+        let pipeline = r#"
+version: 2
+runs_on: machine
+jobs:
+  main:
+    runs_on: machine
+    steps:
+    - rm -rf "$PWD" && exit 2
+"#;
+
+        let (result, output) = project.run(pipeline).await;
+        assert!(result.is_err());
+        assert!(
+            output.contains("unable to dispose the platform of the job"),
+            "{output}"
+        );
+        assert!(output.contains("exit code: 2"), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn images_are_published_after_their_job_succeeds() {
+        let project = TestProject::new();
+        let pipeline = r"
+version: 2
+runs_on: machine
+publish:
+- image: bld-publish-missing:1.0
+  repository: localhost:5000/bld-publish-missing
+  after: build
+jobs:
+  build:
+  - echo build
+  test:
+  - sleep 0.3 && echo test
+";
+
+        let (_, output) = project.run(pipeline).await;
+        assert!(
+            output.contains("unable to publish image bld-publish-missing:1.0"),
+            "{output}"
+        );
+    }
+
+    #[actix_web::test]
+    async fn images_are_not_published_when_their_job_fails() {
+        let project = TestProject::new();
+        let pipeline = r"
+version: 2
+runs_on: machine
+publish:
+- image: bld-publish-missing:1.0
+  repository: localhost:5000/bld-publish-missing
+  after: build
+- image: bld-publish-missing:2.0
+  repository: localhost:5000/bld-publish-missing
+jobs:
+  build:
+  - exit 1
+  test:
+  - echo test
+";
+
+        let (_, output) = project.run(pipeline).await;
+        assert!(output.contains("One or more jobs"), "{output}");
+        assert!(!output.contains("unable to publish image"), "{output}");
+    }
+
+    #[actix_web::test]
+    async fn images_are_not_published_until_all_matrix_instances_succeed() {
+        let project = TestProject::new();
+        let pipeline = r"
+version: 2
+runs_on: machine
+publish:
+- image: bld-publish-missing:1.0
+  repository: localhost:5000/bld-publish-missing
+  after: build
+jobs:
+  build:
+    matrix:
+      version: [1, 2]
+    steps:
+    - test ${{ matrix.version }} -eq 1
+";
+
+        let (_, output) = project.run(pipeline).await;
+        assert!(output.contains("One or more jobs"), "{output}");
+        assert!(!output.contains("unable to publish image"), "{output}");
+    }
+}
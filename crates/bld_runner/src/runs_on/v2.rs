@@ -1,10 +1,10 @@
 use crate::registry::v2::Registry;
 use bld_config::SshConfig;
 use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 #[cfg(feature = "all")]
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 #[cfg(feature = "all")]
 use bld_config::SshUserAuth;
@@ -12,21 +12,192 @@ use bld_config::SshUserAuth;
 #[cfg(feature = "all")]
 use crate::token_context::v2::PipelineContext;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullPolicy {
+    Always,
+    IfNotPresent,
+    Never,
+}
+
+impl PullPolicy {
+    /// Resolves the policy of an image, falling back to the pull flag for pipelines
+    /// that don't define a policy.
+    pub fn resolve(policy: Option<PullPolicy>, pull: Option<bool>) -> Self {
+        match (policy, pull) {
+            (Some(policy), _) => policy,
+            (None, Some(true)) => Self::Always,
+            (None, _) => Self::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerMount {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub source: Option<String>,
+    pub target: String,
+    pub read_only: Option<bool>,
+}
+
+impl ContainerMount {
+    pub fn kind(&self) -> &str {
+        self.kind.as_deref().unwrap_or("bind")
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &PipelineContext<'a>) -> Result<()> {
+        if let Some(source) = self.source.as_mut() {
+            *source = context.transform(source.to_owned()).await?;
+        }
+        self.target = context.transform(self.target.to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ContainerSettings {
+    pub volumes: Option<Vec<String>>,
+    pub mounts: Option<Vec<ContainerMount>>,
+    pub network: Option<String>,
+    pub user: Option<String>,
+    pub privileged: Option<bool>,
+    pub cpus: Option<f64>,
+    pub memory: Option<String>,
+    pub extra_hosts: Option<Vec<String>>,
+}
+
+impl ContainerSettings {
+    /// Parses the memory limit which is either a number of bytes or a number
+    /// followed by one of the b, k, m or g units, where the k, m and g units can
+    /// also end with a b e.g. `512mb` or `1GB`.
+    #[cfg(feature = "all")]
+    pub fn memory_in_bytes(&self) -> Result<Option<i64>> {
+        let Some(memory) = self.memory.as_deref() else {
+            return Ok(None);
+        };
+        let memory = memory.trim().to_lowercase();
+        let number = memory.strip_suffix('b').unwrap_or(&memory);
+        let (value, multiplier) = match number.chars().last() {
+            Some('k') => (&number[..number.len() - 1], 1024),
+            Some('m') => (&number[..number.len() - 1], 1024 * 1024),
+            Some('g') => (&number[..number.len() - 1], 1024 * 1024 * 1024),
+            _ => (number, 1),
+        };
+        let value = value
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| anyhow!("invalid memory limit {memory}"))?;
+        value
+            .checked_mul(multiplier)
+            .filter(|x| *x > 0)
+            .map(Some)
+            .ok_or_else(|| anyhow!("invalid memory limit {memory}"))
+    }
+
+    pub fn nano_cpus(&self) -> Option<i64> {
+        self.cpus.map(|x| (x * 1_000_000_000.0) as i64)
+    }
+
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &PipelineContext<'a>) -> Result<()> {
+        if let Some(volumes) = self.volumes.as_mut() {
+            for volume in volumes.iter_mut() {
+                *volume = context.transform(volume.to_owned()).await?;
+            }
+        }
+        if let Some(mounts) = self.mounts.as_mut() {
+            for mount in mounts.iter_mut() {
+                mount.apply_tokens(context).await?;
+            }
+        }
+        if let Some(network) = self.network.as_mut() {
+            *network = context.transform(network.to_owned()).await?;
+        }
+        if let Some(user) = self.user.as_mut() {
+            *user = context.transform(user.to_owned()).await?;
+        }
+        if let Some(memory) = self.memory.as_mut() {
+            *memory = context.transform(memory.to_owned()).await?;
+        }
+        if let Some(extra_hosts) = self.extra_hosts.as_mut() {
+            for host in extra_hosts.iter_mut() {
+                *host = context.transform(host.to_owned()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubernetesResources {
+    pub requests: Option<HashMap<String, String>>,
+    pub limits: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubernetesSettings {
+    pub kubernetes: String,
+    pub image: String,
+    pub namespace: Option<String>,
+    pub resources: Option<KubernetesResources>,
+    pub node_selector: Option<HashMap<String, String>>,
+    pub service_account: Option<String>,
+}
+
+impl KubernetesSettings {
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &PipelineContext<'a>) -> Result<()> {
+        self.kubernetes = context.transform(self.kubernetes.to_owned()).await?;
+        self.image = context.transform(self.image.to_owned()).await?;
+        if let Some(namespace) = self.namespace.as_mut() {
+            *namespace = context.transform(namespace.to_owned()).await?;
+        }
+        if let Some(resources) = self.resources.as_mut() {
+            for (_, v) in resources.requests.iter_mut().flatten() {
+                *v = context.transform(v.to_owned()).await?;
+            }
+            for (_, v) in resources.limits.iter_mut().flatten() {
+                *v = context.transform(v.to_owned()).await?;
+            }
+        }
+        for (_, v) in self.node_selector.iter_mut().flatten() {
+            *v = context.transform(v.to_owned()).await?;
+        }
+        if let Some(service_account) = self.service_account.as_mut() {
+            *service_account = context.transform(service_account.to_owned()).await?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum RunsOn {
     ContainerOrMachine(String),
+    Kubernetes(Box<KubernetesSettings>),
     Pull {
         image: String,
         registry: Option<Registry>,
         pull: Option<bool>,
+        pull_policy: Option<PullPolicy>,
         docker_url: Option<String>,
+        #[serde(flatten)]
+        settings: Box<ContainerSettings>,
     },
     Build {
         name: String,
         tag: String,
         dockerfile: String,
+        context: Option<String>,
+        build_args: Option<HashMap<String, String>>,
+        target: Option<String>,
+        no_cache: Option<bool>,
+        labels: Option<HashMap<String, String>>,
         docker_url: Option<String>,
+        #[serde(flatten)]
+        settings: Box<ContainerSettings>,
     },
     Ssh(SshConfig),
     SshFromGlobalConfig {
@@ -39,6 +210,9 @@ impl Display for RunsOn {
         match self {
             Self::ContainerOrMachine(image) if image == "machine" => write!(f, "machine"),
             Self::ContainerOrMachine(image) => write!(f, "{image}"),
+            Self::Kubernetes(settings) => {
+                write!(f, "{} ({})", settings.image, settings.kubernetes)
+            }
             Self::Pull { image, .. } => write!(f, "{image}"),
             Self::Build { name, tag, .. } => write!(f, "{name}:{tag}"),
             Self::SshFromGlobalConfig { ssh_config } => write!(f, "{}", ssh_config),
@@ -79,6 +253,7 @@ impl RunsOn {
                 image,
                 registry,
                 docker_url,
+                settings,
                 ..
             } => {
                 *image = context.transform(image.to_owned()).await?;
@@ -88,24 +263,46 @@ impl RunsOn {
                 if let Some(registry) = registry.as_mut() {
                     registry.apply_tokens(context).await?;
                 }
+                settings.apply_tokens(context).await?;
             }
 
             RunsOn::Build {
                 name,
                 tag,
                 dockerfile,
+                context: build_context,
+                build_args,
+                target,
+                labels,
                 docker_url,
+                settings,
+                ..
             } => {
                 *name = context.transform(name.to_owned()).await?;
                 *tag = context.transform(tag.to_owned()).await?;
                 *dockerfile = context.transform(dockerfile.to_owned()).await?;
+                if let Some(build_context) = build_context {
+                    *build_context = context.transform(build_context.to_owned()).await?;
+                }
+                for (_, v) in build_args.iter_mut().flatten() {
+                    *v = context.transform(v.to_owned()).await?;
+                }
+                if let Some(target) = target {
+                    *target = context.transform(target.to_owned()).await?;
+                }
+                for (_, v) in labels.iter_mut().flatten() {
+                    *v = context.transform(v.to_owned()).await?;
+                }
                 *docker_url = if let Some(url) = docker_url {
                     Some(context.transform(url.to_owned()).await?)
                 } else {
                     None
                 };
+                settings.apply_tokens(context).await?;
             }
 
+            RunsOn::Kubernetes(settings) => settings.apply_tokens(context).await?,
+
             RunsOn::ContainerOrMachine(image) if image != "machine" => {
                 *image = context.transform(image.to_owned()).await?;
             }
@@ -137,6 +334,12 @@ impl RunsOn {
                         let password = context.transform(password.to_owned()).await?;
                         SshUserAuth::Password { password }
                     }
+                };
+                if let Some(known_hosts) = config.known_hosts.as_mut() {
+                    *known_hosts = context.transform(known_hosts.to_owned()).await?;
+                }
+                if let Some(fingerprint) = config.fingerprint.as_mut() {
+                    *fingerprint = context.transform(fingerprint.to_owned()).await?;
                 }
             }
 
@@ -147,3 +350,48 @@ impl RunsOn {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use super::*;
+
+    fn memory_in_bytes(memory: &str) -> Result<Option<i64>> {
+        ContainerSettings {
+            memory: Some(memory.to_owned()),
+            ..Default::default()
+        }
+        .memory_in_bytes()
+    }
+
+    #[test]
+    fn memory_limit_is_optional() {
+        let settings = ContainerSettings::default();
+        assert_eq!(settings.memory_in_bytes().unwrap(), None);
+    }
+
+    #[test]
+    fn memory_limit_units_are_parsed() {
+        let cases = [
+            ("1024", 1024),
+            ("100b", 100),
+            ("2k", 2 * 1024),
+            ("2kb", 2 * 1024),
+            ("512m", 512 * 1024 * 1024),
+            ("512mb", 512 * 1024 * 1024),
+            ("512MB", 512 * 1024 * 1024),
+            ("1g", 1024 * 1024 * 1024),
+            ("1GB", 1024 * 1024 * 1024),
+            (" 1 Gb ", 1024 * 1024 * 1024),
+        ];
+        for (memory, bytes) in cases {
+            assert_eq!(memory_in_bytes(memory).unwrap(), Some(bytes), "{memory}");
+        }
+    }
+
+    #[test]
+    fn invalid_memory_limits_are_rejected() {
+        for memory in ["", "mb", "1tb", "1bb", "-1g", "0", "one", "9999999999g"] {
+            assert!(memory_in_bytes(memory).is_err(), "{memory}");
+        }
+    }
+}
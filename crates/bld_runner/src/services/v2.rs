@@ -0,0 +1,76 @@
+use crate::{registry::v2::Registry, runs_on::v2::PullPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(feature = "all")]
+use anyhow::Result;
+
+#[cfg(feature = "all")]
+use crate::token_context::v2::PipelineContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceHealthCheck {
+    pub command: String,
+    pub interval: Option<u64>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u64>,
+}
+
+impl ServiceHealthCheck {
+    pub fn interval(&self) -> u64 {
+        self.interval.unwrap_or(5)
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or(5)
+    }
+
+    pub fn retries(&self) -> u64 {
+        self.retries.unwrap_or(10)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Service {
+    pub image: String,
+    pub pull: Option<bool>,
+    pub pull_policy: Option<PullPolicy>,
+    pub registry: Option<Registry>,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub ports: Vec<String>,
+    pub health_check: Option<ServiceHealthCheck>,
+}
+
+impl Service {
+    #[cfg(feature = "all")]
+    pub async fn apply_tokens<'a>(&mut self, context: &'a PipelineContext<'a>) -> Result<()> {
+        self.image = context.transform(self.image.to_owned()).await?;
+
+        if let Some(registry) = self.registry.as_mut() {
+            registry.apply_tokens(context).await?;
+        }
+
+        for (_, v) in self.environment.iter_mut() {
+            *v = context.transform(v.to_owned()).await?;
+        }
+
+        if let Some(command) = self.command.as_mut() {
+            for arg in command.iter_mut() {
+                *arg = context.transform(arg.to_owned()).await?;
+            }
+        }
+
+        for port in self.ports.iter_mut() {
+            *port = context.transform(port.to_owned()).await?;
+        }
+
+        if let Some(health_check) = self.health_check.as_mut() {
+            health_check.command = context.transform(health_check.command.to_owned()).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::checkout::v2::Checkout;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "all")]
@@ -19,6 +20,12 @@ pub enum BuildStep {
     Many {
         name: Option<String>,
         working_dir: Option<String>,
+        #[serde(rename(serialize = "if", deserialize = "if"))]
+        condition: Option<String>,
+        retries: Option<u64>,
+        retry_delay: Option<u64>,
+        timeout: Option<u64>,
+        continue_on_error: Option<bool>,
         #[serde(default)]
         exec: Vec<BuildStepExec>,
     },
@@ -59,6 +66,20 @@ impl BuildStep {
         Ok(())
     }
 
+    pub fn condition(&self) -> Option<&str> {
+        match self {
+            Self::One(_) => None,
+            Self::Many { condition, .. } => condition.as_deref(),
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::One(_) => None,
+            Self::Many { name, .. } => name.as_deref(),
+        }
+    }
+
     pub fn is(&self, name: &str) -> bool {
         let Self::Many { name: n, .. } = self else {
             return false;
@@ -76,6 +97,11 @@ pub enum BuildStepExec {
         #[serde(rename(serialize = "ext", deserialize = "ext"))]
         value: String,
     },
+
+    Checkout {
+        #[serde(rename(serialize = "checkout", deserialize = "checkout"))]
+        settings: Box<Checkout>,
+    },
 }
 
 impl BuildStepExec {
@@ -98,6 +124,9 @@ impl BuildStepExec {
             Self::External { value } => {
                 *value = context.transform(value.to_owned()).await?;
             }
+            Self::Checkout { settings } => {
+                settings.apply_tokens(context).await?;
+            }
         }
         Ok(())
     }
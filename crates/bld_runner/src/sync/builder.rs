@@ -6,7 +6,7 @@ use bld_core::{
     logger::Logger,
     platform::{
         builder::{PlatformBuilder, PlatformOptions},
-        Image,
+        ContainerHostOptions, Image,
     },
     regex::RegexCache,
     signals::UnixSignalsBackend,
@@ -20,6 +20,7 @@ use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 use crate::{
+    inputs::v2::Input,
     pipeline::{
         traits::Load,
         versioned::{VersionedPipeline, Yaml},
@@ -164,6 +165,7 @@ impl RunnerBuilder {
                     image => PlatformOptions::Container {
                         image: Image::Use(image),
                         docker_url: None,
+                        host: ContainerHostOptions::default(),
                     },
                 };
 
@@ -200,19 +202,28 @@ impl RunnerBuilder {
             }
 
             VersionedPipeline::Version2(mut pipeline) => {
+                let secrets = context.secrets().await?;
+                let secret_values: Vec<String> = secrets.values().cloned().collect();
+                self.logger.add_secrets(secret_values.clone()).await?;
+
+                let inputs = Input::resolve(&pipeline.inputs, &vars)?;
+
                 let pipeline_context = PipelineContextBuilder::default()
                     .root_dir(&config.root_dir)
                     .project_dir(&config.project_dir)
                     .add_variables(&pipeline.variables)
+                    .add_variables(&inputs)
                     .add_variables(&vars)
                     .add_environment(&pipeline.environment)
                     .add_environment(&env)
+                    .add_secrets(&secrets)
                     .run_id(&self.run_id)
                     .run_start_time(&self.run_start_time)
                     .regex_cache(self.regex_cache.clone())
                     .build()?;
 
                 pipeline.apply_tokens(&pipeline_context).await?;
+                let symbols = pipeline_context.symbols().into_arc();
 
                 VersionedRunner::V2(v2::Runner {
                     run_id: self.run_id,
@@ -227,6 +238,8 @@ impl RunnerBuilder {
                     env,
                     context,
                     platform: None,
+                    symbols,
+                    secrets: secret_values.into_arc(),
                     is_child: self.is_child,
                     has_faulted: false,
                 })
@@ -1,9 +1,12 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use bld_config::definitions::{
-    KEYWORD_BLD_DIR_V2, KEYWORD_PROJECT_DIR_V2, KEYWORD_RUN_PROPS_ID_V2,
-    KEYWORD_RUN_PROPS_START_TIME_V2,
+    KEYWORD_BLD_DIR_V2, KEYWORD_MATRIX_V2, KEYWORD_PROJECT_DIR_V2, KEYWORD_RUN_PROPS_ID_V2,
+    KEYWORD_RUN_PROPS_START_TIME_V2, KEYWORD_SECRETS_V2,
 };
 use bld_core::regex::RegexCache;
 use bld_utils::sync::IntoArc;
@@ -15,6 +18,7 @@ pub struct PipelineContextBuilder<'a> {
     project_dir: Option<&'a str>,
     variables: HashMap<String, String>,
     environment: HashMap<String, String>,
+    secrets: HashMap<String, String>,
     run_id: Option<&'a str>,
     run_start_time: Option<&'a str>,
     regex_cache: Option<Arc<RegexCache>>,
@@ -45,6 +49,13 @@ impl<'a> PipelineContextBuilder<'a> {
         self
     }
 
+    pub fn add_secrets(mut self, secrets: &HashMap<String, String>) -> Self {
+        for (k, v) in secrets.iter() {
+            self.secrets.insert(k.to_owned(), v.to_owned());
+        }
+        self
+    }
+
     pub fn run_id(mut self, run_id: &'a str) -> Self {
         self.run_id = Some(run_id);
         self
@@ -86,8 +97,10 @@ impl<'a> PipelineContextBuilder<'a> {
             project_dir,
             variables: self.variables,
             environment: self.environment,
+            secrets: self.secrets,
             run_id,
             run_start_time,
+            matrix: BTreeMap::new(),
             regex_cache,
         })
     }
@@ -98,14 +111,30 @@ pub struct PipelineContext<'a> {
     pub project_dir: &'a str,
     pub variables: HashMap<String, String>,
     pub environment: HashMap<String, String>,
+    pub secrets: HashMap<String, String>,
     pub run_id: &'a str,
     pub run_start_time: &'a str,
+    pub matrix: BTreeMap<String, String>,
     regex_cache: Arc<RegexCache>,
 }
 
 impl<'a> PipelineContext<'a> {
-    fn get_regex_pattern(keyword: &'a str) -> String {
-        format!("{}{}{}", r"\$\{\{\s*", keyword, r"\s*\}\}")
+    fn get_regex_pattern(keyword: &str) -> String {
+        format!("{}{}{}", r"\$\{\{\s*", regex::escape(keyword), r"\s*\}\}")
+    }
+
+    pub fn with_matrix(&self, matrix: &BTreeMap<String, String>) -> PipelineContext<'a> {
+        PipelineContext {
+            root_dir: self.root_dir,
+            project_dir: self.project_dir,
+            variables: self.variables.clone(),
+            environment: self.environment.clone(),
+            secrets: self.secrets.clone(),
+            run_id: self.run_id,
+            run_start_time: self.run_start_time,
+            matrix: matrix.clone(),
+            regex_cache: self.regex_cache.clone(),
+        }
     }
 
     async fn cache_new_regex(&self, pattern: String) -> Result<Arc<Regex>> {
@@ -164,6 +193,45 @@ impl<'a> PipelineContext<'a> {
         Ok(text)
     }
 
+    async fn matrix_transform(&'a self, mut text: String) -> Result<String> {
+        for (k, v) in self.matrix.iter() {
+            let pattern = Self::get_regex_pattern(&format!("{KEYWORD_MATRIX_V2}.{k}"));
+            let re = match self.regex_cache.get(pattern.clone()).await? {
+                Some(v) => v,
+                None => self.cache_new_regex(pattern).await?,
+            };
+            text = re.replace_all(&text, v).to_string();
+        }
+        Ok(text)
+    }
+
+    async fn secrets_transform(&'a self, mut text: String) -> Result<String> {
+        for (k, v) in self.secrets.iter() {
+            let pattern = Self::get_regex_pattern(&format!("{KEYWORD_SECRETS_V2}.{k}"));
+            let re = match self.regex_cache.get(pattern.clone()).await? {
+                Some(v) => v,
+                None => self.cache_new_regex(pattern).await?,
+            };
+            text = re.replace_all(&text, regex::NoExpand(v)).to_string();
+        }
+
+        // a secret that is still referenced after the transform isn't available to
+        // the run and leaving the expression in place would run the step without it.
+        let pattern = format!(
+            r"\$\{{\{{\s*{}\.([\w.]+)\s*\}}\}}",
+            regex::escape(KEYWORD_SECRETS_V2)
+        );
+        let re = match self.regex_cache.get(pattern.clone()).await? {
+            Some(v) => v,
+            None => self.cache_new_regex(pattern).await?,
+        };
+        if let Some(name) = re.captures(&text).and_then(|x| x.get(1)) {
+            bail!("secret {} isn't available to the run", name.as_str());
+        }
+
+        Ok(text)
+    }
+
     async fn run_id_transform(&'a self, text: String) -> Result<String> {
         let pattern = Self::get_regex_pattern(KEYWORD_RUN_PROPS_ID_V2);
         let re = match self.regex_cache.get(pattern.clone()).await? {
@@ -182,12 +250,84 @@ impl<'a> PipelineContext<'a> {
         Ok(re.replace_all(&text, self.run_start_time).to_string())
     }
 
+    pub fn symbols(&self) -> HashMap<String, String> {
+        let mut symbols = HashMap::new();
+        symbols.insert(KEYWORD_BLD_DIR_V2.to_owned(), self.root_dir.to_owned());
+        symbols.insert(
+            KEYWORD_PROJECT_DIR_V2.to_owned(),
+            self.project_dir.to_owned(),
+        );
+        symbols.insert(KEYWORD_RUN_PROPS_ID_V2.to_owned(), self.run_id.to_owned());
+        symbols.insert(
+            KEYWORD_RUN_PROPS_START_TIME_V2.to_owned(),
+            self.run_start_time.to_owned(),
+        );
+
+        for (k, v) in self.environment.iter() {
+            symbols.insert(k.to_owned(), v.to_owned());
+        }
+
+        for (k, v) in self.variables.iter() {
+            symbols.insert(k.to_owned(), v.to_owned());
+        }
+
+        symbols
+    }
+
     pub async fn transform(&self, mut text: String) -> Result<String> {
         text = self.root_dir_transform(text).await?;
         text = self.project_dir_transform(text).await?;
         text = self.run_id_transform(text).await?;
         text = self.run_start_time_transform(text).await?;
+        text = self.matrix_transform(text).await?;
+        text = self.secrets_transform(text).await?;
         text = self.variables_transform(text).await?;
         self.environment_transform(text).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(secrets: &HashMap<String, String>) -> PipelineContext<'static> {
+        PipelineContextBuilder::default()
+            .root_dir("/root")
+            .project_dir("/project")
+            .add_secrets(secrets)
+            .run_id("run")
+            .run_start_time("now")
+            .regex_cache(RegexCache::default().into_arc())
+            .build()
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn secrets_are_replaced_with_their_values() {
+        let secrets = HashMap::from([("token".to_owned(), "$value".to_owned())]);
+        let text = context(&secrets)
+            .transform("echo ${{ secrets.token }} ${{secrets.token}}".to_owned())
+            .await
+            .unwrap();
+        assert_eq!(text, "echo $value $value");
+    }
+
+    #[actix_web::test]
+    async fn unknown_secrets_are_rejected() {
+        let secrets = HashMap::from([("token".to_owned(), "value".to_owned())]);
+        let error = context(&secrets)
+            .transform("echo ${{ secrets.token }} ${{ secrets.other }}".to_owned())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "secret other isn't available to the run");
+    }
+
+    #[actix_web::test]
+    async fn secrets_are_rejected_without_secrets() {
+        let error = context(&HashMap::new())
+            .transform("echo ${{ secrets.token }}".to_owned())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "secret token isn't available to the run");
+    }
+}
@@ -1,29 +1,40 @@
 use crate::{
+    checkout::v2::Checkout,
+    expression::v2::Expression,
+    inputs::v2::{Input, InputType},
+    matrix::v2::Matrix,
     pipeline::v2::Pipeline,
+    publish::v2::Publish,
     registry::v2::Registry,
-    runs_on::v2::RunsOn,
+    runs_on::v2::{ContainerSettings, KubernetesSettings, RunsOn},
+    services::v2::Service,
     step::v2::{BuildStep, BuildStepExec},
 };
 use anyhow::{bail, Result};
 use bld_config::{
     definitions::{
-        KEYWORD_BLD_DIR_V2, KEYWORD_PROJECT_DIR_V2, KEYWORD_RUN_PROPS_ID_V2,
-        KEYWORD_RUN_PROPS_START_TIME_V2,
+        KEYWORD_BLD_DIR_V2, KEYWORD_CHECKOUT_COMMIT_V2, KEYWORD_MATRIX_V2, KEYWORD_PROJECT_DIR_V2,
+        KEYWORD_RUN_PROPS_ID_V2, KEYWORD_RUN_PROPS_START_TIME_V2, KEYWORD_SECRETS_V2, PUSH,
     },
-    DockerUrl,
+    ContainerEngine, DockerUrl,
 };
-use bld_config::{path, BldConfig, SshUserAuth};
+use bld_config::{path, BldConfig, BldNotificationTarget, SshConfig, SshUserAuth};
 use bld_core::fs::FileSystem;
+use bld_models::secrets;
 use bld_utils::fs::IsYaml;
 use cron::Schedule;
 use regex::Regex;
 use std::{
     collections::{HashMap, HashSet},
     fmt::Write,
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
     str::FromStr,
     sync::Arc,
 };
+use tracing::warn;
+
+/// The paths of the docker socket which can't be used by rootless podman.
+const DOCKER_SOCKETS: [&str; 2] = ["/var/run/docker.sock", "/run/docker.sock"];
 
 pub struct PipelineValidator<'a> {
     pipeline: &'a Pipeline,
@@ -32,7 +43,10 @@ pub struct PipelineValidator<'a> {
     regex: Regex,
     keywords: HashSet<&'a str>,
     symbols: HashSet<&'a str>,
+    matrix_symbols: HashSet<String>,
+    secrets: Option<HashSet<String>>,
     errors: String,
+    warnings: String,
 }
 
 impl<'a> PipelineValidator<'a> {
@@ -41,7 +55,7 @@ impl<'a> PipelineValidator<'a> {
         config: Arc<BldConfig>,
         fs: Arc<FileSystem>,
     ) -> Result<Self> {
-        let regex = Regex::new(r"\$\{\{\s*(\b\w+\b)\s*\}\}")?;
+        let regex = Regex::new(r"\$\{\{\s*(\b[\w.]+\b)\s*\}\}")?;
         let keywords = Self::prepare_keywords();
         let symbols = Self::prepare_symbols(pipeline);
         let errors = String::new();
@@ -52,7 +66,10 @@ impl<'a> PipelineValidator<'a> {
             regex,
             keywords,
             symbols,
+            matrix_symbols: HashSet::new(),
+            secrets: None,
             errors,
+            warnings: String::new(),
         })
     }
 
@@ -62,6 +79,7 @@ impl<'a> PipelineValidator<'a> {
         keywords.insert(KEYWORD_PROJECT_DIR_V2);
         keywords.insert(KEYWORD_RUN_PROPS_ID_V2);
         keywords.insert(KEYWORD_RUN_PROPS_START_TIME_V2);
+        keywords.insert(KEYWORD_CHECKOUT_COMMIT_V2);
         keywords
     }
 
@@ -76,6 +94,10 @@ impl<'a> PipelineValidator<'a> {
             symbols.insert(k);
         }
 
+        for (k, _) in pipeline.inputs.iter() {
+            symbols.insert(k);
+        }
+
         for (k, _) in pipeline.environment.iter() {
             symbols.insert(k);
         }
@@ -83,14 +105,14 @@ impl<'a> PipelineValidator<'a> {
         symbols
     }
 
+    /// Validates the pipeline, the warnings are only logged since they don't
+    /// prevent the pipeline from running.
     pub async fn validate(mut self) -> Result<()> {
-        self.validate_runs_on();
-        self.validate_cron();
-        self.validate_variables(None, &self.pipeline.variables);
-        self.validate_environment(None, &self.pipeline.environment);
-        self.validate_external().await;
-        self.validate_artifacts();
-        self.validate_jobs().await;
+        self.check().await;
+
+        for warning in self.warnings.lines() {
+            warn!("{warning}");
+        }
 
         if !self.errors.is_empty() {
             bail!(self.errors)
@@ -99,6 +121,22 @@ impl<'a> PipelineValidator<'a> {
         Ok(())
     }
 
+    async fn check(&mut self) {
+        self.load_secrets().await;
+        self.validate_runs_on("runs_on", &self.pipeline.runs_on);
+        self.validate_cron();
+        self.validate_variables(None, &self.pipeline.variables);
+        self.validate_inputs();
+        self.validate_environment(None, &self.pipeline.environment);
+        self.validate_external().await;
+        self.validate_artifacts();
+        self.validate_cache();
+        self.validate_services();
+        self.validate_publish();
+        self.validate_notifications();
+        self.validate_jobs().await;
+    }
+
     fn sanitize_symbol(symbol: &'a str) -> &'a str {
         symbol[3..symbol.len() - 2].trim()
     }
@@ -111,7 +149,7 @@ impl<'a> PipelineValidator<'a> {
 
     fn validate_symbols(&mut self, section: &str, value: &'a str) {
         for symbol in self.regex.find_iter(value).map(|x| x.as_str()) {
-            if !self.symbols.contains(Self::sanitize_symbol(symbol)) {
+            if !self.is_symbol(Self::sanitize_symbol(symbol)) {
                 let _ = writeln!(
                     self.errors,
                     "[{section} > {symbol}] Expression isn't a keyword or variable",
@@ -120,55 +158,154 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
+    /// The checkout commit is only known once a checkout step has been executed, so
+    /// it's only available to the shell commands of the steps that follow it.
+    fn validate_shell_symbols(&mut self, section: &str, value: &'a str) {
+        for symbol in self.regex.find_iter(value).map(|x| x.as_str()) {
+            let name = Self::sanitize_symbol(symbol);
+            if name != KEYWORD_CHECKOUT_COMMIT_V2 && !self.is_symbol(name) {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > {symbol}] Expression isn't a keyword or variable",
+                );
+            }
+        }
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.symbols.contains(symbol)
+            || self.matrix_symbols.contains(symbol)
+            || self.is_secret(symbol)
+    }
+
+    /// Loads the names of the secrets stored in the server so that references to unknown
+    /// secrets are rejected, a local file system has no secrets so only the format of the
+    /// symbol can be validated.
+    async fn load_secrets(&mut self) {
+        let Some(conn) = self.fs.get_conn() else {
+            return;
+        };
+
+        // secrets can't be decrypted without a key so none of them is available to a run.
+        if self.config.local.server.secrets_key.is_none() {
+            self.secrets = Some(HashSet::new());
+            return;
+        }
+
+        match secrets::select_all(conn.as_ref()).await {
+            Ok(secrets) => self.secrets = Some(secrets.into_iter().map(|x| x.name).collect()),
+            Err(e) => {
+                let _ = writeln!(self.errors, "[secrets] Unable to load secrets, {e}");
+            }
+        }
+    }
+
+    fn is_secret(&self, symbol: &str) -> bool {
+        let Some(name) = symbol
+            .strip_prefix(KEYWORD_SECRETS_V2)
+            .and_then(|x| x.strip_prefix('.'))
+            .filter(|x| !x.is_empty())
+        else {
+            return false;
+        };
+        self.secrets.as_ref().is_none_or(|x| x.contains(name))
+    }
+
     fn contains_symbols(&mut self, value: &str) -> bool {
         self.regex.find(value).is_some()
     }
 
-    fn validate_runs_on(&mut self) {
-        match &self.pipeline.runs_on {
+    fn validate_runs_on(&mut self, section: &str, runs_on: &'a RunsOn) {
+        match runs_on {
             RunsOn::Build {
                 name,
                 tag,
                 dockerfile,
+                context,
+                build_args,
+                target,
+                labels,
                 docker_url,
+                settings,
+                ..
             } => {
-                self.validate_symbols("runs_on > name", name);
-                self.validate_symbols("runs_on > tag", tag);
-                self.validate_symbols("runs_on > dockerfile", dockerfile);
-                self.validate_file_path("runs_on > dockerfile", dockerfile);
+                self.validate_symbols(&format!("{section} > name"), name);
+                self.validate_symbols(&format!("{section} > tag"), tag);
+                self.validate_symbols(&format!("{section} > dockerfile"), dockerfile);
+                self.validate_file_path(&format!("{section} > dockerfile"), dockerfile);
+                if let Some(context) = context {
+                    self.validate_symbols(&format!("{section} > context"), context);
+                    self.validate_dir_path(&format!("{section} > context"), context);
+                }
+                for (key, value) in build_args.iter().flatten() {
+                    self.validate_symbols(&format!("{section} > build_args > {key}"), value);
+                }
+                if let Some(target) = target {
+                    self.validate_symbols(&format!("{section} > target"), target);
+                    if target.trim().is_empty() {
+                        let _ = writeln!(self.errors, "[{section} > target] Can't be empty");
+                    }
+                }
+                for (key, value) in labels.iter().flatten() {
+                    self.validate_symbols(&format!("{section} > labels > {key}"), value);
+                }
                 if let Some(docker_url) = docker_url {
-                    self.validate_symbols("runs_on > docker_url", docker_url);
-                    self.validate_docker_url(docker_url);
+                    self.validate_symbols(&format!("{section} > docker_url"), docker_url);
+                    self.validate_docker_url(&format!("{section} > docker_url"), docker_url);
                 }
+                self.validate_container_settings(section, settings);
             }
 
             RunsOn::Pull {
                 image,
                 docker_url,
-                pull: _pull,
+                pull,
+                pull_policy,
                 registry,
+                settings,
             } => {
-                self.validate_symbols("runs_on > image", image);
+                self.validate_symbols(&format!("{section} > image"), image);
+                self.validate_image_reference(&format!("{section} > image"), image);
+                self.validate_pull_policy(section, *pull, pull_policy.is_some());
                 if let Some(docker_url) = docker_url {
-                    self.validate_symbols("runs_on > docker_url", docker_url);
-                    self.validate_docker_url(docker_url);
+                    self.validate_symbols(&format!("{section} > docker_url"), docker_url);
+                    self.validate_docker_url(&format!("{section} > docker_url"), docker_url);
                 }
                 if let Some(registry) = registry {
-                    self.validate_registry("runs_on > registry", registry);
+                    self.validate_registry(&format!("{section} > registry"), registry);
                 }
+                self.validate_container_settings(section, settings);
             }
 
-            RunsOn::ContainerOrMachine(value) => self.validate_symbols("runs_on", value),
+            RunsOn::ContainerOrMachine(value) => self.validate_symbols(section, value),
+
+            RunsOn::Kubernetes(_) if !self.pipeline.services.is_empty() => {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section}] Services aren't supported for kubernetes platforms"
+                );
+            }
+
+            RunsOn::Kubernetes(settings) => self.validate_kubernetes(section, settings),
+
+            RunsOn::SshFromGlobalConfig { .. } | RunsOn::Ssh(_)
+                if !self.pipeline.services.is_empty() =>
+            {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section}] Services aren't supported for ssh platforms"
+                );
+            }
 
             RunsOn::SshFromGlobalConfig { ssh_config } => {
-                self.validate_symbols("runs_on > ssh_config", ssh_config);
-                self.validate_global_ssh_config("runs_on > ssh_config", ssh_config);
+                self.validate_symbols(&format!("{section} > ssh_config"), ssh_config);
+                self.validate_global_ssh_config(&format!("{section} > ssh_config"), ssh_config);
             }
 
             RunsOn::Ssh(config) => {
-                self.validate_symbols("runs_on > host", &config.host);
-                self.validate_symbols("runs_on > port", &config.port);
-                self.validate_symbols("runs_on > user", &config.user);
+                self.validate_symbols(&format!("{section} > host"), &config.host);
+                self.validate_symbols(&format!("{section} > port"), &config.port);
+                self.validate_symbols(&format!("{section} > user"), &config.user);
                 match &config.userauth {
                     SshUserAuth::Agent => {}
                     SshUserAuth::Keys {
@@ -176,16 +313,173 @@ impl<'a> PipelineValidator<'a> {
                         private_key,
                     } => {
                         if let Some(pubkey) = public_key {
-                            self.validate_symbols("runs_on > auth > public_key", pubkey);
-                            self.validate_file_path("runs_on > auth > public_key", pubkey);
+                            self.validate_symbols(
+                                &format!("{section} > auth > public_key"),
+                                pubkey,
+                            );
+                            self.validate_file_path(
+                                &format!("{section} > auth > public_key"),
+                                pubkey,
+                            );
                         }
-                        self.validate_symbols("runs_on > auth > private_key", private_key);
-                        self.validate_file_path("runs_on > auth > private_key", private_key);
+                        self.validate_symbols(
+                            &format!("{section} > auth > private_key"),
+                            private_key,
+                        );
+                        self.validate_file_path(
+                            &format!("{section} > auth > private_key"),
+                            private_key,
+                        );
                     }
                     SshUserAuth::Password { password } => {
-                        self.validate_symbols("runs_on > auth > password", password);
+                        self.validate_symbols(&format!("{section} > auth > password"), password);
                     }
                 }
+                self.validate_ssh_host_key(section, config);
+            }
+        }
+    }
+
+    fn validate_container_settings(&mut self, section: &str, settings: &'a ContainerSettings) {
+        for volume in settings.volumes.iter().flatten() {
+            let section = format!("{section} > volumes");
+            self.validate_symbols(&section, volume);
+            if self.contains_symbols(volume) {
+                continue;
+            }
+            let parts: Vec<&str> = volume.split(':').collect();
+            if !(2..=3).contains(&parts.len()) || parts[..2].iter().any(|x| x.is_empty()) {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > {volume}] Expected a volume in the source:target[:mode] format"
+                );
+            } else if !parts[1].starts_with('/') {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > {volume}] The target of the volume must be an absolute path"
+                );
+            } else if self.is_rootless() && Self::is_docker_socket(parts[0]) {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > {volume}] The docker socket can't be used by rootless podman"
+                );
+            } else if self.is_rootless() && Self::is_host_path(parts[0]) {
+                let _ = writeln!(
+                    self.warnings,
+                    "[{section} > {volume}] Host paths are mounted with the permissions of the user of rootless podman"
+                );
+            }
+        }
+
+        for mount in settings.mounts.iter().flatten() {
+            let section = format!("{section} > mounts");
+            if let Some(source) = &mount.source {
+                self.validate_symbols(&format!("{section} > source"), source);
+            }
+            self.validate_symbols(&format!("{section} > target"), &mount.target);
+
+            let kind = mount.kind();
+            if !matches!(kind, "bind" | "volume" | "tmpfs") {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > type > {kind}] Expected one of bind, volume or tmpfs"
+                );
+            }
+            match (kind, &mount.source) {
+                ("bind" | "volume", None) => {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > source] Required for a {kind} mount"
+                    );
+                }
+                ("tmpfs", Some(_)) => {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > source] Not allowed for a tmpfs mount"
+                    );
+                }
+                _ => {}
+            }
+            if kind == "bind" && self.is_rootless() {
+                let source = mount.source.as_deref().unwrap_or_default();
+                if Self::is_docker_socket(source) {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > source > {source}] The docker socket can't be used by rootless podman"
+                    );
+                } else {
+                    let _ = writeln!(
+                        self.warnings,
+                        "[{section} > source > {source}] Host paths are mounted with the permissions of the user of rootless podman"
+                    );
+                }
+            }
+            if !self.contains_symbols(&mount.target) && !mount.target.starts_with('/') {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > target > {}] Must be an absolute path",
+                    mount.target
+                );
+            }
+        }
+
+        if self.is_rootless() && settings.privileged == Some(true) {
+            let _ = writeln!(
+                self.warnings,
+                "[{section} > privileged] Privileged containers only have the privileges of the user of rootless podman"
+            );
+        }
+
+        if let Some(network) = &settings.network {
+            self.validate_symbols(&format!("{section} > network"), network);
+            if !self.pipeline.services.is_empty() {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > network] Can't be used together with services"
+                );
+            }
+            if network.trim().is_empty() {
+                let _ = writeln!(self.errors, "[{section} > network] Can't be empty");
+            }
+        }
+
+        if let Some(user) = &settings.user {
+            self.validate_symbols(&format!("{section} > user"), user);
+            if user.trim().is_empty() {
+                let _ = writeln!(self.errors, "[{section} > user] Can't be empty");
+            }
+        }
+
+        if let Some(cpus) = settings.cpus {
+            if cpus <= 0.0 {
+                let _ = writeln!(self.errors, "[{section} > cpus] Must be greater than zero");
+            }
+        }
+
+        if let Some(memory) = &settings.memory {
+            self.validate_symbols(&format!("{section} > memory"), memory);
+            if !self.contains_symbols(memory) && settings.memory_in_bytes().is_err() {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > memory > {memory}] Expected a number of bytes optionally followed by b, k, m or g"
+                );
+            }
+        }
+
+        for host in settings.extra_hosts.iter().flatten() {
+            let section = format!("{section} > extra_hosts");
+            self.validate_symbols(&section, host);
+            if self.contains_symbols(host) {
+                continue;
+            }
+            let valid = host
+                .split_once(':')
+                .is_some_and(|(name, ip)| !name.is_empty() && !ip.is_empty());
+            if !valid {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > {host}] Expected a host in the name:ip format"
+                );
             }
         }
     }
@@ -200,26 +494,75 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
-    fn validate_docker_url(&mut self, value: &str) {
+    fn validate_dir_path(&mut self, section: &str, value: &str) {
+        if self.contains_symbols(value) {
+            return;
+        }
+        let path = path![value];
+        if !path.is_dir() {
+            let _ = writeln!(self.errors, "[{section} > {value}] Directory not found");
+        }
+    }
+
+    fn validate_docker_url(&mut self, section: &str, value: &str) {
         if self.contains_symbols(value) {
             return;
         }
+        if self.config.local.container_engine == ContainerEngine::Podman {
+            let _ = writeln!(
+                self.errors,
+                "[{section}] Docker urls can't be used with the podman container engine"
+            );
+            return;
+        }
         match &self.config.local.docker_url {
             DockerUrl::Single(_) => {
                 let _ = writeln!(
                     self.errors,
-                    "[runs_on > docker_url] Only a single docker url is defined in the config file"
+                    "[{section}] Only a single docker url is defined in the config file"
                 );
             }
             DockerUrl::Multiple(urls) => {
                 let url = urls.keys().find(|x| x.as_str() == value);
                 if url.is_none() {
-                    let _ = writeln!(self.errors, "[runs_on > docker_url] The defined docker url key wasn't found in the config file");
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section}] The defined docker url key wasn't found in the config file"
+                    );
                 }
             }
         }
     }
 
+    fn validate_image_reference(&mut self, section: &str, image: &str) {
+        if self.contains_symbols(image) {
+            return;
+        }
+        let Some((repository, digest)) = image.split_once('@') else {
+            return;
+        };
+        let is_valid_digest = digest.strip_prefix("sha256:").is_some_and(|x| {
+            x.len() == 64
+                && x.chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        });
+        if repository.is_empty() || !is_valid_digest {
+            let _ = writeln!(
+                self.errors,
+                "[{section} > {image}] Expected a digest in the image@sha256:<digest> format"
+            );
+        }
+    }
+
+    fn validate_pull_policy(&mut self, section: &str, pull: Option<bool>, has_policy: bool) {
+        if pull.is_some() && has_policy {
+            let _ = writeln!(
+                self.errors,
+                "[{section} > pull_policy] Can't be used together with pull"
+            );
+        }
+    }
+
     fn validate_registry(&mut self, section: &str, registry: &'a Registry) {
         match registry {
             Registry::FromConfig(config) => {
@@ -259,6 +602,77 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
+    fn validate_kubernetes(&mut self, section: &str, settings: &'a KubernetesSettings) {
+        let kubernetes_section = format!("{section} > kubernetes");
+        self.validate_symbols(&kubernetes_section, &settings.kubernetes);
+        if !self.contains_symbols(&settings.kubernetes) {
+            if let Err(e) = self.config.kubernetes(&settings.kubernetes) {
+                let _ = writeln!(self.errors, "[{kubernetes_section}] {e}");
+            }
+        }
+
+        let image_section = format!("{section} > image");
+        self.validate_symbols(&image_section, &settings.image);
+        if settings.image.trim().is_empty() {
+            let _ = writeln!(self.errors, "[{image_section}] Can't be empty");
+        }
+        self.validate_image_reference(&image_section, &settings.image);
+
+        if let Some(namespace) = settings.namespace.as_ref() {
+            self.validate_symbols(&format!("{section} > namespace"), namespace);
+            if namespace.trim().is_empty() {
+                let _ = writeln!(self.errors, "[{section} > namespace] Can't be empty");
+            }
+        }
+
+        if let Some(resources) = settings.resources.as_ref() {
+            for (key, value) in resources.requests.iter().flatten() {
+                self.validate_symbols(&format!("{section} > resources > requests > {key}"), value);
+            }
+            for (key, value) in resources.limits.iter().flatten() {
+                self.validate_symbols(&format!("{section} > resources > limits > {key}"), value);
+            }
+        }
+
+        for (key, value) in settings.node_selector.iter().flatten() {
+            self.validate_symbols(&format!("{section} > node_selector > {key}"), value);
+        }
+
+        if let Some(service_account) = settings.service_account.as_ref() {
+            self.validate_symbols(&format!("{section} > service_account"), service_account);
+        }
+    }
+
+    fn validate_ssh_host_key(&mut self, section: &str, config: &'a SshConfig) {
+        if let Some(known_hosts) = config.known_hosts.as_ref() {
+            self.validate_symbols(&format!("{section} > known_hosts"), known_hosts);
+        }
+
+        let Some(fingerprint) = config.fingerprint.as_ref() else {
+            return;
+        };
+        let fingerprint_section = format!("{section} > fingerprint");
+        self.validate_symbols(&fingerprint_section, fingerprint);
+        if config.known_hosts.is_some() {
+            let _ = writeln!(
+                self.errors,
+                "[{fingerprint_section}] Can't be used together with known_hosts"
+            );
+        }
+        if config.trust_on_first_use {
+            let _ = writeln!(
+                self.errors,
+                "[{fingerprint_section}] Can't be used together with trust_on_first_use"
+            );
+        }
+        if !self.contains_symbols(fingerprint) && !fingerprint.starts_with("SHA256:") {
+            let _ = writeln!(
+                self.errors,
+                "[{fingerprint_section}] Expected a SHA256 fingerprint in the SHA256:<base64> format"
+            );
+        }
+    }
+
     fn validate_cron(&mut self) {
         let Some(cron) = self.pipeline.cron.as_ref() else {
             return;
@@ -281,6 +695,58 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
+    fn validate_inputs(&mut self) {
+        for (name, input) in self.pipeline.inputs.iter() {
+            let section = format!("inputs > {name}");
+            self.validate_keywords(&section, name);
+            if self.pipeline.variables.contains_key(name) {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section}] Invalid name, already declared as a variable"
+                );
+            }
+            self.validate_input(&section, input);
+        }
+    }
+
+    fn validate_input(&mut self, section: &str, input: &'a Input) {
+        match input.input_type {
+            InputType::Choice if input.options.is_empty() => {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > options] A choice input needs at least one option"
+                );
+            }
+            InputType::Choice => {}
+            _ if !input.options.is_empty() => {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > options] Options are only supported for choice inputs"
+                );
+            }
+            _ => {}
+        }
+
+        let Some(default) = input.default.as_ref() else {
+            return;
+        };
+        let default_section = format!("{section} > default");
+        if input.required {
+            let _ = writeln!(
+                self.errors,
+                "[{default_section}] A required input can't have a default value"
+            );
+        }
+        if self.contains_symbols(default) {
+            let _ = writeln!(
+                self.errors,
+                "[{default_section}] Expressions aren't supported in default values"
+            );
+        } else if let Err(e) = input.check(default) {
+            let _ = writeln!(self.errors, "[{default_section}] Invalid value, {e}");
+        }
+    }
+
     fn validate_environment(
         &mut self,
         section: Option<&str>,
@@ -356,7 +822,14 @@ impl<'a> PipelineValidator<'a> {
     fn validate_artifacts(&mut self) {
         for artifact in self.pipeline.artifacts.iter() {
             self.validate_symbols("artifacts > from", &artifact.from);
-            self.validate_symbols("artifacts > to", &artifact.to);
+            if let Some(to) = artifact.to.as_ref() {
+                self.validate_symbols("artifacts > to", to);
+            } else if artifact.method == PUSH {
+                let _ = writeln!(
+                    self.errors,
+                    "[artifacts > to] Required for the {PUSH} method"
+                );
+            }
             self.validate_artifact_after(artifact.after.as_ref());
         }
     }
@@ -376,7 +849,7 @@ impl<'a> PipelineValidator<'a> {
             .pipeline
             .jobs
             .iter()
-            .any(|(name, steps)| name == after || steps.iter().any(|s| s.is(after)));
+            .any(|(name, job)| name == after || job.steps().iter().any(|s| s.is(after)));
 
         if !is_job_or_step {
             let _ = writeln!(
@@ -386,14 +859,416 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
+    fn validate_cache(&mut self) {
+        for entry in self.pipeline.cache.iter() {
+            self.validate_symbols("cache > key", &entry.key);
+
+            if entry.key.trim().is_empty() {
+                let _ = writeln!(self.errors, "[cache > key] Can't be empty");
+            }
+
+            if entry.paths.is_empty() {
+                let _ = writeln!(self.errors, "[cache > paths] At least one path is required");
+            }
+
+            for path in entry.paths.iter() {
+                self.validate_symbols("cache > paths", path);
+
+                // only the home directory of the user that runs the job is supported.
+                let is_home = path == "~" || path.starts_with("~/");
+                let is_valid = (is_home || !path.starts_with('~'))
+                    && !Path::new(path)
+                        .components()
+                        .any(|x| x == Component::ParentDir);
+
+                if !is_valid {
+                    let _ = writeln!(
+                        self.errors,
+                        "[cache > paths > {path}] Must be a relative, absolute or home path without parent directories"
+                    );
+                }
+            }
+
+            for job in entry.jobs.iter() {
+                if !self.pipeline.jobs.contains_key(job) {
+                    let _ = writeln!(
+                        self.errors,
+                        "[cache > jobs > {job}] Not a declared job name"
+                    );
+                }
+            }
+        }
+    }
+
+    fn is_rootless(&self) -> bool {
+        self.config.local.container_engine == ContainerEngine::Podman
+            && self.config.local.podman.rootless
+    }
+
+    fn is_host_path(source: &str) -> bool {
+        source.starts_with('/') || source.starts_with('.') || source.starts_with('~')
+    }
+
+    fn is_docker_socket(source: &str) -> bool {
+        let source = path![source];
+        DOCKER_SOCKETS.iter().any(|x| source == Path::new(x))
+    }
+
+    fn validate_services(&mut self) {
+        for (name, service) in self.pipeline.services.iter() {
+            self.validate_service(name, service);
+        }
+    }
+
+    fn validate_service(&mut self, name: &str, service: &'a Service) {
+        let section = format!("services > {name}");
+
+        let is_valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+        if !is_valid_name {
+            let _ = writeln!(
+                self.errors,
+                "[{section}] The name can only contain alphanumeric characters, '-', '_' or '.'"
+            );
+        }
+
+        self.validate_symbols(&format!("{section} > image"), &service.image);
+        if service.image.trim().is_empty() {
+            let _ = writeln!(self.errors, "[{section} > image] Can't be empty");
+        }
+        self.validate_image_reference(&format!("{section} > image"), &service.image);
+        self.validate_pull_policy(&section, service.pull, service.pull_policy.is_some());
+
+        if let Some(registry) = &service.registry {
+            self.validate_registry(&format!("{section} > registry"), registry);
+        }
+
+        for (key, value) in service.environment.iter() {
+            self.validate_symbols(&format!("{section} > environment > {key}"), value);
+        }
+
+        for arg in service.command.iter().flatten() {
+            self.validate_symbols(&format!("{section} > command"), arg);
+        }
+
+        for port in service.ports.iter() {
+            self.validate_symbols(&format!("{section} > ports"), port);
+            if self.contains_symbols(port) {
+                continue;
+            }
+            let host_port = port.split_once(':').and_then(|(host, container)| {
+                let container = container
+                    .strip_suffix("/tcp")
+                    .or_else(|| container.strip_suffix("/udp"))
+                    .unwrap_or(container);
+                container.parse::<u16>().ok()?;
+                host.parse::<u16>().ok()
+            });
+            match host_port {
+                None => {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > ports > {port}] Expected a port in the host:container[/protocol] format"
+                    );
+                }
+                Some(host_port) if host_port < 1024 && self.is_rootless() => {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > ports > {port}] Privileged ports can't be published by rootless podman"
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(health_check) = &service.health_check {
+            let section = format!("{section} > health_check");
+            self.validate_symbols(&format!("{section} > command"), &health_check.command);
+            if health_check.command.trim().is_empty() {
+                let _ = writeln!(self.errors, "[{section} > command] Can't be empty");
+            }
+            if health_check.interval() == 0 {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > interval] Must be greater than zero"
+                );
+            }
+            if health_check.timeout() == 0 {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > timeout] Must be greater than zero"
+                );
+            }
+            if health_check.retries() == 0 {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > retries] Must be greater than zero"
+                );
+            }
+        }
+    }
+
+    fn validate_publish(&mut self) {
+        for entry in self.pipeline.publish.iter() {
+            self.validate_publish_entry(entry);
+        }
+    }
+
+    fn validate_publish_entry(&mut self, entry: &'a Publish) {
+        let section = format!("publish > {}", entry.image);
+
+        self.validate_symbols(&format!("{section} > image"), &entry.image);
+        if entry.image.trim().is_empty() {
+            let _ = writeln!(self.errors, "[publish > image] Can't be empty");
+        }
+
+        self.validate_symbols(&format!("{section} > repository"), &entry.repository);
+        if entry.repository.trim().is_empty() {
+            let _ = writeln!(self.errors, "[{section} > repository] Can't be empty");
+        }
+
+        for tag in entry.tags.iter() {
+            self.validate_symbols(&format!("{section} > tags"), tag);
+            if tag.trim().is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ':') {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > tags > {tag}] Not a valid image tag"
+                );
+            }
+        }
+
+        if let Some(registry) = &entry.registry {
+            self.validate_registry(&format!("{section} > registry"), registry);
+        }
+
+        if let Some(docker_url) = &entry.docker_url {
+            self.validate_symbols(&format!("{section} > docker_url"), docker_url);
+            self.validate_docker_url(&format!("{section} > docker_url"), docker_url);
+        }
+
+        if let Some(after) = &entry.after {
+            if !self.pipeline.jobs.contains_key(after) {
+                let _ = writeln!(
+                    self.errors,
+                    "[{section} > after > {after}] Not a declared job name"
+                );
+            }
+        }
+    }
+
+    fn validate_notifications(&mut self) {
+        let Some(notifications) = self.pipeline.notifications.as_ref() else {
+            return;
+        };
+        for (i, notification) in notifications.iter().enumerate() {
+            let section = format!("notifications > {i}");
+            self.validate_notification_target(&section, &notification.target);
+        }
+    }
+
+    fn validate_notification_target(&mut self, section: &str, target: &'a BldNotificationTarget) {
+        match target {
+            BldNotificationTarget::Webhook { url, headers } => {
+                self.validate_notification_url(&format!("{section} > url"), url);
+                for (name, value) in headers.iter() {
+                    self.validate_symbols(&format!("{section} > headers > {name}"), value);
+                }
+            }
+
+            BldNotificationTarget::Slack { url } | BldNotificationTarget::Teams { url } => {
+                self.validate_notification_url(&format!("{section} > url"), url);
+            }
+
+            BldNotificationTarget::Matrix {
+                homeserver,
+                room_id,
+                access_token,
+            } => {
+                self.validate_notification_url(&format!("{section} > homeserver"), homeserver);
+
+                self.validate_symbols(&format!("{section} > room_id"), room_id);
+                if !self.contains_symbols(room_id) && !room_id.starts_with('!') {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > room_id] Must be a room id starting with !"
+                    );
+                }
+
+                self.validate_symbols(&format!("{section} > access_token"), access_token);
+                if access_token.trim().is_empty() {
+                    let _ = writeln!(self.errors, "[{section} > access_token] Can't be empty");
+                }
+            }
+
+            BldNotificationTarget::Email { to } => {
+                if to.is_empty() {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > to] At least one address is required"
+                    );
+                }
+                for address in to.iter() {
+                    self.validate_symbols(&format!("{section} > to"), address);
+                    if !self.contains_symbols(address) && !address.contains('@') {
+                        let _ = writeln!(
+                            self.errors,
+                            "[{section} > to > {address}] Not a valid email address"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_notification_url(&mut self, section: &str, url: &'a str) {
+        self.validate_symbols(section, url);
+        if self.contains_symbols(url) {
+            return;
+        }
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            let _ = writeln!(self.errors, "[{section}] Must be an http or https url");
+        }
+    }
+
     async fn validate_jobs(&mut self) {
-        for (job, steps) in self.pipeline.jobs.iter() {
-            for step in steps.iter() {
-                self.validate_step(job, step).await;
+        for (name, job) in self.pipeline.jobs.iter() {
+            self.validate_job_needs(name, job.needs());
+            if let Some(matrix) = job.matrix() {
+                self.validate_matrix(name, matrix);
+                self.matrix_symbols = matrix
+                    .keys()
+                    .into_iter()
+                    .map(|k| format!("{KEYWORD_MATRIX_V2}.{k}"))
+                    .collect();
+            }
+            if let Some(runs_on) = job.runs_on() {
+                self.validate_runs_on(&format!("jobs > {name} > runs_on"), runs_on);
+            }
+            if let Some(condition) = job.condition() {
+                self.validate_condition(&format!("jobs > {name} > if"), condition);
+            }
+            if job.steps().is_empty() {
+                let _ = writeln!(
+                    self.errors,
+                    "[jobs > {name} > steps] At least one step is required"
+                );
+            }
+            for step in job.steps().iter() {
+                self.validate_step(name, step).await;
+            }
+            self.matrix_symbols.clear();
+        }
+        self.validate_job_cycles();
+    }
+
+    fn validate_matrix(&mut self, job: &str, matrix: &'a Matrix) {
+        let section = format!("jobs > {job} > matrix");
+
+        if matrix.values.is_empty() && matrix.include.is_empty() {
+            let _ = writeln!(
+                self.errors,
+                "[{section}] At least one matrix value or include entry is required"
+            );
+        }
+
+        if matrix.max_parallel == Some(0) {
+            let _ = writeln!(
+                self.errors,
+                "[{section} > max_parallel] The max_parallel must be greater than zero"
+            );
+        }
+
+        for (k, values) in matrix.values.iter() {
+            if values.is_empty() {
+                let _ = writeln!(self.errors, "[{section} > {k}] No values declared");
+            }
+            for value in values.iter() {
+                self.validate_symbols(&format!("{section} > {k}"), value);
+            }
+        }
+
+        for entry in matrix.include.iter() {
+            for (k, v) in entry.iter() {
+                self.validate_symbols(&format!("{section} > include > {k}"), v);
+            }
+        }
+
+        for entry in matrix.exclude.iter() {
+            for k in entry.keys() {
+                if !matrix.values.contains_key(k) {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > exclude > {k}] Not a declared matrix key"
+                    );
+                }
+            }
+        }
+    }
+
+    fn validate_job_needs(&mut self, job: &str, needs: &'a [String]) {
+        for need in needs.iter() {
+            if need == job {
+                let _ = writeln!(
+                    self.errors,
+                    "[jobs > {job} > needs > {need}] A job can't depend on itself"
+                );
+            } else if !self.pipeline.jobs.contains_key(need) {
+                let _ = writeln!(
+                    self.errors,
+                    "[jobs > {job} > needs > {need}] Not a declared job name"
+                );
             }
         }
     }
 
+    fn validate_job_cycles(&mut self) {
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut names: Vec<&String> = self.pipeline.jobs.keys().collect();
+        names.sort();
+
+        for name in names {
+            let mut path = vec![];
+            if let Some(cycle) = self.find_job_cycle(name, &mut path, &mut visited) {
+                let _ = writeln!(
+                    self.errors,
+                    "[jobs > {} > needs] Circular dependency between jobs {}",
+                    cycle[0],
+                    cycle.join(" -> ")
+                );
+            }
+        }
+    }
+
+    fn find_job_cycle(
+        &self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(position) = path.iter().position(|x| *x == name) {
+            let mut cycle = path[position..].to_vec();
+            cycle.push(name);
+            return Some(cycle);
+        }
+
+        if !visited.insert(name) {
+            return None;
+        }
+
+        let job = self.pipeline.jobs.get(name)?;
+        path.push(name);
+        for need in job.needs().iter().filter(|x| x.as_str() != name) {
+            if let Some(cycle) = self.find_job_cycle(need, path, visited) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+
+        None
+    }
+
     async fn validate_step(&mut self, job: &str, step: &'a BuildStep) {
         let mut section = format!("jobs > {job} > steps");
 
@@ -405,11 +1280,25 @@ impl<'a> PipelineValidator<'a> {
                 exec,
                 working_dir,
                 name,
+                condition,
+                timeout,
+                ..
             } => {
                 if let Some(name) = name {
                     let _ = write!(section, " > {name}");
                 }
 
+                if *timeout == Some(0) {
+                    let _ = writeln!(
+                        self.errors,
+                        "[{section} > timeout] The timeout must be greater than zero"
+                    );
+                }
+
+                if let Some(condition) = condition.as_ref() {
+                    self.validate_condition(&format!("{section} > if"), condition);
+                }
+
                 if let Some(wd) = working_dir.as_ref() {
                     self.validate_symbols(&section, wd)
                 }
@@ -421,14 +1310,76 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 
+    fn validate_condition(&mut self, section: &str, condition: &'a str) {
+        match Expression::parse(condition) {
+            Ok(expression) => {
+                for symbol in expression.symbols() {
+                    if !self.is_symbol(symbol) {
+                        let _ = writeln!(
+                            self.errors,
+                            "[{section} > {symbol}] Expression isn't a keyword or variable",
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(self.errors, "[{section} > {condition}] {e}");
+            }
+        }
+    }
+
     async fn validate_exec(&mut self, section: &str, step: &'a BuildStepExec) {
         match step {
             BuildStepExec::Shell(value) => {
-                self.validate_symbols(section, value);
+                self.validate_shell_symbols(section, value);
             }
             BuildStepExec::External { value } => {
                 self.validate_exec_ext(section, value).await;
             }
+            BuildStepExec::Checkout { settings } => {
+                self.validate_checkout(&format!("{section} > checkout"), settings);
+            }
+        }
+    }
+
+    fn validate_checkout(&mut self, section: &str, checkout: &'a Checkout) {
+        let repository_section = format!("{section} > repository");
+        self.validate_symbols(&repository_section, &checkout.repository);
+        if checkout.repository.trim().is_empty() {
+            let _ = writeln!(self.errors, "[{repository_section}] Can't be empty");
+        }
+
+        if let Some(git_ref) = checkout.git_ref.as_ref() {
+            self.validate_symbols(&format!("{section} > ref"), git_ref);
+            if git_ref.trim().is_empty() {
+                let _ = writeln!(self.errors, "[{section} > ref] Can't be empty");
+            }
+        }
+
+        if let Some(path) = checkout.path.as_ref() {
+            self.validate_symbols(&format!("{section} > path"), path);
+        }
+
+        if checkout.depth == Some(0) {
+            let _ = writeln!(self.errors, "[{section} > depth] Must be greater than 0");
+        }
+
+        let Some(credentials) = checkout.credentials.as_ref() else {
+            return;
+        };
+        let credentials_section = format!("{section} > credentials");
+        self.validate_symbols(&credentials_section, credentials);
+        if self.contains_symbols(credentials) {
+            return;
+        }
+        if let Err(e) = self.config.git(credentials) {
+            let _ = writeln!(self.errors, "[{credentials_section}] {e}");
+        }
+        if !self.contains_symbols(&checkout.repository) && checkout.origin().is_none() {
+            let _ = writeln!(
+                self.errors,
+                "[{credentials_section}] Credentials are only supported for http and https repositories"
+            );
         }
     }
 
@@ -455,3 +1406,506 @@ impl<'a> PipelineValidator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use bld_models::new_connection_pool;
+    use uuid::Uuid;
+
+    use super::*;
+
+    async fn validate(yaml: &str) -> Result<()> {
+        validate_with_config(yaml, BldConfig::default()).await
+    }
+
+    async fn validate_with_config(yaml: &str, config: BldConfig) -> Result<()> {
+        let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+        let config = Arc::new(config);
+        let fs = Arc::new(FileSystem::local(config.clone()));
+        PipelineValidator::new(&pipeline, config, fs)?
+            .validate()
+            .await
+    }
+
+    /// Validates the pipeline with the file system of a server that has the given secrets.
+    async fn validate_on_server(
+        yaml: &str,
+        secrets_key: Option<&str>,
+        names: &[&str],
+    ) -> Result<()> {
+        let dir = path![temp_dir(), format!("bld-validator-{}", Uuid::new_v4())];
+        std::fs::create_dir_all(&dir)?;
+        let mut config = BldConfig::default();
+        config.local.server.db = Some(format!(
+            "sqlite://{}?mode=rwc",
+            path![&dir, "test.db"].display()
+        ));
+        config.local.server.secrets_key = secrets_key.map(|x| x.to_owned());
+        let config = Arc::new(config);
+        let conn = Arc::new(new_connection_pool(config.clone()).await?);
+        for name in names {
+            secrets::upsert(conn.as_ref(), secrets_key.unwrap_or("key"), name, "value").await?;
+        }
+
+        let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+        let fs = Arc::new(FileSystem::server(config.clone(), conn));
+        let result = PipelineValidator::new(&pipeline, config, fs)?
+            .validate()
+            .await;
+        let _ = std::fs::remove_dir_all(dir);
+        result
+    }
+
+    fn cycle_errors(result: Result<()>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .to_string()
+            .lines()
+            .filter(|x| x.contains("Circular dependency"))
+            .map(|x| x.to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn jobs_without_cycles() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  build:
+    - echo build
+  test:
+    needs: [build]
+    steps:
+      - echo test
+  lint:
+    needs: [build]
+    steps:
+      - echo lint
+  deploy:
+    needs: [test, lint]
+    steps:
+      - echo deploy
+";
+        validate(yaml).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn direct_cycle() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  a:
+    needs: [b]
+    steps:
+      - echo a
+  b:
+    needs: [a]
+    steps:
+      - echo b
+";
+        assert_eq!(
+            cycle_errors(validate(yaml).await),
+            vec!["[jobs > a > needs] Circular dependency between jobs a -> b -> a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn indirect_cycle() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  a:
+    needs: [b]
+    steps:
+      - echo a
+  b:
+    needs: [c]
+    steps:
+      - echo b
+  c:
+    needs: [d]
+    steps:
+      - echo c
+  d:
+    needs: [b]
+    steps:
+      - echo d
+";
+        assert_eq!(
+            cycle_errors(validate(yaml).await),
+            vec!["[jobs > b > needs] Circular dependency between jobs b -> c -> d -> b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn separate_cycles() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  a:
+    needs: [b]
+    steps:
+      - echo a
+  b:
+    needs: [a]
+    steps:
+      - echo b
+  c:
+    needs: [d]
+    steps:
+      - echo c
+  d:
+    needs: [c]
+    steps:
+      - echo d
+";
+        assert_eq!(
+            cycle_errors(validate(yaml).await),
+            vec![
+                "[jobs > a > needs] Circular dependency between jobs a -> b -> a",
+                "[jobs > c > needs] Circular dependency between jobs c -> d -> c",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn job_without_steps() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  build:
+    - echo build
+  test:
+    needs: [build]
+    step:
+      - echo test
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        assert_eq!(
+            error.trim(),
+            "[jobs > test > steps] At least one step is required"
+        );
+    }
+
+    #[tokio::test]
+    async fn self_and_unknown_needs() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  a:
+    needs: [a, missing]
+    steps:
+      - echo a
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        assert!(error.contains("[jobs > a > needs > a] A job can't depend on itself"));
+        assert!(error.contains("[jobs > a > needs > missing] Not a declared job name"));
+        assert!(!error.contains("Circular dependency"));
+    }
+
+    #[tokio::test]
+    async fn services_are_validated() {
+        let yaml = r"
+runs_on: alpine:latest
+services:
+  db:
+    image: postgres:16
+    environment:
+      POSTGRES_PASSWORD: password
+    ports:
+    - 5432:5432
+    - 5353:53/udp
+    health_check:
+      command: pg_isready
+      interval: 1
+  cache:
+    image: redis:7
+jobs:
+  main:
+    - echo main
+";
+        validate(yaml).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_services_are_rejected() {
+        let yaml = r"
+runs_on: alpine:latest
+services:
+  db server:
+    image: postgres:16
+  cache:
+    image: ''
+    ports:
+    - 6379
+    - 6379:redis
+    health_check:
+      command: ''
+      interval: 0
+      timeout: 0
+      retries: 0
+jobs:
+  main:
+    - echo main
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        let mut errors: Vec<&str> = error.lines().collect();
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                "[services > cache > health_check > command] Can't be empty",
+                "[services > cache > health_check > interval] Must be greater than zero",
+                "[services > cache > health_check > retries] Must be greater than zero",
+                "[services > cache > health_check > timeout] Must be greater than zero",
+                "[services > cache > image] Can't be empty",
+                "[services > cache > ports > 6379:redis] Expected a port in the host:container[/protocol] format",
+                "[services > cache > ports > 6379] Expected a port in the host:container[/protocol] format",
+                "[services > db server] The name can only contain alphanumeric characters, '-', '_' or '.'",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn services_are_rejected_for_unsupported_platforms() {
+        let service = r"
+services:
+  db:
+    image: postgres:16
+jobs:
+  main:
+    - echo main
+";
+        let platforms = [
+            (
+                "runs_on:\n  image: alpine:latest\n  network: host",
+                "[runs_on > network] Can't be used together with services",
+            ),
+            (
+                "runs_on:\n  host: localhost\n  user: bld\n  userauth:\n    type: agent",
+                "[runs_on] Services aren't supported for ssh platforms",
+            ),
+        ];
+        for (runs_on, expected) in platforms {
+            let yaml = format!("{runs_on}\n{service}");
+            let error = validate(&yaml).await.unwrap_err().to_string();
+            assert!(error.lines().any(|x| x == expected), "{error}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rootless_podman_rejects_only_unsupported_operations() {
+        let yaml = r"
+runs_on:
+  image: alpine:latest
+  privileged: true
+  volumes:
+  - /var/run/docker.sock:/var/run/docker.sock
+  - ./data:/data
+  - cache:/cache
+  mounts:
+  - source: /home/user/src
+    target: /src
+  - source: /run/docker.sock
+    target: /var/run/docker.sock
+  - type: volume
+    source: registry
+    target: /registry
+  - type: tmpfs
+    target: /tmp/scratch
+services:
+  db:
+    image: postgres:16
+    ports:
+    - 80:5432
+    - 5432:5432
+jobs:
+  main:
+    - echo main
+";
+        let mut config = BldConfig::default();
+        config.local.container_engine = ContainerEngine::Podman;
+        config.local.podman.rootless = true;
+
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        let config = Arc::new(config);
+        let fs = Arc::new(FileSystem::local(config.clone()));
+        let mut validator = PipelineValidator::new(&pipeline, config, fs).unwrap();
+        validator.check().await;
+
+        let mut errors: Vec<&str> = validator.errors.lines().collect();
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                "[runs_on > mounts > source > /run/docker.sock] The docker socket can't be used by rootless podman",
+                "[runs_on > volumes > /var/run/docker.sock:/var/run/docker.sock] The docker socket can't be used by rootless podman",
+                "[services > db > ports > 80:5432] Privileged ports can't be published by rootless podman",
+            ]
+        );
+
+        let mut warnings: Vec<&str> = validator.warnings.lines().collect();
+        warnings.sort();
+        assert_eq!(
+            warnings,
+            vec![
+                "[runs_on > mounts > source > /home/user/src] Host paths are mounted with the permissions of the user of rootless podman",
+                "[runs_on > privileged] Privileged containers only have the privileges of the user of rootless podman",
+                "[runs_on > volumes > ./data:/data] Host paths are mounted with the permissions of the user of rootless podman",
+            ]
+        );
+
+        validate(yaml).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn secrets_are_validated_by_format_locally() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  main:
+    - echo ${{ secrets.token }}
+    - echo ${{ secrets }}
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        assert_eq!(
+            error.lines().collect::<Vec<&str>>(),
+            vec!["[jobs > main > steps > ${{ secrets }}] Expression isn't a keyword or variable"]
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_secrets_are_rejected_on_server() {
+        let yaml = r"
+runs_on: machine
+jobs:
+  main:
+    - echo ${{ secrets.token }}
+    - echo ${{ secrets.other }}
+";
+        validate_on_server(yaml, Some("key"), &["token", "other"])
+            .await
+            .unwrap();
+
+        let error = validate_on_server(yaml, Some("key"), &["token"])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error.lines().count(), 1, "{error}");
+        assert!(error.contains("${{ secrets.other }}"), "{error}");
+
+        let error = validate_on_server(yaml, None, &["token", "other"])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error.lines().count(), 2, "{error}");
+    }
+
+    #[tokio::test]
+    async fn cache_paths_can_be_relative_absolute_or_home_paths() {
+        let yaml = r"
+runs_on: machine
+cache:
+- key: deps
+  paths:
+  - target
+  - /usr/local/cargo/registry
+  - ~/.npm
+  - ~
+jobs:
+  main:
+    - echo main
+";
+        validate(yaml).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_paths_with_parent_directories_are_rejected() {
+        let yaml = r"
+runs_on: machine
+cache:
+- key: deps
+  paths:
+  - ../target
+  - /usr/local/../cargo
+  - ~/../.npm
+  - ~other/.npm
+jobs:
+  main:
+    - echo main
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        let paths: Vec<&str> = error
+            .lines()
+            .filter_map(|x| x.strip_prefix("[cache > paths > "))
+            .filter_map(|x| x.split(']').next())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "../target",
+                "/usr/local/../cargo",
+                "~/../.npm",
+                "~other/.npm"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn publish_entries_are_validated() {
+        let yaml = r"
+runs_on: machine
+publish:
+- image: app:1.0
+  repository: registry.example.com/app
+  tags: [1.0, latest]
+  after: build
+- image: app:1.0
+  repository: registry.example.com/app
+  registry:
+    url: registry.example.com
+    username: user
+    password: pass
+jobs:
+  build:
+  - echo build
+";
+        validate(yaml).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_publish_entries_are_rejected() {
+        let yaml = r"
+runs_on: machine
+publish:
+- image: app
+  repository: ' '
+  tags: ['', 'v 1', 'a:b']
+  registry: missing
+  after: deploy
+jobs:
+  build:
+  - echo build
+";
+        let error = validate(yaml).await.unwrap_err().to_string();
+        let sections: Vec<&str> = error
+            .lines()
+            .filter_map(|x| x.strip_prefix('['))
+            .filter_map(|x| x.split(']').next())
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                "publish > app > repository",
+                "publish > app > tags > ",
+                "publish > app > tags > v 1",
+                "publish > app > tags > a:b",
+                "publish > app > registry",
+                "publish > app > after > deploy",
+            ],
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::rt::{spawn, time::interval};
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_models::pipeline_run_artifacts;
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::DatabaseConnection;
+use tokio::fs::remove_dir_all;
+use tracing::{debug, error};
+
+const RETENTION_INTERVAL: u64 = 3600;
+
+/// Periodically removes the stored artifacts of runs that are outside of the
+/// retention policies of the server's configuration.
+pub struct ArtifactsRetention {
+    config: Arc<BldConfig>,
+    conn: Arc<DatabaseConnection>,
+}
+
+impl ArtifactsRetention {
+    pub fn start(config: Arc<BldConfig>, conn: Arc<DatabaseConnection>) {
+        let artifacts = &config.local.server.artifacts;
+        if artifacts.max_age.is_none() && artifacts.max_runs.is_none() {
+            debug!("no retention policy set for artifacts");
+            return;
+        }
+
+        let retention = Self { config, conn };
+        spawn(async move {
+            let mut interval = interval(Duration::from_secs(RETENTION_INTERVAL));
+            loop {
+                interval.tick().await;
+                if let Err(e) = retention.apply().await {
+                    error!("unable to apply the artifacts retention policy, {e}");
+                }
+            }
+        });
+    }
+
+    async fn apply(&self) -> Result<()> {
+        let artifacts = &self.config.local.server.artifacts;
+        let runs = pipeline_run_artifacts::select_runs(self.conn.as_ref()).await?;
+        let oldest_date = artifacts
+            .max_age
+            .map(|x| Utc::now().naive_utc() - ChronoDuration::days(x as i64));
+
+        for (index, run) in (0..).zip(runs.iter()) {
+            let over_max_runs = artifacts.max_runs.is_some_and(|x| index >= x);
+            let over_max_age = oldest_date.is_some_and(|x| run.last_date_created < x);
+            if !over_max_runs && !over_max_age {
+                continue;
+            }
+
+            debug!("removing the artifacts of run {}", run.run_id);
+            let dir = self.config.artifacts_full_path(&run.run_id);
+            if dir.is_dir() {
+                remove_dir_all(&dir).await?;
+            }
+            pipeline_run_artifacts::delete_by_run_id(self.conn.as_ref(), &run.run_id).await?;
+        }
+
+        Ok(())
+    }
+}
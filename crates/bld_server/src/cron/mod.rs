@@ -1,4 +1,11 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use anyhow::{anyhow, bail, Result};
 use bld_core::fs::FileSystem;
@@ -23,6 +30,7 @@ pub struct CronScheduler {
     conn: Arc<DatabaseConnection>,
     supervisor: Arc<SupervisorMessageSender>,
     scheduler: JobScheduler,
+    paused: Arc<AtomicBool>,
 }
 
 impl CronScheduler {
@@ -38,11 +46,22 @@ impl CronScheduler {
             conn,
             supervisor,
             scheduler,
+            paused: Arc::new(AtomicBool::new(false)),
         };
         instance.load_jobs().await?;
         Ok(instance)
     }
 
+    /// Stops the scheduled jobs from enqueuing new runs, the jobs are still
+    /// kept in the scheduler and can be resumed at any time.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
     fn variables_into_hash_map(variables: Vec<CronJobVariable>) -> Option<HashMap<String, String>> {
         let variables: HashMap<String, String> =
             variables.into_iter().map(|v| (v.name, v.value)).collect();
@@ -170,6 +189,7 @@ impl CronScheduler {
         let fs = self.fs.clone();
         let conn = self.conn.clone();
         let supervisor = self.supervisor.clone();
+        let paused = self.paused.clone();
         let pipeline_id = pipeline_id.to_owned();
         let variables = variables.clone();
         let environment = environment.clone();
@@ -178,10 +198,14 @@ impl CronScheduler {
             let fs = fs.clone();
             let conn = conn.clone();
             let supervisor = supervisor.clone();
+            let paused = paused.clone();
             let pipeline_id = pipeline_id.to_owned();
             let variables = variables.clone();
             let environment = environment.clone();
             Box::pin(async move {
+                if paused.load(Ordering::Relaxed) {
+                    return;
+                }
                 let Ok(pipeline) = pipeline::select_by_id(conn.as_ref(), &pipeline_id).await else {
                     error!("unable to find pipeline with id: {pipeline_id}");
                     return;
@@ -191,7 +215,7 @@ impl CronScheduler {
                     environment,
                     variables,
                 };
-                if let Err(e) = enqueue_worker("Cron", fs, conn, supervisor, data).await {
+                if let Err(e) = enqueue_worker("Cron", None, fs, conn, supervisor, data).await {
                     error!("unable to enqueue cron run due to: {e}");
                 }
             })
@@ -200,6 +224,7 @@ impl CronScheduler {
 
         let mut job_data = job.job_data()?;
         job_data.id.replace(job_id.into());
+        job.set_job_data(job_data)?;
 
         Ok(job)
     }
@@ -208,15 +233,14 @@ impl CronScheduler {
         &self,
         conn: &DatabaseConnection,
         add_job: &AddJobRequest,
+        job_id: &Uuid,
         pipeline: &Pipeline,
     ) -> Result<()> {
-        let job_id = Uuid::new_v4();
-
         let variables = add_job.variables.as_ref().cloned();
         let environment = add_job.environment.as_ref().cloned();
 
         let scheduled_job = self.create_scheduled_job(
-            &job_id,
+            job_id,
             &add_job.schedule,
             &pipeline.id,
             variables,
@@ -264,6 +288,12 @@ impl CronScheduler {
     }
 
     pub async fn add(&self, add_job: &AddJobRequest) -> Result<()> {
+        self.add_with_id(&Uuid::new_v4(), add_job).await
+    }
+
+    /// Adds a new cron job using the provided id, so that the same job can be
+    /// created with the same id on multiple servers.
+    pub async fn add_with_id(&self, job_id: &Uuid, add_job: &AddJobRequest) -> Result<()> {
         let conn = self.conn.as_ref();
         let pipeline = pipeline::select_by_name(conn, &add_job.pipeline).await?;
         let job_exists = add_job.is_default
@@ -275,7 +305,7 @@ impl CronScheduler {
             bail!("cron job already exists");
         }
 
-        self.add_inner(conn, add_job, &pipeline).await
+        self.add_inner(conn, add_job, job_id, &pipeline).await
     }
 
     pub async fn update(&self, update_job: &UpdateJobRequest) -> Result<()> {
@@ -286,6 +316,18 @@ impl CronScheduler {
     }
 
     pub async fn upsert_default(&self, schedule: &str, pipeline: &str) -> Result<()> {
+        self.upsert_default_with_id(&Uuid::new_v4(), schedule, pipeline)
+            .await
+    }
+
+    /// Updates the default cron job of the pipeline or creates a new one
+    /// with the provided id if it doesn't exist.
+    pub async fn upsert_default_with_id(
+        &self,
+        job_id: &Uuid,
+        schedule: &str,
+        pipeline: &str,
+    ) -> Result<()> {
         let job = {
             let conn = self.conn.as_ref();
             let pipeline = pipeline::select_by_name(conn, pipeline).await?;
@@ -299,7 +341,7 @@ impl CronScheduler {
             Err(_) => {
                 let add_job =
                     AddJobRequest::new(schedule.to_owned(), pipeline.to_owned(), None, None, true);
-                self.add(&add_job).await
+                self.add_with_id(job_id, &add_job).await
             }
         }
     }
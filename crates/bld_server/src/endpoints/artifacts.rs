@@ -0,0 +1,89 @@
+use crate::extractors::User;
+use actix_web::{
+    body::SizedStream,
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::{Data, Path},
+    HttpResponse, Responder,
+};
+use anyhow::{bail, Result};
+use bld_config::{path, BldConfig};
+use bld_models::{dtos::ArtifactResponse, pipeline_run_artifacts};
+use sea_orm::DatabaseConnection;
+use std::path::{Component, PathBuf};
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+use tracing::info;
+
+#[get("/v1/artifacts/{run_id}")]
+pub async fn get(
+    _user: User,
+    conn: Data<DatabaseConnection>,
+    path: Path<String>,
+) -> impl Responder {
+    info!("Reached handler for /artifacts route");
+    let run_id = path.into_inner();
+    match pipeline_run_artifacts::select_by_run_id(conn.get_ref(), &run_id).await {
+        Ok(models) => {
+            let response: Vec<ArtifactResponse> = models.into_iter().map(From::from).collect();
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[get("/v1/artifacts/{run_id}/{id}")]
+pub async fn download(
+    _user: User,
+    config: Data<BldConfig>,
+    conn: Data<DatabaseConnection>,
+    path: Path<(String, String)>,
+) -> impl Responder {
+    info!("Reached handler for /artifacts/download route");
+    let (run_id, id) = path.into_inner();
+    match do_download(config.get_ref(), conn.get_ref(), &run_id, &id).await {
+        Ok(response) => response,
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn do_download(
+    config: &BldConfig,
+    conn: &DatabaseConnection,
+    run_id: &str,
+    id: &str,
+) -> Result<HttpResponse> {
+    let artifact = pipeline_run_artifacts::select_by_id(conn, id).await?;
+    if artifact.run_id != run_id {
+        bail!("pipeline run artifact not found");
+    }
+
+    let relative_path = PathBuf::from(&artifact.name);
+    if relative_path
+        .components()
+        .any(|x| !matches!(x, Component::Normal(_)))
+    {
+        bail!("invalid pipeline run artifact path");
+    }
+
+    let file_path = path![config.artifacts_full_path(run_id), relative_path];
+    let file = File::open(&file_path).await?;
+    let size = file.metadata().await?.len();
+
+    let file_name = artifact
+        .name
+        .rsplit('/')
+        .next()
+        .unwrap_or(&artifact.name)
+        .to_owned();
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(file_name)],
+    };
+
+    let body = SizedStream::new(size, ReaderStream::new(file));
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header(disposition)
+        .body(body))
+}
@@ -3,20 +3,29 @@ use actix_web::{
     web::{Data, Json},
     HttpResponse, Responder,
 };
-use bld_core::fs::FileSystem;
 use bld_models::dtos::PipelinePathRequest;
 use tracing::info;
+use uuid::Uuid;
 
-use crate::extractors::User;
+use crate::{
+    extractors::User,
+    ha::{HaCommand, HighAvailability},
+};
 
 #[post("/v1/copy")]
 pub async fn post(
     _user: User,
-    fs: Data<FileSystem>,
+    ha: Data<HighAvailability>,
     body: Json<PipelinePathRequest>,
 ) -> impl Responder {
     info!("Reached handler for /copy route");
-    match fs.copy(&body.pipeline, &body.target).await {
+    let body = body.into_inner();
+    let command = HaCommand::PipelineCopy {
+        id: Uuid::new_v4().to_string(),
+        source: body.pipeline,
+        target: body.target,
+    };
+    match ha.propose(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
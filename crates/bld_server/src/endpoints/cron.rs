@@ -5,8 +5,13 @@ use actix_web::{
 };
 use bld_models::dtos::{AddJobRequest, JobFiltersParams, UpdateJobRequest};
 use tracing::info;
+use uuid::Uuid;
 
-use crate::{cron::CronScheduler, extractors::User};
+use crate::{
+    cron::CronScheduler,
+    extractors::User,
+    ha::{HaCommand, HighAvailability},
+};
 
 #[get("/v1/cron")]
 pub async fn get(
@@ -22,9 +27,17 @@ pub async fn get(
 }
 
 #[post("/v1/cron")]
-pub async fn post(_: User, cron: Data<CronScheduler>, body: Json<AddJobRequest>) -> impl Responder {
+pub async fn post(
+    _: User,
+    ha: Data<HighAvailability>,
+    body: Json<AddJobRequest>,
+) -> impl Responder {
     info!("Reached handler for POST /cron route");
-    match cron.add(&body).await {
+    let command = HaCommand::CronAdd {
+        id: Uuid::new_v4().to_string(),
+        job: body.into_inner(),
+    };
+    match ha.propose(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
@@ -33,21 +46,26 @@ pub async fn post(_: User, cron: Data<CronScheduler>, body: Json<AddJobRequest>)
 #[patch("/v1/cron")]
 pub async fn patch(
     _: User,
-    cron: Data<CronScheduler>,
+    ha: Data<HighAvailability>,
     body: Json<UpdateJobRequest>,
 ) -> impl Responder {
     info!("Reached handler for PATCH /cron route");
-    match cron.update(&body).await {
+    let command = HaCommand::CronUpdate {
+        job: body.into_inner(),
+    };
+    match ha.propose(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
 #[delete("/v1/cron/{cron_job_id}")]
-pub async fn delete(_: User, cron: Data<CronScheduler>, path: Path<String>) -> impl Responder {
+pub async fn delete(_: User, ha: Data<HighAvailability>, path: Path<String>) -> impl Responder {
     info!("Reached handler for DELETE /cron route");
-    let cron_job_id = path.into_inner();
-    match cron.remove(&cron_job_id).await {
+    let command = HaCommand::CronRemove {
+        id: path.into_inner(),
+    };
+    match ha.propose(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
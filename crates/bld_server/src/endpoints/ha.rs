@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use actix_web::{
+    get, post,
+    web::{Data, Json},
+    HttpResponse, Responder,
+};
+use bld_core::fs::FileSystem;
+use bld_models::dtos::{AppendEntriesRequest, InstallSnapshotRequest, VoteRequest};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, info};
+
+use crate::{
+    extractors::{HaMember, User},
+    ha::{HaCommand, HaRunRequest, HighAvailability},
+    supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
+};
+
+#[get("/v1/ha/status")]
+pub async fn status(_: User, ha: Data<HighAvailability>) -> impl Responder {
+    info!("Reached handler for /ha/status route");
+    match ha.status().await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/append")]
+pub async fn append(
+    _: HaMember,
+    ha: Data<HighAvailability>,
+    body: Json<AppendEntriesRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/append route");
+    match ha.append_entries(body.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/vote")]
+pub async fn vote(
+    _: HaMember,
+    ha: Data<HighAvailability>,
+    body: Json<VoteRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/vote route");
+    match ha.request_vote(body.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// The maximum size of a snapshot sent by the leader, since a snapshot contains
+/// every pipeline and run of the server.
+pub const SNAPSHOT_LIMIT: usize = 256 * 1024 * 1024;
+
+pub async fn snapshot(
+    _: HaMember,
+    ha: Data<HighAvailability>,
+    body: Json<InstallSnapshotRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/snapshot route");
+    match ha.install_snapshot(body.into_inner()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/propose")]
+pub async fn propose(
+    _: HaMember,
+    ha: Data<HighAvailability>,
+    body: Json<HaCommand>,
+) -> impl Responder {
+    info!("Reached handler for /ha/propose route");
+    match ha.propose_forwarded(body.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/run")]
+pub async fn run(
+    _: HaMember,
+    ha: Data<HighAvailability>,
+    fs: Data<FileSystem>,
+    conn: Data<DatabaseConnection>,
+    supervisor: Data<SupervisorMessageSender>,
+    body: Json<HaRunRequest>,
+) -> impl Responder {
+    info!("Reached handler for /ha/run route");
+    if !matches!(ha.remote_leader().await, Ok(None)) {
+        return HttpResponse::BadRequest().body("server isn't the leader of the cluster");
+    }
+
+    let request = body.into_inner();
+    let result = enqueue_worker(
+        &request.user,
+        request.hook,
+        Arc::clone(&fs),
+        Arc::clone(&conn),
+        Arc::clone(&supervisor),
+        request.data,
+    )
+    .await;
+
+    match result {
+        Ok(run_id) => HttpResponse::Ok().json(run_id),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
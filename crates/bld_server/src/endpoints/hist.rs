@@ -1,8 +1,15 @@
 use crate::extractors::User;
-use actix_web::{get, web::Data, web::Query, HttpResponse, Responder};
+use actix_web::{
+    get,
+    web::{Data, Path, Query},
+    HttpResponse, Responder,
+};
 use anyhow::Result;
 use bld_models::{
-    dtos::{HistQueryParams, HistoryEntry},
+    dtos::{
+        HistQueryParams, HistoryEntry, HistoryImageEntry, HistoryJobEntry, HistoryNotificationEntry,
+    },
+    pipeline_run_containers, pipeline_run_jobs, pipeline_run_notifications, pipeline_run_steps,
     pipeline_runs,
 };
 use sea_orm::DatabaseConnection;
@@ -32,3 +39,77 @@ async fn history_info(
         .unwrap_or_else(|_| vec![]);
     Ok(entries)
 }
+
+#[get("/v1/hist/{run_id}/steps")]
+pub async fn get_steps(
+    _user: User,
+    conn: Data<DatabaseConnection>,
+    path: Path<String>,
+) -> impl Responder {
+    info!("Reached handler for /hist/steps route");
+    match steps_info(conn.get_ref(), &path.into_inner()).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn steps_info(conn: &DatabaseConnection, run_id: &str) -> Result<Vec<HistoryJobEntry>> {
+    let run = pipeline_runs::select_by_id(conn, run_id).await?;
+    let jobs = pipeline_run_jobs::select_by_run_id(conn, &run.id).await?;
+    let run_steps = pipeline_run_steps::select_by_run_id(conn, &run.id).await?;
+    let entries = jobs
+        .into_iter()
+        .map(|job| HistoryJobEntry::new(job, &run_steps))
+        .collect();
+    Ok(entries)
+}
+
+#[get("/v1/hist/{run_id}/images")]
+pub async fn get_images(
+    _user: User,
+    conn: Data<DatabaseConnection>,
+    path: Path<String>,
+) -> impl Responder {
+    info!("Reached handler for /hist/images route");
+    match images_info(conn.get_ref(), &path.into_inner()).await {
+        Ok(images) => HttpResponse::Ok().json(images),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn images_info(conn: &DatabaseConnection, run_id: &str) -> Result<Vec<HistoryImageEntry>> {
+    let run = pipeline_runs::select_by_id(conn, run_id).await?;
+    let containers = pipeline_run_containers::select_by_run_id(conn, &run.id).await?;
+    let entries = containers
+        .into_iter()
+        .filter(|x| x.image.is_some())
+        .map(HistoryImageEntry::from)
+        .collect();
+    Ok(entries)
+}
+
+#[get("/v1/hist/{run_id}/notifications")]
+pub async fn get_notifications(
+    _user: User,
+    conn: Data<DatabaseConnection>,
+    path: Path<String>,
+) -> impl Responder {
+    info!("Reached handler for /hist/notifications route");
+    match notifications_info(conn.get_ref(), &path.into_inner()).await {
+        Ok(notifications) => HttpResponse::Ok().json(notifications),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn notifications_info(
+    conn: &DatabaseConnection,
+    run_id: &str,
+) -> Result<Vec<HistoryNotificationEntry>> {
+    let run = pipeline_runs::select_by_id(conn, run_id).await?;
+    let notifications = pipeline_run_notifications::select_by_run_id(conn, &run.id).await?;
+    let entries = notifications
+        .into_iter()
+        .map(HistoryNotificationEntry::from)
+        .collect();
+    Ok(entries)
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::{
+    ha::HighAvailability,
+    hooks::{parse, provider_from_path, verify},
+    supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
+};
+use actix_web::{
+    web::{Bytes, Data},
+    HttpRequest, HttpResponse, Responder,
+};
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_core::fs::FileSystem;
+use bld_models::{dtos::ExecClientMessage, pipeline_runs::PipelineRunHook};
+use sea_orm::DatabaseConnection;
+use tracing::{error, info};
+
+const HOOK_USER: &str = "Hook";
+
+/// The maximum size of a hook delivery, since the payloads of providers can be
+/// larger than the default limit of the body, e.g. for pushes with many commits.
+pub const PAYLOAD_LIMIT: usize = 25 * 1024 * 1024;
+
+pub async fn post(
+    config: Data<BldConfig>,
+    fs: Data<FileSystem>,
+    conn: Data<DatabaseConnection>,
+    supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
+    req: HttpRequest,
+    body: Bytes,
+) -> impl Responder {
+    info!("reached handler for /v1/hooks route");
+
+    let provider = req.match_info().get("provider").unwrap_or_default();
+    let Some(provider) = provider_from_path(provider) else {
+        return HttpResponse::NotFound().body("unknown hook provider");
+    };
+
+    let hooks: Vec<_> = config
+        .local
+        .server
+        .hooks
+        .iter()
+        .filter(|x| x.provider == provider)
+        .filter(|x| verify(provider, &x.secret, req.headers(), &body))
+        .collect();
+
+    if hooks.is_empty() {
+        error!("unable to verify {provider} hook delivery");
+        return HttpResponse::Unauthorized().body("invalid hook signature");
+    }
+
+    let delivery = match parse(provider, req.headers(), &body) {
+        Ok(Some(delivery)) => delivery,
+        Ok(None) => return HttpResponse::Ok().json(Vec::<String>::new()),
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let mut run_ids = vec![];
+    for hook in hooks.into_iter().filter(|x| delivery.matches(x)) {
+        info!(
+            "starting pipeline {} for {provider} {} of {} at {}",
+            hook.pipeline, delivery.event, delivery.repository, delivery.commit_sha
+        );
+        let data = ExecClientMessage::EnqueueRun {
+            name: hook.pipeline.to_owned(),
+            environment: None,
+            variables: Some(delivery.variables()),
+        };
+        match enqueue(&fs, &conn, &supervisor, &ha, delivery.origin(), data).await {
+            Ok(run_id) => run_ids.push(run_id),
+            Err(e) => {
+                error!("unable to enqueue hook run due to: {e}");
+                return HttpResponse::BadRequest().body(e.to_string());
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(run_ids)
+}
+
+async fn enqueue(
+    fs: &Data<FileSystem>,
+    conn: &Data<DatabaseConnection>,
+    supervisor: &Data<SupervisorMessageSender>,
+    ha: &Data<HighAvailability>,
+    origin: PipelineRunHook,
+    data: ExecClientMessage,
+) -> Result<String> {
+    match ha.remote_leader().await? {
+        Some(leader) => {
+            info!("forwarding hook run to the leader of the cluster");
+            ha.forward_run(&leader, HOOK_USER, Some(origin), data).await
+        }
+        None => {
+            enqueue_worker(
+                HOOK_USER,
+                Some(origin),
+                Arc::clone(fs),
+                Arc::clone(conn),
+                Arc::clone(supervisor),
+                data,
+            )
+            .await
+        }
+    }
+}
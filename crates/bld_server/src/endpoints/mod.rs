@@ -1,10 +1,13 @@
+pub mod artifacts;
 pub mod auth;
 pub mod check;
 pub mod copy;
 pub mod cron;
 pub mod deps;
+pub mod ha;
 pub mod hist;
 pub mod home;
+pub mod hooks;
 pub mod list;
 pub mod r#move;
 pub mod print;
@@ -12,5 +15,6 @@ pub mod pull;
 pub mod push;
 pub mod remove;
 pub mod run;
+pub mod secrets;
 pub mod stop;
 pub mod ui;
@@ -3,20 +3,27 @@ use actix_web::{
     web::{Data, Json},
     HttpResponse, Responder,
 };
-use bld_core::fs::FileSystem;
 use bld_models::dtos::PipelinePathRequest;
 use tracing::info;
 
-use crate::extractors::User;
+use crate::{
+    extractors::User,
+    ha::{HaCommand, HighAvailability},
+};
 
 #[patch("/v1/move")]
 pub async fn patch(
     _user: User,
-    fs: Data<FileSystem>,
+    ha: Data<HighAvailability>,
     body: Json<PipelinePathRequest>,
 ) -> impl Responder {
     info!("Reached handler for /move route");
-    match fs.mv(&body.pipeline, &body.target).await {
+    let body = body.into_inner();
+    let command = HaCommand::PipelineMove {
+        source: body.pipeline,
+        target: body.target,
+    };
+    match ha.propose(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     extractors::User,
+    ha::HighAvailability,
     supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
 };
 use actix_web::{
@@ -20,18 +21,30 @@ pub async fn post(
     fs: Data<FileSystem>,
     conn: Data<DatabaseConnection>,
     supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
     data: Json<ExecClientMessage>,
 ) -> impl Responder {
     info!("reached handler for /run route");
 
-    let result = enqueue_worker(
-        &user.name,
-        Arc::clone(&fs),
-        Arc::clone(&conn),
-        Arc::clone(&supervisor),
-        data.into_inner(),
-    )
-    .await;
+    let result = match ha.remote_leader().await {
+        Ok(Some(leader)) => {
+            info!("forwarding run to the leader of the cluster");
+            ha.forward_run(&leader, &user.name, None, data.into_inner())
+                .await
+        }
+        Ok(None) => {
+            enqueue_worker(
+                &user.name,
+                None,
+                Arc::clone(&fs),
+                Arc::clone(&conn),
+                Arc::clone(&supervisor),
+                data.into_inner(),
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
 
     match result {
         Ok(run_id) => HttpResponse::Ok().json(run_id),
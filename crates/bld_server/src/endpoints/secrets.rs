@@ -0,0 +1,65 @@
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpResponse, Responder,
+};
+use anyhow::{anyhow, Result};
+use bld_config::BldConfig;
+use bld_models::{
+    dtos::{AddSecretRequest, SecretResponse},
+    secrets,
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+
+use crate::extractors::User;
+
+#[get("/v1/secrets")]
+pub async fn get(_: User, conn: Data<DatabaseConnection>) -> impl Responder {
+    info!("Reached handler for GET /secrets route");
+    match secrets::select_all(conn.get_ref()).await {
+        Ok(models) => {
+            let response: Vec<SecretResponse> = models.into_iter().map(From::from).collect();
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/secrets")]
+pub async fn post(
+    _: User,
+    config: Data<BldConfig>,
+    conn: Data<DatabaseConnection>,
+    body: Json<AddSecretRequest>,
+) -> impl Responder {
+    info!("Reached handler for POST /secrets route");
+    match do_upsert(config.get_ref(), conn.get_ref(), &body).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn do_upsert(
+    config: &BldConfig,
+    conn: &DatabaseConnection,
+    body: &AddSecretRequest,
+) -> Result<()> {
+    let key = config
+        .local
+        .server
+        .secrets_key
+        .as_ref()
+        .ok_or_else(|| anyhow!("no secrets key has been configured for the server"))?;
+    secrets::upsert(conn, key, &body.name, &body.value).await
+}
+
+#[delete("/v1/secrets/{name}")]
+pub async fn delete(_: User, conn: Data<DatabaseConnection>, path: Path<String>) -> impl Responder {
+    info!("Reached handler for DELETE /secrets route");
+    let name = path.into_inner();
+    match secrets::delete_by_name(conn.get_ref(), &name).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
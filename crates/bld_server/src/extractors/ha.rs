@@ -0,0 +1,38 @@
+use actix_web::dev::Payload;
+use actix_web::error::ErrorUnauthorized;
+use actix_web::web::Data;
+use actix_web::{Error, FromRequest, HttpRequest};
+use bld_config::BldConfig;
+use futures_util::future::{ready, Ready};
+
+use crate::{ha::HA_TOKEN_HEADER, hooks::constant_time_eq};
+
+/// Extractor for the internal requests sent between the members of a high
+/// availability cluster, the request is authorized by the shared token of
+/// the configuration.
+#[derive(Debug)]
+pub struct HaMember;
+
+impl FromRequest for HaMember {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = req
+            .headers()
+            .get(HA_TOKEN_HEADER)
+            .and_then(|x| x.to_str().ok());
+
+        let authorized = req
+            .app_data::<Data<BldConfig>>()
+            .and_then(|config| config.local.server.ha.as_ref())
+            .zip(token)
+            .is_some_and(|(ha, token)| constant_time_eq(ha.token.as_bytes(), token.as_bytes()));
+
+        if authorized {
+            ready(Ok(HaMember))
+        } else {
+            ready(Err(ErrorUnauthorized("")))
+        }
+    }
+}
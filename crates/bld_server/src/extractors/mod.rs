@@ -1,3 +1,5 @@
+mod ha;
 mod user;
 
+pub use ha::*;
 pub use user::*;
@@ -0,0 +1,118 @@
+use bld_models::{
+    dtos::{AddJobRequest, ExecClientMessage, UpdateJobRequest},
+    pipeline_runs::{PipelineRunHook, PipelineRuns, UpsertPipelineRun},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// The changes that are replicated to all the members of the cluster. Any
+/// generated id is part of the command so that every member applies the
+/// exact same change.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum HaCommand {
+    PipelinePush {
+        id: String,
+        cron_job_id: String,
+        name: String,
+        content: String,
+    },
+    PipelineRemove {
+        name: String,
+    },
+    PipelineMove {
+        source: String,
+        target: String,
+    },
+    PipelineCopy {
+        id: String,
+        source: String,
+        target: String,
+    },
+    CronAdd {
+        id: String,
+        job: AddJobRequest,
+    },
+    CronUpdate {
+        job: UpdateJobRequest,
+    },
+    CronRemove {
+        id: String,
+    },
+    RunUpsert {
+        run: HaPipelineRun,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HaPipelineRun {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    pub app_user: String,
+    pub start_date: Option<NaiveDateTime>,
+    pub end_date: Option<NaiveDateTime>,
+    pub date_created: NaiveDateTime,
+    pub date_updated: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub hook_provider: Option<String>,
+    #[serde(default)]
+    pub hook_repository: Option<String>,
+    #[serde(default)]
+    pub hook_commit_sha: Option<String>,
+}
+
+impl From<PipelineRuns> for HaPipelineRun {
+    fn from(value: PipelineRuns) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            state: value.state,
+            app_user: value.app_user,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            date_created: value.date_created,
+            date_updated: value.date_updated,
+            hook_provider: value.hook_provider,
+            hook_repository: value.hook_repository,
+            hook_commit_sha: value.hook_commit_sha,
+        }
+    }
+}
+
+impl From<HaPipelineRun> for UpsertPipelineRun {
+    fn from(value: HaPipelineRun) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            state: value.state,
+            app_user: value.app_user,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            date_created: value.date_created,
+            date_updated: value.date_updated,
+            hook_provider: value.hook_provider,
+            hook_repository: value.hook_repository,
+            hook_commit_sha: value.hook_commit_sha,
+        }
+    }
+}
+
+/// A run request forwarded by a member of the cluster to the leader.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HaRunRequest {
+    pub user: String,
+    #[serde(default)]
+    pub hook: Option<PipelineRunHook>,
+    pub data: ExecClientMessage,
+}
+
+impl HaRunRequest {
+    pub fn new(user: &str, hook: Option<PipelineRunHook>, data: ExecClientMessage) -> Self {
+        Self {
+            user: user.to_owned(),
+            hook,
+            data,
+        }
+    }
+}
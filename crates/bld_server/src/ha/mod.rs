@@ -0,0 +1,223 @@
+mod command;
+mod node;
+mod state_machine;
+
+pub use command::*;
+
+use std::sync::Arc;
+
+use actix_web::rt::spawn;
+use anyhow::{anyhow, bail, Result};
+use bld_config::{BldConfig, BldHaConfig, BldHaMember};
+use bld_core::fs::FileSystem;
+use bld_http::{Request, RequestError};
+use bld_models::{
+    dtos::{
+        AppendEntriesRequest, AppendEntriesResponse, ExecClientMessage, HaStatusResponse,
+        InstallSnapshotRequest, InstallSnapshotResponse, VoteRequest, VoteResponse,
+    },
+    pipeline_runs::PipelineRunHook,
+};
+use sea_orm::DatabaseConnection;
+use tokio::sync::{
+    mpsc::{channel, Sender},
+    oneshot,
+};
+
+use crate::cron::CronScheduler;
+
+use self::{
+    node::{HaMessage, HaNode, HaTransport, ProposeResponse},
+    state_machine::HaStateMachine,
+};
+
+pub const HA_TOKEN_HEADER: &str = "X-Bld-Ha-Token";
+
+pub(crate) fn ha_request(url: &str, token: &str) -> Request {
+    Request::post(url).header(HA_TOKEN_HEADER, token)
+}
+
+/// Applies the changes of the server. When high availability is enabled, the
+/// changes are replicated to the members of the cluster through the raft log
+/// and are applied by every member once they are committed.
+pub enum HighAvailability {
+    Disabled(HaStateMachine),
+    Enabled {
+        config: BldHaConfig,
+        tx: Sender<HaMessage>,
+    },
+}
+
+impl HighAvailability {
+    pub async fn new(
+        config: Arc<BldConfig>,
+        conn: Arc<DatabaseConnection>,
+        fs: Arc<FileSystem>,
+        cron: Arc<CronScheduler>,
+    ) -> Result<Self> {
+        let state_machine = HaStateMachine::new(fs, Arc::clone(&cron), Arc::clone(&conn));
+
+        let Some(config) = config.local.server.ha.clone() else {
+            return Ok(Self::Disabled(state_machine));
+        };
+
+        if config.token.trim().is_empty() {
+            bail!("a non empty token is required in the ha section of the server configuration");
+        }
+
+        let (tx, rx) = channel(4096);
+        let transport = HaTransport::http(&config);
+        let node = HaNode::new(
+            config.clone(),
+            conn,
+            state_machine,
+            cron,
+            transport,
+            tx.clone(),
+            rx,
+        )
+        .await?;
+        spawn(node.receive());
+
+        Ok(Self::Enabled { config, tx })
+    }
+
+    async fn send<T>(
+        tx: &Sender<HaMessage>,
+        message: impl FnOnce(oneshot::Sender<T>) -> HaMessage,
+    ) -> Result<T> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        tx.send(message(resp_tx))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Applies the command to the server, in high availability mode the command
+    /// is proposed to the leader of the cluster and this call completes once
+    /// the command has been committed and applied by the leader.
+    pub async fn propose(&self, command: HaCommand) -> Result<()> {
+        let (config, tx) = match self {
+            Self::Disabled(state_machine) => return state_machine.apply(command).await,
+            Self::Enabled { config, tx } => (config, tx),
+        };
+
+        let response = Self::send(tx, |resp_tx| HaMessage::Propose { command, resp_tx }).await?;
+
+        match response {
+            ProposeResponse::Applied(result) => result,
+            ProposeResponse::NotLeader { leader, command } => {
+                let leader = Self::leader_member(config, leader)?;
+                let url = format!("{}/v1/ha/propose", leader.base_url_http());
+                ha_request(&url, &config.token)
+                    .text_with_data(&command)
+                    .await
+                    .map(|_| ())
+                    .map_err(Self::forwarded_error)
+            }
+        }
+    }
+
+    /// Applies a command forwarded by another member of the cluster, the command
+    /// is rejected if this server isn't the leader.
+    pub async fn propose_forwarded(&self, command: HaCommand) -> Result<()> {
+        let Self::Enabled { tx, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+
+        let response = Self::send(tx, |resp_tx| HaMessage::Propose { command, resp_tx }).await?;
+
+        match response {
+            ProposeResponse::Applied(result) => result,
+            ProposeResponse::NotLeader { .. } => bail!("server isn't the leader of the cluster"),
+        }
+    }
+
+    pub async fn append_entries(
+        &self,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        let Self::Enabled { tx, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+        Self::send(tx, |resp_tx| HaMessage::AppendEntries { request, resp_tx }).await?
+    }
+
+    pub async fn request_vote(&self, request: VoteRequest) -> Result<VoteResponse> {
+        let Self::Enabled { tx, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+        Self::send(tx, |resp_tx| HaMessage::RequestVote { request, resp_tx }).await?
+    }
+
+    pub async fn install_snapshot(
+        &self,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        let Self::Enabled { tx, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+        Self::send(tx, |resp_tx| HaMessage::InstallSnapshot {
+            request,
+            resp_tx,
+        })
+        .await?
+    }
+
+    pub async fn status(&self) -> Result<HaStatusResponse> {
+        let Self::Enabled { tx, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+        Self::send(tx, HaMessage::Status).await
+    }
+
+    /// Returns the leader of the cluster if it's a different server, `None` is
+    /// returned when this server is the leader or high availability is disabled.
+    pub async fn remote_leader(&self) -> Result<Option<BldHaMember>> {
+        let Self::Enabled { config, .. } = self else {
+            return Ok(None);
+        };
+
+        let status = self.status().await?;
+        if status.leader == Some(config.node_id) {
+            return Ok(None);
+        }
+
+        Self::leader_member(config, status.leader).map(Some)
+    }
+
+    /// Forwards a run request to the leader of the cluster.
+    pub async fn forward_run(
+        &self,
+        leader: &BldHaMember,
+        user: &str,
+        hook: Option<PipelineRunHook>,
+        data: ExecClientMessage,
+    ) -> Result<String> {
+        let Self::Enabled { config, .. } = self else {
+            bail!("high availability mode isn't enabled");
+        };
+
+        let url = format!("{}/v1/ha/run", leader.base_url_http());
+        ha_request(&url, &config.token)
+            .json_with_data(&HaRunRequest::new(user, hook, data))
+            .await
+            .map_err(Self::forwarded_error)
+    }
+
+    /// Keeps only the message of an error returned by the leader so that it's
+    /// returned as is to the client.
+    fn forwarded_error(error: anyhow::Error) -> anyhow::Error {
+        match error.downcast_ref::<RequestError>() {
+            Some(e) => anyhow!("{}", e.text()),
+            None => error,
+        }
+    }
+
+    fn leader_member(config: &BldHaConfig, leader: Option<i32>) -> Result<BldHaMember> {
+        leader
+            .and_then(|id| config.member(id))
+            .cloned()
+            .ok_or_else(|| anyhow!("no leader has been elected for the cluster"))
+    }
+}
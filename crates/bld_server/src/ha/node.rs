@@ -0,0 +1,1562 @@
+use std::{
+    cmp::{max, min},
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::rt::spawn;
+use anyhow::{anyhow, bail, Result};
+use bld_config::{BldHaConfig, BldHaMember};
+use bld_models::{
+    dtos::{
+        AppendEntriesRequest, AppendEntriesResponse, HaLogEntry, HaStatusResponse,
+        InstallSnapshotRequest, InstallSnapshotResponse, VoteRequest, VoteResponse,
+    },
+    ha_hard_state::{self, InsertHighAvailHardState},
+    ha_log::{self, HighAvailLog, InsertHighAvailLog},
+    ha_members::{self, InsertHighAvailMembers},
+    ha_snapshot::{self, HighAvailSnapshot, InsertHighAvailSnapshot},
+    ha_state_machine, pipeline_runs,
+};
+use chrono::{DateTime, NaiveDateTime};
+use rand::Rng;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use tokio::{
+    sync::{
+        mpsc::{Receiver, Sender},
+        oneshot,
+    },
+    time::{interval, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::cron::CronScheduler;
+
+use super::{command::HaCommand, ha_request, state_machine::HaStateMachine};
+
+const TICK_INTERVAL: u64 = 50;
+const RUNS_POLL_INTERVAL: u64 = 1000;
+const MAX_ENTRIES_PER_REQUEST: i32 = 100;
+
+pub enum HaMessage {
+    Propose {
+        command: HaCommand,
+        resp_tx: oneshot::Sender<ProposeResponse>,
+    },
+    AppendEntries {
+        request: AppendEntriesRequest,
+        resp_tx: oneshot::Sender<Result<AppendEntriesResponse>>,
+    },
+    RequestVote {
+        request: VoteRequest,
+        resp_tx: oneshot::Sender<Result<VoteResponse>>,
+    },
+    InstallSnapshot {
+        request: InstallSnapshotRequest,
+        resp_tx: oneshot::Sender<Result<InstallSnapshotResponse>>,
+    },
+    AppendEntriesResult {
+        peer: i32,
+        last_index: i32,
+        response: AppendEntriesResponse,
+    },
+    RequestVoteResult {
+        peer: i32,
+        response: VoteResponse,
+    },
+    InstallSnapshotResult {
+        peer: i32,
+        last_index: i32,
+        response: InstallSnapshotResponse,
+    },
+    Status(oneshot::Sender<HaStatusResponse>),
+}
+
+pub enum ProposeResponse {
+    Applied(Result<()>),
+    NotLeader {
+        leader: Option<i32>,
+        command: Box<HaCommand>,
+    },
+}
+
+/// The way that the requests of a node reach the other members of the cluster.
+pub enum HaTransport {
+    Http {
+        token: String,
+    },
+    /// Delivers the requests to the nodes of the same process, a request only
+    /// reaches a member while both nodes are part of the peers.
+    #[cfg(test)]
+    InMemory {
+        node_id: i32,
+        peers: Arc<std::sync::RwLock<HashMap<i32, Sender<HaMessage>>>>,
+    },
+}
+
+impl HaTransport {
+    pub fn http(config: &BldHaConfig) -> Self {
+        Self::Http {
+            token: config.token.to_owned(),
+        }
+    }
+
+    #[cfg(test)]
+    fn in_memory_peer(
+        node_id: i32,
+        peers: &std::sync::RwLock<HashMap<i32, Sender<HaMessage>>>,
+        peer: i32,
+    ) -> Result<Sender<HaMessage>> {
+        let peers = peers
+            .read()
+            .map_err(|_| anyhow!("peers lock is poisoned"))?;
+        peers
+            .get(&node_id)
+            .and(peers.get(&peer))
+            .cloned()
+            .ok_or_else(|| anyhow!("member {peer} is unreachable"))
+    }
+
+    async fn request_vote(&self, peer: &BldHaMember, request: VoteRequest) -> Result<VoteResponse> {
+        match self {
+            Self::Http { token } => {
+                let url = format!("{}/v1/ha/vote", peer.base_url_http());
+                ha_request(&url, token).json_with_data(&request).await
+            }
+            #[cfg(test)]
+            Self::InMemory { node_id, peers } => {
+                let tx = Self::in_memory_peer(*node_id, peers, peer.id)?;
+                let (resp_tx, resp_rx) = oneshot::channel();
+                tx.send(HaMessage::RequestVote { request, resp_tx })
+                    .await
+                    .map_err(|e| anyhow!(e.to_string()))?;
+                resp_rx.await?
+            }
+        }
+    }
+
+    async fn append_entries(
+        &self,
+        peer: &BldHaMember,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        match self {
+            Self::Http { token } => {
+                let url = format!("{}/v1/ha/append", peer.base_url_http());
+                ha_request(&url, token).json_with_data(&request).await
+            }
+            #[cfg(test)]
+            Self::InMemory { node_id, peers } => {
+                let tx = Self::in_memory_peer(*node_id, peers, peer.id)?;
+                let (resp_tx, resp_rx) = oneshot::channel();
+                tx.send(HaMessage::AppendEntries { request, resp_tx })
+                    .await
+                    .map_err(|e| anyhow!(e.to_string()))?;
+                resp_rx.await?
+            }
+        }
+    }
+
+    async fn install_snapshot(
+        &self,
+        peer: &BldHaMember,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        match self {
+            Self::Http { token } => {
+                let url = format!("{}/v1/ha/snapshot", peer.base_url_http());
+                ha_request(&url, token).json_with_data(&request).await
+            }
+            #[cfg(test)]
+            Self::InMemory { node_id, peers } => {
+                let tx = Self::in_memory_peer(*node_id, peers, peer.id)?;
+                let (resp_tx, resp_rx) = oneshot::channel();
+                tx.send(HaMessage::InstallSnapshot { request, resp_tx })
+                    .await
+                    .map_err(|e| anyhow!(e.to_string()))?;
+                resp_rx.await?
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Follower => "follower",
+            Self::Candidate => "candidate",
+            Self::Leader => "leader",
+        }
+    }
+}
+
+pub struct HaNode {
+    config: BldHaConfig,
+    conn: Arc<DatabaseConnection>,
+    state_machine: HaStateMachine,
+    cron: Arc<CronScheduler>,
+    transport: Arc<HaTransport>,
+    tx: Sender<HaMessage>,
+    rx: Receiver<HaMessage>,
+    role: Role,
+    leader_id: Option<i32>,
+    hard_state_id: i32,
+    current_term: i32,
+    voted_for: Option<i32>,
+    state_machine_id: i32,
+    last_applied: i32,
+    commit_index: i32,
+    last_log_index: i32,
+    last_log_term: i32,
+    snapshot_index: i32,
+    snapshot_term: i32,
+    term_start_index: i32,
+    votes: HashSet<i32>,
+    next_index: HashMap<i32, i32>,
+    match_index: HashMap<i32, i32>,
+    snapshots_sent: HashMap<i32, Instant>,
+    pending: HashMap<i32, oneshot::Sender<ProposeResponse>>,
+    election_deadline: Instant,
+    last_heartbeat: Instant,
+    last_runs_poll: Instant,
+    runs_watermark: NaiveDateTime,
+}
+
+impl HaNode {
+    pub async fn new(
+        config: BldHaConfig,
+        conn: Arc<DatabaseConnection>,
+        state_machine: HaStateMachine,
+        cron: Arc<CronScheduler>,
+        transport: HaTransport,
+        tx: Sender<HaMessage>,
+        rx: Receiver<HaMessage>,
+    ) -> Result<Self> {
+        if config.member(config.node_id).is_none() {
+            bail!(
+                "node id {} isn't one of the cluster members",
+                config.node_id
+            );
+        }
+
+        let db = conn.as_ref();
+
+        let hard_state = match ha_hard_state::select_first(db).await {
+            Ok(hard_state) => hard_state,
+            Err(_) => {
+                ha_hard_state::insert(db, InsertHighAvailHardState::new(0, None)).await?;
+                ha_hard_state::select_first(db).await?
+            }
+        };
+
+        let applied = match ha_state_machine::select_first(db).await {
+            Ok(applied) => applied,
+            Err(_) => {
+                ha_state_machine::insert(db, 0).await?;
+                ha_state_machine::select_first(db).await?
+            }
+        };
+
+        let snapshot = ha_snapshot::select_last(db).await.ok();
+        if let Some(snapshot) = &snapshot {
+            Self::check_snapshot_members(&config, &conn, snapshot.id).await?;
+        }
+        let (snapshot_index, snapshot_term) =
+            snapshot.as_ref().map(|x| (x.id, x.term)).unwrap_or((0, 0));
+
+        let (last_log_index, last_log_term) = ha_log::select_last(db)
+            .await
+            .map(|entry| (entry.id, entry.term))
+            .unwrap_or((snapshot_index, snapshot_term));
+
+        let runs_watermark =
+            Self::applied_runs_watermark(&conn, snapshot.as_ref(), applied.last_applied_log)
+                .await?;
+
+        // the members of the cluster start as followers so the scheduled jobs
+        // are only enqueued by the leader.
+        cron.pause();
+
+        let now = Instant::now();
+        let mut node = Self {
+            config,
+            conn,
+            state_machine,
+            cron,
+            transport: Arc::new(transport),
+            tx,
+            rx,
+            role: Role::Follower,
+            leader_id: None,
+            hard_state_id: hard_state.id,
+            current_term: hard_state.current_term,
+            voted_for: hard_state.voted_for,
+            state_machine_id: applied.id,
+            last_applied: applied.last_applied_log,
+            commit_index: applied.last_applied_log,
+            last_log_index,
+            last_log_term,
+            snapshot_index,
+            snapshot_term,
+            term_start_index: 0,
+            votes: HashSet::new(),
+            next_index: HashMap::new(),
+            match_index: HashMap::new(),
+            snapshots_sent: HashMap::new(),
+            pending: HashMap::new(),
+            election_deadline: now,
+            last_heartbeat: now,
+            last_runs_poll: now,
+            runs_watermark,
+        };
+        node.reset_election_deadline();
+
+        Ok(node)
+    }
+
+    /// Warns when the members of the cluster have changed since the last snapshot, since
+    /// the members of the configuration should be the same for every server.
+    async fn check_snapshot_members(
+        config: &BldHaConfig,
+        conn: &DatabaseConnection,
+        snapshot_id: i32,
+    ) -> Result<()> {
+        let mut members: Vec<i32> = ha_members::select(conn, snapshot_id)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+        members.sort_unstable();
+
+        let mut configured: Vec<i32> = config.members.iter().map(|x| x.id).collect();
+        configured.sort_unstable();
+
+        if members != configured {
+            warn!(
+                "the members of the cluster have changed from {members:?} to {configured:?} since the last snapshot"
+            );
+        }
+        Ok(())
+    }
+
+    /// The last change of the pipeline runs that has been applied from the snapshot and
+    /// the log, so that a node that becomes the leader proposes every run change after it.
+    /// The epoch is used when no run changes have been applied.
+    async fn applied_runs_watermark(
+        conn: &DatabaseConnection,
+        snapshot: Option<&HighAvailSnapshot>,
+        last_applied: i32,
+    ) -> Result<NaiveDateTime> {
+        let mut watermark = DateTime::UNIX_EPOCH.naive_utc();
+        if let Some(snapshot) = snapshot {
+            let commands: Vec<HaCommand> = serde_json::from_slice(&snapshot.data)?;
+            for change in commands.iter().filter_map(Self::run_change) {
+                watermark = max(watermark, change);
+            }
+        }
+
+        let mut start = snapshot.map(|x| x.id).unwrap_or(0) + 1;
+        while start <= last_applied {
+            let end = min(start + MAX_ENTRIES_PER_REQUEST - 1, last_applied);
+            for entry in ha_log::select_between_ids(conn, start, end).await? {
+                if let Some(change) = Self::entry_run_change(&entry) {
+                    watermark = max(watermark, change);
+                }
+            }
+            start = end + 1;
+        }
+        Ok(watermark)
+    }
+
+    fn entry_run_change(entry: &HighAvailLog) -> Option<NaiveDateTime> {
+        if entry.payload_type != ha_log::NORMAL {
+            return None;
+        }
+        Self::run_change(&serde_json::from_str(&entry.payload).ok()?)
+    }
+
+    fn run_change(command: &HaCommand) -> Option<NaiveDateTime> {
+        match command {
+            HaCommand::RunUpsert { run } => Some(run.date_updated.unwrap_or(run.date_created)),
+            _ => None,
+        }
+    }
+
+    pub async fn receive(mut self) {
+        info!(
+            "starting high availability node {} at term {}",
+            self.config.node_id, self.current_term
+        );
+
+        let mut ticker = interval(Duration::from_millis(TICK_INTERVAL));
+
+        loop {
+            tokio::select! {
+                message = self.rx.recv() => {
+                    let Some(message) = message else {
+                        break;
+                    };
+                    if let Err(e) = self.handle(message).await {
+                        error!("high availability node error: {e}");
+                    }
+                }
+                _ = ticker.tick() => {
+                    if let Err(e) = self.tick().await {
+                        error!("high availability node error: {e}");
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle(&mut self, message: HaMessage) -> Result<()> {
+        match message {
+            HaMessage::Propose { command, resp_tx } => self.propose(command, resp_tx).await,
+
+            HaMessage::AppendEntries { request, resp_tx } => {
+                let response = self.append_entries(request).await;
+                let _ = resp_tx.send(response);
+                Ok(())
+            }
+
+            HaMessage::RequestVote { request, resp_tx } => {
+                let response = self.request_vote(request).await;
+                let _ = resp_tx.send(response);
+                Ok(())
+            }
+
+            HaMessage::InstallSnapshot { request, resp_tx } => {
+                let response = self.install_snapshot(request).await;
+                let _ = resp_tx.send(response);
+                Ok(())
+            }
+
+            HaMessage::AppendEntriesResult {
+                peer,
+                last_index,
+                response,
+            } => self.append_entries_result(peer, last_index, response).await,
+
+            HaMessage::RequestVoteResult { peer, response } => {
+                self.request_vote_result(peer, response).await
+            }
+
+            HaMessage::InstallSnapshotResult {
+                peer,
+                last_index,
+                response,
+            } => {
+                self.install_snapshot_result(peer, last_index, response)
+                    .await
+            }
+
+            HaMessage::Status(resp_tx) => {
+                let _ = resp_tx.send(self.status());
+                Ok(())
+            }
+        }
+    }
+
+    async fn tick(&mut self) -> Result<()> {
+        let heartbeat_interval = Duration::from_millis(self.config.heartbeat_interval);
+        let runs_poll_interval = Duration::from_millis(RUNS_POLL_INTERVAL);
+
+        match self.role {
+            Role::Leader => {
+                if self.last_runs_poll.elapsed() >= runs_poll_interval {
+                    self.propose_run_changes().await?;
+                }
+                if self.last_heartbeat.elapsed() >= heartbeat_interval {
+                    self.replicate().await?;
+                }
+                Ok(())
+            }
+            Role::Follower | Role::Candidate if Instant::now() >= self.election_deadline => {
+                self.start_election().await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn status(&self) -> HaStatusResponse {
+        HaStatusResponse {
+            node_id: self.config.node_id,
+            role: self.role.as_str().to_owned(),
+            term: self.current_term,
+            leader: self.leader_id,
+            last_log_index: self.last_log_index,
+            commit_index: self.commit_index,
+            last_applied: self.last_applied,
+        }
+    }
+
+    fn peers(&self) -> Vec<BldHaMember> {
+        self.config.peers().cloned().collect()
+    }
+
+    fn majority(&self) -> usize {
+        self.config.members.len() / 2 + 1
+    }
+
+    fn reset_election_deadline(&mut self) {
+        let timeout = self.config.election_timeout;
+        let timeout = rand::thread_rng().gen_range(timeout..timeout * 2);
+        self.election_deadline = Instant::now() + Duration::from_millis(timeout);
+    }
+
+    async fn persist_hard_state(&self) -> Result<()> {
+        ha_hard_state::update(
+            self.conn.as_ref(),
+            self.hard_state_id,
+            self.current_term,
+            self.voted_for,
+        )
+        .await
+    }
+
+    async fn term_at(&self, index: i32) -> Result<i32> {
+        if index == self.snapshot_index {
+            return Ok(self.snapshot_term);
+        }
+        if index == self.last_log_index {
+            return Ok(self.last_log_term);
+        }
+        if index < self.snapshot_index {
+            bail!("log entry {index} has been compacted into a snapshot");
+        }
+        ha_log::select_by_id(self.conn.as_ref(), index)
+            .await
+            .map(|entry| entry.term)
+    }
+
+    async fn append(&mut self, payload_type: &str, payload: Option<&str>) -> Result<i32> {
+        let index = self.last_log_index + 1;
+        let entry = InsertHighAvailLog::new(index, self.current_term, payload_type, payload);
+        ha_log::insert(self.conn.as_ref(), entry).await?;
+        self.last_log_index = index;
+        self.last_log_term = self.current_term;
+        Ok(index)
+    }
+
+    async fn propose(
+        &mut self,
+        command: HaCommand,
+        resp_tx: oneshot::Sender<ProposeResponse>,
+    ) -> Result<()> {
+        if self.role != Role::Leader {
+            let _ = resp_tx.send(ProposeResponse::NotLeader {
+                leader: self.leader_id,
+                command: Box::new(command),
+            });
+            return Ok(());
+        }
+
+        let payload = serde_json::to_string(&command)?;
+        match self.append(ha_log::NORMAL, Some(&payload)).await {
+            Ok(index) => {
+                self.pending.insert(index, resp_tx);
+            }
+            Err(e) => {
+                let _ = resp_tx.send(ProposeResponse::Applied(Err(e)));
+                return Ok(());
+            }
+        }
+
+        self.advance_commit_index().await?;
+        self.replicate().await
+    }
+
+    async fn propose_run_changes(&mut self) -> Result<()> {
+        self.last_runs_poll = Instant::now();
+
+        let runs =
+            pipeline_runs::select_changed_after(self.conn.as_ref(), &self.runs_watermark).await?;
+
+        if runs.is_empty() {
+            return Ok(());
+        }
+
+        for run in runs {
+            let last_change = run.date_updated.unwrap_or(run.date_created);
+            self.runs_watermark = max(self.runs_watermark, last_change);
+            let command = HaCommand::RunUpsert { run: run.into() };
+            let payload = serde_json::to_string(&command)?;
+            self.append(ha_log::NORMAL, Some(&payload)).await?;
+        }
+
+        self.advance_commit_index().await
+    }
+
+    async fn start_election(&mut self) -> Result<()> {
+        self.role = Role::Candidate;
+        self.current_term += 1;
+        self.voted_for = Some(self.config.node_id);
+        self.leader_id = None;
+        self.persist_hard_state().await?;
+        self.reset_election_deadline();
+
+        self.votes.clear();
+        self.votes.insert(self.config.node_id);
+
+        info!(
+            "node {} started an election for term {}",
+            self.config.node_id, self.current_term
+        );
+
+        if self.votes.len() >= self.majority() {
+            return self.become_leader().await;
+        }
+
+        for peer in self.peers() {
+            let request = VoteRequest {
+                term: self.current_term,
+                candidate_id: self.config.node_id,
+                last_log_index: self.last_log_index,
+                last_log_term: self.last_log_term,
+            };
+            self.send_request_vote(&peer, request);
+        }
+
+        Ok(())
+    }
+
+    async fn become_leader(&mut self) -> Result<()> {
+        info!(
+            "node {} became the leader for term {}",
+            self.config.node_id, self.current_term
+        );
+
+        self.role = Role::Leader;
+        self.leader_id = Some(self.config.node_id);
+        self.next_index.clear();
+        self.match_index.clear();
+        for peer in self.peers() {
+            self.next_index.insert(peer.id, self.last_log_index + 1);
+            self.match_index.insert(peer.id, 0);
+        }
+
+        // a blank entry is appended at the start of the term so that entries
+        // of previous terms can be committed as soon as possible.
+        self.term_start_index = self.append(ha_log::BLANK, None).await?;
+        self.cron.resume();
+
+        self.advance_commit_index().await?;
+        self.replicate().await
+    }
+
+    async fn become_follower(&mut self, term: i32) -> Result<()> {
+        if term > self.current_term {
+            self.current_term = term;
+            self.voted_for = None;
+            self.persist_hard_state().await?;
+        }
+
+        if self.role == Role::Leader {
+            info!(
+                "node {} is no longer the leader of the cluster",
+                self.config.node_id
+            );
+            self.cron.pause();
+            for (_, resp_tx) in self.pending.drain() {
+                let _ = resp_tx.send(ProposeResponse::Applied(Err(anyhow!(
+                    "leadership was lost before the change was committed"
+                ))));
+            }
+        }
+
+        self.role = Role::Follower;
+        self.leader_id = None;
+        self.reset_election_deadline();
+        Ok(())
+    }
+
+    async fn request_vote(&mut self, request: VoteRequest) -> Result<VoteResponse> {
+        if request.term > self.current_term {
+            self.become_follower(request.term).await?;
+        }
+
+        let log_is_up_to_date = request.last_log_term > self.last_log_term
+            || (request.last_log_term == self.last_log_term
+                && request.last_log_index >= self.last_log_index);
+
+        let can_vote = self.voted_for.is_none_or(|id| id == request.candidate_id);
+
+        let vote_granted = request.term == self.current_term && can_vote && log_is_up_to_date;
+
+        if vote_granted {
+            debug!(
+                "node {} voted for node {} at term {}",
+                self.config.node_id, request.candidate_id, request.term
+            );
+            self.voted_for = Some(request.candidate_id);
+            self.persist_hard_state().await?;
+            self.reset_election_deadline();
+        }
+
+        Ok(VoteResponse {
+            term: self.current_term,
+            vote_granted,
+        })
+    }
+
+    async fn request_vote_result(&mut self, peer: i32, response: VoteResponse) -> Result<()> {
+        if response.term > self.current_term {
+            return self.become_follower(response.term).await;
+        }
+
+        if self.role != Role::Candidate
+            || response.term != self.current_term
+            || !response.vote_granted
+        {
+            return Ok(());
+        }
+
+        self.votes.insert(peer);
+        if self.votes.len() >= self.majority() {
+            self.become_leader().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn append_entries(
+        &mut self,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        if request.term < self.current_term {
+            return Ok(self.append_entries_response(false, self.last_log_index));
+        }
+
+        if request.term > self.current_term || self.role != Role::Follower {
+            self.become_follower(request.term).await?;
+        }
+        self.leader_id = Some(request.leader_id);
+        self.reset_election_deadline();
+
+        if request.prev_log_index > self.last_log_index {
+            return Ok(self.append_entries_response(false, self.last_log_index));
+        }
+
+        // the entries of the snapshot are committed, so they always match the
+        // entries of the leader.
+        if request.prev_log_index >= self.snapshot_index
+            && self.term_at(request.prev_log_index).await? != request.prev_log_term
+        {
+            return Ok(self.append_entries_response(false, request.prev_log_index - 1));
+        }
+
+        let last_new_index = request.prev_log_index + request.entries.len() as i32;
+        let mut entries = vec![];
+
+        for entry in request.entries {
+            if entry.index <= self.snapshot_index {
+                continue;
+            }
+            if entry.index <= self.last_log_index {
+                if self.term_at(entry.index).await? == entry.term {
+                    continue;
+                }
+                debug!(
+                    "removing conflicting log entries from index {}",
+                    entry.index
+                );
+                let previous_term = self.term_at(entry.index - 1).await?;
+                ha_log::delete_from_id(self.conn.as_ref(), entry.index).await?;
+                self.last_log_index = entry.index - 1;
+                self.last_log_term = previous_term;
+            }
+            entries.push(entry);
+        }
+
+        if let Some(last) = entries.last() {
+            self.last_log_index = last.index;
+            self.last_log_term = last.term;
+            let entries = entries
+                .into_iter()
+                .map(|e| {
+                    InsertHighAvailLog::new(e.index, e.term, &e.payload_type, Some(&e.payload))
+                })
+                .collect();
+            ha_log::insert_many(self.conn.as_ref(), entries).await?;
+        }
+
+        if request.leader_commit > self.commit_index {
+            self.commit_index = max(
+                self.commit_index,
+                min(request.leader_commit, last_new_index),
+            );
+            self.apply().await?;
+        }
+
+        Ok(self.append_entries_response(true, self.last_log_index))
+    }
+
+    fn append_entries_response(&self, success: bool, last_log_index: i32) -> AppendEntriesResponse {
+        AppendEntriesResponse {
+            term: self.current_term,
+            success,
+            last_log_index,
+        }
+    }
+
+    async fn append_entries_result(
+        &mut self,
+        peer: i32,
+        last_index: i32,
+        response: AppendEntriesResponse,
+    ) -> Result<()> {
+        if response.term > self.current_term {
+            return self.become_follower(response.term).await;
+        }
+
+        if self.role != Role::Leader || response.term != self.current_term {
+            return Ok(());
+        }
+
+        if response.success {
+            let match_index = self.match_index.entry(peer).or_insert(0);
+            *match_index = max(*match_index, last_index);
+            let next_index = *match_index + 1;
+            self.next_index.insert(peer, next_index);
+            self.advance_commit_index().await
+        } else {
+            let next_index = self.next_index.entry(peer).or_insert(1);
+            *next_index = max(1, min(*next_index - 1, response.last_log_index + 1));
+            Ok(())
+        }
+    }
+
+    async fn install_snapshot(
+        &mut self,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        if request.term < self.current_term {
+            return Ok(InstallSnapshotResponse {
+                term: self.current_term,
+            });
+        }
+
+        if request.term > self.current_term || self.role != Role::Follower {
+            self.become_follower(request.term).await?;
+        }
+        self.leader_id = Some(request.leader_id);
+        self.reset_election_deadline();
+
+        let index = request.last_included_index;
+        let term = request.last_included_term;
+        let response = InstallSnapshotResponse {
+            term: self.current_term,
+        };
+
+        if index <= self.snapshot_index {
+            return Ok(response);
+        }
+
+        // the log already contains the last entry of the snapshot, so the
+        // entries up to it are applied from the log instead.
+        if index <= self.last_log_index && self.term_at(index).await? == term {
+            self.commit_index = max(self.commit_index, index);
+            self.apply().await?;
+            return Ok(response);
+        }
+
+        info!(
+            "node {} is installing the snapshot of the leader up to index {index}",
+            self.config.node_id
+        );
+
+        let commands: Vec<HaCommand> = serde_json::from_str(&request.data)?;
+        for change in commands.iter().filter_map(Self::run_change) {
+            self.runs_watermark = max(self.runs_watermark, change);
+        }
+        self.state_machine.restore(commands).await?;
+
+        ha_log::delete(self.conn.as_ref()).await?;
+        self.save_snapshot(index, term, request.data.into_bytes(), &request.members)
+            .await?;
+        self.last_log_index = index;
+        self.last_log_term = term;
+        self.last_applied = index;
+        self.commit_index = max(self.commit_index, index);
+        ha_state_machine::update(self.conn.as_ref(), self.state_machine_id, self.last_applied)
+            .await?;
+
+        Ok(response)
+    }
+
+    async fn install_snapshot_result(
+        &mut self,
+        peer: i32,
+        last_index: i32,
+        response: InstallSnapshotResponse,
+    ) -> Result<()> {
+        self.snapshots_sent.remove(&peer);
+
+        if response.term > self.current_term {
+            return self.become_follower(response.term).await;
+        }
+
+        if self.role != Role::Leader || response.term != self.current_term {
+            return Ok(());
+        }
+
+        let match_index = self.match_index.entry(peer).or_insert(0);
+        *match_index = max(*match_index, last_index);
+        let next_index = *match_index + 1;
+        self.next_index.insert(peer, next_index);
+        self.advance_commit_index().await
+    }
+
+    /// Takes a snapshot of the state once enough entries have been applied since
+    /// the last one, the entries of the snapshot are then removed from the log.
+    async fn compact(&mut self) -> Result<()> {
+        if self.last_applied - self.snapshot_index < self.config.snapshot_threshold {
+            return Ok(());
+        }
+
+        let index = self.last_applied;
+        let term = self.term_at(index).await?;
+        let data = serde_json::to_vec(&self.state_machine.snapshot().await?)?;
+        let members: Vec<i32> = self.config.members.iter().map(|x| x.id).collect();
+        self.save_snapshot(index, term, data, &members).await?;
+
+        info!(
+            "node {} took a snapshot of the state up to index {index}",
+            self.config.node_id
+        );
+        Ok(())
+    }
+
+    /// Stores the snapshot along with the members of the cluster, the older snapshots
+    /// and the log entries up to the last entry of the snapshot are removed.
+    async fn save_snapshot(
+        &mut self,
+        index: i32,
+        term: i32,
+        data: Vec<u8>,
+        members: &[i32],
+    ) -> Result<()> {
+        let txn = self.conn.begin().await?;
+        ha_snapshot::insert(&txn, InsertHighAvailSnapshot::new(index, term, data)).await?;
+        ha_members::delete(&txn).await?;
+        let members = members
+            .iter()
+            .map(|id| InsertHighAvailMembers::new(*id, index))
+            .collect();
+        ha_members::insert_many(&txn, members).await?;
+        ha_snapshot::delete_until_id(&txn, index).await?;
+        ha_log::delete_until_id(&txn, index + 1).await?;
+        txn.commit().await?;
+
+        self.snapshot_index = index;
+        self.snapshot_term = term;
+        Ok(())
+    }
+
+    async fn advance_commit_index(&mut self) -> Result<()> {
+        let mut indexes: Vec<i32> = self
+            .config
+            .peers()
+            .map(|p| self.match_index.get(&p.id).copied().unwrap_or(0))
+            .collect();
+        indexes.push(self.last_log_index);
+        indexes.sort_unstable_by(|a, b| b.cmp(a));
+
+        let index = indexes[self.majority() - 1];
+        if index > self.commit_index && index >= self.term_start_index {
+            self.commit_index = index;
+            self.apply().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn apply(&mut self) -> Result<()> {
+        if self.commit_index <= self.last_applied {
+            return Ok(());
+        }
+
+        let entries = ha_log::select_between_ids(
+            self.conn.as_ref(),
+            self.last_applied + 1,
+            self.commit_index,
+        )
+        .await?;
+
+        for entry in entries {
+            let result = self.apply_entry(&entry).await;
+            if let Err(e) = &result {
+                error!("unable to apply log entry {} due to: {e}", entry.id);
+            }
+
+            self.last_applied = entry.id;
+            if let Some(change) = Self::entry_run_change(&entry) {
+                self.runs_watermark = max(self.runs_watermark, change);
+            }
+            if let Some(resp_tx) = self.pending.remove(&entry.id) {
+                let _ = resp_tx.send(ProposeResponse::Applied(result));
+            }
+        }
+
+        ha_state_machine::update(self.conn.as_ref(), self.state_machine_id, self.last_applied)
+            .await?;
+        self.compact().await
+    }
+
+    async fn apply_entry(&self, entry: &HighAvailLog) -> Result<()> {
+        if entry.payload_type != ha_log::NORMAL {
+            return Ok(());
+        }
+        let command: HaCommand = serde_json::from_str(&entry.payload)?;
+        self.state_machine.apply(command).await
+    }
+
+    async fn replicate(&mut self) -> Result<()> {
+        self.last_heartbeat = Instant::now();
+
+        for peer in self.peers() {
+            let next_index = self
+                .next_index
+                .get(&peer.id)
+                .copied()
+                .unwrap_or(self.last_log_index + 1);
+            if next_index <= self.snapshot_index {
+                self.send_snapshot(&peer).await?;
+                continue;
+            }
+
+            let prev_log_index = next_index - 1;
+            let prev_log_term = self.term_at(prev_log_index).await?;
+            let last_index = min(
+                self.last_log_index,
+                prev_log_index + MAX_ENTRIES_PER_REQUEST,
+            );
+
+            let entries = if next_index <= last_index {
+                ha_log::select_between_ids(self.conn.as_ref(), next_index, last_index)
+                    .await?
+                    .into_iter()
+                    .map(|e| HaLogEntry {
+                        index: e.id,
+                        term: e.term,
+                        payload_type: e.payload_type,
+                        payload: e.payload,
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            let request = AppendEntriesRequest {
+                term: self.current_term,
+                leader_id: self.config.node_id,
+                prev_log_index,
+                prev_log_term,
+                entries,
+                leader_commit: self.commit_index,
+            };
+            self.send_append_entries(&peer, request);
+        }
+
+        Ok(())
+    }
+
+    fn send_request_vote(&self, peer: &BldHaMember, request: VoteRequest) {
+        let transport = Arc::clone(&self.transport);
+        let tx = self.tx.clone();
+        let peer = peer.clone();
+
+        spawn(async move {
+            match transport.request_vote(&peer, request).await {
+                Ok(response) => {
+                    let _ = tx
+                        .send(HaMessage::RequestVoteResult {
+                            peer: peer.id,
+                            response,
+                        })
+                        .await;
+                }
+                Err(e) => debug!("vote request to member {} failed due to: {e}", peer.id),
+            }
+        });
+    }
+
+    fn send_append_entries(&self, peer: &BldHaMember, request: AppendEntriesRequest) {
+        let transport = Arc::clone(&self.transport);
+        let tx = self.tx.clone();
+        let peer = peer.clone();
+        let last_index = request.prev_log_index + request.entries.len() as i32;
+
+        spawn(async move {
+            match transport.append_entries(&peer, request).await {
+                Ok(response) => {
+                    let _ = tx
+                        .send(HaMessage::AppendEntriesResult {
+                            peer: peer.id,
+                            last_index,
+                            response,
+                        })
+                        .await;
+                }
+                Err(e) => debug!(
+                    "append entries request to member {} failed due to: {e}",
+                    peer.id
+                ),
+            }
+        });
+    }
+
+    /// Sends the last snapshot to a member whose next entries have been removed from
+    /// the log, the snapshot isn't sent again while the member could still be installing it.
+    async fn send_snapshot(&mut self, peer: &BldHaMember) -> Result<()> {
+        let timeout = Duration::from_millis(self.config.election_timeout);
+        let in_flight = self
+            .snapshots_sent
+            .get(&peer.id)
+            .is_some_and(|x| x.elapsed() < timeout);
+        if in_flight {
+            return Ok(());
+        }
+
+        let snapshot = ha_snapshot::select_last(self.conn.as_ref()).await?;
+        let members = ha_members::select(self.conn.as_ref(), snapshot.id)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+        let request = InstallSnapshotRequest {
+            term: self.current_term,
+            leader_id: self.config.node_id,
+            last_included_index: snapshot.id,
+            last_included_term: snapshot.term,
+            members,
+            data: String::from_utf8(snapshot.data)?,
+        };
+        self.snapshots_sent.insert(peer.id, Instant::now());
+
+        let transport = Arc::clone(&self.transport);
+        let tx = self.tx.clone();
+        let peer = peer.clone();
+        let last_index = request.last_included_index;
+
+        spawn(async move {
+            match transport.install_snapshot(&peer, request).await {
+                Ok(response) => {
+                    let _ = tx
+                        .send(HaMessage::InstallSnapshotResult {
+                            peer: peer.id,
+                            last_index,
+                            response,
+                        })
+                        .await;
+                }
+                Err(e) => debug!(
+                    "install snapshot request to member {} failed due to: {e}",
+                    peer.id
+                ),
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs, path::PathBuf, sync::RwLock};
+
+    use bld_config::{path, BldConfig};
+    use bld_core::fs::FileSystem;
+    use bld_models::{new_connection_pool, pipeline_runs::PipelineRuns};
+    use bld_utils::sync::IntoArc;
+    use chrono::Utc;
+    use tokio::{sync::mpsc::channel, time::sleep};
+    use uuid::Uuid;
+
+    use crate::{ha::HaPipelineRun, supervisor::channel::SupervisorMessageSender};
+
+    use super::*;
+
+    const WAIT_TIMEOUT: Duration = Duration::from_secs(20);
+    const WAIT_INTERVAL: Duration = Duration::from_millis(50);
+
+    struct TestNode {
+        conn: Arc<DatabaseConnection>,
+        tx: Sender<HaMessage>,
+    }
+
+    struct TestCluster {
+        dir: PathBuf,
+        size: i32,
+        election_timeout: u64,
+        snapshot_threshold: i32,
+        peers: Arc<RwLock<HashMap<i32, Sender<HaMessage>>>>,
+        nodes: HashMap<i32, TestNode>,
+    }
+
+    impl TestCluster {
+        async fn start(size: i32, election_timeout: u64, snapshot_threshold: i32) -> Self {
+            let mut cluster = Self::new(size, election_timeout, snapshot_threshold);
+            for id in 1..=size {
+                cluster.start_node(id).await;
+            }
+            cluster
+        }
+
+        fn new(size: i32, election_timeout: u64, snapshot_threshold: i32) -> Self {
+            Self {
+                dir: path![temp_dir(), format!("bld-ha-{}", Uuid::new_v4())],
+                size,
+                election_timeout,
+                snapshot_threshold,
+                peers: Arc::new(RwLock::new(HashMap::new())),
+                nodes: HashMap::new(),
+            }
+        }
+
+        async fn start_node(&mut self, id: i32) {
+            let root_dir = path![&self.dir, format!("node-{id}")];
+            let ha = BldHaConfig {
+                node_id: id,
+                members: (1..=self.size)
+                    .map(|id| BldHaMember {
+                        id,
+                        host: "localhost".to_owned(),
+                        port: 6080 + id as i64,
+                        tls: false,
+                    })
+                    .collect(),
+                token: "token".to_owned(),
+                heartbeat_interval: 50,
+                election_timeout: self.election_timeout,
+                snapshot_threshold: self.snapshot_threshold,
+            };
+
+            let mut config = BldConfig {
+                root_dir: root_dir.display().to_string(),
+                ..Default::default()
+            };
+            let db = path![&root_dir, "bld.db"];
+            config.local.server.db = Some(format!("sqlite://{}?mode=rwc", db.display()));
+            config.local.server.ha = Some(ha.clone());
+            let config = config.into_arc();
+            fs::create_dir_all(config.server_pipelines()).unwrap();
+
+            let conn = new_connection_pool(config.clone())
+                .await
+                .unwrap()
+                .into_arc();
+            let fs = FileSystem::server(config.clone(), conn.clone()).into_arc();
+            let supervisor = SupervisorMessageSender::detached().into_arc();
+            let cron = CronScheduler::new(fs.clone(), conn.clone(), supervisor)
+                .await
+                .unwrap()
+                .into_arc();
+            let state_machine = HaStateMachine::new(fs, cron.clone(), conn.clone());
+            let transport = HaTransport::InMemory {
+                node_id: id,
+                peers: self.peers.clone(),
+            };
+
+            let (tx, rx) = channel(4096);
+            let node = HaNode::new(
+                ha,
+                conn.clone(),
+                state_machine,
+                cron,
+                transport,
+                tx.clone(),
+                rx,
+            )
+            .await
+            .unwrap();
+            spawn(node.receive());
+
+            self.peers.write().unwrap().insert(id, tx.clone());
+            self.nodes.insert(id, TestNode { conn, tx });
+        }
+
+        fn node(&self, id: i32) -> &TestNode {
+            &self.nodes[&id]
+        }
+
+        fn disconnect(&self, id: i32) {
+            self.peers.write().unwrap().remove(&id);
+        }
+
+        fn connect(&self, id: i32) {
+            let tx = self.node(id).tx.clone();
+            self.peers.write().unwrap().insert(id, tx);
+        }
+
+        fn connected(&self) -> Vec<i32> {
+            let mut ids: Vec<i32> = self.peers.read().unwrap().keys().copied().collect();
+            ids.sort_unstable();
+            ids
+        }
+
+        async fn status(&self, id: i32) -> HaStatusResponse {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            self.node(id)
+                .tx
+                .send(HaMessage::Status(resp_tx))
+                .await
+                .unwrap();
+            resp_rx.await.unwrap()
+        }
+
+        async fn send<T>(
+            &self,
+            id: i32,
+            message: impl FnOnce(oneshot::Sender<T>) -> HaMessage,
+        ) -> T {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            self.node(id).tx.send(message(resp_tx)).await.unwrap();
+            resp_rx.await.unwrap()
+        }
+
+        async fn propose(&self, id: i32, command: HaCommand) -> Result<()> {
+            let response = self
+                .send(id, |resp_tx| HaMessage::Propose { command, resp_tx })
+                .await;
+            match response {
+                ProposeResponse::Applied(result) => result,
+                ProposeResponse::NotLeader { .. } => bail!("node {id} isn't the leader"),
+            }
+        }
+
+        /// Waits until the connected nodes agree on a leader, for a term after the given one.
+        async fn wait_for_leader(&self, after_term: i32) -> (i32, i32) {
+            let start = Instant::now();
+            while start.elapsed() < WAIT_TIMEOUT {
+                let mut statuses = vec![];
+                for id in self.connected() {
+                    statuses.push(self.status(id).await);
+                }
+                let leader = statuses
+                    .iter()
+                    .find(|x| x.role == "leader" && x.term > after_term)
+                    .map(|x| (x.node_id, x.term));
+                if let Some((leader, term)) = leader {
+                    let agreed = statuses
+                        .iter()
+                        .all(|x| x.term == term && x.leader == Some(leader));
+                    if agreed {
+                        return (leader, term);
+                    }
+                }
+                sleep(WAIT_INTERVAL).await;
+            }
+            panic!("no leader was elected for the cluster");
+        }
+
+        async fn wait_for_run(&self, id: i32, run_id: &str) -> PipelineRuns {
+            let start = Instant::now();
+            while start.elapsed() < WAIT_TIMEOUT {
+                if let Ok(run) =
+                    pipeline_runs::select_by_id(self.node(id).conn.as_ref(), run_id).await
+                {
+                    return run;
+                }
+                sleep(WAIT_INTERVAL).await;
+            }
+            panic!("run {run_id} wasn't replicated to node {id}");
+        }
+    }
+
+    impl Drop for TestCluster {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn run_upsert(id: &str) -> HaCommand {
+        HaCommand::RunUpsert {
+            run: HaPipelineRun {
+                id: id.to_owned(),
+                name: "test.yaml".to_owned(),
+                state: "finished".to_owned(),
+                app_user: "test".to_owned(),
+                start_date: None,
+                end_date: None,
+                date_created: Utc::now().naive_utc(),
+                date_updated: None,
+                hook_provider: None,
+                hook_repository: None,
+                hook_commit_sha: None,
+            },
+        }
+    }
+
+    fn entry(index: i32, term: i32, command: &HaCommand) -> HaLogEntry {
+        HaLogEntry {
+            index,
+            term,
+            payload_type: ha_log::NORMAL.to_owned(),
+            payload: serde_json::to_string(command).unwrap(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn a_new_leader_is_elected_when_the_leader_is_disconnected() {
+        let cluster = TestCluster::start(3, 200, 1000).await;
+        let (leader, term) = cluster.wait_for_leader(0).await;
+
+        cluster.disconnect(leader);
+        let (new_leader, new_term) = cluster.wait_for_leader(term).await;
+        assert_ne!(new_leader, leader);
+
+        cluster.connect(leader);
+        let (_, reconnected_term) = cluster.wait_for_leader(new_term - 1).await;
+        let status = cluster.status(leader).await;
+        assert_eq!(status.term, reconnected_term);
+        assert!(status.leader.is_some());
+    }
+
+    #[actix_web::test]
+    async fn proposals_are_replicated_to_every_node() {
+        let cluster = TestCluster::start(3, 200, 1000).await;
+        let (leader, _) = cluster.wait_for_leader(0).await;
+
+        let run_ids: Vec<String> = (0..3).map(|_| Uuid::new_v4().to_string()).collect();
+        for run_id in &run_ids {
+            cluster.propose(leader, run_upsert(run_id)).await.unwrap();
+        }
+
+        let follower = if leader == 1 { 2 } else { 1 };
+        assert!(cluster
+            .propose(follower, run_upsert("other"))
+            .await
+            .is_err());
+
+        for id in 1..=3 {
+            for run_id in &run_ids {
+                let run = cluster.wait_for_run(id, run_id).await;
+                assert_eq!(run.state, "finished");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn conflicting_entries_are_truncated() {
+        // only a single node of the cluster is started, which receives the
+        // requests of the leaders directly.
+        let mut cluster = TestCluster::new(3, 60_000, 1000);
+        cluster.start_node(1).await;
+        let conn = cluster.node(1).conn.clone();
+
+        let first = run_upsert("first");
+        let conflicting = run_upsert("conflicting");
+        let replacement = run_upsert("replacement");
+
+        let request = AppendEntriesRequest {
+            term: 1,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![entry(1, 1, &first), entry(2, 1, &conflicting)],
+            leader_commit: 1,
+        };
+        let response = cluster
+            .send(1, |resp_tx| HaMessage::AppendEntries { request, resp_tx })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.last_log_index, 2);
+
+        // the previous entry of a request has to match the log of the node.
+        let request = AppendEntriesRequest {
+            term: 2,
+            leader_id: 2,
+            prev_log_index: 2,
+            prev_log_term: 2,
+            entries: vec![],
+            leader_commit: 1,
+        };
+        let response = cluster
+            .send(1, |resp_tx| HaMessage::AppendEntries { request, resp_tx })
+            .await
+            .unwrap();
+        assert!(!response.success);
+
+        let request = AppendEntriesRequest {
+            term: 2,
+            leader_id: 2,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![entry(2, 2, &replacement)],
+            leader_commit: 2,
+        };
+        let response = cluster
+            .send(1, |resp_tx| HaMessage::AppendEntries { request, resp_tx })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.last_log_index, 2);
+
+        let last = ha_log::select_last(conn.as_ref()).await.unwrap();
+        assert_eq!((last.id, last.term), (2, 2));
+        assert_eq!(last.payload, serde_json::to_string(&replacement).unwrap());
+
+        assert!(pipeline_runs::select_by_id(conn.as_ref(), "first")
+            .await
+            .is_ok());
+        assert!(pipeline_runs::select_by_id(conn.as_ref(), "replacement")
+            .await
+            .is_ok());
+        assert!(pipeline_runs::select_by_id(conn.as_ref(), "conflicting")
+            .await
+            .is_err());
+
+        let status = cluster.status(1).await;
+        assert_eq!(status.term, 2);
+        assert_eq!(status.leader, Some(2));
+        assert_eq!(status.last_applied, 2);
+    }
+
+    #[actix_web::test]
+    async fn lagging_node_installs_the_snapshot_of_the_leader() {
+        let cluster = TestCluster::start(3, 200, 5).await;
+        let (leader, _) = cluster.wait_for_leader(0).await;
+        let lagging = if leader == 3 { 2 } else { 3 };
+
+        cluster.disconnect(lagging);
+        let run_ids: Vec<String> = (0..8).map(|_| Uuid::new_v4().to_string()).collect();
+        for run_id in &run_ids {
+            cluster.propose(leader, run_upsert(run_id)).await.unwrap();
+        }
+
+        let leader_conn = cluster.node(leader).conn.clone();
+        let snapshot = ha_snapshot::select_last(leader_conn.as_ref())
+            .await
+            .unwrap();
+        assert!(snapshot.id >= 5);
+        assert!(ha_log::select_by_id(leader_conn.as_ref(), snapshot.id)
+            .await
+            .is_err());
+
+        cluster.connect(lagging);
+        for run_id in &run_ids {
+            cluster.wait_for_run(lagging, run_id).await;
+        }
+
+        let conn = cluster.node(lagging).conn.clone();
+        let snapshot = ha_snapshot::select_last(conn.as_ref()).await.unwrap();
+        let mut members: Vec<i32> = ha_members::select(conn.as_ref(), snapshot.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+        members.sort_unstable();
+        assert_eq!(members, vec![1, 2, 3]);
+    }
+}
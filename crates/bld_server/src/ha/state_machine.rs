@@ -0,0 +1,152 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::Result;
+use bld_core::fs::FileSystem;
+use bld_models::{
+    dtos::{AddJobRequest, JobFiltersParams, UpdateJobRequest},
+    pipeline, pipeline_runs,
+};
+use bld_runner::{Load, VersionedPipeline, Yaml};
+use chrono::DateTime;
+use sea_orm::DatabaseConnection;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::cron::CronScheduler;
+
+use super::command::HaCommand;
+
+pub struct HaStateMachine {
+    fs: Arc<FileSystem>,
+    cron: Arc<CronScheduler>,
+    conn: Arc<DatabaseConnection>,
+}
+
+impl HaStateMachine {
+    pub fn new(
+        fs: Arc<FileSystem>,
+        cron: Arc<CronScheduler>,
+        conn: Arc<DatabaseConnection>,
+    ) -> Self {
+        Self { fs, cron, conn }
+    }
+
+    pub async fn apply(&self, command: HaCommand) -> Result<()> {
+        debug!("applying command {command:?}");
+        match command {
+            HaCommand::PipelinePush {
+                id,
+                cron_job_id,
+                name,
+                content,
+            } => self.push(&id, &cron_job_id, &name, &content).await,
+
+            HaCommand::PipelineRemove { name } => {
+                self.cron.remove_scheduled_jobs(&name).await?;
+                self.fs.remove(&name).await
+            }
+
+            HaCommand::PipelineMove { source, target } => self.fs.mv(&source, &target).await,
+
+            HaCommand::PipelineCopy { id, source, target } => {
+                let content = self.fs.read(&source).await?;
+                self.fs.create_with_id(&id, &target, &content, false).await
+            }
+
+            HaCommand::CronAdd { id, job } => {
+                let job_id = Uuid::from_str(&id)?;
+                self.cron.add_with_id(&job_id, &job).await
+            }
+
+            HaCommand::CronUpdate { job } => self.cron.update(&job).await,
+
+            HaCommand::CronRemove { id } => self.cron.remove(&id).await,
+
+            HaCommand::RunUpsert { run } => {
+                pipeline_runs::upsert(self.conn.as_ref(), run.into()).await
+            }
+        }
+    }
+
+    /// Returns the commands that recreate the current state, which is the snapshot
+    /// that replaces the log entries that have already been applied.
+    pub async fn snapshot(&self) -> Result<Vec<HaCommand>> {
+        let conn = self.conn.as_ref();
+        let jobs = self.cron.get(&JobFiltersParams::default()).await?;
+        let mut commands = vec![];
+
+        for pipeline in pipeline::select_all(conn).await? {
+            let content = self.fs.read(&pipeline.name).await?;
+            let cron_job_id = jobs
+                .iter()
+                .find(|x| x.is_default && x.pipeline == pipeline.name)
+                .map(|x| x.id.to_owned())
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            commands.push(HaCommand::PipelinePush {
+                id: pipeline.id,
+                cron_job_id,
+                name: pipeline.name,
+                content,
+            });
+        }
+
+        // the default jobs are created by pushing their pipeline so only their
+        // schedule and variables are restored.
+        for job in jobs {
+            let command = if job.is_default {
+                let job =
+                    UpdateJobRequest::new(job.id, job.schedule, job.variables, job.environment);
+                HaCommand::CronUpdate { job }
+            } else {
+                let id = job.id;
+                let job = AddJobRequest::new(
+                    job.schedule,
+                    job.pipeline,
+                    job.variables,
+                    job.environment,
+                    false,
+                );
+                HaCommand::CronAdd { id, job }
+            };
+            commands.push(command);
+        }
+
+        let epoch = DateTime::UNIX_EPOCH.naive_utc();
+        for run in pipeline_runs::select_changed_after(conn, &epoch).await? {
+            commands.push(HaCommand::RunUpsert { run: run.into() });
+        }
+
+        Ok(commands)
+    }
+
+    /// Replaces the pipelines and cron jobs with the ones of a snapshot, the runs of
+    /// the snapshot are upserted since runs are never removed.
+    pub async fn restore(&self, commands: Vec<HaCommand>) -> Result<()> {
+        for pipeline in pipeline::select_all(self.conn.as_ref()).await? {
+            self.cron.remove_by_pipeline(&pipeline.name).await?;
+            self.fs.remove(&pipeline.name).await?;
+        }
+
+        for command in commands {
+            if let Err(e) = self.apply(command).await {
+                error!("unable to restore snapshot command due to: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn push(&self, id: &str, cron_job_id: &str, name: &str, content: &str) -> Result<()> {
+        self.fs.create_with_id(id, name, content, true).await?;
+        let pipeline: VersionedPipeline = Yaml::load(content)?;
+        match pipeline.cron() {
+            Some(schedule) => {
+                let job_id = Uuid::from_str(cron_job_id)?;
+                self.cron
+                    .upsert_default_with_id(&job_id, schedule, name)
+                    .await
+            }
+            None => self.cron.remove_by_pipeline(name).await,
+        }
+    }
+}
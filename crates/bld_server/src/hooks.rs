@@ -0,0 +1,684 @@
+use std::collections::HashMap;
+
+use actix_web::http::header::HeaderMap;
+use anyhow::{anyhow, bail, Result};
+use bld_config::{BldHookConfig, BldHookEvent, BldHookProvider};
+use bld_models::pipeline_runs::PipelineRunHook;
+use glob::Pattern;
+use ring::hmac;
+use serde_json::Value;
+
+const GITHUB_EVENT_HEADER: &str = "X-GitHub-Event";
+const GITHUB_SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+const GITEA_EVENT_HEADER: &str = "X-Gitea-Event";
+const GITEA_SIGNATURE_HEADER: &str = "X-Gitea-Signature";
+const GITLAB_EVENT_HEADER: &str = "X-Gitlab-Event";
+const GITLAB_TOKEN_HEADER: &str = "X-Gitlab-Token";
+
+const PULL_REQUEST_ACTIONS: [&str; 7] = [
+    "opened",
+    "reopened",
+    "synchronize",
+    "synchronized",
+    "open",
+    "reopen",
+    "update",
+];
+
+/// The information of a push or a pull request delivery that is used to
+/// select the hooks to run and is passed to the pipeline as variables.
+#[derive(Debug)]
+pub struct HookDelivery {
+    pub provider: BldHookProvider,
+    pub event: BldHookEvent,
+    pub repository: String,
+    pub branch: String,
+    pub target_branch: String,
+    pub commit_sha: String,
+    pub author: String,
+}
+
+impl HookDelivery {
+    /// The origin that is recorded for the runs started by the delivery.
+    pub fn origin(&self) -> PipelineRunHook {
+        PipelineRunHook {
+            provider: self.provider.to_string(),
+            repository: self.repository.to_owned(),
+            commit_sha: self.commit_sha.to_owned(),
+        }
+    }
+
+    pub fn variables(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("provider".to_owned(), self.provider.to_string()),
+            ("event".to_owned(), self.event.to_string()),
+            ("repository".to_owned(), self.repository.to_owned()),
+            ("branch".to_owned(), self.branch.to_owned()),
+            ("target_branch".to_owned(), self.target_branch.to_owned()),
+            ("commit_sha".to_owned(), self.commit_sha.to_owned()),
+            ("author".to_owned(), self.author.to_owned()),
+        ])
+    }
+
+    pub fn matches(&self, hook: &BldHookConfig) -> bool {
+        let repository = hook
+            .repository
+            .as_ref()
+            .map(|x| x.eq_ignore_ascii_case(&self.repository))
+            .unwrap_or(true);
+
+        let event = hook.events.is_empty() || hook.events.contains(&self.event);
+
+        let branch = hook.branches.is_empty()
+            || hook.branches.iter().any(|x| {
+                Pattern::new(x)
+                    .map(|p| p.matches(&self.target_branch))
+                    .unwrap_or_else(|_| x == &self.target_branch)
+            });
+
+        repository && event && branch
+    }
+}
+
+pub fn provider_from_path(provider: &str) -> Option<BldHookProvider> {
+    match provider {
+        "github" => Some(BldHookProvider::Github),
+        "gitlab" => Some(BldHookProvider::Gitlab),
+        "gitea" => Some(BldHookProvider::Gitea),
+        _ => None,
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|x| x.to_str().ok())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+/// Verifies a delivery using the secret of a hook. Github and gitea sign the body
+/// with HMAC-SHA256, while gitlab sends the secret token as is.
+pub fn verify(provider: BldHookProvider, secret: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+    let signature = match provider {
+        BldHookProvider::Github => {
+            header(headers, GITHUB_SIGNATURE_HEADER).and_then(|x| x.strip_prefix("sha256="))
+        }
+        BldHookProvider::Gitea => header(headers, GITEA_SIGNATURE_HEADER),
+        BldHookProvider::Gitlab => {
+            return header(headers, GITLAB_TOKEN_HEADER)
+                .is_some_and(|x| constant_time_eq(x.as_bytes(), secret.as_bytes()));
+        }
+    };
+
+    let Some(signature) = signature.and_then(decode_hex) else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, body, &signature).is_ok()
+}
+
+fn string_at(payload: &Value, pointers: &[&str]) -> Option<String> {
+    pointers
+        .iter()
+        .filter_map(|x| payload.pointer(x).and_then(|v| v.as_str()))
+        .find(|x| !x.is_empty())
+        .map(|x| x.to_owned())
+}
+
+fn required_at(payload: &Value, pointers: &[&str]) -> Result<String> {
+    string_at(payload, pointers).ok_or_else(|| anyhow!("{} not found in payload", pointers[0]))
+}
+
+fn push_delivery(
+    provider: BldHookProvider,
+    payload: &Value,
+    sha: &[&str],
+    repository: &[&str],
+    author: &[&str],
+) -> Result<Option<HookDelivery>> {
+    let git_ref = required_at(payload, &["/ref"])?;
+    let Some(branch) = git_ref.strip_prefix("refs/heads/") else {
+        return Ok(None);
+    };
+
+    let Some(commit_sha) = string_at(payload, sha) else {
+        return Ok(None);
+    };
+    if commit_sha.chars().all(|x| x == '0') {
+        return Ok(None);
+    }
+
+    Ok(Some(HookDelivery {
+        provider,
+        event: BldHookEvent::Push,
+        repository: required_at(payload, repository)?,
+        branch: branch.to_owned(),
+        target_branch: branch.to_owned(),
+        commit_sha,
+        author: string_at(payload, author).unwrap_or_default(),
+    }))
+}
+
+struct PullRequestPointers<'a> {
+    action: &'a str,
+    sha: &'a str,
+    branch: &'a str,
+    target_branch: &'a str,
+    repository: &'a str,
+    author: &'a str,
+}
+
+fn pull_request_delivery(
+    provider: BldHookProvider,
+    payload: &Value,
+    pointers: PullRequestPointers,
+) -> Result<Option<HookDelivery>> {
+    let action = required_at(payload, &[pointers.action])?;
+    if !PULL_REQUEST_ACTIONS.contains(&action.as_str()) {
+        return Ok(None);
+    }
+
+    Ok(Some(HookDelivery {
+        provider,
+        event: BldHookEvent::PullRequest,
+        repository: required_at(payload, &[pointers.repository])?,
+        branch: required_at(payload, &[pointers.branch])?,
+        target_branch: required_at(payload, &[pointers.target_branch])?,
+        commit_sha: required_at(payload, &[pointers.sha])?,
+        author: string_at(payload, &[pointers.author]).unwrap_or_default(),
+    }))
+}
+
+/// Parses the payload of a delivery, returning nothing for events that
+/// don't start a run such as pings, tag pushes or closed pull requests.
+pub fn parse(
+    provider: BldHookProvider,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Option<HookDelivery>> {
+    let event_header = match provider {
+        BldHookProvider::Github => GITHUB_EVENT_HEADER,
+        BldHookProvider::Gitea => GITEA_EVENT_HEADER,
+        BldHookProvider::Gitlab => GITLAB_EVENT_HEADER,
+    };
+    let Some(event) = header(headers, event_header) else {
+        bail!("{event_header} header not found");
+    };
+
+    let payload: Value = serde_json::from_slice(body)?;
+
+    match (provider, event) {
+        (BldHookProvider::Github | BldHookProvider::Gitea, "push") => push_delivery(
+            provider,
+            &payload,
+            &["/after"],
+            &["/repository/full_name"],
+            &[
+                "/head_commit/author/username",
+                "/pusher/login",
+                "/pusher/username",
+                "/pusher/name",
+            ],
+        ),
+
+        (BldHookProvider::Github | BldHookProvider::Gitea, "pull_request") => {
+            pull_request_delivery(
+                provider,
+                &payload,
+                PullRequestPointers {
+                    action: "/action",
+                    sha: "/pull_request/head/sha",
+                    branch: "/pull_request/head/ref",
+                    target_branch: "/pull_request/base/ref",
+                    repository: "/repository/full_name",
+                    author: "/pull_request/user/login",
+                },
+            )
+        }
+
+        (BldHookProvider::Gitlab, "Push Hook") => push_delivery(
+            provider,
+            &payload,
+            &["/checkout_sha"],
+            &["/project/path_with_namespace"],
+            &["/user_username", "/user_name"],
+        ),
+
+        (BldHookProvider::Gitlab, "Merge Request Hook") => {
+            // updates without an oldrev don't contain new commits.
+            let action = payload.pointer("/object_attributes/action");
+            if action.and_then(|x| x.as_str()) == Some("update")
+                && payload.pointer("/object_attributes/oldrev").is_none()
+            {
+                return Ok(None);
+            }
+            pull_request_delivery(
+                provider,
+                &payload,
+                PullRequestPointers {
+                    action: "/object_attributes/action",
+                    sha: "/object_attributes/last_commit/id",
+                    branch: "/object_attributes/source_branch",
+                    target_branch: "/object_attributes/target_branch",
+                    repository: "/project/path_with_namespace",
+                    author: "/user/username",
+                },
+            )
+        }
+
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use serde_json::json;
+
+    use super::*;
+
+    const GITHUB_SECRET: &str = "It's a Secret to Everybody";
+    const GITEA_SECRET: &str = "gitea-secret";
+    const GITLAB_TOKEN: &str = "gitlab-token";
+
+    const GITHUB_PUSH: &[u8] = include_bytes!("../fixtures/hooks/github_push.json");
+    const GITHUB_PUSH_SIGNATURE: &str =
+        "sha256=a788a1fe03e2e0958821bf1c9ad0fb0a1373ea863320c26cd1ac901589200891";
+    const GITHUB_PULL_REQUEST: &[u8] = include_bytes!("../fixtures/hooks/github_pull_request.json");
+    const GITHUB_PULL_REQUEST_SIGNATURE: &str =
+        "sha256=28b6708f4c03bba2b3e36a6ecf649e21bae9f5af9b4c06bcf30953d29dd74b26";
+    const GITEA_PUSH: &[u8] = include_bytes!("../fixtures/hooks/gitea_push.json");
+    const GITEA_PUSH_SIGNATURE: &str =
+        "fe0b6f37c1869cd0613fc63753d4b402550967d36015b2016923c5914694d1d0";
+    const GITLAB_PUSH: &[u8] = include_bytes!("../fixtures/hooks/gitlab_push.json");
+    const GITLAB_MERGE_REQUEST: &[u8] =
+        include_bytes!("../fixtures/hooks/gitlab_merge_request.json");
+
+    fn headers(values: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn edit(body: &[u8], pointer: &str, value: Value) -> Vec<u8> {
+        let mut payload: Value = serde_json::from_slice(body).unwrap();
+        *payload.pointer_mut(pointer).unwrap() = value;
+        serde_json::to_vec(&payload).unwrap()
+    }
+
+    fn hook(provider: BldHookProvider) -> BldHookConfig {
+        BldHookConfig {
+            provider,
+            secret: String::new(),
+            pipeline: "ci.yaml".to_owned(),
+            repository: None,
+            branches: vec![],
+            events: vec![],
+        }
+    }
+
+    fn github_push() -> HookDelivery {
+        let headers = headers(&[(GITHUB_EVENT_HEADER, "push")]);
+        parse(BldHookProvider::Github, &headers, GITHUB_PUSH)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn verifies_github_documented_signature() {
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        let headers = headers(&[(GITHUB_SIGNATURE_HEADER, signature)]);
+        let body = b"Hello, World!";
+
+        assert!(verify(
+            BldHookProvider::Github,
+            GITHUB_SECRET,
+            &headers,
+            body
+        ));
+        assert!(!verify(BldHookProvider::Github, "other", &headers, body));
+        assert!(!verify(
+            BldHookProvider::Github,
+            GITHUB_SECRET,
+            &headers,
+            b"Hello"
+        ));
+    }
+
+    #[test]
+    fn verifies_github_deliveries() {
+        let push = headers(&[(GITHUB_SIGNATURE_HEADER, GITHUB_PUSH_SIGNATURE)]);
+        assert!(verify(
+            BldHookProvider::Github,
+            GITHUB_SECRET,
+            &push,
+            GITHUB_PUSH
+        ));
+
+        let pull_request = headers(&[(GITHUB_SIGNATURE_HEADER, GITHUB_PULL_REQUEST_SIGNATURE)]);
+        assert!(verify(
+            BldHookProvider::Github,
+            GITHUB_SECRET,
+            &pull_request,
+            GITHUB_PULL_REQUEST
+        ));
+
+        let tampered = edit(GITHUB_PUSH, "/ref", json!("refs/heads/other"));
+        assert!(!verify(
+            BldHookProvider::Github,
+            GITHUB_SECRET,
+            &push,
+            &tampered
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_github_signatures() {
+        let signature = GITHUB_PUSH_SIGNATURE.strip_prefix("sha256=").unwrap();
+        let cases = [
+            headers(&[]),
+            headers(&[(GITHUB_SIGNATURE_HEADER, signature)]),
+            headers(&[(GITHUB_SIGNATURE_HEADER, "sha256=abc")]),
+            headers(&[(GITHUB_SIGNATURE_HEADER, "sha256=zz")]),
+            headers(&[(GITHUB_SIGNATURE_HEADER, "sha256=")]),
+        ];
+        for headers in cases {
+            assert!(!verify(
+                BldHookProvider::Github,
+                GITHUB_SECRET,
+                &headers,
+                GITHUB_PUSH
+            ));
+        }
+    }
+
+    #[test]
+    fn verifies_gitea_deliveries() {
+        let valid = headers(&[(GITEA_SIGNATURE_HEADER, GITEA_PUSH_SIGNATURE)]);
+        assert!(verify(
+            BldHookProvider::Gitea,
+            GITEA_SECRET,
+            &valid,
+            GITEA_PUSH
+        ));
+        assert!(!verify(BldHookProvider::Gitea, "other", &valid, GITEA_PUSH));
+
+        let prefixed = format!("sha256={GITEA_PUSH_SIGNATURE}");
+        let prefixed = headers(&[(GITEA_SIGNATURE_HEADER, &prefixed)]);
+        assert!(!verify(
+            BldHookProvider::Gitea,
+            GITEA_SECRET,
+            &prefixed,
+            GITEA_PUSH
+        ));
+    }
+
+    #[test]
+    fn verifies_gitlab_tokens() {
+        let valid = headers(&[(GITLAB_TOKEN_HEADER, GITLAB_TOKEN)]);
+        assert!(verify(
+            BldHookProvider::Gitlab,
+            GITLAB_TOKEN,
+            &valid,
+            GITLAB_PUSH
+        ));
+
+        let invalid = headers(&[(GITLAB_TOKEN_HEADER, "gitlab-tokem")]);
+        assert!(!verify(
+            BldHookProvider::Gitlab,
+            GITLAB_TOKEN,
+            &invalid,
+            GITLAB_PUSH
+        ));
+
+        let prefix = headers(&[(GITLAB_TOKEN_HEADER, "gitlab")]);
+        assert!(!verify(
+            BldHookProvider::Gitlab,
+            GITLAB_TOKEN,
+            &prefix,
+            GITLAB_PUSH
+        ));
+
+        assert!(!verify(
+            BldHookProvider::Gitlab,
+            GITLAB_TOKEN,
+            &headers(&[]),
+            GITLAB_PUSH
+        ));
+    }
+
+    #[test]
+    fn parses_github_push() {
+        let delivery = github_push();
+        assert_eq!(delivery.provider, BldHookProvider::Github);
+        assert_eq!(delivery.event, BldHookEvent::Push);
+        assert_eq!(delivery.repository, "Codertocat/Hello-World");
+        assert_eq!(delivery.branch, "main");
+        assert_eq!(delivery.target_branch, "main");
+        assert_eq!(
+            delivery.commit_sha,
+            "0d1a26e67d8f5eaf1f6ba5c57fc3c7d91ac0fd1c"
+        );
+        assert_eq!(delivery.author, "Codertocat");
+
+        let variables = delivery.variables();
+        assert_eq!(variables["provider"], "github");
+        assert_eq!(variables["event"], "push");
+        assert_eq!(variables["branch"], "main");
+
+        let origin = delivery.origin();
+        assert_eq!(origin.provider, "github");
+        assert_eq!(origin.repository, "Codertocat/Hello-World");
+        assert_eq!(origin.commit_sha, delivery.commit_sha);
+    }
+
+    #[test]
+    fn parses_github_pull_request() {
+        let headers = headers(&[(GITHUB_EVENT_HEADER, "pull_request")]);
+        let delivery = parse(BldHookProvider::Github, &headers, GITHUB_PULL_REQUEST)
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.event, BldHookEvent::PullRequest);
+        assert_eq!(delivery.repository, "Codertocat/Hello-World");
+        assert_eq!(delivery.branch, "changes");
+        assert_eq!(delivery.target_branch, "master");
+        assert_eq!(
+            delivery.commit_sha,
+            "ec26c3e57ca3a959ca5aad62de7213c562f8c821"
+        );
+        assert_eq!(delivery.author, "Codertocat");
+
+        let body = edit(GITHUB_PULL_REQUEST, "/action", json!("synchronize"));
+        assert!(parse(BldHookProvider::Github, &headers, &body)
+            .unwrap()
+            .is_some());
+
+        let body = edit(GITHUB_PULL_REQUEST, "/action", json!("closed"));
+        assert!(parse(BldHookProvider::Github, &headers, &body)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn parses_gitea_push() {
+        let headers = headers(&[(GITEA_EVENT_HEADER, "push")]);
+        let delivery = parse(BldHookProvider::Gitea, &headers, GITEA_PUSH)
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.provider, BldHookProvider::Gitea);
+        assert_eq!(delivery.event, BldHookEvent::Push);
+        assert_eq!(delivery.repository, "gitea/webhooks");
+        assert_eq!(delivery.branch, "develop");
+        assert_eq!(
+            delivery.commit_sha,
+            "bffeb74224043ba2feb48d137756c8a9331c449a"
+        );
+        assert_eq!(delivery.author, "gitea");
+    }
+
+    #[test]
+    fn parses_gitlab_push() {
+        let headers = headers(&[(GITLAB_EVENT_HEADER, "Push Hook")]);
+        let delivery = parse(BldHookProvider::Gitlab, &headers, GITLAB_PUSH)
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.provider, BldHookProvider::Gitlab);
+        assert_eq!(delivery.event, BldHookEvent::Push);
+        assert_eq!(delivery.repository, "mike/diaspora");
+        assert_eq!(delivery.branch, "master");
+        assert_eq!(
+            delivery.commit_sha,
+            "da1560886d4f094c3e6c9ef40349f7d38b5d27d7"
+        );
+        assert_eq!(delivery.author, "jsmith");
+    }
+
+    #[test]
+    fn parses_gitlab_merge_request() {
+        let headers = headers(&[(GITLAB_EVENT_HEADER, "Merge Request Hook")]);
+        let delivery = parse(BldHookProvider::Gitlab, &headers, GITLAB_MERGE_REQUEST)
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.event, BldHookEvent::PullRequest);
+        assert_eq!(delivery.repository, "gitlabhq/gitlab-test");
+        assert_eq!(delivery.branch, "ms-viewport");
+        assert_eq!(delivery.target_branch, "master");
+        assert_eq!(
+            delivery.commit_sha,
+            "da1560886d4f094c3e6c9ef40349f7d38b5d27d7"
+        );
+        assert_eq!(delivery.author, "root");
+
+        let update = edit(
+            GITLAB_MERGE_REQUEST,
+            "/object_attributes/action",
+            json!("update"),
+        );
+        assert!(parse(BldHookProvider::Gitlab, &headers, &update)
+            .unwrap()
+            .is_none());
+
+        let mut payload: Value = serde_json::from_slice(&update).unwrap();
+        payload["object_attributes"]["oldrev"] = json!("95790bf891e76fee5e1747ab589903a6a1f80f22");
+        let update = serde_json::to_vec(&payload).unwrap();
+        assert!(parse(BldHookProvider::Gitlab, &headers, &update)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn ignores_deliveries_that_dont_start_runs() {
+        let push = headers(&[(GITHUB_EVENT_HEADER, "push")]);
+
+        let tag = edit(GITHUB_PUSH, "/ref", json!("refs/tags/v1.0.0"));
+        assert!(parse(BldHookProvider::Github, &push, &tag)
+            .unwrap()
+            .is_none());
+
+        let deleted = edit(
+            GITHUB_PUSH,
+            "/after",
+            json!("0000000000000000000000000000000000000000"),
+        );
+        assert!(parse(BldHookProvider::Github, &push, &deleted)
+            .unwrap()
+            .is_none());
+
+        let ping = headers(&[(GITHUB_EVENT_HEADER, "ping")]);
+        let body = br#"{"zen":"Design for failure.","hook_id":1}"#;
+        assert!(parse(BldHookProvider::Github, &ping, body)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_deliveries() {
+        let error = parse(BldHookProvider::Github, &headers(&[]), GITHUB_PUSH)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "X-GitHub-Event header not found");
+
+        let push = headers(&[(GITHUB_EVENT_HEADER, "push")]);
+        assert!(parse(BldHookProvider::Github, &push, b"not json").is_err());
+
+        let mut payload: Value = serde_json::from_slice(GITHUB_PUSH).unwrap();
+        payload["repository"]
+            .as_object_mut()
+            .unwrap()
+            .remove("full_name");
+        let body = serde_json::to_vec(&payload).unwrap();
+        let error = parse(BldHookProvider::Github, &push, &body).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "/repository/full_name not found in payload"
+        );
+    }
+
+    #[test]
+    fn matches_hooks_by_repository() {
+        let delivery = github_push();
+
+        let mut hook = hook(BldHookProvider::Github);
+        assert!(delivery.matches(&hook));
+
+        hook.repository = Some("codertocat/hello-world".to_owned());
+        assert!(delivery.matches(&hook));
+
+        hook.repository = Some("Codertocat/Other".to_owned());
+        assert!(!delivery.matches(&hook));
+    }
+
+    #[test]
+    fn matches_hooks_by_event() {
+        let delivery = github_push();
+
+        let mut hook = hook(BldHookProvider::Github);
+        hook.events = vec![BldHookEvent::Push];
+        assert!(delivery.matches(&hook));
+
+        hook.events = vec![BldHookEvent::PullRequest];
+        assert!(!delivery.matches(&hook));
+    }
+
+    #[test]
+    fn matches_hooks_by_branch_pattern() {
+        let headers = headers(&[(GITHUB_EVENT_HEADER, "pull_request")]);
+        let delivery = parse(BldHookProvider::Github, &headers, GITHUB_PULL_REQUEST)
+            .unwrap()
+            .unwrap();
+
+        let mut hook = hook(BldHookProvider::Github);
+        hook.branches = vec!["master".to_owned()];
+        assert!(delivery.matches(&hook));
+
+        // pull requests are matched by their target branch.
+        hook.branches = vec!["changes".to_owned()];
+        assert!(!delivery.matches(&hook));
+
+        hook.branches = vec!["release/*".to_owned(), "mas*".to_owned()];
+        assert!(delivery.matches(&hook));
+
+        hook.branches = vec!["release/*".to_owned()];
+        assert!(!delivery.matches(&hook));
+
+        // an invalid pattern is compared as is.
+        hook.branches = vec!["[master".to_owned()];
+        assert!(!delivery.matches(&hook));
+    }
+}
@@ -1,6 +1,9 @@
+mod artifacts;
 pub mod cron;
 pub mod endpoints;
 pub mod extractors;
+pub mod ha;
+mod hooks;
 mod server;
 pub mod sockets;
 mod supervisor;
@@ -1,15 +1,17 @@
+use crate::artifacts::ArtifactsRetention;
 use crate::cron::CronScheduler;
 use crate::endpoints::auth::WebCoreClient;
 use crate::endpoints::{
-    auth, check, copy, cron, deps, hist, home, list, print, pull, push, r#move, remove, run, stop,
-    ui,
+    artifacts, auth, check, copy, cron, deps, ha, hist, home, hooks, list, print, pull, push,
+    r#move, remove, run, secrets, stop, ui,
 };
+use crate::ha::HighAvailability;
 use crate::sockets::{exec, login, monit};
 use crate::supervisor::channel::SupervisorMessageSender;
 use actix_cors::Cors;
 use actix_web::{
     middleware,
-    web::{get, resource},
+    web::{get, post, resource, JsonConfig, PayloadConfig},
     App, HttpServer,
 };
 use anyhow::Result;
@@ -42,6 +44,15 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
     )
     .await?
     .into_data();
+    let ha = HighAvailability::new(
+        Arc::clone(&config),
+        Arc::clone(&pool),
+        Arc::clone(&fs),
+        Arc::clone(&cron),
+    )
+    .await?
+    .into_data();
+    ArtifactsRetention::start(Arc::clone(&config), Arc::clone(&pool));
 
     set_var("RUST_LOG", "actix_server=info,actix_web=debug");
     let mut server = HttpServer::new(move || {
@@ -58,6 +69,7 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .app_data(pool.clone())
             .app_data(fs.clone())
             .app_data(cron.clone())
+            .app_data(ha.clone())
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .service(auth::available)
@@ -68,9 +80,19 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .service(check::get)
             .service(copy::post)
             .service(hist::get)
+            .service(hist::get_steps)
+            .service(hist::get_images)
+            .service(hist::get_notifications)
+            .service(artifacts::get)
+            .service(artifacts::download)
             .service(list::get)
             .service(remove::delete)
             .service(run::post)
+            .service(
+                resource("/v1/hooks/{provider}")
+                    .app_data(PayloadConfig::new(hooks::PAYLOAD_LIMIT))
+                    .route(post().to(hooks::post)),
+            )
             .service(push::post)
             .service(deps::get)
             .service(pull::get)
@@ -81,6 +103,19 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .service(cron::post)
             .service(cron::patch)
             .service(cron::delete)
+            .service(secrets::get)
+            .service(secrets::post)
+            .service(secrets::delete)
+            .service(ha::status)
+            .service(ha::append)
+            .service(ha::vote)
+            .service(
+                resource("/v1/ha/snapshot")
+                    .app_data(JsonConfig::default().limit(ha::SNAPSHOT_LIMIT))
+                    .route(post().to(ha::snapshot)),
+            )
+            .service(ha::propose)
+            .service(ha::run)
             .service(ui::queued_pipelines)
             .service(ui::running_pipelines)
             .service(ui::completed_pipelines)
@@ -1,10 +1,11 @@
 use crate::{
     extractors::User,
+    ha::HighAvailability,
     supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
 };
 use actix::prelude::*;
 use actix_web::{
-    error::ErrorUnauthorized,
+    error::{ErrorBadRequest, ErrorUnauthorized},
     web::{Data, Payload},
     Error, HttpRequest, HttpResponse,
 };
@@ -114,7 +115,7 @@ impl ExecutePipelineSocket {
         let supervisor = Arc::clone(&self.supervisor);
 
         let enqueue_fut =
-            async move { enqueue_worker(&username, fs, pool, supervisor, message).await }
+            async move { enqueue_worker(&username, None, fs, pool, supervisor, message).await }
                 .into_actor(self)
                 .then(|res, act, ctx| match res {
                     Ok(run_id) => {
@@ -190,6 +191,16 @@ pub async fn ws(
     fs: Data<FileSystem>,
 ) -> Result<HttpResponse, Error> {
     let user = user.ok_or_else(|| ErrorUnauthorized(""))?;
+    let leader = match req.app_data::<Data<HighAvailability>>() {
+        Some(ha) => ha.remote_leader().await.map_err(ErrorBadRequest)?,
+        None => None,
+    };
+    if let Some(leader) = leader {
+        return Err(ErrorBadRequest(format!(
+            "server isn't the leader of the cluster, the leader is at {}",
+            leader.base_url_http()
+        )));
+    }
     println!("{req:?}");
     let socket = ExecutePipelineSocket::new(user, cfg, supervisor_sender, conn, fs);
     let res = ws::start(socket, &req, stream);
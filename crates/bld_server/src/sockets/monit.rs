@@ -21,6 +21,9 @@ use tracing::{debug, error};
 
 pub struct MonitorPipelineSocket {
     id: String,
+    job: Option<String>,
+    step: Option<String>,
+    structured: bool,
     conn: Data<DatabaseConnection>,
     config: Data<BldConfig>,
     scanner: Option<Arc<FileScanner>>,
@@ -30,6 +33,9 @@ impl MonitorPipelineSocket {
     pub fn new(conn: Data<DatabaseConnection>, config: Data<BldConfig>) -> Self {
         Self {
             id: String::new(),
+            job: None,
+            step: None,
+            structured: false,
             conn,
             config,
             scanner: None,
@@ -43,10 +49,18 @@ impl MonitorPipelineSocket {
         let scanner = scanner.clone();
         let scan_fut = async move { scanner.scan().await }
             .into_actor(act)
-            .then(|res, _, ctx| {
-                if let Ok(lines) = res {
-                    for line in lines.iter() {
-                        ctx.text(line.to_string());
+            .then(|res, act, ctx| {
+                let Ok(entries) = res else {
+                    return ready(());
+                };
+                for entry in entries.iter() {
+                    if !entry.is_match(act.job.as_deref(), act.step.as_deref()) {
+                        continue;
+                    }
+                    if !act.structured {
+                        ctx.text(entry.to_string());
+                    } else if let Ok(data) = serde_json::to_string(entry) {
+                        ctx.text(data);
                     }
                 }
                 ready(())
@@ -78,6 +92,7 @@ impl MonitorPipelineSocket {
         let conn = self.conn.clone();
         let pipeline_fut = async move {
             let data = serde_json::from_str::<MonitInfo>(&data)?;
+            let filters = (data.job, data.step, data.structured);
             let run = if data.last {
                 pipeline_runs::select_last(conn.as_ref()).await
             } else if let Some(id) = data.id {
@@ -87,13 +102,17 @@ impl MonitorPipelineSocket {
             } else {
                 bail!("pipeline not found");
             };
-            run.map_err(|_| anyhow!("pipeline not found"))
+            run.map(|run| (run, filters))
+                .map_err(|_| anyhow!("pipeline not found"))
         }
         .into_actor(self)
         .then(|res, act, ctx| match res {
-            Ok(run) => {
+            Ok((run, (job, step, structured))) => {
                 debug!("starting scan for run");
                 act.id.clone_from(&run.id);
+                act.job = job;
+                act.step = step;
+                act.structured = structured;
                 act.scanner = Some(FileScanner::new(act.config.as_ref(), &run.id).into_arc());
                 ready(())
             }
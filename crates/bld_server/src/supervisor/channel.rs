@@ -143,6 +143,17 @@ impl SupervisorMessageSender {
         }
     }
 
+    /// A sender whose messages are dropped, for tests that don't start a supervisor.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        let (tx, mut rx) = channel(4096);
+        let rx_task = spawn(async move { while rx.recv().await.is_some() {} });
+        Self {
+            tx,
+            _rx_task: rx_task,
+        }
+    }
+
     pub async fn enqueue(
         &self,
         pipeline: String,
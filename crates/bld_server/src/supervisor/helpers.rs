@@ -3,8 +3,9 @@ use anyhow::{bail, Result};
 use bld_core::fs::FileSystem;
 use bld_models::{
     dtos::ExecClientMessage,
-    pipeline_runs::{self, InsertPipelineRun},
+    pipeline_runs::{self, InsertPipelineRun, PipelineRunHook},
 };
+use bld_runner::{Load, Yaml};
 use bld_utils::fs::IsYaml;
 use sea_orm::DatabaseConnection;
 use std::{collections::HashMap, sync::Arc};
@@ -13,6 +14,7 @@ use uuid::Uuid;
 
 pub async fn enqueue_worker(
     user_name: &str,
+    hook: Option<PipelineRunHook>,
     fs: Arc<FileSystem>,
     conn: Arc<DatabaseConnection>,
     supervisor_sender: Arc<SupervisorMessageSender>,
@@ -29,11 +31,15 @@ pub async fn enqueue_worker(
         bail!("pipeline file not found");
     }
 
+    let pipeline = Yaml::load(&fs.read(&name).await?)?;
+    pipeline.validate_inputs(&variables.clone().unwrap_or_default())?;
+
     let run_id = Uuid::new_v4().to_string();
     let model = InsertPipelineRun {
         id: run_id.to_owned(),
         name: name.to_owned(),
         app_user: user_name.to_owned(),
+        hook,
     };
     pipeline_runs::insert(conn.as_ref(), model).await?;
 
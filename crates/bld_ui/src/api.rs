@@ -1,16 +1,18 @@
 use anyhow::{anyhow, bail, Result};
 use bld_models::dtos::{
-    AddJobRequest, AuthTokens, CompletedPipelinesKpi, CronJobResponse, HistQueryParams,
-    HistoryEntry, JobFiltersParams, ListResponse, PipelineInfoQueryParams, PipelinePathRequest,
+    AddJobRequest, ArtifactResponse, AuthTokens, CompletedPipelinesKpi, CronJobResponse,
+    HistQueryParams, HistoryEntry, HistoryImageEntry, HistoryJobEntry, HistoryNotificationEntry,
+    JobFiltersParams, ListResponse, PipelineInfoQueryParams, PipelinePathRequest,
     PipelinePerCompletedStateKpi, PipelineQueryParams, PipelineRunsPerMonthKpi, QueuedPipelinesKpi,
     RunningPipelinesKpi, RunsPerUserKpi, UpdateJobRequest,
 };
-use leptos::leptos_dom::logging;
+use js_sys::{Array, Uint8Array};
+use leptos::{leptos_dom::logging, wasm_bindgen::JsCast};
 use leptos_router::{use_navigate, NavigateOptions};
 use reqwest::{Client, RequestBuilder, StatusCode};
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Display};
-use web_sys::{window, Storage};
+use web_sys::{window, Blob, HtmlAnchorElement, Storage, Url};
 
 const LOCAL_STORAGE_AUTH_AVAILABLE_KEY: &str = "auth_available";
 const LOCAL_STORAGE_AUTH_TOKENS_KEY: &str = "auth_tokens";
@@ -367,6 +369,93 @@ pub async fn hist(params: HistQueryParams) -> Result<Vec<HistoryEntry>> {
     }
 }
 
+pub async fn hist_steps(run_id: String) -> Result<Vec<HistoryJobEntry>> {
+    let url = build_url(format!("/v1/hist/{run_id}/steps"))?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
+pub async fn hist_images(run_id: String) -> Result<Vec<HistoryImageEntry>> {
+    let url = build_url(format!("/v1/hist/{run_id}/images"))?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
+pub async fn hist_notifications(run_id: String) -> Result<Vec<HistoryNotificationEntry>> {
+    let url = build_url(format!("/v1/hist/{run_id}/notifications"))?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
+pub async fn artifacts(run_id: String) -> Result<Vec<ArtifactResponse>> {
+    let url = build_url(format!("/v1/artifacts/{run_id}"))?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
+pub async fn artifacts_download(run_id: String, id: String, name: String) -> Result<()> {
+    let url = build_url(format!("/v1/artifacts/{run_id}/{id}"))?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        let bytes = response.bytes().await?;
+        save_file(&name, &bytes)
+    }
+}
+
+/// Saves the content to a file of the user by clicking on a temporary link
+/// to an object url of the content.
+fn save_file(name: &str, content: &[u8]) -> Result<()> {
+    let parts = Array::of1(&Uint8Array::from(content));
+    let blob =
+        Blob::new_with_u8_array_sequence(&parts).map_err(|_| anyhow!("unable to create blob"))?;
+    let url = Url::create_object_url_with_blob(&blob)
+        .map_err(|_| anyhow!("unable to create object url"))?;
+
+    let window = window().ok_or_else(|| anyhow!("window not found"))?;
+    let document = window
+        .document()
+        .ok_or_else(|| anyhow!("document not found"))?;
+    let anchor = document
+        .create_element("a")
+        .map_err(|_| anyhow!("unable to create anchor element"))?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|_| anyhow!("unable to create anchor element"))?;
+
+    anchor.set_href(&url);
+    anchor.set_download(name.rsplit('/').next().unwrap_or(name));
+    anchor.click();
+
+    Url::revoke_object_url(&url).map_err(|_| anyhow!("unable to revoke object url"))
+}
+
 pub async fn print(params: PipelineInfoQueryParams) -> Result<String> {
     let url = build_url("/v1/print")?;
     let request = add_authorization_header(Client::builder().build()?.get(&url))?;
@@ -25,6 +25,22 @@ pub fn Input(
     }
 }
 
+#[component]
+pub fn Checkbox(
+    #[prop(into, optional)] id: String,
+    #[prop()] value: RwSignal<bool>,
+) -> impl IntoView {
+    view! {
+        <input
+            id=id
+            type="checkbox"
+            class="h-[20px] w-[20px] rounded accent-slate-500"
+            prop:checked=move || value.get()
+            on:change=move |ev| value.set(event_target_checked(&ev))
+        />
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SelectItem {
     pub value: String,
@@ -0,0 +1,186 @@
+use crate::{
+    api,
+    components::table::{Body, Cell, Header, Headers, Row, Table},
+    error::Error,
+};
+use bld_models::dtos::{HistoryImageEntry, HistoryJobEntry, HistoryNotificationEntry};
+use leptos::*;
+
+use super::table::HistoryEntryState;
+
+#[component]
+pub fn HistoryDetails(#[prop(into)] run_id: Signal<Option<String>>) -> impl IntoView {
+    let data = create_resource(
+        move || run_id.get(),
+        |run_id| async move {
+            match run_id {
+                Some(id) => api::hist_steps(id).await.map_err(|e| e.to_string()),
+                None => Ok(vec![]),
+            }
+        },
+    );
+
+    let images = create_resource(
+        move || run_id.get(),
+        |run_id| async move {
+            match run_id {
+                Some(id) => api::hist_images(id).await.map_err(|e| e.to_string()),
+                None => Ok(vec![]),
+            }
+        },
+    );
+
+    let has_images = move || matches!(images.get(), Some(Ok(x)) if !x.is_empty());
+
+    let notifications = create_resource(
+        move || run_id.get(),
+        |run_id| async move {
+            match run_id {
+                Some(id) => api::hist_notifications(id).await.map_err(|e| e.to_string()),
+                None => Ok(vec![]),
+            }
+        },
+    );
+
+    let has_notifications = move || matches!(notifications.get(), Some(Ok(x)) if !x.is_empty());
+
+    view! {
+        <Show when=move || run_id.get().is_some() fallback=|| view! {}>
+            <div class="text-xl mt-8 mb-4">
+                {move || format!("Jobs and steps of run {}", run_id.get().unwrap_or_default())}
+            </div>
+            <Show when=move || matches!(data.get(), Some(Err(_))) fallback=|| view! {}>
+                <Error error=move || data.get().unwrap().unwrap_err()/>
+            </Show>
+            <Show when=move || matches!(data.get(), Some(Ok(_))) fallback=|| view! {}>
+                <Table>
+                    <Headers>
+                        <Header>"Job"</Header>
+                        <Header>"Step"</Header>
+                        <Header>"Exit Code"</Header>
+                        <Header>"Start Date"</Header>
+                        <Header>"End Date"</Header>
+                        <Header>"State"</Header>
+                    </Headers>
+                    <Body>
+                        <For
+                            each=move || data.get().unwrap().unwrap().into_iter()
+                            key=move |j| j.name.clone()
+                            let:child
+                        >
+                            <HistoryJobRows job=child/>
+                        </For>
+                    </Body>
+                </Table>
+            </Show>
+            <Show when=has_images fallback=|| view! {}>
+                <div class="text-xl mt-8 mb-4">"Images"</div>
+                <Table>
+                    <Headers>
+                        <Header>"Image"</Header>
+                        <Header>"Digest"</Header>
+                        <Header>"Date"</Header>
+                    </Headers>
+                    <Body>
+                        <For
+                            each=move || images.get().unwrap().unwrap().into_iter()
+                            key=move |i| i.container_id.clone()
+                            let:child
+                        >
+                            <HistoryImageRow image=child/>
+                        </For>
+                    </Body>
+                </Table>
+            </Show>
+            <Show when=has_notifications fallback=|| view! {}>
+                <div class="text-xl mt-8 mb-4">"Notifications"</div>
+                <Table>
+                    <Headers>
+                        <Header>"Type"</Header>
+                        <Header>"Event"</Header>
+                        <Header>"Attempt"</Header>
+                        <Header>"Error"</Header>
+                        <Header>"Date"</Header>
+                        <Header>"State"</Header>
+                    </Headers>
+                    <Body>
+                        <For
+                            each=move || notifications.get().unwrap().unwrap().into_iter()
+                            key=move |n| n.id.clone()
+                            let:child
+                        >
+                            <HistoryNotificationRow notification=child/>
+                        </For>
+                    </Body>
+                </Table>
+            </Show>
+        </Show>
+    }
+}
+
+#[component]
+fn HistoryImageRow(image: HistoryImageEntry) -> impl IntoView {
+    view! {
+        <Row>
+            <Cell>{image.image}</Cell>
+            <Cell>{image.digest.unwrap_or_default()}</Cell>
+            <Cell>{image.date_time}</Cell>
+        </Row>
+    }
+}
+
+#[component]
+fn HistoryNotificationRow(notification: HistoryNotificationEntry) -> impl IntoView {
+    view! {
+        <Row>
+            <Cell>{notification.target}</Cell>
+            <Cell>{notification.event}</Cell>
+            <Cell>{notification.attempt}</Cell>
+            <Cell>{notification.error.unwrap_or_default()}</Cell>
+            <Cell>{notification.date_time}</Cell>
+            <Cell>
+                <HistoryEntryState state=notification.state/>
+            </Cell>
+        </Row>
+    }
+}
+
+#[component]
+fn HistoryJobRows(job: HistoryJobEntry) -> impl IntoView {
+    let steps = job
+        .steps
+        .into_iter()
+        .map(|step| {
+            let name = job.name.clone();
+            let label = step
+                .name
+                .unwrap_or_else(|| format!("#{}", step.position + 1));
+            view! {
+                <Row>
+                    <Cell>{name}</Cell>
+                    <Cell>{label}</Cell>
+                    <Cell>{step.exit_code.map(|x| x.to_string()).unwrap_or_default()}</Cell>
+                    <Cell>{step.start_date_time.unwrap_or_default()}</Cell>
+                    <Cell>{step.end_date_time.unwrap_or_default()}</Cell>
+                    <Cell>
+                        <HistoryEntryState state=step.state/>
+                    </Cell>
+                </Row>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <Row>
+            <Cell>{job.name}</Cell>
+            <Cell>""</Cell>
+            <Cell>""</Cell>
+            <Cell>{job.start_date_time.unwrap_or_default()}</Cell>
+            <Cell>{job.end_date_time.unwrap_or_default()}</Cell>
+            <Cell>
+                <HistoryEntryState state=job.state/>
+            </Cell>
+        </Row>
+        {steps}
+    }
+}
@@ -1,8 +1,10 @@
+mod details;
 mod filters;
 pub mod table;
 
 use crate::{components::card::Card, context::RefreshHistory};
 use bld_models::dtos::HistQueryParams;
+use details::HistoryDetails;
 use filters::HistoryFilters;
 use leptos::*;
 use table::HistoryTable;
@@ -26,6 +28,7 @@ pub fn History() -> impl IntoView {
     let limit = create_rw_signal("100".to_string());
     let pipeline: RwSignal<String> = create_rw_signal(String::new());
     let params = move || get_params(state.get(), limit.get(), pipeline.get());
+    let selected: RwSignal<Option<String>> = create_rw_signal(None);
 
     provide_context(RefreshHistory(create_rw_signal(())));
 
@@ -43,7 +46,8 @@ pub fn History() -> impl IntoView {
                         <HistoryFilters state=state limit=limit pipeline=pipeline />
                     </div>
                 </div>
-                <HistoryTable params=params />
+                <HistoryTable params=params selected=selected />
+                <HistoryDetails run_id=selected />
             </div>
         </Card>
     }
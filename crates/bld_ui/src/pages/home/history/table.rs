@@ -2,6 +2,7 @@ use crate::{
     api,
     components::{
         badge::Badge,
+        button::IconButton,
         link::Link,
         table::{Body, Cell, Header, Headers, Row, Table},
         user_pill::UserPill,
@@ -27,6 +28,9 @@ pub fn HistoryEntryState(#[prop(into)] state: String) -> impl IntoView {
         "running" => ("iconoir-running", "Running", ""),
         "finished" => ("iconoir-check-circle", "Finished", "bg-emerable-600"),
         "faulted" => ("iconoir-minus-circle", "Faulted", "bg-red-600"),
+        "skipped" => ("iconoir-skip-next", "Skipped", "bg-slate-600"),
+        "delivered" => ("iconoir-send", "Delivered", "bg-emerable-600"),
+        "failed" => ("iconoir-minus-circle", "Failed", "bg-red-600"),
         _ => ("", "Unknown", "bg-black"),
     };
 
@@ -43,7 +47,44 @@ pub fn HistoryEntryState(#[prop(into)] state: String) -> impl IntoView {
 }
 
 #[component]
-pub fn HistoryTable(#[prop(into)] params: Signal<Option<HistQueryParams>>) -> impl IntoView {
+fn HistoryEntryRow(
+    entry: HistoryEntry,
+    selected: Option<RwSignal<Option<String>>>,
+) -> impl IntoView {
+    let id = entry.id.clone();
+    let details = selected.map(|selected| {
+        view! {
+            <Cell>
+                <IconButton
+                    icon="iconoir-list"
+                    on:click=move |_| selected.set(Some(id.clone()))
+                />
+            </Cell>
+        }
+    });
+
+    view! {
+        <Row>
+            <Cell>
+                <Link href=format!("/monit?id={}", entry.id)>{entry.id}</Link>
+            </Cell>
+            <Cell>{entry.name}</Cell>
+            <Cell><UserPill name=move || entry.user.clone() /></Cell>
+            <Cell>{entry.start_date_time.unwrap_or_default()}</Cell>
+            <Cell>{entry.end_date_time.unwrap_or_default()}</Cell>
+            <Cell>
+                <HistoryEntryState state=entry.state/>
+            </Cell>
+            {details}
+        </Row>
+    }
+}
+
+#[component]
+pub fn HistoryTable(
+    #[prop(into)] params: Signal<Option<HistQueryParams>>,
+    #[prop(into, optional)] selected: Option<RwSignal<Option<String>>>,
+) -> impl IntoView {
     let refresh = use_context::<RefreshHistory>();
     let params_debounced = signal_debounced(params, 500.0);
 
@@ -77,6 +118,9 @@ pub fn HistoryTable(#[prop(into)] params: Signal<Option<HistQueryParams>>) -> im
                     <Header>"Start Date"</Header>
                     <Header>"End Date"</Header>
                     <Header>"State"</Header>
+                    <Show when=move || selected.is_some() fallback=|| view! {}>
+                        <Header>"Details"</Header>
+                    </Show>
                 </Headers>
                 <Body>
                     <For
@@ -84,18 +128,7 @@ pub fn HistoryTable(#[prop(into)] params: Signal<Option<HistQueryParams>>) -> im
                         key=move |e| e.id.clone()
                         let:child
                     >
-                        <Row>
-                            <Cell>
-                                <Link href=format!("/monit?id={}", child.id)>{child.id}</Link>
-                            </Cell>
-                            <Cell>{child.name}</Cell>
-                            <Cell><UserPill name=move || child.user.clone() /></Cell>
-                            <Cell>{child.start_date_time.unwrap_or_default()}</Cell>
-                            <Cell>{child.end_date_time.unwrap_or_default()}</Cell>
-                            <Cell>
-                                <HistoryEntryState state=child.state/>
-                            </Cell>
-                        </Row>
+                        <HistoryEntryRow entry=child selected=selected/>
                     </For>
                 </Body>
             </Table>
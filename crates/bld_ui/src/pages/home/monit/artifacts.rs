@@ -0,0 +1,87 @@
+use crate::{
+    api,
+    components::{
+        button::Button,
+        table::{Body, Cell, Header, Headers, Row, Table},
+    },
+    error::Error,
+};
+use bld_models::dtos::ArtifactResponse;
+use leptos::{leptos_dom::logging, *};
+
+#[component]
+pub fn MonitArtifacts(
+    #[prop(into)] run_id: Signal<Option<String>>,
+    #[prop(into)] finished: Signal<bool>,
+) -> impl IntoView {
+    let data = create_resource(
+        move || (run_id.get(), finished.get()),
+        |(run_id, _)| async move {
+            match run_id {
+                Some(id) => api::artifacts(id).await.map_err(|e| e.to_string()),
+                None => Ok(vec![]),
+            }
+        },
+    );
+
+    let has_artifacts = move || matches!(data.get(), Some(Ok(x)) if !x.is_empty());
+
+    view! {
+        <Show when=move || matches!(data.get(), Some(Err(_))) fallback=|| view! {}>
+            <Error error=move || data.get().unwrap().unwrap_err()/>
+        </Show>
+        <Show when=has_artifacts fallback=|| view! {}>
+            <div class="text-xl">"Artifacts"</div>
+            <Table>
+                <Headers>
+                    <Header>"Name"</Header>
+                    <Header>"Size"</Header>
+                    <Header>"Date"</Header>
+                    <Header>""</Header>
+                </Headers>
+                <Body>
+                    <For
+                        each=move || data.get().unwrap().unwrap().into_iter()
+                        key=move |a| a.id.clone()
+                        let:child
+                    >
+                        <MonitArtifactRow artifact=child/>
+                    </For>
+                </Body>
+            </Table>
+        </Show>
+    }
+}
+
+#[component]
+fn MonitArtifactRow(artifact: ArtifactResponse) -> impl IntoView {
+    let download_action = create_action(|artifact: &ArtifactResponse| {
+        let artifact = artifact.clone();
+        async move {
+            if let Err(e) =
+                api::artifacts_download(artifact.run_id, artifact.id, artifact.name).await
+            {
+                logging::console_error(&e.to_string());
+            }
+        }
+    });
+
+    let name = artifact.name.clone();
+    let size = format!("{} bytes", artifact.size);
+    let date_created = artifact.date_created.clone();
+
+    view! {
+        <Row>
+            <Cell>{name}</Cell>
+            <Cell>{size}</Cell>
+            <Cell>{date_created}</Cell>
+            <Cell>
+                <div class="w-32">
+                    <Button on:click=move |_| download_action.dispatch(artifact.clone())>
+                        "Download"
+                    </Button>
+                </div>
+            </Cell>
+        </Row>
+    }
+}
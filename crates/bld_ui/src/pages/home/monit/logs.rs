@@ -0,0 +1,139 @@
+use crate::components::input::{Select, SelectItem};
+use bld_models::dtos::{LogEntry, LogStream};
+use leptos::*;
+
+#[derive(Debug, Clone)]
+struct LogSection {
+    title: String,
+    entries: Vec<LogEntry>,
+}
+
+fn section_title(entry: &LogEntry) -> String {
+    match (entry.job.as_deref(), entry.step.as_deref()) {
+        (Some(job), Some(step)) => format!("{job} > {step}"),
+        (Some(job), None) => job.to_string(),
+        (None, _) => "Pipeline".to_string(),
+    }
+}
+
+fn group_sections(entries: Vec<LogEntry>) -> Vec<LogSection> {
+    let mut sections: Vec<LogSection> = vec![];
+    for entry in entries {
+        let title = section_title(&entry);
+        match sections.last_mut() {
+            Some(section) if section.title == title => section.entries.push(entry),
+            _ => sections.push(LogSection {
+                title,
+                entries: vec![entry],
+            }),
+        }
+    }
+    sections
+}
+
+#[component]
+fn LogLine(#[prop()] entry: LogEntry) -> impl IntoView {
+    let class = if entry.stream == LogStream::Stderr {
+        "text-red-300"
+    } else {
+        ""
+    };
+    view! { <pre class=class>{entry.message}</pre> }
+}
+
+#[component]
+pub fn MonitLogs(#[prop()] entries: ReadSignal<Vec<LogEntry>>) -> impl IntoView {
+    let job: RwSignal<Option<String>> = create_rw_signal(None);
+    let view_mode: RwSignal<Option<String>> = create_rw_signal(None);
+    let (jobs, set_jobs) = create_signal(vec![]);
+    let (view_modes, _set_view_modes) = create_signal(vec![
+        SelectItem {
+            value: "structured".to_string(),
+            label: "Structured".to_string(),
+        },
+        SelectItem {
+            value: "plain".to_string(),
+            label: "Plain text".to_string(),
+        },
+    ]);
+
+    create_effect(move |_| {
+        let mut items = vec![SelectItem {
+            value: "all".to_string(),
+            label: "All jobs".to_string(),
+        }];
+        for entry in entries.get().iter() {
+            let Some(name) = entry.job.as_ref() else {
+                continue;
+            };
+            if !items.iter().any(|x| &x.value == name) {
+                items.push(SelectItem {
+                    value: name.to_string(),
+                    label: name.to_string(),
+                });
+            }
+        }
+        set_jobs.set(items);
+    });
+
+    let filtered = move || {
+        let job = job.get().filter(|x| x != "all");
+        entries
+            .get()
+            .into_iter()
+            .filter(|x| x.is_match(job.as_deref(), None))
+            .collect::<Vec<LogEntry>>()
+    };
+
+    let is_plain = move || view_mode.get().as_deref() == Some("plain");
+
+    view! {
+        <div class="flex flex-col gap-4">
+            <div class="flex justify-end gap-4">
+                <div class="min-w-[150px]">
+                    <Select items=jobs value=job/>
+                </div>
+                <div class="min-w-[150px]">
+                    <Select items=view_modes value=view_mode/>
+                </div>
+            </div>
+            <div class="border border-slate-600 rounded-lg p-8 text-sm text-gray-200">
+                <Show
+                    when=is_plain
+                    fallback=move || {
+                        view! {
+                            <For
+                                each=move || group_sections(filtered()).into_iter().enumerate()
+                                key=|(index, section)| (*index, section.entries.len())
+                                let:child
+                            >
+                                <details open class="mb-2">
+                                    <summary class="cursor-pointer text-gray-400">
+                                        {child.1.title}
+                                    </summary>
+                                    <div class="pl-4">
+                                        {child
+                                            .1
+                                            .entries
+                                            .into_iter()
+                                            .map(|entry| view! { <LogLine entry=entry/> })
+                                            .collect_view()}
+                                    </div>
+                                </details>
+                            </For>
+                        }
+                    }
+                >
+
+                    <For
+                        each=move || filtered().into_iter().enumerate()
+                        key=|(index, _)| *index
+                        let:child
+                    >
+                        <LogLine entry=child.1/>
+                    </For>
+                </Show>
+            </div>
+        </div>
+    }
+}
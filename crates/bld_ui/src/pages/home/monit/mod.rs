@@ -1,12 +1,18 @@
+mod artifacts;
+mod logs;
+
 use crate::{
     api::{self, build_ws_url, get_access_token},
     components::{button::Button, card::Card, colors::Colors},
     context::{AppDialog, AppDialogContent},
     error::ErrorDialog,
 };
+use artifacts::MonitArtifacts;
+use bld_models::dtos::LogEntry;
 use leptos::{html::Dialog, leptos_dom::logging, *};
 use leptos_router::*;
 use leptos_use::{core::ConnectionReadyState, use_websocket, UseWebsocketReturn};
+use logs::MonitLogs;
 use serde::{Deserialize, Serialize};
 
 type StopActionArgs = (String, NodeRef<Dialog>, RwSignal<Option<View>>);
@@ -16,6 +22,7 @@ struct MonitInfo {
     id: Option<String>,
     pipeline: Option<String>,
     last: bool,
+    structured: bool,
 }
 
 #[component]
@@ -26,6 +33,7 @@ pub fn Monit() -> impl IntoView {
         id: id(),
         pipeline: None,
         last: false,
+        structured: true,
     };
     let (history, set_history) = create_signal(vec![]);
     let app_dialog = use_context::<AppDialog>();
@@ -58,6 +66,8 @@ pub fn Monit() -> impl IntoView {
         ConnectionReadyState::Closed => "Closed",
     };
 
+    let finished = Signal::derive(move || ready_state.get() == ConnectionReadyState::Closed);
+
     create_effect(move |_| {
         if ready_state.get() == ConnectionReadyState::Open {
             let info = info();
@@ -67,7 +77,7 @@ pub fn Monit() -> impl IntoView {
 
     create_effect(move |_| {
         if let Some(data) = message.get() {
-            set_history.update(|v: &mut Vec<String>| v.push(data));
+            set_history.update(|v: &mut Vec<LogEntry>| v.push(LogEntry::parse(&data)));
         }
     });
 
@@ -118,15 +128,8 @@ pub fn Monit() -> impl IntoView {
                         </Button>
                     </div>
                 </div>
-                <div class="border border-slate-600 rounded-lg p-8 text-sm text-gray-200">
-                    <For
-                        each=move || history.get().into_iter().enumerate()
-                        key=|(index, _)| *index
-                        let:child
-                    >
-                        <pre>{child.1}</pre>
-                    </For>
-                </div>
+                <MonitArtifacts run_id=Signal::derive(id) finished=finished/>
+                <MonitLogs entries=history/>
             </div>
         </Card>
     }
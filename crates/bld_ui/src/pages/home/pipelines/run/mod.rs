@@ -11,13 +11,14 @@ use crate::{
 use anyhow::{anyhow, Result};
 use bld_models::dtos::PipelineInfoQueryParams;
 use bld_runner::{
+    inputs::v2::Input,
     pipeline::{v1, v2},
     VersionedPipeline,
 };
 use leptos::{html::Dialog, *};
 use leptos_router::*;
 
-use self::variables::RunPipelineVariables;
+use self::variables::{RunPipelineInputs, RunPipelineVariables};
 
 type RequestInterRepr = (
     String,
@@ -55,6 +56,18 @@ fn hash_map_rw_signals(items: HashMap<String, String>) -> HashMap<String, RwSign
         .collect()
 }
 
+fn input_rw_signals(items: HashMap<String, Input>) -> Vec<(String, Input, RwSignal<String>)> {
+    let mut items: Vec<(String, Input, RwSignal<String>)> = items
+        .into_iter()
+        .map(|(k, v)| {
+            let value = create_rw_signal(v.default.clone().unwrap_or_default());
+            (k, v, value)
+        })
+        .collect();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    items
+}
+
 fn hash_map_strings(items: HashMap<String, RwSignal<String>>) -> HashMap<String, String> {
     items
         .into_iter()
@@ -69,6 +82,7 @@ pub fn RunPipeline() -> impl IntoView {
     let name = move || params.with(|p| p.get("name").cloned());
     let variables = create_rw_signal(HashMap::new());
     let environment = create_rw_signal(HashMap::new());
+    let inputs = create_rw_signal(vec![]);
     let app_dialog = use_context::<AppDialog>();
     let app_dialog_content = use_context::<AppDialogContent>();
 
@@ -103,14 +117,19 @@ pub fn RunPipeline() -> impl IntoView {
             variables: var,
             environment: env,
             ..
-        })))
-        | Some(Ok(VersionedPipeline::Version2(v2::Pipeline {
+        }))) => {
+            variables.set(hash_map_rw_signals(var));
+            environment.set(hash_map_rw_signals(env));
+        }
+        Some(Ok(VersionedPipeline::Version2(v2::Pipeline {
             variables: var,
             environment: env,
+            inputs: inp,
             ..
         }))) => {
             variables.set(hash_map_rw_signals(var));
             environment.set(hash_map_rw_signals(env));
+            inputs.set(input_rw_signals(inp));
         }
         _ => {}
     });
@@ -149,10 +168,12 @@ pub fn RunPipeline() -> impl IntoView {
                                 let Some(AppDialogContent(content)) = app_dialog_content else {
                                     return;
                                 };
+                                let mut vars = variables.get();
+                                vars.extend(inputs.get().into_iter().map(|(k, _, v)| (k, v)));
                                 start_run
                                     .dispatch((
                                         name().unwrap(),
-                                        variables.get(),
+                                        vars,
                                         environment.get(),
                                         dialog,
                                         content,
@@ -161,6 +182,9 @@ pub fn RunPipeline() -> impl IntoView {
                         </div>
                     </div>
                 </Card>
+                <Show when=move || !inputs.get().is_empty() fallback=move || view! {}>
+                    <RunPipelineInputs items=inputs/>
+                </Show>
                 <Show when=move || !variables.get().is_empty() fallback=move || view! {}>
                     <RunPipelineVariables
                         title="Variables"
@@ -1,4 +1,8 @@
-use crate::components::{card::Card, input::Input};
+use crate::components::{
+    card::Card,
+    input::{Checkbox, Input, Select, SelectItem},
+};
+use bld_runner::inputs::v2::{Input as PipelineInput, InputType};
 use leptos::*;
 use std::collections::HashMap;
 
@@ -25,3 +29,75 @@ pub fn RunPipelineVariables(
         </Card>
     }
 }
+
+#[component]
+fn RunPipelineInputControl(
+    #[prop()] input: PipelineInput,
+    #[prop()] value: RwSignal<String>,
+) -> impl IntoView {
+    match input.input_type {
+        InputType::Bool => {
+            let checked = create_rw_signal(value.get_untracked() == "true");
+            create_effect(move |_| value.set(checked.get().to_string()));
+            view! { <Checkbox value=checked/> }.into_view()
+        }
+        InputType::Number => view! { <Input input_type="number" value=value/> }.into_view(),
+        InputType::Choice => {
+            let (items, _) = create_signal(
+                input
+                    .options
+                    .iter()
+                    .map(|x| SelectItem {
+                        value: x.to_owned(),
+                        label: x.to_owned(),
+                    })
+                    .collect(),
+            );
+            if value.get_untracked().is_empty() {
+                if let Some(first) = input.options.first() {
+                    value.set(first.to_owned());
+                }
+            }
+            let selected = create_rw_signal(Some(value.get_untracked()));
+            create_effect(move |_| {
+                if let Some(x) = selected.get() {
+                    value.set(x);
+                }
+            });
+            view! { <Select items=items value=selected/> }.into_view()
+        }
+        InputType::String => view! { <Input value=value/> }.into_view(),
+    }
+}
+
+#[component]
+pub fn RunPipelineInputs(
+    #[prop(into)] items: Signal<Vec<(String, PipelineInput, RwSignal<String>)>>,
+) -> impl IntoView {
+    view! {
+        <Card>
+            <div class="flex flex-col px-8 py-12 gap-4">
+                <div class="text-2xl">"Inputs"</div>
+                <div class="text-gray-400 mb-8">
+                    "The typed inputs of the pipeline, the required ones must be provided."
+                </div>
+                <div class="grid grid-cols-3 gap-4">
+                    <For each=move || items.get() key=|(name, _, _)| name.clone() let:item>
+                        <div class="flex flex-col">
+                            <div>
+                                {item.0.clone()}
+                                {item.1.required.then_some(" *")}
+                            </div>
+                            <div class="text-sm text-gray-400">
+                                {item.1.description.clone()}
+                            </div>
+                        </div>
+                        <div class="col-span-2">
+                            <RunPipelineInputControl input=item.1 value=item.2/>
+                        </div>
+                    </For>
+                </div>
+            </div>
+        </Card>
+    }
+}
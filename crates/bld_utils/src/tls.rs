@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use rustls::{Certificate, PrivateKey, RootCertStore};
 use rustls_native_certs::load_native_certs;
-use rustls_pemfile::{certs, pkcs8_private_keys};
+use rustls_pemfile::{certs, pkcs8_private_keys, read_all, Item};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -37,3 +37,17 @@ pub fn load_server_private_key<P: AsRef<Path>>(path: &P) -> Result<PrivateKey> {
 
     Ok(PrivateKey(key))
 }
+
+/// Loads the first private key of a pem file in any of the pkcs8, rsa or ec formats.
+pub fn load_private_key<P: AsRef<Path>>(path: &P) -> Result<PrivateKey> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    read_all(&mut reader)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("private key not found"))
+}
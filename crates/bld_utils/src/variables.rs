@@ -1,13 +1,19 @@
+use anyhow::{bail, Result};
 use std::collections::HashMap;
 
-pub fn parse_variables(variables: &[String]) -> HashMap<String, String> {
-    variables
-        .iter()
-        .flat_map(|v| {
-            let mut split = v.split('=');
-            let name = split.next()?.to_owned();
-            let value = split.next()?.to_owned();
-            Some((name, value))
-        })
-        .collect::<HashMap<String, String>>()
+/// Parses arguments in the name=value format, the value is everything after
+/// the first = so it can contain the character itself.
+pub fn parse_variables(variables: &[String]) -> Result<HashMap<String, String>> {
+    let mut parsed = HashMap::new();
+    for variable in variables.iter() {
+        let Some((name, value)) = variable.split_once('=') else {
+            bail!("invalid argument {variable:?}, expected the name=value format");
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("invalid argument {variable:?}, the name can't be empty");
+        }
+        parsed.insert(name.to_owned(), value.to_owned());
+    }
+    Ok(parsed)
 }